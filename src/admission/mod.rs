@@ -0,0 +1,103 @@
+pub mod plugins;
+pub mod quota;
+pub mod webhook;
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{config::AdmissionPluginConfig, entities::container::ContainerSpec};
+
+#[derive(Debug, Error)]
+pub enum AdmissionError {
+    #[error("admission plugin {plugin} denied container {container}: {message}")]
+    Denied {
+        plugin: String,
+        container: String,
+        message: String,
+    },
+    #[error("admission plugin {plugin} failed for container {container}: {source}")]
+    Failed {
+        plugin: String,
+        container: String,
+        #[source]
+        source: anyhow::Error,
+    },
+}
+
+// Sees every container spec before it is persisted, and can change it or reject it.
+#[async_trait]
+pub trait AdmissionPlugin {
+    fn name(&self) -> &str;
+    async fn admit(&self, spec: ContainerSpec) -> Result<ContainerSpec, AdmissionError>;
+}
+
+struct Entry {
+    plugin: Arc<dyn AdmissionPlugin + Send + Sync>,
+    // Empty for every app.
+    apps: Vec<String>,
+}
+
+// Runs the configured plugins in order, each on the spec returned by the one before.
+#[derive(Default)]
+pub struct AdmissionChain {
+    plugins: Vec<Entry>,
+}
+
+impl AdmissionChain {
+    pub fn new(configs: &[AdmissionPluginConfig]) -> Result<Self, anyhow::Error> {
+        let plugins = configs
+            .iter()
+            .map(|config| {
+                Ok(Entry {
+                    plugin: plugin(config)?,
+                    apps: config.apps.clone(),
+                })
+            })
+            .collect::<Result<_, anyhow::Error>>()?;
+        Ok(AdmissionChain { plugins })
+    }
+
+    // Adds a plugin that runs for every app after the configured ones.
+    pub fn with_plugin(mut self, plugin: Arc<dyn AdmissionPlugin + Send + Sync>) -> Self {
+        self.plugins.push(Entry {
+            plugin,
+            apps: Vec::new(),
+        });
+        self
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.plugins
+            .iter()
+            .map(|entry| entry.plugin.name())
+            .collect()
+    }
+
+    pub async fn admit(&self, mut spec: ContainerSpec) -> Result<ContainerSpec, AdmissionError> {
+        for entry in self.plugins.iter() {
+            let app = spec.app_name();
+            if entry.apps.is_empty() || entry.apps.iter().any(|enabled| enabled == app) {
+                spec = entry.plugin.admit(spec).await?;
+            }
+        }
+        Ok(spec)
+    }
+}
+
+fn plugin(
+    config: &AdmissionPluginConfig,
+) -> Result<Arc<dyn AdmissionPlugin + Send + Sync>, anyhow::Error> {
+    match config.name.to_ascii_lowercase().as_str() {
+        "default-resources" => Ok(Arc::new(plugins::DefaultResources::new(config)?)),
+        "require-resources" => Ok(Arc::new(plugins::RequireResources)),
+        "disallow-latest-tag" => Ok(Arc::new(plugins::DisallowLatestTag)),
+        "webhook" => Ok(Arc::new(webhook::Webhook::new(config)?)),
+        _ => Err(anyhow!(
+            "unknown admission plugin {:?}: expected default-resources, require-resources, disallow-latest-tag or webhook",
+            config.name
+        )),
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::anyhow;
+use async_trait::async_trait;
+
+use crate::{config::AdmissionPluginConfig, entities::container::ContainerSpec};
+
+use super::{AdmissionError, AdmissionPlugin};
+
+fn denied(plugin: &str, spec: &ContainerSpec, message: String) -> AdmissionError {
+    AdmissionError::Denied {
+        plugin: String::from(plugin),
+        container: spec.name.clone(),
+        message,
+    }
+}
+
+// Fills in the CPU and memory requests that a spec leaves out.
+pub struct DefaultResources {
+    cpus: f64,
+    memory_bytes: u64,
+}
+
+impl DefaultResources {
+    pub fn new(config: &AdmissionPluginConfig) -> Result<Self, anyhow::Error> {
+        let cpus = config.cpus.unwrap_or_default();
+        let memory_bytes = config.memory_bytes.unwrap_or_default();
+        if !cpus.is_finite() || cpus < 0.0 || (cpus == 0.0 && memory_bytes == 0) {
+            return Err(anyhow!(
+                "the default-resources admission plugin needs positive cpus or memory_bytes"
+            ));
+        }
+        Ok(DefaultResources { cpus, memory_bytes })
+    }
+}
+
+#[async_trait]
+impl AdmissionPlugin for DefaultResources {
+    fn name(&self) -> &str {
+        "default-resources"
+    }
+
+    async fn admit(&self, mut spec: ContainerSpec) -> Result<ContainerSpec, AdmissionError> {
+        if spec.resources.cpus == 0.0 {
+            spec.resources.cpus = self.cpus;
+        }
+        if spec.resources.memory_bytes == 0 {
+            spec.resources.memory_bytes = self.memory_bytes;
+        }
+        Ok(spec)
+    }
+}
+
+// Rejects containers without both CPU and memory requests, which are also their limits.
+pub struct RequireResources;
+
+#[async_trait]
+impl AdmissionPlugin for RequireResources {
+    fn name(&self) -> &str {
+        "require-resources"
+    }
+
+    async fn admit(&self, spec: ContainerSpec) -> Result<ContainerSpec, AdmissionError> {
+        let mut missing = Vec::new();
+        if spec.resources.cpus <= 0.0 {
+            missing.push("cpus");
+        }
+        if spec.resources.memory_bytes == 0 {
+            missing.push("memory_bytes");
+        }
+        if !missing.is_empty() {
+            return Err(denied(
+                self.name(),
+                &spec,
+                format!("resources must set {}", missing.join(" and ")),
+            ));
+        }
+        Ok(spec)
+    }
+}
+
+// Rejects images that are untagged or tagged `latest`, since what they run changes with every
+// push. Images pinned to a digest are always allowed.
+pub struct DisallowLatestTag;
+
+#[async_trait]
+impl AdmissionPlugin for DisallowLatestTag {
+    fn name(&self) -> &str {
+        "disallow-latest-tag"
+    }
+
+    async fn admit(&self, spec: ContainerSpec) -> Result<ContainerSpec, AdmissionError> {
+        let images =
+            std::iter::once(&spec.image).chain(spec.init_containers.iter().map(|init| &init.image));
+        for image in images {
+            if is_latest(image) {
+                return Err(denied(
+                    self.name(),
+                    &spec,
+                    format!("image {:?} must use a tag other than latest", image),
+                ));
+            }
+        }
+        Ok(spec)
+    }
+}
+
+fn is_latest(image: &str) -> bool {
+    if image.contains('@') {
+        return false;
+    }
+    // A colon after the last slash starts the tag; before it, it is a registry port.
+    let last_slash = image.rfind('/').map_or(0, |slash| slash + 1);
+    match image[last_slash..].split_once(':') {
+        Some((_, tag)) => tag == "latest",
+        None => true,
+    }
+}
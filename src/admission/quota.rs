@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{
+    entities::{
+        container::ContainerSpec,
+        resource_quota::{QuotaUsage, ResourceQuota, ResourceQuotaSpec},
+    },
+    store::state::StateStore,
+    watchers::container_status::ContainerStatusWatcher,
+};
+
+use super::{AdmissionError, AdmissionPlugin};
+
+pub(crate) const KIND: &str = "resourcequotas";
+
+// Caps what the containers of an app may request in total. Unlike the configured plugins it is
+// always part of the admission chain, and only acts on apps that have a quota.
+pub struct ResourceQuotas {
+    state: Arc<StateStore>,
+    status_watcher: Arc<ContainerStatusWatcher>,
+}
+
+impl ResourceQuotas {
+    pub fn new(state: Arc<StateStore>, status_watcher: Arc<ContainerStatusWatcher>) -> Self {
+        ResourceQuotas {
+            state,
+            status_watcher,
+        }
+    }
+
+    // Containers already over the new quota keep running; it only applies to later creates.
+    pub async fn create(&self, spec: ResourceQuotaSpec) -> Result<ResourceQuota, anyhow::Error> {
+        spec.validate()?;
+        let mut quota = ResourceQuota::new(spec);
+        self.state.put(KIND, quota.name(), &quota).await?;
+        quota.used = self.usage(quota.name(), None).await;
+        Ok(quota)
+    }
+
+    pub async fn get(&self, app: &str) -> Result<Option<ResourceQuota>, anyhow::Error> {
+        let mut quota: Option<ResourceQuota> = self.state.get(KIND, app).await?;
+        if let Some(quota) = &mut quota {
+            quota.used = self.usage(app, None).await;
+        }
+        Ok(quota)
+    }
+
+    pub async fn list(&self) -> Result<Vec<ResourceQuota>, anyhow::Error> {
+        let mut quotas: Vec<ResourceQuota> = self.state.list(KIND).await?;
+        quotas.sort_by(|a, b| a.name().cmp(b.name()));
+        for quota in quotas.iter_mut() {
+            quota.used = self.usage(&quota.spec.app, None).await;
+        }
+        Ok(quotas)
+    }
+
+    pub async fn delete(&self, app: &str) -> Result<bool, anyhow::Error> {
+        self.state.delete(KIND, app).await
+    }
+
+    // What the app's containers request, leaving out the container named `except`.
+    async fn usage(&self, app: &str, except: Option<&str>) -> QuotaUsage {
+        self.status_watcher
+            .list()
+            .await
+            .iter()
+            .filter(|container| container.app == app)
+            .filter(|container| except != Some(container.name.as_str()))
+            .fold(QuotaUsage::default(), |used, container| QuotaUsage {
+                cpus: used.cpus + container.spec.resources.cpus,
+                memory_bytes: used.memory_bytes + container.spec.resources.memory_bytes,
+                containers: used.containers + 1,
+            })
+    }
+}
+
+#[async_trait]
+impl AdmissionPlugin for ResourceQuotas {
+    fn name(&self) -> &str {
+        "resource-quota"
+    }
+
+    async fn admit(&self, spec: ContainerSpec) -> Result<ContainerSpec, AdmissionError> {
+        let app = spec.app_name();
+        let denied = |message: String| AdmissionError::Denied {
+            plugin: String::from(self.name()),
+            container: spec.name.clone(),
+            message,
+        };
+        let quota: Option<ResourceQuota> =
+            self.state
+                .get(KIND, app)
+                .await
+                .map_err(|source| AdmissionError::Failed {
+                    plugin: String::from(self.name()),
+                    container: spec.name.clone(),
+                    source,
+                })?;
+        let Some(quota) = quota else {
+            return Ok(spec);
+        };
+
+        // Without a request the container could use any amount of what the quota limits.
+        let mut missing = Vec::new();
+        if quota.spec.cpus.is_some() && spec.resources.cpus <= 0.0 {
+            missing.push("cpus");
+        }
+        if quota.spec.memory_bytes.is_some() && spec.resources.memory_bytes == 0 {
+            missing.push("memory_bytes");
+        }
+        if !missing.is_empty() {
+            return Err(denied(format!(
+                "resources must set {}, which the quota of app {} limits",
+                missing.join(" and "),
+                app
+            )));
+        }
+
+        // A container replacing one by the same name takes over its share.
+        let current = self.usage(app, Some(&spec.name)).await;
+        let used = QuotaUsage {
+            cpus: current.cpus + spec.resources.cpus,
+            memory_bytes: current.memory_bytes + spec.resources.memory_bytes,
+            containers: current.containers + 1,
+        };
+        if let Some(exceeded) = quota.spec.exceeded(&used) {
+            return Err(denied(format!(
+                "exceeds the quota of app {}: would use {}",
+                app, exceeded
+            )));
+        }
+        Ok(spec)
+    }
+}
@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::AdmissionPluginConfig, entities::container::ContainerSpec};
+
+use super::{AdmissionError, AdmissionPlugin};
+
+#[derive(Serialize)]
+struct Review<'a> {
+    spec: &'a ContainerSpec,
+}
+
+// Omitting `spec` admits the container unchanged.
+#[derive(Deserialize)]
+struct Response {
+    allowed: bool,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    spec: Option<ContainerSpec>,
+}
+
+// Hands the spec to an external service, which can return a changed spec or reject it.
+pub struct Webhook {
+    url: String,
+    ignore_failures: bool,
+    http: reqwest::Client,
+}
+
+impl Webhook {
+    pub fn new(config: &AdmissionPluginConfig) -> Result<Self, anyhow::Error> {
+        let url = config
+            .url
+            .clone()
+            .ok_or_else(|| anyhow!("the webhook admission plugin needs a url"))?;
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()?;
+
+        Ok(Webhook {
+            url,
+            ignore_failures: config.ignore_failures,
+            http,
+        })
+    }
+
+    async fn review(&self, spec: &ContainerSpec) -> Result<Response, anyhow::Error> {
+        let response = self
+            .http
+            .post(&self.url)
+            .json(&Review { spec })
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+}
+
+#[async_trait]
+impl AdmissionPlugin for Webhook {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    async fn admit(&self, spec: ContainerSpec) -> Result<ContainerSpec, AdmissionError> {
+        let response = match self.review(&spec).await {
+            Ok(response) => response,
+            Err(error) if self.ignore_failures => {
+                println!(
+                    "Admission webhook {} failed, admitting container {} anyway: {}",
+                    self.url, spec.name, error
+                );
+                return Ok(spec);
+            }
+            Err(source) => {
+                return Err(AdmissionError::Failed {
+                    plugin: self.url.clone(),
+                    container: spec.name,
+                    source,
+                })
+            }
+        };
+
+        if !response.allowed {
+            return Err(AdmissionError::Denied {
+                plugin: self.url.clone(),
+                container: spec.name,
+                message: response
+                    .message
+                    .unwrap_or_else(|| String::from("no reason given")),
+            });
+        }
+        // The container keeps its name whatever the webhook returns.
+        Ok(match response.spec {
+            Some(mutated) => ContainerSpec {
+                name: spec.name,
+                ..mutated
+            },
+            None => spec,
+        })
+    }
+}
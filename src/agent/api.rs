@@ -0,0 +1,256 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    api::{auth::bearer, ApiError},
+    auth::AuthError,
+    entities::{container::Container, resource_usage::ResourceUsage},
+    runtime::{
+        remote::{CreateRequest, ExecRequest, KillRequest, PullRequest, StopRequest},
+        ContainerRuntime, ExecOutput, Image, LogLine,
+    },
+    store::config_maps::write_files,
+};
+
+#[derive(Clone)]
+pub struct AgentState {
+    pub runtime: Arc<dyn ContainerRuntime + Send + Sync>,
+    pub mounts_dir: PathBuf,
+    // What the control plane has to send as its bearer token; the agent hands it over when it
+    // registers.
+    pub token: Arc<str>,
+}
+
+#[derive(Deserialize)]
+pub struct LogsQuery {
+    tail: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct LogLinesQuery {
+    since: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct RemoveVolumeQuery {
+    #[serde(default)]
+    force: bool,
+}
+
+pub fn router(state: AgentState) -> Router {
+    Router::new()
+        .route("/runtime/pull", post(pull))
+        .route("/runtime/images", get(list_images))
+        .route("/runtime/images/pull", post(pull_image))
+        .route("/runtime/images/usage", get(image_disk_usage))
+        .route("/runtime/images/remove", post(remove_image))
+        .route("/runtime/containers", get(list).post(create))
+        .route("/runtime/containers/{id}", get(inspect).delete(remove))
+        .route("/runtime/containers/{id}/start", post(start))
+        .route("/runtime/containers/{id}/stop", post(stop))
+        .route("/runtime/containers/{id}/kill", post(kill))
+        .route("/runtime/containers/{id}/exec", post(exec))
+        .route("/runtime/containers/{id}/restart", post(restart))
+        .route("/runtime/containers/{id}/wait", post(wait))
+        .route("/runtime/containers/{id}/logs", get(logs))
+        .route("/runtime/containers/{id}/log-lines", get(log_lines))
+        .route("/runtime/stats", post(stats))
+        .route(
+            "/runtime/volumes/{name}",
+            post(create_volume).delete(remove_volume),
+        )
+        .layer(middleware::from_fn_with_state(state.clone(), authenticate))
+        .with_state(state)
+}
+
+// Whoever can reach the agent could otherwise run anything on its host, so every call needs the
+// token it registered with.
+async fn authenticate(State(state): State<AgentState>, request: Request, next: Next) -> Response {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    let error = match bearer(authorization) {
+        None => AuthError::Unauthenticated,
+        // Comparing digests takes as long wherever the tokens differ.
+        Some(token) if Sha256::digest(token) == Sha256::digest(&*state.token) => {
+            return next.run(request).await
+        }
+        Some(_) => AuthError::InvalidToken,
+    };
+    ApiError::from(anyhow::Error::from(error)).into_response()
+}
+
+async fn pull(
+    State(state): State<AgentState>,
+    Json(request): Json<PullRequest>,
+) -> Result<Json<bool>, ApiError> {
+    Ok(Json(state.runtime.pull_if_missing(&request.image).await?))
+}
+
+async fn pull_image(
+    State(state): State<AgentState>,
+    Json(request): Json<PullRequest>,
+) -> Result<Json<String>, ApiError> {
+    Ok(Json(state.runtime.pull(&request.image).await?))
+}
+
+async fn create(
+    State(state): State<AgentState>,
+    Json(request): Json<CreateRequest>,
+) -> Result<Json<Container>, ApiError> {
+    let CreateRequest { spec, mut options } = request;
+
+    // Config map files were written on the control plane's host; this node needs its own copy.
+    for mount in options
+        .mounts
+        .iter_mut()
+        .filter(|mount| !mount.files.is_empty())
+    {
+        let name = std::path::Path::new(&mount.source)
+            .file_name()
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!("invalid mount source {}", mount.source))
+            })?;
+        let dir = state.mounts_dir.join(name);
+        write_files(&dir, &mount.files).await?;
+        mount.source = dir.display().to_string();
+    }
+
+    Ok(Json(state.runtime.create(&spec, &options).await?))
+}
+
+async fn list(State(state): State<AgentState>) -> Result<Json<Vec<Container>>, ApiError> {
+    Ok(Json(state.runtime.list_managed().await?))
+}
+
+async fn inspect(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+) -> Result<Json<Container>, ApiError> {
+    state
+        .runtime
+        .inspect(&id)
+        .await
+        .map(Json)
+        .map_err(|error| ApiError::NotFound(error.to_string()))
+}
+
+async fn start(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+) -> Result<Json<()>, ApiError> {
+    Ok(Json(state.runtime.start(&id).await?))
+}
+
+async fn stop(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+    Json(request): Json<StopRequest>,
+) -> Result<Json<()>, ApiError> {
+    let grace_period = request.grace_period_seconds.map(Duration::from_secs);
+    Ok(Json(state.runtime.stop(&id, grace_period).await?))
+}
+
+async fn kill(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+    Json(request): Json<KillRequest>,
+) -> Result<Json<()>, ApiError> {
+    Ok(Json(state.runtime.kill(&id, &request.signal).await?))
+}
+
+async fn exec(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+    Json(request): Json<ExecRequest>,
+) -> Result<Json<ExecOutput>, ApiError> {
+    Ok(Json(state.runtime.exec(&id, &request.command).await?))
+}
+
+async fn restart(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+) -> Result<Json<()>, ApiError> {
+    Ok(Json(state.runtime.restart(&id).await?))
+}
+
+async fn wait(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+) -> Result<Json<i64>, ApiError> {
+    Ok(Json(state.runtime.wait(&id).await?))
+}
+
+async fn remove(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.runtime.remove(&id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn stats(
+    State(state): State<AgentState>,
+    Json(ids): Json<Vec<String>>,
+) -> Result<Json<Vec<ResourceUsage>>, ApiError> {
+    Ok(Json(state.runtime.stats(&ids).await?))
+}
+
+async fn logs(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+    Query(query): Query<LogsQuery>,
+) -> Result<String, ApiError> {
+    Ok(state.runtime.logs(&id, query.tail).await?)
+}
+
+async fn log_lines(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+    Query(query): Query<LogLinesQuery>,
+) -> Result<Json<Vec<LogLine>>, ApiError> {
+    Ok(Json(state.runtime.logs_since(&id, query.since).await?))
+}
+
+async fn create_volume(
+    State(state): State<AgentState>,
+    Path(name): Path<String>,
+) -> Result<Json<()>, ApiError> {
+    Ok(Json(state.runtime.create_volume(&name).await?))
+}
+
+async fn remove_volume(
+    State(state): State<AgentState>,
+    Path(name): Path<String>,
+    Query(query): Query<RemoveVolumeQuery>,
+) -> Result<StatusCode, ApiError> {
+    state.runtime.remove_volume(&name, query.force).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_images(State(state): State<AgentState>) -> Result<Json<Vec<Image>>, ApiError> {
+    Ok(Json(state.runtime.list_images().await?))
+}
+
+async fn image_disk_usage(State(state): State<AgentState>) -> Result<Json<u64>, ApiError> {
+    Ok(Json(state.runtime.image_disk_usage().await?))
+}
+
+async fn remove_image(
+    State(state): State<AgentState>,
+    Json(image): Json<Image>,
+) -> Result<Json<()>, ApiError> {
+    Ok(Json(state.runtime.remove_image(&image).await?))
+}
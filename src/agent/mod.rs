@@ -0,0 +1,116 @@
+pub mod api;
+
+use std::{sync::Arc, time::Duration};
+
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cli::client::ApiClient,
+    config::Config,
+    entities::node::{Node, NodeStatus},
+    runtime::{
+        self,
+        limit::{LimitedRuntime, OperationLimit},
+        retry::RetryingRuntime,
+    },
+};
+
+use self::api::AgentState;
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:6444";
+
+// Runs containers on this host for the control plane at `server`.
+pub async fn run(
+    client: ApiClient,
+    name: String,
+    listen: &str,
+    advertise: Option<String>,
+    config: Config,
+) -> Result<(), anyhow::Error> {
+    // A new token every start; registering hands it to the control plane again.
+    let mut random = [0u8; 32];
+    OsRng.fill_bytes(&mut random);
+    let token = URL_SAFE_NO_PAD.encode(random);
+    let mounts_dir = config.config_maps_dir();
+    tokio::fs::create_dir_all(&mounts_dir).await?;
+    let (engine, capacity) = runtime::local(&config).await?;
+    let state = AgentState {
+        runtime: Arc::new(RetryingRuntime::new(
+            Arc::new(LimitedRuntime::new(
+                engine,
+                Arc::new(OperationLimit::new(
+                    config.runtime.max_concurrent_operations,
+                )),
+            )),
+            &config.retry,
+        )?),
+        mounts_dir: mounts_dir.canonicalize()?,
+        token: Arc::from(token.as_str()),
+    };
+
+    let listener = TcpListener::bind(listen).await?;
+    let address = match advertise {
+        Some(address) => address,
+        None => listener.local_addr()?.to_string(),
+    };
+    println!("Agent {} listening on {}", name, listener.local_addr()?);
+
+    let shutdown = CancellationToken::new();
+    let register_shutdown = shutdown.clone();
+    let node = Node {
+        name,
+        address: Some(address),
+        capacity,
+        labels: config.nodes.labels.clone(),
+        status: NodeStatus::Ready,
+        registered: String::new(),
+        last_seen: String::new(),
+        token: Some(token),
+    };
+    let heartbeat_interval = Duration::from_secs(config.nodes.heartbeat_interval_seconds);
+    // The registration doubles as the heartbeat, so a restarted control plane also learns about
+    // the node again.
+    let heartbeat = tokio::spawn(async move {
+        let mut registered = false;
+        loop {
+            match client.register_node(&node).await {
+                Ok(_) if !registered => {
+                    println!(
+                        "Registered with {} as node {}",
+                        client.base_url(),
+                        node.name
+                    );
+                    registered = true;
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    println!("Failed to register with {}: {}", client.base_url(), error);
+                    registered = false;
+                }
+            }
+
+            tokio::select! {
+                _ = register_shutdown.cancelled() => return,
+                _ = tokio::time::sleep(heartbeat_interval) => {}
+            }
+        }
+    });
+
+    let server_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        crate::shutdown_signal().await;
+        println!("Shutting down");
+        server_shutdown.cancel();
+    });
+
+    axum::serve(listener, api::router(state))
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+        .await?;
+    shutdown.cancel();
+    let _ = heartbeat.await;
+    println!("Agent stopped");
+    Ok(())
+}
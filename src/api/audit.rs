@@ -0,0 +1,121 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    body::{self, Body},
+    extract::{ConnectInfo, Query, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::Response,
+    Json,
+};
+
+use crate::{
+    auth::Identity,
+    entities::audit::{AuditEntry, AuditQuery, USER_HEADER},
+    store::audit::AuditLog,
+};
+
+use super::{ApiError, ApiState};
+
+// Error bodies are short messages; anything longer is cut off in the log.
+const MAX_ERROR_LEN: usize = 1024;
+
+pub async fn list(
+    State(state): State<ApiState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditEntry>>, ApiError> {
+    Ok(Json(state.audit.list(&query).await?))
+}
+
+// Calls that don't name their user, e.g. from agents or plain curl, are recorded as unknown.
+pub(super) fn user(header: Option<&str>) -> String {
+    header
+        .filter(|user| !user.is_empty())
+        .map_or_else(|| String::from("unknown"), String::from)
+}
+
+// What a call does, by its method and path: `POST /containers` creates a container,
+// `POST /containers/web-1/restart` restarts one and `GET /watch/containers` watches them.
+pub(super) fn operation(method: &Method, path: &str) -> Option<(String, String, Option<String>)> {
+    let mut segments = path.trim_matches('/').split('/').map(String::from);
+    let resource = segments.next().filter(|resource| !resource.is_empty())?;
+    if resource == "watch" {
+        return Some((String::from("watch"), segments.next()?, None));
+    }
+    let name = segments.next();
+    let action = segments.next();
+
+    let operation = match *method {
+        Method::GET | Method::HEAD if name.is_some() => String::from("get"),
+        Method::GET | Method::HEAD => String::from("list"),
+        Method::POST => action.unwrap_or_else(|| String::from("create")),
+        Method::PUT | Method::PATCH => String::from("update"),
+        Method::DELETE => String::from("delete"),
+        _ => return None,
+    };
+    Some((operation, resource, name))
+}
+
+// Records every mutating call to the audit log once it has been answered, and every call turned
+// away for want of a valid token or permission.
+pub async fn record(State(audit): State<Arc<AuditLog>>, request: Request, next: Next) -> Response {
+    let Some((operation, resource, name)) = operation(request.method(), request.uri().path())
+    else {
+        return next.run(request).await;
+    };
+    let reading = matches!(*request.method(), Method::GET | Method::HEAD);
+    let user = user(
+        request
+            .headers()
+            .get(USER_HEADER)
+            .and_then(|user| user.to_str().ok()),
+    );
+    let source = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map_or_else(|| String::from("unknown"), |info| info.0.to_string());
+
+    let response = next.run(request).await;
+    let status = response.status();
+    let denied = status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN;
+    if reading && !denied {
+        return response;
+    }
+    // Authenticated calls are recorded under their token rather than the user they claim.
+    let user = response
+        .extensions()
+        .get::<Identity>()
+        .map_or(user, |identity| identity.user.clone());
+    let (response, error) = if status.is_client_error() || status.is_server_error() {
+        let (parts, body) = response.into_parts();
+        let bytes = body::to_bytes(body, usize::MAX).await.unwrap_or_default();
+        let mut error = String::from_utf8_lossy(&bytes).trim().to_string();
+        if error.len() > MAX_ERROR_LEN {
+            let mut end = MAX_ERROR_LEN;
+            while !error.is_char_boundary(end) {
+                end -= 1;
+            }
+            error.truncate(end);
+        }
+        if error.is_empty() {
+            error = status.to_string();
+        }
+        (Response::from_parts(parts, Body::from(bytes)), Some(error))
+    } else {
+        (response, None)
+    };
+
+    audit
+        .record(AuditEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            user,
+            source,
+            operation,
+            resource,
+            name,
+            status: status.as_u16(),
+            error,
+        })
+        .await;
+    response
+}
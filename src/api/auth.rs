@@ -0,0 +1,193 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{FromRequest, Path, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::{
+    admission::quota,
+    auth::{self, AuthError, Authenticator, Identity},
+    controllers::{autoscaler, cron_job, job, rollout},
+    entities::{
+        container,
+        token::{IssuedToken, Role, Token, TokenSpec},
+    },
+    store::state::validate_name,
+};
+
+use super::{audit::operation, ApiError, ApiState};
+
+// The secret of `Bearer <secret>`.
+pub(crate) fn bearer(authorization: Option<&str>) -> Option<&str> {
+    let (scheme, secret) = authorization?.split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then(|| secret.trim())
+        .filter(|secret| !secret.is_empty())
+}
+
+// Resources named after the app they belong to, as in `/rollouts/<app>`.
+const APP_RESOURCES: [&str; 4] = [autoscaler::KIND, rollout::KIND, quota::KIND, "endpoints"];
+
+// The app a call is about when its path doesn't name it, found by `resolve_app`.
+#[derive(Clone, Debug)]
+pub struct RequestApp(pub String);
+
+// Finds the app of the container, job or cron job a call names, of the one it creates, or of the
+// containers it watches, for `authorize` to check rules limited to some apps against.
+pub async fn resolve_app(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let Some((operation, resource, name)) = operation(request.method(), request.uri().path())
+    else {
+        return next.run(request).await;
+    };
+    let app = match name {
+        Some(name) if resource == container::KIND => state
+            .cluster
+            .status_watcher
+            .find(&name)
+            .await
+            .map(|container| container.app),
+        Some(name) if resource == job::KIND => state
+            .jobs
+            .get(&name)
+            .await
+            .ok()
+            .flatten()
+            .map(|job| job.spec.template.app_name().to_string()),
+        Some(name) if resource == cron_job::KIND => state
+            .cron_jobs
+            .get(&name)
+            .await
+            .ok()
+            .flatten()
+            .map(|cron_job| cron_job.spec.job.template.app_name().to_string()),
+        Some(_) => None,
+        None if operation == "create" => {
+            // Read like the handlers' extractors do, so the body limit holds before the caller
+            // is authenticated.
+            let (parts, body) = request.into_parts();
+            let bytes =
+                match Bytes::from_request(Request::from_parts(parts.clone(), body), &state).await {
+                    Ok(bytes) => bytes,
+                    Err(rejection) => return rejection.into_response(),
+                };
+            let app = created_app(&resource, &bytes);
+            let mut request = Request::from_parts(parts, Body::from(bytes));
+            if let Some(app) = app {
+                request.extensions_mut().insert(RequestApp(app));
+            }
+            return next.run(request).await;
+        }
+        // Watches can be limited to one app.
+        None if operation == "watch" => {
+            Query::<HashMap<String, String>>::try_from_uri(request.uri())
+                .ok()
+                .and_then(|Query(mut query)| query.remove("app"))
+        }
+        None => None,
+    };
+
+    let mut request = request;
+    if let Some(app) = app {
+        request.extensions_mut().insert(RequestApp(app));
+    }
+    next.run(request).await
+}
+
+// Containers, jobs and cron jobs belong to their `app`, or the app named after them; the objects
+// of app resources to the app they name.
+fn created_app(resource: &str, body: &[u8]) -> Option<String> {
+    let spec: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let app = spec.get("app").and_then(|app| app.as_str());
+    let app = match resource {
+        container::KIND | job::KIND | cron_job::KIND => {
+            app.or_else(|| spec.get("name").and_then(|name| name.as_str()))
+        }
+        resource if APP_RESOURCES.contains(&resource) => app,
+        _ => None,
+    };
+    app.map(String::from)
+}
+
+// Lets a call through only if its token's roles allow it. The identity goes on the response so
+// the audit log, which wraps this, can record who made the call.
+pub async fn authorize(
+    State(auth): State<Arc<Authenticator>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some((operation, resource, name)) = operation(request.method(), request.uri().path())
+    else {
+        return next.run(request).await;
+    };
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    let app = match request.extensions().get::<RequestApp>() {
+        Some(RequestApp(app)) => Some(app.clone()),
+        None => name.filter(|_| APP_RESOURCES.contains(&resource.as_str())),
+    };
+
+    match auth
+        .authorize(
+            bearer(authorization),
+            auth::verb(&operation),
+            &resource,
+            app.as_deref(),
+        )
+        .await
+    {
+        Ok(identity) => {
+            let mut response = next.run(request).await;
+            if let Some(identity) = identity {
+                response.extensions_mut().insert(identity);
+            }
+            response
+        }
+        Err(error) => {
+            let identity = match error.downcast_ref() {
+                Some(AuthError::Forbidden { user, .. }) => Some(Identity { user: user.clone() }),
+                _ => None,
+            };
+            let mut response = ApiError::from(error).into_response();
+            if let Some(identity) = identity {
+                response.extensions_mut().insert(identity);
+            }
+            response
+        }
+    }
+}
+
+pub async fn list_tokens(State(state): State<ApiState>) -> Result<Json<Vec<Token>>, ApiError> {
+    Ok(Json(state.auth.list_tokens().await?))
+}
+
+pub async fn create_token(
+    State(state): State<ApiState>,
+    Json(spec): Json<TokenSpec>,
+) -> Result<(StatusCode, Json<IssuedToken>), ApiError> {
+    validate_name(&spec.name).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+
+    let issued = state.auth.create_token(spec).await?;
+    Ok((StatusCode::CREATED, Json(issued)))
+}
+
+pub async fn delete_token(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !state.auth.delete_token(&name).await? {
+        return Err(ApiError::NotFound(format!("token {} not found", name)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_roles(State(state): State<ApiState>) -> Json<Vec<Role>> {
+    Json(state.auth.roles())
+}
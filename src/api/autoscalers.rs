@@ -0,0 +1,57 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    entities::autoscaler::{Autoscaler, AutoscalerSpec},
+    store::state::validate_name,
+};
+
+use super::{ApiError, ApiState};
+
+pub async fn list(State(state): State<ApiState>) -> Result<Json<Vec<Autoscaler>>, ApiError> {
+    Ok(Json(state.autoscalers.list().await?))
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    Path(app): Path<String>,
+) -> Result<Json<Autoscaler>, ApiError> {
+    state
+        .autoscalers
+        .get(&app)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("autoscaler {} not found", app)))
+}
+
+pub async fn create(
+    State(state): State<ApiState>,
+    Json(spec): Json<AutoscalerSpec>,
+) -> Result<(StatusCode, Json<Autoscaler>), ApiError> {
+    validate_name(&spec.app).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+    spec.validate()
+        .map_err(|error| ApiError::BadRequest(error.to_string()))?;
+
+    if state.autoscalers.get(&spec.app).await?.is_some() {
+        return Err(ApiError::Conflict(format!(
+            "autoscaler {} already exists",
+            spec.app
+        )));
+    }
+
+    let autoscaler = state.autoscalers.create(spec).await?;
+    Ok((StatusCode::CREATED, Json(autoscaler)))
+}
+
+pub async fn delete(
+    State(state): State<ApiState>,
+    Path(app): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !state.autoscalers.delete(&app).await? {
+        return Err(ApiError::NotFound(format!("autoscaler {} not found", app)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
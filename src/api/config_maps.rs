@@ -0,0 +1,82 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{entities::config_map::ConfigMap, store::state::validate_name};
+
+use super::{ApiError, ApiState};
+
+pub async fn list(State(state): State<ApiState>) -> Result<Json<Vec<ConfigMap>>, ApiError> {
+    Ok(Json(state.cluster.config_maps.list().await?))
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<ConfigMap>, ApiError> {
+    state
+        .cluster
+        .config_maps
+        .get(&name)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("config map {} not found", name)))
+}
+
+pub async fn create(
+    State(state): State<ApiState>,
+    Json(config_map): Json<ConfigMap>,
+) -> Result<(StatusCode, Json<ConfigMap>), ApiError> {
+    validate_name(&config_map.name).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+    for key in config_map.data.keys() {
+        validate_name(key).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+    }
+
+    let status = match state.cluster.config_maps.put(&config_map).await? {
+        None => StatusCode::CREATED,
+        Some(previous) if previous.data != config_map.data => {
+            config_map.restart_consumers(&state.cluster).await?;
+            StatusCode::OK
+        }
+        Some(_) => StatusCode::OK,
+    };
+
+    Ok((status, Json(config_map)))
+}
+
+pub async fn delete(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let consumers: Vec<String> = state
+        .cluster
+        .status_watcher
+        .list()
+        .await
+        .into_iter()
+        .filter(|container| {
+            container
+                .spec
+                .config_maps
+                .iter()
+                .any(|mount| mount.name == name)
+        })
+        .map(|container| container.name)
+        .collect();
+
+    // Removing the files would leave dangling bind mounts behind.
+    if !consumers.is_empty() {
+        return Err(ApiError::Conflict(format!(
+            "config map {} is mounted by {}",
+            name,
+            consumers.join(", ")
+        )));
+    }
+
+    if !state.cluster.config_maps.delete(&name).await? {
+        return Err(ApiError::NotFound(format!("config map {} not found", name)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,186 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{
+        container::{Container, ContainerSpec},
+        resource_usage::ResourceUsage,
+    },
+    events::{
+        event::{Event, EventReason},
+        recorder::EventRecorder,
+    },
+    watchers::container_status::StatusTransition,
+};
+
+use super::{ApiError, ApiState};
+
+#[derive(Serialize, Deserialize)]
+pub struct Description {
+    pub container: Container,
+    pub events: Vec<Event>,
+    #[serde(default)]
+    pub history: Vec<StatusTransition>,
+}
+
+#[derive(Deserialize)]
+pub struct LogsQuery {
+    tail: Option<usize>,
+    // Read the persisted logs, which also hold the output of earlier containers by that name.
+    #[serde(default)]
+    persisted: bool,
+}
+
+pub async fn list(State(state): State<ApiState>) -> Json<Vec<Container>> {
+    Json(state.cluster.status_watcher.list().await)
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> Result<Json<Container>, ApiError> {
+    Ok(Json(find(&state, &id).await?))
+}
+
+pub async fn describe(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> Result<Json<Description>, ApiError> {
+    let container = find(&state, &id).await?;
+
+    Ok(Json(Description {
+        events: state.cluster.events.list(Some(&container.name)).await,
+        history: state.cluster.status_watcher.history(&container.id).await,
+        container,
+    }))
+}
+
+pub async fn history(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<StatusTransition>>, ApiError> {
+    let container = find(&state, &id).await?;
+    Ok(Json(
+        state.cluster.status_watcher.history(&container.id).await,
+    ))
+}
+
+pub async fn create(
+    State(state): State<ApiState>,
+    Json(spec): Json<ContainerSpec>,
+) -> Result<(StatusCode, Json<Container>), ApiError> {
+    spec.validate()
+        .map_err(|error| ApiError::BadRequest(error.to_string()))?;
+    Container::check_dependencies(&spec, &state.cluster)
+        .await
+        .map_err(|error| ApiError::BadRequest(error.to_string()))?;
+    let container = Container::new(&spec, &state.cluster).await?;
+    Ok((StatusCode::CREATED, Json(container)))
+}
+
+pub async fn delete(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let container = find(&state, &id).await?;
+
+    record_killed(&state.cluster.events, &container).await;
+    container.delete(&state.cluster).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn restart(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let container = find(&state, &id).await?;
+
+    state.cluster.status_watcher.release(&container.id).await;
+    state.cluster.runtime.restart(&container.id).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub async fn stop(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let container = find(&state, &id).await?;
+
+    record_killed(&state.cluster.events, &container).await;
+    state
+        .cluster
+        .status_watcher
+        .hold_stopped(&container.id)
+        .await;
+    state.cluster.runtime.stop(&container.id, None).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub async fn stats(State(state): State<ApiState>) -> Json<Vec<ResourceUsage>> {
+    Json(state.resource_usage_watcher.list().await)
+}
+
+pub async fn events(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Event>>, ApiError> {
+    let container = find(&state, &id).await?;
+    Ok(Json(state.cluster.events.list(Some(&container.name)).await))
+}
+
+pub async fn logs(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+    Query(query): Query<LogsQuery>,
+) -> Result<String, ApiError> {
+    let container = state.cluster.status_watcher.find(&id).await;
+    if let (Some(container), false) = (&container, query.persisted) {
+        return Ok(state
+            .cluster
+            .runtime
+            .logs(&container.id, query.tail)
+            .await?);
+    }
+
+    let Some(logs) = &state.cluster.logs else {
+        return Err(ApiError::NotFound(format!(
+            "container {} not found and logs are not persisted",
+            id
+        )));
+    };
+    // Containers that are gone are looked up by name.
+    let name = match container {
+        Some(container) => {
+            logs.flush(&container).await;
+            container.name
+        }
+        None => id,
+    };
+    logs.store()
+        .read(&name, query.tail)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("no logs found for container {}", name)))
+}
+
+async fn record_killed(events: &EventRecorder, container: &Container) {
+    events
+        .record_container(
+            &container.name,
+            EventReason::Killed,
+            format!("Stopping container {}", container.name),
+        )
+        .await
+}
+
+async fn find(state: &ApiState, id: &str) -> Result<Container, ApiError> {
+    state
+        .cluster
+        .status_watcher
+        .find(id)
+        .await
+        .ok_or_else(|| ApiError::NotFound(format!("container {} not found", id)))
+}
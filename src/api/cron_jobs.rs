@@ -0,0 +1,53 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    controllers::schedule::Schedule,
+    entities::cron_job::{CronJob, CronJobSpec},
+    store::state::validate_name,
+};
+
+use super::{ApiError, ApiState};
+
+pub async fn list(State(state): State<ApiState>) -> Result<Json<Vec<CronJob>>, ApiError> {
+    Ok(Json(state.cron_jobs.list().await?))
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<CronJob>, ApiError> {
+    state
+        .cron_jobs
+        .get(&name)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("cron job {} not found", name)))
+}
+
+pub async fn create(
+    State(state): State<ApiState>,
+    Json(spec): Json<CronJobSpec>,
+) -> Result<(StatusCode, Json<CronJob>), ApiError> {
+    let name = spec.job.template.name.clone();
+    validate_name(&name).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+    spec.schedule
+        .parse::<Schedule>()
+        .map_err(|error| ApiError::BadRequest(error.to_string()))?;
+
+    let cron_job = state.cron_jobs.create(spec).await?;
+    Ok((StatusCode::CREATED, Json(cron_job)))
+}
+
+pub async fn delete(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !state.cron_jobs.delete(&name).await? {
+        return Err(ApiError::NotFound(format!("cron job {} not found", name)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
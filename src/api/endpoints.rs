@@ -0,0 +1,25 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::{controllers::rollout, entities::endpoints::Endpoints};
+
+use super::{ApiError, ApiState};
+
+pub async fn list(State(state): State<ApiState>) -> Result<Json<Vec<Endpoints>>, ApiError> {
+    let containers = rollout::serving(&state.cluster).await?;
+    Ok(Json(Endpoints::from_containers(&containers)))
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    Path(app): Path<String>,
+) -> Result<Json<Endpoints>, ApiError> {
+    let containers = rollout::serving(&state.cluster).await?;
+    Endpoints::from_containers(&containers)
+        .into_iter()
+        .find(|endpoints| endpoints.app == app)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("app {} not found", app)))
+}
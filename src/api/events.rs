@@ -0,0 +1,21 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::events::event::Event;
+
+use super::ApiState;
+
+#[derive(Deserialize)]
+pub struct EventsQuery {
+    name: Option<String>,
+}
+
+pub async fn list(
+    State(state): State<ApiState>,
+    Query(query): Query<EventsQuery>,
+) -> Json<Vec<Event>> {
+    Json(state.cluster.events.list(query.name.as_deref()).await)
+}
@@ -0,0 +1,661 @@
+use std::pin::Pin;
+
+use tokio_stream::{Stream, StreamExt};
+use tonic::{transport::Server, Request, Response, Status};
+
+use crate::{
+    admission::AdmissionError,
+    auth::{self, AuthError},
+    entities::{
+        audit::{AuditEntry, USER_HEADER},
+        config_map::ConfigMapMount,
+        container::{
+            self, AffinityTerm, Container, Dependency, DependencyCondition, InitContainer, Probe,
+            RestartPolicy,
+        },
+        labels::{LabelExpression, LabelSelector, Operator},
+        lifecycle::{HookFailurePolicy, HttpHook, Lifecycle, LifecycleHook},
+        volume::VolumeClaim,
+    },
+    events::event::EventReason,
+    watchers::container_status::{WatchEvent, WatchEventType},
+};
+
+use super::{audit, auth::bearer, watch::container_events, ApiState};
+
+pub mod proto {
+    tonic::include_proto!("nic8s.v1");
+}
+
+use proto::control_plane_server::{ControlPlane, ControlPlaneServer};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:6444";
+
+pub struct ControlPlaneService {
+    state: ApiState,
+}
+
+// Who made a call and from where, for the audit log.
+struct Caller {
+    user: String,
+    source: String,
+}
+
+impl Caller {
+    fn of<T>(request: &Request<T>) -> Self {
+        Caller {
+            user: audit::user(
+                request
+                    .metadata()
+                    .get(USER_HEADER)
+                    .and_then(|user| user.to_str().ok()),
+            ),
+            source: request
+                .remote_addr()
+                .map_or_else(|| String::from("unknown"), |addr| addr.to_string()),
+        }
+    }
+}
+
+impl ControlPlaneService {
+    // Checks the call's bearer token, recording calls turned away in the audit log.
+    async fn authorize<T>(
+        &self,
+        request: &Request<T>,
+        operation: &str,
+        resource: &str,
+        name: Option<String>,
+        app: Option<&str>,
+    ) -> Result<Caller, Status> {
+        let mut caller = Caller::of(request);
+        let authorization = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok());
+        let error = match self
+            .state
+            .auth
+            .authorize(bearer(authorization), auth::verb(operation), resource, app)
+            .await
+        {
+            Ok(identity) => {
+                if let Some(identity) = identity {
+                    caller.user = identity.user;
+                }
+                return Ok(caller);
+            }
+            Err(error) => error,
+        };
+
+        let status = match error.downcast_ref() {
+            Some(AuthError::Unauthenticated | AuthError::InvalidToken) => {
+                Status::unauthenticated(error.to_string())
+            }
+            Some(AuthError::Forbidden { user, .. }) => {
+                caller.user = user.clone();
+                Status::permission_denied(error.to_string())
+            }
+            None => Status::internal(error.to_string()),
+        };
+        let denied = Err(status);
+        self.audit(caller, operation, resource, name, &denied).await;
+        denied
+    }
+
+    async fn audit<T>(
+        &self,
+        caller: Caller,
+        operation: &str,
+        resource: &str,
+        name: Option<String>,
+        result: &Result<T, Status>,
+    ) {
+        let (status, error) = match result {
+            Ok(_) => (tonic::Code::Ok as u16, None),
+            Err(status) => (status.code() as u16, Some(status.message().to_string())),
+        };
+        self.state
+            .audit
+            .record(AuditEntry {
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                user: caller.user,
+                source: caller.source,
+                operation: String::from(operation),
+                resource: String::from(resource),
+                name,
+                status,
+                error,
+            })
+            .await;
+    }
+
+    async fn create(
+        &self,
+        spec: Option<proto::ContainerSpec>,
+    ) -> Result<Response<proto::CreateContainerResponse>, Status> {
+        let spec: container::ContainerSpec = spec
+            .ok_or_else(|| Status::invalid_argument("spec is required"))?
+            .into();
+        spec.validate()
+            .map_err(|error| Status::invalid_argument(error.to_string()))?;
+        Container::check_dependencies(&spec, &self.state.cluster)
+            .await
+            .map_err(|error| Status::invalid_argument(error.to_string()))?;
+
+        let container = Container::new(&spec, &self.state.cluster)
+            .await
+            .map_err(|error| match error.downcast_ref() {
+                Some(AdmissionError::Denied { .. }) => Status::permission_denied(error.to_string()),
+                _ => Status::internal(error.to_string()),
+            })?;
+
+        Ok(Response::new(proto::CreateContainerResponse {
+            container: Some(container.into()),
+        }))
+    }
+
+    async fn delete_container(&self, id: &str) -> Result<Response<proto::DeleteResponse>, Status> {
+        let container = self
+            .state
+            .cluster
+            .status_watcher
+            .find(id)
+            .await
+            .ok_or_else(|| Status::not_found(format!("container {} not found", id)))?;
+
+        self.state
+            .cluster
+            .events
+            .record_container(
+                &container.name,
+                EventReason::Killed,
+                format!("Stopping container {}", container.name),
+            )
+            .await;
+        container
+            .delete(&self.state.cluster)
+            .await
+            .map_err(|error| Status::internal(error.to_string()))?;
+
+        Ok(Response::new(proto::DeleteResponse {}))
+    }
+}
+
+impl From<Container> for proto::Container {
+    fn from(container: Container) -> Self {
+        let status = match container.get_status() {
+            container::ContainerStatus::Created => proto::ContainerStatus::Created,
+            container::ContainerStatus::Running => proto::ContainerStatus::Running,
+            container::ContainerStatus::Restarting => proto::ContainerStatus::Restarting,
+            container::ContainerStatus::Exited => proto::ContainerStatus::Exited,
+            container::ContainerStatus::Paused => proto::ContainerStatus::Paused,
+            container::ContainerStatus::Dead => proto::ContainerStatus::Dead,
+            container::ContainerStatus::CrashLoopBackOff => {
+                proto::ContainerStatus::CrashLoopBackOff
+            }
+            container::ContainerStatus::Unknown => proto::ContainerStatus::Unknown,
+        };
+
+        proto::Container {
+            id: container.id,
+            name: container.name,
+            app: container.app,
+            spec: Some(container.spec.into()),
+            node: container.node,
+            created: container.created,
+            status: status.into(),
+            started_at: container.started_at,
+            restart_count: container.restart_count,
+            last_restart: container.last_restart,
+            back_off_until: container.back_off_until,
+            last_termination: container
+                .last_termination
+                .map(|termination| proto::Termination {
+                    exit_code: termination.exit_code,
+                    oom_killed: termination.oom_killed,
+                    finished_at: termination.finished_at,
+                }),
+            health: container.health,
+            image_digest: container.image_digest,
+        }
+    }
+}
+
+impl From<proto::Container> for Container {
+    fn from(container: proto::Container) -> Self {
+        let status = match container.status() {
+            proto::ContainerStatus::Created => container::ContainerStatus::Created,
+            proto::ContainerStatus::Running => container::ContainerStatus::Running,
+            proto::ContainerStatus::Restarting => container::ContainerStatus::Restarting,
+            proto::ContainerStatus::Exited => container::ContainerStatus::Exited,
+            proto::ContainerStatus::Paused => container::ContainerStatus::Paused,
+            proto::ContainerStatus::Dead => container::ContainerStatus::Dead,
+            proto::ContainerStatus::CrashLoopBackOff => {
+                container::ContainerStatus::CrashLoopBackOff
+            }
+            proto::ContainerStatus::Unknown => container::ContainerStatus::Unknown,
+        };
+
+        Container {
+            id: container.id,
+            name: container.name,
+            app: container.app,
+            spec: container.spec.map(Into::into).unwrap_or_default(),
+            node: container.node,
+            created: container.created,
+            started_at: container.started_at,
+            restart_count: container.restart_count,
+            last_restart: container.last_restart,
+            back_off_until: container.back_off_until,
+            last_termination: container.last_termination.map(|termination| {
+                container::Termination {
+                    exit_code: termination.exit_code,
+                    oom_killed: termination.oom_killed,
+                    finished_at: termination.finished_at,
+                }
+            }),
+            health: container.health,
+            image_digest: container.image_digest,
+            status,
+        }
+    }
+}
+
+impl From<container::ContainerSpec> for proto::ContainerSpec {
+    fn from(spec: container::ContainerSpec) -> Self {
+        proto::ContainerSpec {
+            name: spec.name,
+            image: spec.image,
+            ports: spec.ports,
+            command: spec.command,
+            app: spec.app,
+            env_from_secret: spec.env_from_secret,
+            config_maps: spec
+                .config_maps
+                .into_iter()
+                .map(|mount| proto::ConfigMapMount {
+                    name: mount.name,
+                    mount_path: mount.mount_path,
+                    restart_on_change: mount.restart_on_change,
+                })
+                .collect(),
+            volumes: spec
+                .volumes
+                .into_iter()
+                .map(|claim| proto::VolumeClaim {
+                    name: claim.name,
+                    mount_path: claim.mount_path,
+                    read_only: claim.read_only,
+                })
+                .collect(),
+            node: spec.node,
+            resources: Some(proto::ResourceRequests {
+                cpus: spec.resources.cpus,
+                memory_bytes: spec.resources.memory_bytes,
+            }),
+            restart_policy: match spec.restart_policy {
+                RestartPolicy::Always => proto::RestartPolicy::Always,
+                RestartPolicy::OnFailure => proto::RestartPolicy::OnFailure,
+                RestartPolicy::Never => proto::RestartPolicy::Never,
+            }
+            .into(),
+            init_containers: spec
+                .init_containers
+                .into_iter()
+                .map(|init| proto::InitContainer {
+                    name: init.name,
+                    image: init.image,
+                    command: init.command,
+                })
+                .collect(),
+            readiness_probe: spec.readiness_probe.map(|probe| proto::Probe {
+                command: probe.command,
+                initial_delay_seconds: probe.initial_delay_seconds,
+                period_seconds: probe.period_seconds,
+                timeout_seconds: probe.timeout_seconds,
+                failure_threshold: probe.failure_threshold,
+            }),
+            auto_update: spec.auto_update,
+            labels: spec.labels.into_iter().collect(),
+            node_selector: spec.node_selector.into_iter().collect(),
+            affinity: spec.affinity.into_iter().map(Into::into).collect(),
+            anti_affinity: spec.anti_affinity.into_iter().map(Into::into).collect(),
+            termination_grace_period_seconds: spec.termination_grace_period_seconds,
+            stop_signal: spec.stop_signal,
+            lifecycle: Some(spec.lifecycle.into()),
+            depends_on: spec
+                .depends_on
+                .into_iter()
+                .map(|dependency| proto::Dependency {
+                    name: dependency.name,
+                    condition: match dependency.condition {
+                        DependencyCondition::Started => proto::DependencyCondition::Started,
+                        DependencyCondition::Healthy => proto::DependencyCondition::Healthy,
+                    }
+                    .into(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<proto::ContainerSpec> for container::ContainerSpec {
+    fn from(spec: proto::ContainerSpec) -> Self {
+        let restart_policy = match spec.restart_policy() {
+            proto::RestartPolicy::Always => RestartPolicy::Always,
+            proto::RestartPolicy::OnFailure => RestartPolicy::OnFailure,
+            proto::RestartPolicy::Never => RestartPolicy::Never,
+        };
+
+        container::ContainerSpec {
+            name: spec.name,
+            image: spec.image,
+            ports: spec.ports,
+            command: spec.command,
+            app: spec.app,
+            env_from_secret: spec.env_from_secret,
+            config_maps: spec
+                .config_maps
+                .into_iter()
+                .map(|mount| ConfigMapMount {
+                    name: mount.name,
+                    mount_path: mount.mount_path,
+                    restart_on_change: mount.restart_on_change,
+                })
+                .collect(),
+            volumes: spec
+                .volumes
+                .into_iter()
+                .map(|claim| VolumeClaim {
+                    name: claim.name,
+                    mount_path: claim.mount_path,
+                    read_only: claim.read_only,
+                })
+                .collect(),
+            node: spec.node,
+            resources: spec
+                .resources
+                .map(|resources| container::ResourceRequests {
+                    cpus: resources.cpus,
+                    memory_bytes: resources.memory_bytes,
+                })
+                .unwrap_or_default(),
+            restart_policy,
+            init_containers: spec
+                .init_containers
+                .into_iter()
+                .map(|init| InitContainer {
+                    name: init.name,
+                    image: init.image,
+                    command: init.command,
+                })
+                .collect(),
+            readiness_probe: spec.readiness_probe.map(|probe| Probe {
+                command: probe.command,
+                initial_delay_seconds: probe.initial_delay_seconds,
+                period_seconds: probe.period_seconds,
+                timeout_seconds: probe.timeout_seconds,
+                failure_threshold: probe.failure_threshold,
+            }),
+            auto_update: spec.auto_update,
+            labels: spec.labels.into_iter().collect(),
+            node_selector: spec.node_selector.into_iter().collect(),
+            affinity: spec.affinity.into_iter().map(Into::into).collect(),
+            anti_affinity: spec.anti_affinity.into_iter().map(Into::into).collect(),
+            termination_grace_period_seconds: spec.termination_grace_period_seconds,
+            stop_signal: spec.stop_signal,
+            lifecycle: spec.lifecycle.map(Into::into).unwrap_or_default(),
+            depends_on: spec
+                .depends_on
+                .into_iter()
+                .map(|dependency| Dependency {
+                    condition: match dependency.condition() {
+                        proto::DependencyCondition::Started => DependencyCondition::Started,
+                        proto::DependencyCondition::Healthy => DependencyCondition::Healthy,
+                    },
+                    name: dependency.name,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<Lifecycle> for proto::Lifecycle {
+    fn from(lifecycle: Lifecycle) -> Self {
+        proto::Lifecycle {
+            post_start: lifecycle.post_start.map(Into::into),
+            pre_stop: lifecycle.pre_stop.map(Into::into),
+        }
+    }
+}
+
+impl From<proto::Lifecycle> for Lifecycle {
+    fn from(lifecycle: proto::Lifecycle) -> Self {
+        Lifecycle {
+            post_start: lifecycle.post_start.map(Into::into),
+            pre_stop: lifecycle.pre_stop.map(Into::into),
+        }
+    }
+}
+
+impl From<LifecycleHook> for proto::LifecycleHook {
+    fn from(hook: LifecycleHook) -> Self {
+        proto::LifecycleHook {
+            exec: hook.exec,
+            http: hook.http.map(|http| proto::HttpHook {
+                url: http.url,
+                method: http.method,
+            }),
+            timeout_seconds: hook.timeout_seconds,
+            on_failure: match hook.on_failure {
+                HookFailurePolicy::Ignore => proto::HookFailurePolicy::Ignore,
+                HookFailurePolicy::Fail => proto::HookFailurePolicy::Fail,
+            }
+            .into(),
+        }
+    }
+}
+
+impl From<proto::LifecycleHook> for LifecycleHook {
+    fn from(hook: proto::LifecycleHook) -> Self {
+        LifecycleHook {
+            on_failure: match hook.on_failure() {
+                proto::HookFailurePolicy::Ignore => HookFailurePolicy::Ignore,
+                proto::HookFailurePolicy::Fail => HookFailurePolicy::Fail,
+            },
+            exec: hook.exec,
+            http: hook.http.map(|http| HttpHook {
+                url: http.url,
+                method: http.method,
+            }),
+            timeout_seconds: hook.timeout_seconds,
+        }
+    }
+}
+
+impl From<AffinityTerm> for proto::AffinityTerm {
+    fn from(term: AffinityTerm) -> Self {
+        proto::AffinityTerm {
+            match_labels: term.selector.match_labels.into_iter().collect(),
+            match_expressions: term
+                .selector
+                .match_expressions
+                .into_iter()
+                .map(|expression| proto::LabelExpression {
+                    key: expression.key,
+                    operator: match expression.operator {
+                        Operator::In => proto::LabelOperator::In,
+                        Operator::NotIn => proto::LabelOperator::NotIn,
+                        Operator::Exists => proto::LabelOperator::Exists,
+                        Operator::DoesNotExist => proto::LabelOperator::DoesNotExist,
+                    }
+                    .into(),
+                    values: expression.values,
+                })
+                .collect(),
+            topology_key: term.topology_key,
+        }
+    }
+}
+
+impl From<proto::AffinityTerm> for AffinityTerm {
+    fn from(term: proto::AffinityTerm) -> Self {
+        AffinityTerm {
+            selector: LabelSelector {
+                match_labels: term.match_labels.into_iter().collect(),
+                match_expressions: term
+                    .match_expressions
+                    .into_iter()
+                    .map(|expression| LabelExpression {
+                        operator: match expression.operator() {
+                            proto::LabelOperator::In => Operator::In,
+                            proto::LabelOperator::NotIn => Operator::NotIn,
+                            proto::LabelOperator::Exists => Operator::Exists,
+                            proto::LabelOperator::DoesNotExist => Operator::DoesNotExist,
+                        },
+                        key: expression.key,
+                        values: expression.values,
+                    })
+                    .collect(),
+            },
+            topology_key: term.topology_key,
+        }
+    }
+}
+
+impl From<WatchEvent> for proto::WatchContainersResponse {
+    fn from(event: WatchEvent) -> Self {
+        let event_type = match event.event_type {
+            WatchEventType::Added => proto::WatchEventType::Added,
+            WatchEventType::Modified => proto::WatchEventType::Modified,
+            WatchEventType::Deleted => proto::WatchEventType::Deleted,
+        };
+
+        proto::WatchContainersResponse {
+            r#type: event_type.into(),
+            container: Some(event.object.into()),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl ControlPlane for ControlPlaneService {
+    async fn create_container(
+        &self,
+        request: Request<proto::CreateContainerRequest>,
+    ) -> Result<Response<proto::CreateContainerResponse>, Status> {
+        let name = request
+            .get_ref()
+            .spec
+            .as_ref()
+            .map(|spec| spec.name.clone());
+        let app = request
+            .get_ref()
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.app.clone())
+            .or_else(|| name.clone());
+        let caller = self
+            .authorize(
+                &request,
+                "create",
+                container::KIND,
+                name.clone(),
+                app.as_deref(),
+            )
+            .await?;
+
+        let result = self.create(request.into_inner().spec).await;
+        self.audit(caller, "create", container::KIND, name, &result)
+            .await;
+        result
+    }
+
+    type WatchContainersStream =
+        Pin<Box<dyn Stream<Item = Result<proto::WatchContainersResponse, Status>> + Send>>;
+
+    async fn watch_containers(
+        &self,
+        request: Request<proto::WatchContainersRequest>,
+    ) -> Result<Response<Self::WatchContainersStream>, Status> {
+        let app = request.get_ref().app.clone();
+        self.authorize(&request, "watch", container::KIND, None, app.as_deref())
+            .await?;
+        let app = request.into_inner().app;
+        let events = container_events(&self.state, app)
+            .await
+            .map(|event| Ok(event.into()));
+
+        Ok(Response::new(Box::pin(events)))
+    }
+
+    async fn scale(
+        &self,
+        request: Request<proto::ScaleRequest>,
+    ) -> Result<Response<proto::ScaleResponse>, Status> {
+        let app = Some(request.get_ref().app.clone());
+        let caller = self
+            .authorize(
+                &request,
+                "scale",
+                container::KIND,
+                app.clone(),
+                app.as_deref(),
+            )
+            .await?;
+        let request = request.into_inner();
+
+        let result = Container::scale(&request.app, request.replicas as usize, &self.state.cluster)
+            .await
+            .map(|containers| {
+                Response::new(proto::ScaleResponse {
+                    containers: containers.into_iter().map(Into::into).collect(),
+                })
+            })
+            .map_err(|error| Status::internal(error.to_string()));
+        self.audit(caller, "scale", container::KIND, app, &result)
+            .await;
+        result
+    }
+
+    async fn delete(
+        &self,
+        request: Request<proto::DeleteRequest>,
+    ) -> Result<Response<proto::DeleteResponse>, Status> {
+        let id = request.get_ref().id.clone();
+        let app = self
+            .state
+            .cluster
+            .status_watcher
+            .find(&id)
+            .await
+            .map(|container| container.app);
+        let caller = self
+            .authorize(
+                &request,
+                "delete",
+                container::KIND,
+                Some(id.clone()),
+                app.as_deref(),
+            )
+            .await?;
+
+        let result = self.delete_container(&id).await;
+        self.audit(caller, "delete", container::KIND, Some(id), &result)
+            .await;
+        result
+    }
+}
+
+pub async fn serve(addr: &str, state: ApiState) -> Result<(), anyhow::Error> {
+    let addr = addr.parse()?;
+    println!("gRPC listening on {}", addr);
+
+    let shutdown = state.shutdown.clone();
+    Server::builder()
+        .add_service(ControlPlaneServer::new(ControlPlaneService { state }))
+        .serve_with_shutdown(addr, shutdown.cancelled_owned())
+        .await?;
+    println!("gRPC stopped");
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use std::{collections::HashMap, future::Future, time::Duration};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::entities::container;
+
+use super::ApiState;
+
+// How late a watcher may be back from its sleep before it counts as stuck.
+const WATCHER_GRACE: Duration = Duration::from_secs(60);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Health {
+    pub healthy: bool,
+    pub checks: Vec<Check>,
+}
+
+// Whether the daemon is alive: its background loops keep coming around.
+pub async fn healthz(
+    State(state): State<ApiState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    respond(vec![watchers(&state).await], query.contains_key("verbose"))
+}
+
+// Whether the daemon can do its work: on top of being alive, it can reach its state store and
+// the local node's container engine.
+pub async fn readyz(
+    State(state): State<ApiState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let mut checks = vec![watchers(&state).await, state_store(&state).await];
+    if let Some(check) = runtime(&state).await {
+        checks.push(check);
+    }
+    respond(checks, query.contains_key("verbose"))
+}
+
+// `ok`, or the failed checks one per line, unless `verbose` asks for every check as JSON.
+fn respond(checks: Vec<Check>, verbose: bool) -> Response {
+    let healthy = checks.iter().all(|check| check.healthy);
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    if verbose {
+        return (status, Json(Health { healthy, checks })).into_response();
+    }
+    if healthy {
+        return (status, "ok").into_response();
+    }
+
+    let failed: Vec<String> = checks
+        .iter()
+        .filter(|check| !check.healthy)
+        .map(|check| format!("{}: {}", check.name, check.message.as_deref().unwrap_or("")))
+        .collect();
+    (status, failed.join("\n")).into_response()
+}
+
+async fn watchers(state: &ApiState) -> Check {
+    let problems = state.watchers.problems(WATCHER_GRACE).await;
+    Check {
+        name: "watchers",
+        healthy: problems.is_empty(),
+        message: (!problems.is_empty()).then(|| problems.join("; ")),
+    }
+}
+
+async fn state_store(state: &ApiState) -> Check {
+    let listed = state.cluster.state.list::<Value>(container::KIND);
+    check("state", listed).await
+}
+
+// None when containers only run on agents, whose health the node status watcher tracks.
+async fn runtime(state: &ApiState) -> Option<Check> {
+    let local = state
+        .cluster
+        .nodes
+        .list()
+        .await
+        .into_iter()
+        .find(|node| node.address.is_none())?;
+    let check = match state.cluster.nodes.node(&local.name).await {
+        Ok(runtime) => check("runtime", runtime.list_managed()).await,
+        Err(error) => Check {
+            name: "runtime",
+            healthy: false,
+            message: Some(error.to_string()),
+        },
+    };
+    Some(check)
+}
+
+async fn check<T>(
+    name: &'static str,
+    probe: impl Future<Output = Result<T, anyhow::Error>>,
+) -> Check {
+    let message = match tokio::time::timeout(CHECK_TIMEOUT, probe).await {
+        Ok(Ok(_)) => None,
+        Ok(Err(error)) => Some(error.to_string()),
+        Err(_) => Some(format!("no answer within {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    Check {
+        name,
+        healthy: message.is_none(),
+        message,
+    }
+}
@@ -0,0 +1,57 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    entities::ingress::{Ingress, IngressSpec},
+    store::state::validate_name,
+};
+
+use super::{ApiError, ApiState};
+
+pub async fn list(State(state): State<ApiState>) -> Result<Json<Vec<Ingress>>, ApiError> {
+    Ok(Json(state.ingresses.list().await?))
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<Ingress>, ApiError> {
+    state
+        .ingresses
+        .get(&name)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("ingress {} not found", name)))
+}
+
+pub async fn create(
+    State(state): State<ApiState>,
+    Json(spec): Json<IngressSpec>,
+) -> Result<(StatusCode, Json<Ingress>), ApiError> {
+    validate_name(&spec.name).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+    spec.validate()
+        .map_err(|error| ApiError::BadRequest(error.to_string()))?;
+
+    if state.ingresses.get(&spec.name).await?.is_some() {
+        return Err(ApiError::Conflict(format!(
+            "ingress {} already exists",
+            spec.name
+        )));
+    }
+
+    let ingress = state.ingresses.create(spec).await?;
+    Ok((StatusCode::CREATED, Json(ingress)))
+}
+
+pub async fn delete(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !state.ingresses.delete(&name).await? {
+        return Err(ApiError::NotFound(format!("ingress {} not found", name)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
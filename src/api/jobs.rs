@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    entities::job::{Job, JobSpec},
+    store::state::validate_name,
+};
+
+use super::{ApiError, ApiState};
+
+pub async fn list(State(state): State<ApiState>) -> Result<Json<Vec<Job>>, ApiError> {
+    Ok(Json(state.jobs.list().await?))
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<Job>, ApiError> {
+    state
+        .jobs
+        .get(&name)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("job {} not found", name)))
+}
+
+pub async fn create(
+    State(state): State<ApiState>,
+    Json(spec): Json<JobSpec>,
+) -> Result<(StatusCode, Json<Job>), ApiError> {
+    validate_name(&spec.template.name).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+
+    if state.jobs.get(&spec.template.name).await?.is_some() {
+        return Err(ApiError::Conflict(format!(
+            "job {} already exists",
+            spec.template.name
+        )));
+    }
+
+    let job = state.jobs.create(spec).await?;
+    Ok((StatusCode::CREATED, Json(job)))
+}
+
+pub async fn delete(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !state.jobs.delete(&name).await? {
+        return Err(ApiError::NotFound(format!("job {} not found", name)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
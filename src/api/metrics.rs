@@ -0,0 +1,50 @@
+use std::fmt::Write;
+
+use axum::{extract::State, http::header, response::IntoResponse};
+
+use super::ApiState;
+
+// In the Prometheus text format.
+pub async fn get(State(state): State<ApiState>) -> impl IntoResponse {
+    let limit = state.operation_limit.snapshot();
+    let metrics = [
+        (
+            "nic8s_runtime_operations_max",
+            "gauge",
+            "Engine operations the local node runs at once.",
+            limit.max.to_string(),
+        ),
+        (
+            "nic8s_runtime_operations_in_flight",
+            "gauge",
+            "Engine operations running on the local node.",
+            limit.in_flight.to_string(),
+        ),
+        (
+            "nic8s_runtime_operations_queued",
+            "gauge",
+            "Engine operations waiting for one of those running to finish.",
+            limit.queued.to_string(),
+        ),
+        (
+            "nic8s_runtime_operations_total",
+            "counter",
+            "Engine operations started on the local node.",
+            limit.total.to_string(),
+        ),
+        (
+            "nic8s_runtime_operations_queued_seconds_total",
+            "counter",
+            "Time engine operations spent queued, summed over all of them.",
+            limit.queued_time.as_secs_f64().to_string(),
+        ),
+    ];
+
+    let mut body = String::new();
+    for (name, kind, help, value) in metrics {
+        let _ = writeln!(body, "# HELP {} {}", name, help);
+        let _ = writeln!(body, "# TYPE {} {}", name, kind);
+        let _ = writeln!(body, "{} {}", name, value);
+    }
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
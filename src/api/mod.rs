@@ -0,0 +1,239 @@
+pub mod audit;
+pub mod auth;
+pub mod autoscalers;
+pub mod config_maps;
+pub mod containers;
+pub mod cron_jobs;
+pub mod endpoints;
+pub mod events;
+pub mod grpc;
+pub mod health;
+pub mod ingresses;
+pub mod jobs;
+pub mod metrics;
+pub mod nodes;
+pub mod resource_quotas;
+pub mod rollouts;
+pub mod secrets;
+pub mod volumes;
+pub mod watch;
+pub mod watchers;
+
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    http::{header, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Router,
+};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    admission::{quota::ResourceQuotas, AdmissionError},
+    auth::{AuthError, Authenticator},
+    cluster::Cluster,
+    controllers::{
+        autoscaler::AutoscalerController, cron_job::CronJobController, job::JobController,
+        rollout::RolloutController,
+    },
+    entities::{
+        cron_job::CronJobExists, ports::PortConflict, token::TokenError, volume::VolumeError,
+    },
+    ingress::IngressController,
+    runtime::limit::OperationLimit,
+    store::audit::AuditLog,
+    watchers::{registry::WatcherRegistry, resource_usage::ResourceUsageWatcher},
+};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:6443";
+
+#[derive(Clone)]
+pub struct ApiState {
+    pub cluster: Cluster,
+    pub jobs: Arc<JobController>,
+    pub cron_jobs: Arc<CronJobController>,
+    pub autoscalers: Arc<AutoscalerController>,
+    pub rollouts: Arc<RolloutController>,
+    pub quotas: Arc<ResourceQuotas>,
+    pub operation_limit: Arc<OperationLimit>,
+    pub ingresses: Arc<IngressController>,
+    pub resource_usage_watcher: Arc<ResourceUsageWatcher>,
+    pub watchers: Arc<WatcherRegistry>,
+    pub audit: Arc<AuditLog>,
+    pub auth: Arc<Authenticator>,
+    pub shutdown: CancellationToken,
+}
+
+pub enum ApiError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        if error.is::<PortConflict>() || error.is::<CronJobExists>() {
+            return ApiError::Conflict(error.to_string());
+        }
+        if let Some(AdmissionError::Denied { .. }) = error.downcast_ref() {
+            return ApiError::Forbidden(error.to_string());
+        }
+        match error.downcast_ref() {
+            Some(VolumeError::Exists(_) | VolumeError::InUse { .. }) => {
+                return ApiError::Conflict(error.to_string())
+            }
+            Some(VolumeError::NotFound { .. }) => return ApiError::BadRequest(error.to_string()),
+            None => {}
+        }
+        match error.downcast_ref() {
+            Some(AuthError::Unauthenticated | AuthError::InvalidToken) => {
+                return ApiError::Unauthorized(error.to_string())
+            }
+            Some(AuthError::Forbidden { .. }) => return ApiError::Forbidden(error.to_string()),
+            None => {}
+        }
+        match error.downcast_ref() {
+            Some(TokenError::Exists(_)) => return ApiError::Conflict(error.to_string()),
+            Some(TokenError::UnknownRole(_) | TokenError::NoRoles) => {
+                return ApiError::BadRequest(error.to_string())
+            }
+            None => {}
+        }
+        ApiError::Internal(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message).into_response(),
+            ApiError::Unauthorized(message) => (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                message,
+            )
+                .into_response(),
+            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, message).into_response(),
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message).into_response(),
+            ApiError::Conflict(message) => (StatusCode::CONFLICT, message).into_response(),
+            ApiError::Internal(error) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response()
+            }
+        }
+    }
+}
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route(
+            "/containers",
+            get(containers::list).post(containers::create),
+        )
+        .route(
+            "/containers/{id}",
+            get(containers::get).delete(containers::delete),
+        )
+        .route("/containers/{id}/describe", get(containers::describe))
+        .route("/containers/{id}/events", get(containers::events))
+        .route("/containers/{id}/history", get(containers::history))
+        .route("/containers/{id}/logs", get(containers::logs))
+        .route("/containers/{id}/restart", post(containers::restart))
+        .route("/containers/{id}/stop", post(containers::stop))
+        .route("/stats", get(containers::stats))
+        .route("/endpoints", get(endpoints::list))
+        .route("/endpoints/{app}", get(endpoints::get))
+        .route("/watch/containers", get(watch::containers))
+        .route("/metrics", get(metrics::get))
+        .route("/watchers", get(watchers::list))
+        .route("/watchers/{name}/start", post(watchers::start))
+        .route("/watchers/{name}/stop", post(watchers::stop))
+        .route("/events", get(events::list))
+        .route("/secrets", get(secrets::list).post(secrets::create))
+        .route("/secrets/{name}", get(secrets::get).delete(secrets::delete))
+        .route("/volumes", get(volumes::list).post(volumes::create))
+        .route("/volumes/{name}", get(volumes::get).delete(volumes::delete))
+        .route(
+            "/configmaps",
+            get(config_maps::list).post(config_maps::create),
+        )
+        .route(
+            "/configmaps/{name}",
+            get(config_maps::get).delete(config_maps::delete),
+        )
+        .route("/jobs", get(jobs::list).post(jobs::create))
+        .route("/jobs/{name}", get(jobs::get).delete(jobs::delete))
+        .route("/cronjobs", get(cron_jobs::list).post(cron_jobs::create))
+        .route(
+            "/cronjobs/{name}",
+            get(cron_jobs::get).delete(cron_jobs::delete),
+        )
+        .route(
+            "/autoscalers",
+            get(autoscalers::list).post(autoscalers::create),
+        )
+        .route(
+            "/autoscalers/{app}",
+            get(autoscalers::get).delete(autoscalers::delete),
+        )
+        .route("/rollouts", get(rollouts::list).post(rollouts::create))
+        .route(
+            "/rollouts/{app}",
+            get(rollouts::get).delete(rollouts::delete),
+        )
+        .route("/rollouts/{app}/undo", post(rollouts::undo))
+        .route(
+            "/resourcequotas",
+            get(resource_quotas::list).post(resource_quotas::create),
+        )
+        .route(
+            "/resourcequotas/{app}",
+            get(resource_quotas::get).delete(resource_quotas::delete),
+        )
+        .route("/ingresses", get(ingresses::list).post(ingresses::create))
+        .route(
+            "/ingresses/{name}",
+            get(ingresses::get).delete(ingresses::delete),
+        )
+        .route("/nodes", get(nodes::list).post(nodes::register))
+        .route("/nodes/{name}", delete(nodes::delete))
+        .route("/audit", get(audit::list))
+        .route("/tokens", get(auth::list_tokens).post(auth::create_token))
+        .route("/tokens/{name}", delete(auth::delete_token))
+        .route("/roles", get(auth::list_roles))
+        .layer(middleware::from_fn_with_state(
+            state.auth.clone(),
+            auth::authorize,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::resolve_app,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.audit.clone(),
+            audit::record,
+        ))
+        // Added after the layers so monitoring can call them without a token.
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
+        .with_state(state)
+}
+
+pub async fn serve(addr: &str, state: ApiState) -> Result<(), anyhow::Error> {
+    let listener = TcpListener::bind(addr).await?;
+    println!("API listening on {}", listener.local_addr()?);
+
+    let shutdown = state.shutdown.clone();
+    // The audit log records where each call came from.
+    let router = router(state).into_make_service_with_connect_info::<SocketAddr>();
+    axum::serve(listener, router)
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await?;
+    println!("API stopped");
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{entities::node::Node, store::state::validate_name};
+
+use super::{ApiError, ApiState};
+
+pub async fn list(State(state): State<ApiState>) -> Json<Vec<Node>> {
+    Json(state.cluster.nodes.list().await)
+}
+
+// Agents call this on start and periodically afterwards, so registering an existing node only
+// refreshes it.
+pub async fn register(
+    State(state): State<ApiState>,
+    Json(node): Json<Node>,
+) -> Result<Json<Node>, ApiError> {
+    validate_name(&node.name).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+
+    let node = state
+        .cluster
+        .nodes
+        .register(node)
+        .await
+        .map_err(|error| ApiError::BadRequest(error.to_string()))?;
+    Ok(Json(node))
+}
+
+pub async fn delete(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let running: Vec<String> = state
+        .cluster
+        .status_watcher
+        .list()
+        .await
+        .into_iter()
+        .filter(|container| container.node == name)
+        .map(|container| container.name)
+        .collect();
+    if !running.is_empty() {
+        return Err(ApiError::Conflict(format!(
+            "node {} still runs containers: {}",
+            name,
+            running.join(", ")
+        )));
+    }
+
+    if !state.cluster.nodes.deregister(&name).await? {
+        return Err(ApiError::NotFound(format!("node {} not found", name)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    entities::resource_quota::{ResourceQuota, ResourceQuotaSpec},
+    store::state::validate_name,
+};
+
+use super::{ApiError, ApiState};
+
+pub async fn list(State(state): State<ApiState>) -> Result<Json<Vec<ResourceQuota>>, ApiError> {
+    Ok(Json(state.quotas.list().await?))
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    Path(app): Path<String>,
+) -> Result<Json<ResourceQuota>, ApiError> {
+    state
+        .quotas
+        .get(&app)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("resource quota {} not found", app)))
+}
+
+pub async fn create(
+    State(state): State<ApiState>,
+    Json(spec): Json<ResourceQuotaSpec>,
+) -> Result<(StatusCode, Json<ResourceQuota>), ApiError> {
+    validate_name(&spec.app).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+    spec.validate()
+        .map_err(|error| ApiError::BadRequest(error.to_string()))?;
+
+    if state.quotas.get(&spec.app).await?.is_some() {
+        return Err(ApiError::Conflict(format!(
+            "resource quota {} already exists",
+            spec.app
+        )));
+    }
+
+    let quota = state.quotas.create(spec).await?;
+    Ok((StatusCode::CREATED, Json(quota)))
+}
+
+pub async fn delete(
+    State(state): State<ApiState>,
+    Path(app): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !state.quotas.delete(&app).await? {
+        return Err(ApiError::NotFound(format!(
+            "resource quota {} not found",
+            app
+        )));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
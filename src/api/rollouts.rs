@@ -0,0 +1,88 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    entities::rollout::{is_replica, Rollout, RolloutSpec},
+    store::state::validate_name,
+};
+
+use super::{ApiError, ApiState};
+
+pub async fn list(State(state): State<ApiState>) -> Result<Json<Vec<Rollout>>, ApiError> {
+    Ok(Json(state.rollouts.list().await?))
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    Path(app): Path<String>,
+) -> Result<Json<Rollout>, ApiError> {
+    state
+        .rollouts
+        .get(&app)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("rollout {} not found", app)))
+}
+
+pub async fn create(
+    State(state): State<ApiState>,
+    Json(spec): Json<RolloutSpec>,
+) -> Result<(StatusCode, Json<Rollout>), ApiError> {
+    validate_name(&spec.app).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+    spec.validate()
+        .map_err(|error| ApiError::BadRequest(error.to_string()))?;
+
+    let containers = state.cluster.status_watcher.list().await;
+    if !containers
+        .iter()
+        .any(|container| is_replica(container, &spec.app))
+    {
+        return Err(ApiError::BadRequest(format!(
+            "app {} has no containers to roll out",
+            spec.app
+        )));
+    }
+    // A finished rollout is replaced by the next one.
+    if let Some(rollout) = state.rollouts.get(&spec.app).await? {
+        if !rollout.is_finished() {
+            return Err(ApiError::Conflict(format!(
+                "rollout {} is still in progress",
+                spec.app
+            )));
+        }
+    }
+
+    let rollout = state.rollouts.create(spec).await?;
+    Ok((StatusCode::CREATED, Json(rollout)))
+}
+
+pub async fn undo(
+    State(state): State<ApiState>,
+    Path(app): Path<String>,
+) -> Result<Json<Rollout>, ApiError> {
+    let rollout = state
+        .rollouts
+        .get(&app)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("rollout {} not found", app)))?;
+    if !rollout.can_undo() {
+        return Err(ApiError::Conflict(format!(
+            "rollout {} can't be undone while {:?}",
+            app, rollout.status.phase
+        )));
+    }
+    Ok(Json(state.rollouts.undo(&app).await?))
+}
+
+pub async fn delete(
+    State(state): State<ApiState>,
+    Path(app): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !state.rollouts.delete(&app).await? {
+        return Err(ApiError::NotFound(format!("rollout {} not found", app)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    entities::secret::{Secret, SecretMetadata},
+    store::state::validate_name,
+};
+
+use super::{ApiError, ApiState};
+
+pub async fn list(State(state): State<ApiState>) -> Result<Json<Vec<SecretMetadata>>, ApiError> {
+    Ok(Json(state.cluster.secrets.list().await?))
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<SecretMetadata>, ApiError> {
+    state
+        .cluster
+        .secrets
+        .metadata(&name)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("secret {} not found", name)))
+}
+
+pub async fn create(
+    State(state): State<ApiState>,
+    Json(secret): Json<Secret>,
+) -> Result<(StatusCode, Json<SecretMetadata>), ApiError> {
+    validate_name(&secret.name).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+
+    let metadata = state.cluster.secrets.put(&secret).await?;
+    state.ingresses.secrets_changed();
+    Ok((StatusCode::CREATED, Json(metadata)))
+}
+
+pub async fn delete(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !state.cluster.secrets.delete(&name).await? {
+        return Err(ApiError::NotFound(format!("secret {} not found", name)));
+    }
+    state.ingresses.secrets_changed();
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,57 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+
+use crate::{
+    entities::volume::{Volume, VolumeSpec},
+    store::state::validate_name,
+};
+
+use super::{ApiError, ApiState};
+
+#[derive(Deserialize)]
+pub struct DeleteQuery {
+    #[serde(default)]
+    force: bool,
+}
+
+pub async fn list(State(state): State<ApiState>) -> Result<Json<Vec<Volume>>, ApiError> {
+    Ok(Json(state.cluster.volumes.list().await?))
+}
+
+pub async fn get(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<Json<Volume>, ApiError> {
+    state
+        .cluster
+        .volumes
+        .get(&name)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("volume {} not found", name)))
+}
+
+pub async fn create(
+    State(state): State<ApiState>,
+    Json(spec): Json<VolumeSpec>,
+) -> Result<(StatusCode, Json<Volume>), ApiError> {
+    validate_name(&spec.name).map_err(|error| ApiError::BadRequest(error.to_string()))?;
+
+    let volume = state.cluster.volumes.create(spec).await?;
+    Ok((StatusCode::CREATED, Json(volume)))
+}
+
+pub async fn delete(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Query(query): Query<DeleteQuery>,
+) -> Result<StatusCode, ApiError> {
+    if !state.cluster.volumes.delete(&name, query.force).await? {
+        return Err(ApiError::NotFound(format!("volume {} not found", name)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
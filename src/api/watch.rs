@@ -0,0 +1,82 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio_stream::{
+    wrappers::{BroadcastStream, ReceiverStream},
+    Stream, StreamExt,
+};
+
+use crate::watchers::container_status::{WatchEvent, WatchEventType};
+
+use super::ApiState;
+
+#[derive(Deserialize)]
+pub struct WatchQuery {
+    app: Option<String>,
+}
+
+// Streams an ADDED event for every current container followed by live changes from the status
+// event bus, optionally limited to one app. The stream ends when the daemon shuts down.
+pub async fn container_events(state: &ApiState, app: Option<String>) -> ReceiverStream<WatchEvent> {
+    let events = BroadcastStream::new(state.cluster.status_watcher.subscribe());
+
+    let initial: Vec<WatchEvent> = state
+        .cluster
+        .status_watcher
+        .list()
+        .await
+        .into_iter()
+        .map(|container| WatchEvent {
+            event_type: WatchEventType::Added,
+            object: container,
+        })
+        .collect();
+
+    let mut stream = tokio_stream::iter(initial)
+        .chain(events.filter_map(|event| event.ok()))
+        .filter(move |event| app.as_ref().is_none_or(|app| &event.object.app == app));
+
+    let (sender, receiver) = mpsc::channel(16);
+    let shutdown = state.shutdown.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => return,
+                event = stream.next() => match event {
+                    Some(event) => {
+                        if sender.send(event).await.is_err() {
+                            return;
+                        }
+                    }
+                    None => return,
+                },
+            }
+        }
+    });
+
+    ReceiverStream::new(receiver)
+}
+
+pub async fn containers(
+    State(state): State<ApiState>,
+    Query(query): Query<WatchQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = container_events(&state, query.app).await.map(|event| {
+        let event_type = match event.event_type {
+            WatchEventType::Added => "ADDED",
+            WatchEventType::Modified => "MODIFIED",
+            WatchEventType::Deleted => "DELETED",
+        };
+        Ok(Event::default()
+            .event(event_type)
+            .json_data(&event)
+            .unwrap_or_else(|_| Event::default().event(event_type)))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,33 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::watchers::registry::WatcherStatus;
+
+use super::{ApiError, ApiState};
+
+pub async fn list(State(state): State<ApiState>) -> Json<Vec<WatcherStatus>> {
+    Json(state.watchers.list().await)
+}
+
+pub async fn start(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !state.watchers.start(&name).await {
+        return Err(ApiError::NotFound(format!("watcher {} not found", name)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn stop(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !state.watchers.stop(&name).await {
+        return Err(ApiError::NotFound(format!("watcher {} not found", name)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
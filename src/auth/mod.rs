@@ -0,0 +1,208 @@
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::anyhow;
+use thiserror::Error;
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::{
+    config::AuthConfig,
+    entities::token::{IssuedToken, PolicyRule, Role, Token, TokenError, TokenSpec, ANY, VERBS},
+    store::tokens::TokenStore,
+};
+
+pub const ADMIN_ROLE: &str = "admin";
+pub const VIEWER_ROLE: &str = "viewer";
+// What node agents need to register and heartbeat.
+pub const NODE_ROLE: &str = "node";
+
+// The token created when auth is enabled and there is none yet.
+const BOOTSTRAP_TOKEN: &str = "admin";
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("missing bearer token")]
+    Unauthenticated,
+    #[error("invalid bearer token")]
+    InvalidToken,
+    #[error("{user} is not allowed to {verb} {resource}")]
+    Forbidden {
+        user: String,
+        verb: String,
+        resource: String,
+    },
+}
+
+// Who a call was authenticated as.
+#[derive(Clone, Debug)]
+pub struct Identity {
+    pub user: String,
+}
+
+fn builtin_roles() -> Vec<Role> {
+    let rule = |verbs: &[&str], resources: &[&str]| PolicyRule {
+        verbs: verbs.iter().map(|verb| String::from(*verb)).collect(),
+        resources: resources
+            .iter()
+            .map(|resource| String::from(*resource))
+            .collect(),
+        apps: vec![String::from(ANY)],
+    };
+    vec![
+        Role {
+            name: String::from(ADMIN_ROLE),
+            rules: vec![rule(&[ANY], &[ANY])],
+        },
+        Role {
+            name: String::from(VIEWER_ROLE),
+            rules: vec![rule(&["get", "list", "watch"], &[ANY])],
+        },
+        Role {
+            name: String::from(NODE_ROLE),
+            rules: vec![rule(&["create"], &["nodes"])],
+        },
+    ]
+}
+
+// The verb an audited operation needs, e.g. `update` for restarting a container.
+pub fn verb(operation: &str) -> &str {
+    match operation {
+        "get" | "list" | "watch" | "create" | "update" | "delete" => operation,
+        _ => "update",
+    }
+}
+
+// Checks bearer tokens against the roles they were given. With auth disabled every call is let
+// through, but tokens can still be managed so they exist before it is turned on.
+pub struct Authenticator {
+    enabled: bool,
+    roles: BTreeMap<String, Role>,
+    tokens: TokenStore,
+}
+
+impl Authenticator {
+    // Roles from the config are added to the built-in ones, replacing those of the same name.
+    pub fn new(config: &AuthConfig, tokens: TokenStore) -> Result<Self, anyhow::Error> {
+        let mut roles: BTreeMap<String, Role> = builtin_roles()
+            .into_iter()
+            .map(|role| (role.name.clone(), role))
+            .collect();
+        for role in config.roles.iter() {
+            for verb in role.rules.iter().flat_map(|rule| rule.verbs.iter()) {
+                if verb != ANY && !VERBS.contains(&verb.as_str()) {
+                    return Err(anyhow!(
+                        "role {} has unknown verb {}; use one of {} or {}",
+                        role.name,
+                        verb,
+                        VERBS.join(", "),
+                        ANY
+                    ));
+                }
+            }
+            roles.insert(role.name.clone(), role.clone());
+        }
+
+        Ok(Authenticator {
+            enabled: config.enabled,
+            roles,
+            tokens,
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn roles(&self) -> Vec<Role> {
+        self.roles.values().cloned().collect()
+    }
+
+    // `bearer` is the secret from the `Authorization` header, if the caller sent one, and `app`
+    // the app the call is about, if it is about one.
+    pub async fn authorize(
+        &self,
+        bearer: Option<&str>,
+        verb: &str,
+        resource: &str,
+        app: Option<&str>,
+    ) -> Result<Option<Identity>, anyhow::Error> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let secret = bearer.ok_or(AuthError::Unauthenticated)?;
+        let token = self
+            .tokens
+            .verify(secret)
+            .await?
+            .ok_or(AuthError::InvalidToken)?;
+
+        let allowed = token
+            .roles
+            .iter()
+            .filter_map(|role| self.roles.get(role))
+            .any(|role| role.allows(verb, resource, app));
+        if !allowed {
+            return Err(AuthError::Forbidden {
+                user: token.name,
+                verb: String::from(verb),
+                resource: match app {
+                    Some(app) => format!("{} of app {}", resource, app),
+                    None => String::from(resource),
+                },
+            }
+            .into());
+        }
+        Ok(Some(Identity { user: token.name }))
+    }
+
+    pub async fn create_token(&self, spec: TokenSpec) -> Result<IssuedToken, anyhow::Error> {
+        if spec.roles.is_empty() {
+            return Err(TokenError::NoRoles.into());
+        }
+        if let Some(role) = spec
+            .roles
+            .iter()
+            .find(|role| !self.roles.contains_key(*role))
+        {
+            return Err(TokenError::UnknownRole(role.clone()).into());
+        }
+        self.tokens.create(spec).await
+    }
+
+    pub async fn list_tokens(&self) -> Result<Vec<Token>, anyhow::Error> {
+        self.tokens.list().await
+    }
+
+    pub async fn delete_token(&self, name: &str) -> Result<bool, anyhow::Error> {
+        self.tokens.delete(name).await
+    }
+
+    // Without any token an enabled API would lock everyone out, so the first start creates an
+    // admin token and leaves its secret in `path`, readable only by the daemon's user.
+    pub async fn bootstrap(&self, path: &Path) -> Result<(), anyhow::Error> {
+        if !self.enabled || !self.tokens.list().await?.is_empty() {
+            return Ok(());
+        }
+
+        let issued = self
+            .create_token(TokenSpec {
+                name: String::from(BOOTSTRAP_TOKEN),
+                roles: vec![String::from(ADMIN_ROLE)],
+            })
+            .await?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(path).await?;
+        file.write_all(issued.secret.as_bytes()).await?;
+        file.flush().await?;
+        file.sync_all().await?;
+        println!(
+            "Created token {} with role {}; its secret is in {}",
+            BOOTSTRAP_TOKEN,
+            ADMIN_ROLE,
+            path.display()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::anyhow;
+use clap::Args;
+use serde_json::Value;
+
+use crate::manifest::{
+    plan::{self, Action, Change, Desired, Kind, LiveState},
+    template::Values,
+    LoadOptions, Manifest, ManifestFile,
+};
+
+use super::client::ApiClient;
+
+#[derive(Args)]
+pub struct ManifestArgs {
+    /// Path to the manifest
+    #[arg(short, long)]
+    file: PathBuf,
+    /// Fail when the manifest references an unset ${VAR} instead of expanding it to ""
+    #[arg(long)]
+    strict: bool,
+    /// Values to render the manifest with, defaults to values.toml next to it; later files win
+    #[arg(long)]
+    values: Vec<PathBuf>,
+    /// Override a value, like --set image.tag=1.2 or --set replicas=3
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+    /// Also delete containers, jobs and cron jobs that are not in the manifest
+    #[arg(long)]
+    prune: bool,
+}
+
+async fn changes(client: &ApiClient, args: &ManifestArgs) -> Result<Vec<Change>, anyhow::Error> {
+    let options = LoadOptions {
+        strict: args.strict,
+        values: Values::load(&args.file, &args.values, &args.set)?,
+    };
+    let manifest = Manifest::load(&args.file, &options)?;
+
+    let live = LiveState {
+        containers: client.list_containers().await?,
+        jobs: client.list_jobs().await?,
+        cron_jobs: client.list_cron_jobs().await?,
+    };
+    plan::check_dependencies(&manifest, &live, args.prune)?;
+    Ok(plan::plan(&manifest, &live, args.prune))
+}
+
+pub async fn run(
+    client: &ApiClient,
+    args: ManifestArgs,
+    dry_run: bool,
+) -> Result<(), anyhow::Error> {
+    for change in changes(client, &args).await? {
+        let object = format!("{}/{}", change.kind, change.name);
+        let outcome = match change.action {
+            Action::Create => "created",
+            Action::Update => "configured",
+            Action::Unchanged => "unchanged",
+            Action::Delete => "deleted",
+            Action::Immutable => {
+                println!(
+                    "{} unchanged: {}s can't be updated, delete it to apply changes",
+                    object, change.kind
+                );
+                continue;
+            }
+        };
+        if dry_run {
+            println!("{} {} (dry run)", object, outcome);
+            continue;
+        }
+
+        // Containers are updated by recreating them.
+        if matches!(change.action, Action::Update | Action::Delete) {
+            match change.kind {
+                Kind::Container => client.delete_container(&change.name).await?,
+                Kind::Job => client.delete_job(&change.name).await?,
+                Kind::CronJob => client.delete_cron_job(&change.name).await?,
+            }
+        }
+        if matches!(change.action, Action::Create | Action::Update) {
+            match &change.desired {
+                Some(Desired::Container(spec)) => {
+                    client.create_container(spec).await?;
+                }
+                Some(Desired::Job(spec)) => {
+                    client.create_job(spec).await?;
+                }
+                Some(Desired::CronJob(spec)) => {
+                    client.create_cron_job(spec).await?;
+                }
+                None => {}
+            }
+        }
+        println!("{} {}", object, outcome);
+    }
+
+    Ok(())
+}
+
+// Like `apply --dry-run`, with the field-level differences of everything that would change.
+pub async fn diff(client: &ApiClient, args: ManifestArgs) -> Result<(), anyhow::Error> {
+    let color = std::io::stdout().is_terminal();
+    let paint = |code: &str, text: String| {
+        if color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text
+        }
+    };
+
+    let mut unchanged = 0;
+    for change in changes(client, &args).await? {
+        let object = format!("{}/{}", change.kind, change.name);
+        match change.action {
+            Action::Create => println!("{}", paint("32", format!("+ {}", object))),
+            Action::Delete => println!("{}", paint("31", format!("- {}", object))),
+            Action::Update => println!("{}", paint("33", format!("~ {}", object))),
+            Action::Immutable => println!(
+                "{} (can't be updated, delete it to apply changes)",
+                paint("33", format!("~ {}", object))
+            ),
+            Action::Unchanged => unchanged += 1,
+        }
+
+        for field in change.fields.iter() {
+            println!(
+                "    {}: {} -> {}",
+                field.path,
+                paint("31", format_value(field.live.as_ref())),
+                paint("32", format_value(field.desired.as_ref()))
+            );
+        }
+    }
+
+    if unchanged > 0 {
+        println!("{} unchanged", unchanged);
+    }
+    Ok(())
+}
+
+// Only the image value changes in the file; `apply` then rolls the container out.
+pub fn set_image(file: &Path, container: &str, image: &str) -> Result<(), anyhow::Error> {
+    let mut manifest = ManifestFile::read(file)?;
+    let path = manifest.find("containers", container)?.field("image");
+    manifest.set(&path, &toml::Value::String(String::from(image)))?;
+    manifest.write()?;
+    println!(
+        "Set the image of {} to {} in {}",
+        container,
+        image,
+        file.display()
+    );
+    Ok(())
+}
+
+// Opens the container, job or cron job in $VISUAL or $EDITOR, and puts the edited text in its
+// place only if the manifest still parses and validates. The rest of the file stays as written.
+pub fn edit(file: &Path, name: &str) -> Result<(), anyhow::Error> {
+    let mut manifest = ManifestFile::read(file)?;
+    let path = ["containers", "jobs", "cron_jobs"]
+        .iter()
+        .find_map(|kind| manifest.find(kind, name).ok())
+        .ok_or_else(|| {
+            anyhow!(
+                "{}: no container, job or cron job named {}",
+                file.display(),
+                name
+            )
+        })?;
+    let section = manifest.section(&path)?;
+    let original = manifest.contents[section.clone()].to_string();
+
+    // Created afresh and readable only by us, so nothing else in the temp dir can swap it out.
+    let mut scratch = tempfile::Builder::new()
+        .prefix(&format!("nic8s-edit-{}-", name))
+        .suffix(".toml")
+        .tempfile()?;
+    scratch.write_all(original.as_bytes())?;
+    let scratch = scratch.into_temp_path();
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+    // Through the shell, so editors configured with arguments, like `code --wait`, work.
+    let status = process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&scratch)
+        .status()
+        .map_err(|error| anyhow!("failed to run {}: {}", editor, error))?;
+    if !status.success() {
+        return Err(anyhow!(
+            "{} exited with {}, nothing changed",
+            editor,
+            status
+        ));
+    }
+
+    let mut edited = std::fs::read_to_string(&scratch)?;
+    if edited == original {
+        println!("No changes to {} in {}", name, file.display());
+        return Ok(());
+    }
+    if original.ends_with('\n') && !edited.ends_with('\n') {
+        edited.push('\n');
+    }
+    manifest.contents.replace_range(section, &edited);
+
+    let options = LoadOptions {
+        strict: false,
+        values: Values::load(file, &[], &[])?,
+    };
+    let checked = manifest
+        .parse(&options)
+        .and_then(|(parsed, _)| manifest.validate(&parsed));
+    if let Err(error) = checked {
+        let scratch = scratch.keep()?;
+        return Err(anyhow!(
+            "{}\n{} was left as it was; the edit is in {}",
+            error,
+            file.display(),
+            scratch.display()
+        ));
+    }
+    manifest.write()?;
+    println!("Edited {} in {}", name, file.display());
+    Ok(())
+}
+
+fn format_value(value: Option<&Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => String::from("<unset>"),
+    }
+}
@@ -0,0 +1,54 @@
+use clap::Args;
+
+use crate::entities::audit::AuditQuery;
+
+use super::{client::ApiClient, format_age};
+
+#[derive(Args)]
+pub struct AuditArgs {
+    /// Only calls on this kind of resource, e.g. containers or secrets
+    #[arg(long)]
+    resource: Option<String>,
+    /// Only calls on the resource with this name
+    #[arg(long)]
+    name: Option<String>,
+    /// Only calls made by this user
+    #[arg(long)]
+    user: Option<String>,
+    /// Show at most this many of the most recent entries
+    #[arg(long, default_value_t = 50)]
+    limit: usize,
+}
+
+pub async fn run(client: &ApiClient, args: AuditArgs) -> Result<(), anyhow::Error> {
+    let query = AuditQuery {
+        resource: args.resource,
+        name: args.name,
+        user: args.user,
+        limit: Some(args.limit),
+    };
+
+    println!(
+        "{:<8} {:<16} {:<10} {:<32} {:<6} ERROR",
+        "AGE", "USER", "OPERATION", "RESOURCE", "STATUS"
+    );
+    for entry in client.list_audit(&query).await? {
+        let age = chrono::DateTime::parse_from_rfc3339(&entry.timestamp)
+            .map(|timestamp| format_age(chrono::Utc::now().signed_duration_since(timestamp)))
+            .unwrap_or_default();
+        let resource = match &entry.name {
+            Some(name) => format!("{}/{}", entry.resource, name),
+            None => entry.resource.clone(),
+        };
+        println!(
+            "{:<8} {:<16} {:<10} {:<32} {:<6} {}",
+            age,
+            entry.user,
+            entry.operation,
+            resource,
+            entry.status,
+            entry.error.as_deref().unwrap_or("-")
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use clap::Subcommand;
+
+use crate::entities::autoscaler::AutoscalerSpec;
+
+use super::{client::ApiClient, format_age};
+
+#[derive(Subcommand)]
+pub enum AutoscalerCommand {
+    /// Scale an app between --min and --max replicas to hold a target CPU usage
+    Create {
+        app: String,
+        #[arg(long, default_value_t = 1)]
+        min: usize,
+        #[arg(long)]
+        max: usize,
+        /// Average CPU usage per container, as a percentage of its requested CPUs
+        #[arg(long)]
+        cpu_percent: f64,
+        /// Only scale up once load has stayed high for this many seconds
+        #[arg(long, default_value_t = 0)]
+        scale_up_stabilization_seconds: u64,
+        /// Only scale down once load has stayed low for this many seconds
+        #[arg(long, default_value_t = 300)]
+        scale_down_stabilization_seconds: u64,
+    },
+    /// List autoscalers
+    List,
+    /// Delete an autoscaler, leaving its app at the current size
+    Delete { app: String },
+}
+
+pub async fn run(client: &ApiClient, command: AutoscalerCommand) -> Result<(), anyhow::Error> {
+    match command {
+        AutoscalerCommand::Create {
+            app,
+            min,
+            max,
+            cpu_percent,
+            scale_up_stabilization_seconds,
+            scale_down_stabilization_seconds,
+        } => {
+            let spec = AutoscalerSpec {
+                app,
+                min_replicas: min,
+                max_replicas: max,
+                target_cpu_percent: cpu_percent,
+                scale_up_stabilization_seconds,
+                scale_down_stabilization_seconds,
+            };
+
+            let autoscaler = client.create_autoscaler(&spec).await?;
+            println!("autoscaler/{} created", autoscaler.name());
+        }
+        AutoscalerCommand::List => {
+            println!(
+                "{:<24} {:<12} {:<8} {:<8} {:<9} LAST SCALE",
+                "APP", "CPU/TARGET", "MIN", "MAX", "REPLICAS"
+            );
+            for autoscaler in client.list_autoscalers().await? {
+                let cpu = autoscaler
+                    .status
+                    .current_cpu_percent
+                    .map_or(String::from("<unknown>"), |cpu| format!("{:.1}%", cpu));
+                let last_scale = autoscaler
+                    .status
+                    .last_scale_time
+                    .as_ref()
+                    .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+                    .map_or(String::from("<none>"), |time| {
+                        format!(
+                            "{} ago",
+                            format_age(chrono::Utc::now().signed_duration_since(time))
+                        )
+                    });
+
+                println!(
+                    "{:<24} {:<12} {:<8} {:<8} {:<9} {}",
+                    autoscaler.name(),
+                    format!("{}/{}%", cpu, autoscaler.spec.target_cpu_percent),
+                    autoscaler.spec.min_replicas,
+                    autoscaler.spec.max_replicas,
+                    autoscaler.status.current_replicas,
+                    last_scale
+                );
+            }
+        }
+        AutoscalerCommand::Delete { app } => {
+            client.delete_autoscaler(&app).await?;
+            println!("autoscaler/{} deleted", app);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::anyhow;
+
+use crate::{
+    config::Config,
+    store::{
+        backup::Archive,
+        secrets::{self, SecretStore},
+        state::StateStore,
+    },
+};
+
+// Both work on the data dir directly rather than through the API, so a store can be rebuilt
+// before the daemon ever starts on a new host.
+pub async fn backup(config: &Config, output: &Path) -> Result<(), anyhow::Error> {
+    let store = StateStore::open(&config.data_dir).await?;
+    let archive = Archive::create(&store).await?;
+    let contents = serde_json::to_vec_pretty(&archive)?;
+
+    if output == Path::new("-") {
+        println!("{}", String::from_utf8_lossy(&contents));
+        return Ok(());
+    }
+    let tmp = output.with_extension("tmp");
+    tokio::fs::write(&tmp, contents).await?;
+    tokio::fs::rename(&tmp, output).await?;
+    println!("Backed up {} to {}", summary(&archive), output.display());
+    Ok(())
+}
+
+pub async fn restore(config: &Config, input: &Path, replace: bool) -> Result<(), anyhow::Error> {
+    let contents = tokio::fs::read(input)
+        .await
+        .map_err(|error| anyhow!("failed to read {}: {}", input.display(), error))?;
+    let archive = Archive::parse(&contents)?;
+
+    let store = Arc::new(StateStore::open(&config.data_dir).await?);
+    archive.restore(&store, replace).await?;
+    println!(
+        "Restored {} from the backup taken {}; containers are created when the daemon starts",
+        summary(&archive),
+        archive.created
+    );
+
+    // Secrets are encrypted with the original host's master key, which isn't in the backup.
+    if !archive.objects.contains_key(secrets::KIND) {
+        return Ok(());
+    }
+    let key_file = config.master_key_file();
+    if !tokio::fs::try_exists(&key_file).await? {
+        println!(
+            "Copy the master key of the host the backup was taken on to {} before starting the \
+             daemon, or the restored secrets can't be read",
+            key_file.display()
+        );
+        return Ok(());
+    }
+    let secrets = SecretStore::open(store, &key_file).await?;
+    let undecryptable = archive.undecryptable_secrets(&secrets).await;
+    if !undecryptable.is_empty() {
+        println!(
+            "Warning: secrets {} can't be decrypted with {}; copy the master key of the host the \
+             backup was taken on",
+            undecryptable.join(", "),
+            key_file.display()
+        );
+    }
+    Ok(())
+}
+
+fn summary(archive: &Archive) -> String {
+    let counts: Vec<String> = archive
+        .counts()
+        .iter()
+        .map(|(kind, count)| format!("{} {}", count, kind))
+        .collect();
+    match counts.is_empty() {
+        true => String::from("an empty store"),
+        false => counts.join(", "),
+    }
+}
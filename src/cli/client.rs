@@ -0,0 +1,474 @@
+use anyhow::anyhow;
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Url,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    api::containers::Description,
+    entities::{
+        audit::{AuditEntry, AuditQuery, USER_HEADER},
+        autoscaler::{Autoscaler, AutoscalerSpec},
+        config_map::ConfigMap,
+        container::{Container, ContainerSpec},
+        cron_job::{CronJob, CronJobSpec},
+        endpoints::Endpoints,
+        ingress::{Ingress, IngressSpec},
+        job::{Job, JobSpec},
+        node::Node,
+        resource_quota::{ResourceQuota, ResourceQuotaSpec},
+        resource_usage::ResourceUsage,
+        rollout::{Rollout, RolloutSpec},
+        secret::{Secret, SecretMetadata},
+        token::{IssuedToken, Role, Token, TokenSpec},
+        volume::{Volume, VolumeSpec},
+    },
+    watchers::container_status::WatchEvent,
+};
+
+pub struct ApiClient {
+    base_url: String,
+    http: reqwest::Client,
+    token: Option<String>,
+}
+
+impl ApiClient {
+    pub fn new(addr: &str) -> Self {
+        let base_url = if addr.starts_with("http://") || addr.starts_with("https://") {
+            addr.trim_end_matches('/').to_string()
+        } else {
+            format!("http://{}", addr)
+        };
+
+        // Names the caller in the daemon's audit log.
+        let mut headers = HeaderMap::new();
+        if let Some(user) = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .ok()
+            .and_then(|user| HeaderValue::from_str(&user).ok())
+        {
+            headers.insert(USER_HEADER, user);
+        }
+
+        ApiClient {
+            base_url,
+            http: reqwest::Client::builder()
+                .default_headers(headers)
+                .build()
+                .unwrap_or_default(),
+            token: None,
+        }
+    }
+
+    // Sent as the bearer token of every request, for servers with auth enabled.
+    pub fn with_token(mut self, token: Option<&str>) -> Self {
+        self.token = token.map(String::from);
+        self
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, anyhow::Error> {
+        let request = match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "{}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        Ok(response)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, anyhow::Error> {
+        let request = self.http.get(format!("{}{}", self.base_url, path));
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    async fn post(&self, path: &str) -> Result<(), anyhow::Error> {
+        let request = self.http.post(format!("{}{}", self.base_url, path));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn create_container(&self, spec: &ContainerSpec) -> Result<Container, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/containers", self.base_url))
+            .json(spec);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_containers(&self) -> Result<Vec<Container>, anyhow::Error> {
+        self.get("/containers").await
+    }
+
+    // Starts with an ADDED event for every current container, then follows their changes.
+    pub async fn watch_containers(
+        &self,
+        app: Option<&str>,
+    ) -> Result<ContainerWatch, anyhow::Error> {
+        let mut url = Url::parse(&format!("{}/watch/containers", self.base_url))?;
+        if let Some(app) = app {
+            url.query_pairs_mut().append_pair("app", app);
+        }
+        let response = self.send(self.http.get(url)).await?;
+        Ok(ContainerWatch {
+            response,
+            buffer: Vec::new(),
+        })
+    }
+
+    pub async fn delete_container(&self, container: &str) -> Result<(), anyhow::Error> {
+        let request = self
+            .http
+            .delete(format!("{}/containers/{}", self.base_url, container));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn describe(&self, container: &str) -> Result<Description, anyhow::Error> {
+        self.get(&format!("/containers/{}/describe", container))
+            .await
+    }
+
+    pub async fn restart(&self, container: &str) -> Result<(), anyhow::Error> {
+        self.post(&format!("/containers/{}/restart", container))
+            .await
+    }
+
+    pub async fn stop(&self, container: &str) -> Result<(), anyhow::Error> {
+        self.post(&format!("/containers/{}/stop", container)).await
+    }
+
+    pub async fn logs(
+        &self,
+        container: &str,
+        tail: Option<usize>,
+        persisted: bool,
+    ) -> Result<String, anyhow::Error> {
+        let mut url = format!(
+            "{}/containers/{}/logs?persisted={}",
+            self.base_url, container, persisted
+        );
+        if let Some(tail) = tail {
+            url.push_str(&format!("&tail={}", tail));
+        }
+        Ok(self.send(self.http.get(url)).await?.text().await?)
+    }
+
+    pub async fn create_secret(&self, secret: &Secret) -> Result<SecretMetadata, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/secrets", self.base_url))
+            .json(secret);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_secrets(&self) -> Result<Vec<SecretMetadata>, anyhow::Error> {
+        self.get("/secrets").await
+    }
+
+    pub async fn delete_secret(&self, name: &str) -> Result<(), anyhow::Error> {
+        let request = self
+            .http
+            .delete(format!("{}/secrets/{}", self.base_url, name));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn create_config_map(
+        &self,
+        config_map: &ConfigMap,
+    ) -> Result<ConfigMap, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/configmaps", self.base_url))
+            .json(config_map);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_config_maps(&self) -> Result<Vec<ConfigMap>, anyhow::Error> {
+        self.get("/configmaps").await
+    }
+
+    pub async fn get_config_map(&self, name: &str) -> Result<ConfigMap, anyhow::Error> {
+        self.get(&format!("/configmaps/{}", name)).await
+    }
+
+    pub async fn delete_config_map(&self, name: &str) -> Result<(), anyhow::Error> {
+        let request = self
+            .http
+            .delete(format!("{}/configmaps/{}", self.base_url, name));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn create_job(&self, spec: &JobSpec) -> Result<Job, anyhow::Error> {
+        let request = self.http.post(format!("{}/jobs", self.base_url)).json(spec);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_jobs(&self) -> Result<Vec<Job>, anyhow::Error> {
+        self.get("/jobs").await
+    }
+
+    pub async fn delete_job(&self, name: &str) -> Result<(), anyhow::Error> {
+        let request = self.http.delete(format!("{}/jobs/{}", self.base_url, name));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn create_cron_job(&self, spec: &CronJobSpec) -> Result<CronJob, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/cronjobs", self.base_url))
+            .json(spec);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_cron_jobs(&self) -> Result<Vec<CronJob>, anyhow::Error> {
+        self.get("/cronjobs").await
+    }
+
+    pub async fn delete_cron_job(&self, name: &str) -> Result<(), anyhow::Error> {
+        let request = self
+            .http
+            .delete(format!("{}/cronjobs/{}", self.base_url, name));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn create_autoscaler(
+        &self,
+        spec: &AutoscalerSpec,
+    ) -> Result<Autoscaler, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/autoscalers", self.base_url))
+            .json(spec);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_autoscalers(&self) -> Result<Vec<Autoscaler>, anyhow::Error> {
+        self.get("/autoscalers").await
+    }
+
+    pub async fn delete_autoscaler(&self, app: &str) -> Result<(), anyhow::Error> {
+        let request = self
+            .http
+            .delete(format!("{}/autoscalers/{}", self.base_url, app));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn create_rollout(&self, spec: &RolloutSpec) -> Result<Rollout, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/rollouts", self.base_url))
+            .json(spec);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_rollouts(&self) -> Result<Vec<Rollout>, anyhow::Error> {
+        self.get("/rollouts").await
+    }
+
+    pub async fn undo_rollout(&self, app: &str) -> Result<Rollout, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/rollouts/{}/undo", self.base_url, app));
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn delete_rollout(&self, app: &str) -> Result<(), anyhow::Error> {
+        let request = self
+            .http
+            .delete(format!("{}/rollouts/{}", self.base_url, app));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn create_quota(
+        &self,
+        spec: &ResourceQuotaSpec,
+    ) -> Result<ResourceQuota, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/resourcequotas", self.base_url))
+            .json(spec);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_quotas(&self) -> Result<Vec<ResourceQuota>, anyhow::Error> {
+        self.get("/resourcequotas").await
+    }
+
+    pub async fn delete_quota(&self, app: &str) -> Result<(), anyhow::Error> {
+        let request = self
+            .http
+            .delete(format!("{}/resourcequotas/{}", self.base_url, app));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn create_ingress(&self, spec: &IngressSpec) -> Result<Ingress, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/ingresses", self.base_url))
+            .json(spec);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_ingresses(&self) -> Result<Vec<Ingress>, anyhow::Error> {
+        self.get("/ingresses").await
+    }
+
+    pub async fn delete_ingress(&self, name: &str) -> Result<(), anyhow::Error> {
+        let request = self
+            .http
+            .delete(format!("{}/ingresses/{}", self.base_url, name));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn create_volume(&self, spec: &VolumeSpec) -> Result<Volume, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/volumes", self.base_url))
+            .json(spec);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_volumes(&self) -> Result<Vec<Volume>, anyhow::Error> {
+        self.get("/volumes").await
+    }
+
+    pub async fn delete_volume(&self, name: &str, force: bool) -> Result<(), anyhow::Error> {
+        let request = self.http.delete(format!(
+            "{}/volumes/{}?force={}",
+            self.base_url, name, force
+        ));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn list_audit(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, anyhow::Error> {
+        let mut url = Url::parse(&format!("{}/audit", self.base_url))?;
+        {
+            let mut pairs = url.query_pairs_mut();
+            let filters = [
+                ("resource", &query.resource),
+                ("name", &query.name),
+                ("user", &query.user),
+            ];
+            for (key, value) in filters {
+                if let Some(value) = value {
+                    pairs.append_pair(key, value);
+                }
+            }
+            if let Some(limit) = query.limit {
+                pairs.append_pair("limit", &limit.to_string());
+            }
+        }
+        Ok(self.send(self.http.get(url)).await?.json().await?)
+    }
+
+    pub async fn create_token(&self, spec: &TokenSpec) -> Result<IssuedToken, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/tokens", self.base_url))
+            .json(spec);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_tokens(&self) -> Result<Vec<Token>, anyhow::Error> {
+        self.get("/tokens").await
+    }
+
+    pub async fn delete_token(&self, name: &str) -> Result<(), anyhow::Error> {
+        let request = self
+            .http
+            .delete(format!("{}/tokens/{}", self.base_url, name));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn list_roles(&self) -> Result<Vec<Role>, anyhow::Error> {
+        self.get("/roles").await
+    }
+
+    pub async fn register_node(&self, node: &Node) -> Result<Node, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}/nodes", self.base_url))
+            .json(node);
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    pub async fn list_endpoints(&self) -> Result<Vec<Endpoints>, anyhow::Error> {
+        self.get("/endpoints").await
+    }
+
+    pub async fn list_nodes(&self) -> Result<Vec<Node>, anyhow::Error> {
+        self.get("/nodes").await
+    }
+
+    pub async fn delete_node(&self, name: &str) -> Result<(), anyhow::Error> {
+        let request = self
+            .http
+            .delete(format!("{}/nodes/{}", self.base_url, name));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    pub async fn stats(&self) -> Result<Vec<ResourceUsage>, anyhow::Error> {
+        self.get("/stats").await
+    }
+}
+
+// The server-sent events of `/watch/containers`.
+pub struct ContainerWatch {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+}
+
+impl ContainerWatch {
+    // The next event, or None once the daemon ends the stream.
+    pub async fn next(&mut self) -> Result<Option<WatchEvent>, anyhow::Error> {
+        loop {
+            // Events end with a blank line. Keep-alives are comments, without any data.
+            while let Some(end) = self.buffer.windows(2).position(|window| window == b"\n\n") {
+                let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+                let data: Vec<&str> = std::str::from_utf8(&block)?
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("data:"))
+                    .map(str::trim_start)
+                    .collect();
+                if !data.is_empty() {
+                    return Ok(Some(serde_json::from_str(&data.join("\n"))?));
+                }
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None => return Ok(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::anyhow;
+use clap::Subcommand;
+
+use crate::entities::config_map::ConfigMap;
+
+use super::client::ApiClient;
+
+#[derive(Subcommand)]
+pub enum ConfigMapCommand {
+    /// Create or replace a config map
+    Create {
+        name: String,
+        /// KEY=VALUE pair, can be repeated
+        #[arg(long = "from-literal")]
+        literals: Vec<String>,
+        /// File to add, keyed by its file name or by KEY=PATH, can be repeated
+        #[arg(long = "from-file")]
+        files: Vec<String>,
+    },
+    /// List config maps and their keys
+    List,
+    /// Print the contents of a config map
+    Get { name: String },
+    /// Delete a config map that no container mounts
+    Delete { name: String },
+}
+
+pub async fn run(client: &ApiClient, command: ConfigMapCommand) -> Result<(), anyhow::Error> {
+    match command {
+        ConfigMapCommand::Create {
+            name,
+            literals,
+            files,
+        } => {
+            let mut data = BTreeMap::new();
+            for literal in literals {
+                let (key, value) = literal
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("expected KEY=VALUE, got {}", literal))?;
+                data.insert(String::from(key), String::from(value));
+            }
+
+            for file in files {
+                let (key, path) = match file.split_once('=') {
+                    Some((key, path)) => (String::from(key), Path::new(path)),
+                    None => {
+                        let path = Path::new(&file);
+                        let key = path
+                            .file_name()
+                            .ok_or_else(|| anyhow!("{} is not a file", file))?
+                            .to_string_lossy()
+                            .to_string();
+                        (key, path)
+                    }
+                };
+                let contents = std::fs::read_to_string(path)
+                    .map_err(|error| anyhow!("failed to read {}: {}", path.display(), error))?;
+                data.insert(key, contents);
+            }
+
+            if data.is_empty() {
+                return Err(anyhow!(
+                    "provide at least one --from-literal or --from-file"
+                ));
+            }
+
+            let config_map = client.create_config_map(&ConfigMap { name, data }).await?;
+            println!("configmap/{} configured", config_map.name);
+        }
+        ConfigMapCommand::List => {
+            println!("{:<24} {:<6} KEYS", "NAME", "DATA");
+            for config_map in client.list_config_maps().await? {
+                println!(
+                    "{:<24} {:<6} {}",
+                    config_map.name,
+                    config_map.data.len(),
+                    config_map
+                        .data
+                        .keys()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(",")
+                );
+            }
+        }
+        ConfigMapCommand::Get { name } => {
+            let config_map = client.get_config_map(&name).await?;
+            for (key, value) in config_map.data.iter() {
+                println!("==> {} <==", key);
+                println!("{}", value.trim_end());
+            }
+        }
+        ConfigMapCommand::Delete { name } => {
+            client.delete_config_map(&name).await?;
+            println!("configmap/{} deleted", name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use clap::Subcommand;
+
+use crate::entities::{
+    container::ContainerSpec,
+    cron_job::{ConcurrencyPolicy, CronJobSpec},
+    job::JobSpec,
+};
+
+use super::{client::ApiClient, format_age};
+
+#[derive(Subcommand)]
+pub enum CronJobCommand {
+    /// Create jobs on a cron schedule (evaluated in UTC)
+    Create {
+        name: String,
+        /// Five-field cron expression or @hourly, @daily, @weekly, @monthly, @yearly
+        #[arg(long)]
+        schedule: String,
+        #[arg(long)]
+        image: String,
+        /// Allow, Forbid or Replace runs while a previous one is still active
+        #[arg(long, default_value = "Allow")]
+        concurrency_policy: ConcurrencyPolicy,
+        /// Skip runs that could not start within this many seconds of their schedule
+        #[arg(long)]
+        starting_deadline_seconds: Option<u64>,
+        /// Failed attempts each job retries before it is marked Failed
+        #[arg(long, default_value_t = 6)]
+        backoff_limit: u32,
+        /// Command to run instead of the image default
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
+    /// List cron jobs
+    List,
+    /// Delete a cron job and the jobs it created
+    Delete { name: String },
+}
+
+pub async fn run(client: &ApiClient, command: CronJobCommand) -> Result<(), anyhow::Error> {
+    match command {
+        CronJobCommand::Create {
+            name,
+            schedule,
+            image,
+            concurrency_policy,
+            starting_deadline_seconds,
+            backoff_limit,
+            command,
+        } => {
+            let spec = CronJobSpec {
+                job: JobSpec {
+                    template: ContainerSpec {
+                        name,
+                        image,
+                        command,
+                        ..ContainerSpec::default()
+                    },
+                    backoff_limit,
+                },
+                schedule,
+                concurrency_policy,
+                starting_deadline_seconds,
+                successful_jobs_history_limit: 3,
+                failed_jobs_history_limit: 1,
+            };
+
+            let cron_job = client.create_cron_job(&spec).await?;
+            println!("cronjob/{} created", cron_job.name());
+        }
+        CronJobCommand::List => {
+            println!(
+                "{:<24} {:<16} {:<8} {:<7} {:<8} LAST SCHEDULE",
+                "NAME", "SCHEDULE", "POLICY", "ACTIVE", "HISTORY"
+            );
+            for cron_job in client.list_cron_jobs().await? {
+                let last_schedule = cron_job
+                    .status
+                    .last_schedule_time
+                    .as_ref()
+                    .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+                    .map_or(String::from("<none>"), |time| {
+                        format!(
+                            "{} ago",
+                            format_age(chrono::Utc::now().signed_duration_since(time))
+                        )
+                    });
+
+                println!(
+                    "{:<24} {:<16} {:<8} {:<7} {:<8} {}",
+                    cron_job.name(),
+                    cron_job.spec.schedule,
+                    format!("{:?}", cron_job.spec.concurrency_policy),
+                    cron_job.status.active.len(),
+                    cron_job.status.history.len(),
+                    last_schedule
+                );
+            }
+        }
+        CronJobCommand::Delete { name } => {
+            client.delete_cron_job(&name).await?;
+            println!("cronjob/{} deleted", name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,336 @@
+use std::{collections::BTreeMap, collections::HashMap, sync::Arc, time::Duration};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState, Wrap},
+    DefaultTerminal, Frame,
+};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tonic::Request;
+
+use crate::{
+    api::grpc::proto::{self, control_plane_client::ControlPlaneClient},
+    entities::{
+        container::{Container, ContainerStatus},
+        resource_usage::{format_size, ResourceUsage},
+    },
+};
+
+use super::{client::ApiClient, format_age};
+
+const STATS_INTERVAL: Duration = Duration::from_secs(2);
+const LOG_LINES: usize = 100;
+
+enum Message {
+    Watch(proto::WatchEventType, Box<Container>),
+    Stats(Vec<ResourceUsage>),
+    Logs(String, String),
+    Status(String),
+    Key(KeyEvent),
+}
+
+struct Dashboard {
+    client: Arc<ApiClient>,
+    sender: UnboundedSender<Message>,
+    containers: BTreeMap<String, Container>,
+    usage: HashMap<String, ResourceUsage>,
+    table_state: TableState,
+    logs: Option<(String, String)>,
+    status_line: String,
+}
+
+pub async fn run(client: ApiClient, grpc_addr: &str) -> Result<(), anyhow::Error> {
+    let client = Arc::new(client);
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+
+    spawn_watch(grpc_addr, client.token().map(String::from), sender.clone());
+    spawn_stats(client.clone(), sender.clone());
+    spawn_keys(sender.clone());
+
+    let mut dashboard = Dashboard {
+        client,
+        sender,
+        containers: BTreeMap::new(),
+        usage: HashMap::new(),
+        table_state: TableState::default(),
+        logs: None,
+        status_line: String::from("connecting..."),
+    };
+
+    let mut terminal = ratatui::init();
+    let result = dashboard.run(&mut terminal, &mut receiver).await;
+    ratatui::restore();
+    result
+}
+
+fn spawn_watch(grpc_addr: &str, token: Option<String>, sender: UnboundedSender<Message>) {
+    let grpc_url = format!("http://{}", grpc_addr);
+
+    tokio::spawn(async move {
+        let result: Result<(), anyhow::Error> = async {
+            let mut client = ControlPlaneClient::connect(grpc_url).await?;
+            let mut request = Request::new(proto::WatchContainersRequest { app: None });
+            if let Some(token) = token {
+                request
+                    .metadata_mut()
+                    .insert("authorization", format!("Bearer {}", token).parse()?);
+            }
+            let mut stream = client.watch_containers(request).await?.into_inner();
+
+            let _ = sender.send(Message::Status(String::from("watching containers")));
+            while let Some(event) = stream.message().await? {
+                if let Some(container) = event.container.clone() {
+                    let _ = sender.send(Message::Watch(event.r#type(), Box::new(container.into())));
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        let message = match result {
+            Ok(()) => String::from("watch stream closed"),
+            Err(error) => format!("watch failed: {}", error),
+        };
+        let _ = sender.send(Message::Status(message));
+    });
+}
+
+fn spawn_stats(client: Arc<ApiClient>, sender: UnboundedSender<Message>) {
+    tokio::spawn(async move {
+        loop {
+            match client.stats().await {
+                Ok(stats) => {
+                    if sender.send(Message::Stats(stats)).is_err() {
+                        return;
+                    }
+                }
+                Err(error) => {
+                    let _ = sender.send(Message::Status(format!("stats failed: {}", error)));
+                }
+            }
+            tokio::time::sleep(STATS_INTERVAL).await;
+        }
+    });
+}
+
+fn spawn_keys(sender: UnboundedSender<Message>) {
+    // crossterm's event::read blocks, so keys are read on a dedicated thread.
+    std::thread::spawn(move || loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                if sender.send(Message::Key(key)).is_err() {
+                    return;
+                }
+            }
+            Ok(_) => {}
+            Err(_) => return,
+        }
+    });
+}
+
+impl Dashboard {
+    async fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        receiver: &mut mpsc::UnboundedReceiver<Message>,
+    ) -> Result<(), anyhow::Error> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Some(message) = receiver.recv().await else {
+                return Ok(());
+            };
+
+            match message {
+                Message::Watch(proto::WatchEventType::Deleted, container) => {
+                    self.containers.remove(&container.name);
+                }
+                Message::Watch(_, container) => {
+                    self.containers.insert(container.name.clone(), *container);
+                }
+                Message::Stats(stats) => {
+                    self.usage = stats
+                        .into_iter()
+                        .map(|usage| (usage.container_id.clone(), usage))
+                        .collect();
+
+                    if let Some((name, _)) = &self.logs {
+                        self.fetch_logs(name.clone());
+                    }
+                }
+                Message::Logs(name, logs) => {
+                    if self.logs.as_ref().is_some_and(|(open, _)| open == &name) {
+                        self.logs = Some((name, logs));
+                    }
+                }
+                Message::Status(status) => self.status_line = status,
+                Message::Key(key) => {
+                    if !self.handle_key(key) {
+                        return Ok(());
+                    }
+                }
+            }
+
+            if self.table_state.selected().is_none() && !self.containers.is_empty() {
+                self.table_state.select(Some(0));
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Esc if self.logs.is_some() => self.logs = None,
+            KeyCode::Esc => return false,
+            KeyCode::Down | KeyCode::Char('j') => self.select(1),
+            KeyCode::Up | KeyCode::Char('k') => self.select(-1),
+            KeyCode::Char('r') => self.act("restart"),
+            KeyCode::Char('s') => self.act("stop"),
+            KeyCode::Char('l') => {
+                if let Some(name) = self.selected() {
+                    self.logs = Some((name.clone(), String::from("loading logs...")));
+                    self.fetch_logs(name);
+                }
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.containers.is_empty() {
+            return;
+        }
+
+        let current = self.table_state.selected().unwrap_or(0) as isize;
+        let last = self.containers.len() as isize - 1;
+        self.table_state
+            .select(Some((current + offset).clamp(0, last) as usize));
+    }
+
+    fn selected(&self) -> Option<String> {
+        let index = self.table_state.selected()?;
+        self.containers.keys().nth(index).cloned()
+    }
+
+    fn act(&mut self, action: &'static str) {
+        let Some(name) = self.selected() else {
+            return;
+        };
+
+        let client = self.client.clone();
+        let sender = self.sender.clone();
+        self.status_line = format!("{} {}...", action, name);
+
+        tokio::spawn(async move {
+            let result = match action {
+                "restart" => client.restart(&name).await,
+                _ => client.stop(&name).await,
+            };
+
+            let status = match result {
+                Ok(()) => format!("{} {}: ok", action, name),
+                Err(error) => format!("{} {} failed: {}", action, name, error),
+            };
+            let _ = sender.send(Message::Status(status));
+        });
+    }
+
+    fn fetch_logs(&self, name: String) {
+        let client = self.client.clone();
+        let sender = self.sender.clone();
+
+        tokio::spawn(async move {
+            let logs = client
+                .logs(&name, Some(LOG_LINES), false)
+                .await
+                .unwrap_or_else(|error| format!("failed to fetch logs: {}", error));
+            let _ = sender.send(Message::Logs(name, logs));
+        });
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let constraints = if self.logs.is_some() {
+            vec![
+                Constraint::Percentage(50),
+                Constraint::Percentage(50),
+                Constraint::Length(1),
+            ]
+        } else {
+            vec![Constraint::Min(3), Constraint::Length(1)]
+        };
+        let areas = Layout::vertical(constraints).split(frame.area());
+
+        let header = Row::new([
+            "NAME", "APP", "STATUS", "UPTIME", "RESTARTS", "CPU", "MEMORY", "IMAGE",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+
+        let rows = self.containers.values().map(|container| {
+            let usage = self.usage.get(&container.id);
+            let status = container.get_status();
+            let color = match status {
+                ContainerStatus::Running => Color::Green,
+                ContainerStatus::Exited
+                | ContainerStatus::Dead
+                | ContainerStatus::CrashLoopBackOff => Color::Red,
+                _ => Color::Yellow,
+            };
+
+            Row::new(vec![
+                container.name.clone(),
+                container.app.clone(),
+                format!("{:?}", status),
+                container.uptime().map_or(String::from("-"), format_age),
+                container.restart_count.to_string(),
+                usage.map_or(String::from("-"), |usage| {
+                    format!("{:.1}%", usage.cpu_percent)
+                }),
+                usage.map_or(String::from("-"), |usage| format_size(usage.memory_bytes)),
+                container.spec.image.clone(),
+            ])
+            .style(Style::default().fg(color))
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(18),
+                Constraint::Percentage(12),
+                Constraint::Length(10),
+                Constraint::Length(9),
+                Constraint::Length(9),
+                Constraint::Length(7),
+                Constraint::Length(10),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title(" nic8s "))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(table, areas[0], &mut self.table_state);
+
+        if let Some((name, logs)) = &self.logs {
+            let height = areas[1].height.saturating_sub(2) as usize;
+            let lines: Vec<&str> = logs.lines().collect();
+            let visible = lines[lines.len().saturating_sub(height)..].join("\n");
+
+            let logs = Paragraph::new(visible).wrap(Wrap { trim: false }).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" logs: {} ", name)),
+            );
+            frame.render_widget(logs, areas[1]);
+        }
+
+        let footer = Paragraph::new(format!(
+            "q quit  j/k move  r restart  s stop  l logs  esc close | {}",
+            self.status_line
+        ));
+        frame.render_widget(footer, areas[areas.len() - 1]);
+    }
+}
@@ -0,0 +1,209 @@
+use std::fmt::Write;
+
+use crate::{
+    api::containers::Description,
+    entities::{labels::format_labels, resource_usage::format_size},
+};
+
+use super::{client::ApiClient, format_age};
+
+pub async fn run(client: &ApiClient, container: &str) -> Result<(), anyhow::Error> {
+    let description = client.describe(container).await?;
+    print!("{}", render(&description));
+    Ok(())
+}
+
+fn render(description: &Description) -> String {
+    let container = &description.container;
+    let mut out = String::new();
+
+    let _ = writeln!(out, "Name:         {}", container.name);
+    let _ = writeln!(out, "ID:           {}", container.id);
+    let _ = writeln!(out, "App:          {}", container.app);
+    if !container.node.is_empty() {
+        let _ = writeln!(out, "Node:         {}", container.node);
+    }
+    let _ = writeln!(out, "Created:      {}", container.created);
+    let _ = writeln!(out, "Status:       {:?}", container.get_status());
+    if let Some(health) = &container.health {
+        let _ = writeln!(out, "Health:       {}", health);
+    }
+    if let Some(started_at) = &container.started_at {
+        let _ = writeln!(out, "Started:      {}", started_at);
+    }
+    match &container.last_restart {
+        Some(last_restart) => {
+            let _ = writeln!(
+                out,
+                "Restarts:     {} (last {})",
+                container.restart_count, last_restart
+            );
+        }
+        None => {
+            let _ = writeln!(out, "Restarts:     {}", container.restart_count);
+        }
+    }
+    if let Some(until) = &container.back_off_until {
+        let _ = writeln!(out, "Back-off:     until {}", until);
+    }
+    if let Some(termination) = &container.last_termination {
+        let _ = writeln!(
+            out,
+            "Last exit:    {} at {}",
+            termination, termination.finished_at
+        );
+    }
+    let _ = writeln!(out, "Spec:");
+    let _ = writeln!(out, "  Image:      {}", container.spec.image);
+    if let Some(digest) = &container.image_digest {
+        let _ = writeln!(out, "  Image ID:   {}", digest);
+    }
+    if container.spec.auto_update {
+        let _ = writeln!(out, "  Updates:    automatic");
+    }
+    let _ = writeln!(out, "  Ports:      {}", container.spec.ports);
+    if !container.spec.resources.is_empty() {
+        let _ = writeln!(
+            out,
+            "  Requests:   {} CPUs, {} memory",
+            container.spec.resources.cpus,
+            format_size(container.spec.resources.memory_bytes)
+        );
+    }
+    let _ = writeln!(out, "  Restart:    {:?}", container.spec.restart_policy);
+    let _ = writeln!(
+        out,
+        "  Stop:       {}, {}s grace period",
+        container.spec.stop_signal(),
+        container.spec.termination_grace_period().as_secs()
+    );
+    for (point, hook) in [
+        ("PostStart", &container.spec.lifecycle.post_start),
+        ("PreStop", &container.spec.lifecycle.pre_stop),
+    ] {
+        if let Some(hook) = hook {
+            let action = match &hook.http {
+                Some(http) => format!("{} {}", http.method, http.url),
+                None => format!("exec {}", hook.exec.join(" ")),
+            };
+            let _ = writeln!(
+                out,
+                "  {:<11} {} (timeout {}s, on failure {:?})",
+                format!("{}:", point),
+                action,
+                hook.timeout_seconds,
+                hook.on_failure
+            );
+        }
+    }
+    if !container.spec.labels.is_empty() {
+        let _ = writeln!(
+            out,
+            "  Labels:     {}",
+            format_labels(&container.spec.labels)
+        );
+    }
+    if !container.spec.node_selector.is_empty() {
+        let _ = writeln!(
+            out,
+            "  Selector:   {}",
+            format_labels(&container.spec.node_selector)
+        );
+    }
+    if !container.spec.affinity.is_empty() || !container.spec.anti_affinity.is_empty() {
+        let _ = writeln!(
+            out,
+            "  Affinity:   {} rule(s), {} anti-affinity rule(s)",
+            container.spec.affinity.len(),
+            container.spec.anti_affinity.len()
+        );
+    }
+    if let Some(probe) = &container.spec.readiness_probe {
+        let _ = writeln!(
+            out,
+            "  Readiness:  {} every {}s after {}s, {} failures (ready: {})",
+            probe.command,
+            probe.period_seconds,
+            probe.initial_delay_seconds,
+            probe.failure_threshold,
+            container.is_ready()
+        );
+    }
+    if !container.spec.depends_on.is_empty() {
+        let _ = writeln!(out, "  Depends On:");
+        for dependency in container.spec.depends_on.iter() {
+            let _ = writeln!(out, "    {} ({})", dependency.name, dependency.condition);
+        }
+    }
+    if !container.spec.init_containers.is_empty() {
+        let _ = writeln!(out, "  Init Containers:");
+        for init in container.spec.init_containers.iter() {
+            let _ = writeln!(out, "    {} ({})", init.name, init.image);
+        }
+    }
+    if let Some(secret) = &container.spec.env_from_secret {
+        let _ = writeln!(out, "  Env From:   secret/{}", secret);
+    }
+    if !container.spec.config_maps.is_empty() || !container.spec.volumes.is_empty() {
+        let _ = writeln!(out, "  Mounts:");
+        for claim in container.spec.volumes.iter() {
+            let _ = writeln!(
+                out,
+                "    {} from volume/{}{}",
+                claim.mount_path,
+                claim.name,
+                if claim.read_only { " (read-only)" } else { "" }
+            );
+        }
+        for mount in container.spec.config_maps.iter() {
+            let _ = writeln!(
+                out,
+                "    {} from configmap/{}{}",
+                mount.mount_path,
+                mount.name,
+                if mount.restart_on_change {
+                    " (restart on change)"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+
+    let now = chrono::Utc::now();
+    if !description.history.is_empty() {
+        let _ = writeln!(out, "Status History:");
+        for transition in description.history.iter() {
+            let _ = writeln!(
+                out,
+                "  {:<10} {} ({} ago)",
+                format!("{:?}", transition.status),
+                transition
+                    .timestamp
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                format_age(now.signed_duration_since(transition.timestamp))
+            );
+        }
+    }
+
+    if description.events.is_empty() {
+        let _ = writeln!(out, "Events:       <none>");
+        return out;
+    }
+
+    let _ = writeln!(out, "Events:");
+    let _ = writeln!(out, "  {:<8} {:<10} {:<6} Message", "Type", "Reason", "Age");
+    let _ = writeln!(out, "  {:<8} {:<10} {:<6} -------", "----", "------", "---");
+    for event in description.events.iter() {
+        let _ = writeln!(
+            out,
+            "  {:<8} {:<10} {:<6} {}",
+            format!("{:?}", event.event_type),
+            format!("{:?}", event.reason),
+            format_age(now.signed_duration_since(event.timestamp)),
+            event.message
+        );
+    }
+
+    out
+}
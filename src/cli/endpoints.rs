@@ -0,0 +1,39 @@
+use crate::entities::endpoints::Endpoint;
+
+use super::client::ApiClient;
+
+pub async fn run(client: &ApiClient, app: Option<&str>) -> Result<(), anyhow::Error> {
+    let endpoints: Vec<_> = client
+        .list_endpoints()
+        .await?
+        .into_iter()
+        .filter(|endpoints| app.is_none_or(|app| endpoints.app == app))
+        .collect();
+    if let (Some(app), true) = (app, endpoints.is_empty()) {
+        return Err(anyhow::anyhow!("app {} not found", app));
+    }
+
+    println!("{:<24} {:<8} {:<40} NOT READY", "APP", "READY", "ENDPOINTS");
+    for endpoints in endpoints {
+        let total = endpoints.ready.len() + endpoints.not_ready.len();
+        println!(
+            "{:<24} {:<8} {:<40} {}",
+            endpoints.app,
+            format!("{}/{}", endpoints.ready.len(), total),
+            format_endpoints(&endpoints.ready),
+            format_endpoints(&endpoints.not_ready)
+        );
+    }
+    Ok(())
+}
+
+fn format_endpoints(endpoints: &[Endpoint]) -> String {
+    if endpoints.is_empty() {
+        return String::from("<none>");
+    }
+    endpoints
+        .iter()
+        .map(|endpoint| format!("{}@{}", endpoint.container, endpoint.node))
+        .collect::<Vec<_>>()
+        .join(",")
+}
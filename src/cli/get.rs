@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{
+    entities::container::{Container, ContainerStatus},
+    watchers::container_status::WatchEventType,
+};
+
+use super::{client::ApiClient, format_age};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    /// The API's JSON, for scripting with jq
+    Json,
+    Toml,
+    /// The table with image, restarts, age and id columns
+    Wide,
+}
+
+// TOML documents can't be a bare array.
+#[derive(Serialize)]
+struct TomlContainers<'a> {
+    containers: &'a [Container],
+}
+
+pub async fn run(
+    client: &ApiClient,
+    name: Option<&str>,
+    app: Option<&str>,
+    output: Option<OutputFormat>,
+    watch: bool,
+) -> Result<(), anyhow::Error> {
+    let mut containers: Vec<Container> = client
+        .list_containers()
+        .await?
+        .into_iter()
+        .filter(|container| name.is_none_or(|name| container.name == name || container.id == name))
+        .filter(|container| app.is_none_or(|app| container.app == app))
+        .collect();
+    containers.sort_by(|a, b| a.name.cmp(&b.name));
+    if let (Some(name), true) = (name, containers.is_empty()) {
+        return Err(anyhow::anyhow!("container {} not found", name));
+    }
+
+    // A single container is printed as an object rather than a list of one.
+    match output {
+        Some(OutputFormat::Json) => match (name, containers.first()) {
+            (Some(_), Some(container)) => println!("{}", serde_json::to_string_pretty(container)?),
+            _ => println!("{}", serde_json::to_string_pretty(&containers)?),
+        },
+        Some(OutputFormat::Toml) => match (name, containers.first()) {
+            (Some(_), Some(container)) => print!("{}", toml::to_string_pretty(container)?),
+            _ => print!(
+                "{}",
+                toml::to_string_pretty(&TomlContainers {
+                    containers: &containers
+                })?
+            ),
+        },
+        Some(OutputFormat::Wide) => print_table(&containers, true),
+        None => print_table(&containers, false),
+    }
+    if watch {
+        follow(client, name, app, output, &containers).await?;
+    }
+    Ok(())
+}
+
+// Prints the changes to the listed containers, and to containers added after them, until the
+// daemon ends the stream. JSON output gets every watch event, one per line.
+async fn follow(
+    client: &ApiClient,
+    name: Option<&str>,
+    app: Option<&str>,
+    output: Option<OutputFormat>,
+    listed: &[Container],
+) -> Result<(), anyhow::Error> {
+    let mut states: HashMap<String, String> = listed
+        .iter()
+        .map(|container| (container.id.clone(), state(container)))
+        .collect();
+    let mut watch = client.watch_containers(app).await?;
+    while let Some(event) = watch.next().await? {
+        let container = &event.object;
+        if name.is_some_and(|name| container.name != name && container.id != name) {
+            continue;
+        }
+        if let Some(OutputFormat::Json) = output {
+            println!("{}", serde_json::to_string(&event)?);
+            continue;
+        }
+
+        // The stream opens with every current container, which mostly repeats the list.
+        let (before, after) = match event.event_type {
+            WatchEventType::Deleted => (states.remove(&container.id), String::from("<deleted>")),
+            WatchEventType::Added | WatchEventType::Modified => (
+                states.insert(container.id.clone(), state(container)),
+                state(container),
+            ),
+        };
+        let before = before.unwrap_or_else(|| String::from("<new>"));
+        if before != after {
+            println!("{:<24} {} -> {}", container.name, before, after);
+        }
+    }
+    Ok(())
+}
+
+// What a transition line shows of a container.
+fn state(container: &Container) -> String {
+    let status = container.get_status();
+    if status == ContainerStatus::Running && !container.is_ready() {
+        return format!("{:?} (not ready)", status);
+    }
+    format!("{:?}", status)
+}
+
+fn print_table(containers: &[Container], wide: bool) {
+    let mut header = format!(
+        "{:<24} {:<16} {:<10} {:<6} {:<12} {:<16}",
+        "NAME", "APP", "STATUS", "READY", "NODE", "PORTS"
+    );
+    if wide {
+        header.push_str(&format!(
+            " {:<24} {:<9} {:<7} ID",
+            "IMAGE", "RESTARTS", "AGE"
+        ));
+    }
+    println!("{}", header.trim_end());
+
+    let now = chrono::Utc::now();
+    for container in containers {
+        let mut row = format!(
+            "{:<24} {:<16} {:<10} {:<6} {:<12} {:<16}",
+            container.name,
+            container.app,
+            format!("{:?}", container.get_status()),
+            container.is_ready(),
+            if container.node.is_empty() {
+                "-"
+            } else {
+                &container.node
+            },
+            if container.spec.ports.is_empty() {
+                "-"
+            } else {
+                &container.spec.ports
+            }
+        );
+        if wide {
+            let age = chrono::DateTime::parse_from_rfc3339(&container.created)
+                .map(|created| format_age(now.signed_duration_since(created)))
+                .unwrap_or_else(|_| String::from("-"));
+            row.push_str(&format!(
+                " {:<24} {:<9} {:<7} {}",
+                container.spec.image,
+                container.restart_count,
+                age,
+                &container.id[..container.id.len().min(12)]
+            ));
+        }
+        println!("{}", row.trim_end());
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::anyhow;
+use clap::Subcommand;
+
+use crate::entities::ingress::{IngressRule, IngressSpec, IngressTls};
+
+use super::{client::ApiClient, format_age};
+
+#[derive(Subcommand)]
+pub enum IngressCommand {
+    /// Route requests to apps; served when ingress is enabled in the daemon config
+    Create {
+        name: String,
+        /// [HOST]/PATH=APP, e.g. example.com/api=web or /=web for any host; repeatable
+        #[arg(long = "rule", required = true, value_parser = parse_rule)]
+        rules: Vec<IngressRule>,
+        /// HOST[,HOST...]=SECRET: terminate TLS for the hosts with the certificate in tls.crt
+        /// and tls.key of the secret; repeatable
+        #[arg(long, value_parser = parse_tls)]
+        tls: Vec<IngressTls>,
+    },
+    /// List ingresses
+    List,
+    /// Delete an ingress
+    Delete { name: String },
+}
+
+fn parse_rule(rule: &str) -> Result<IngressRule, anyhow::Error> {
+    let invalid = || anyhow!("invalid rule {:?}: expected [HOST]/PATH=APP", rule);
+    let (route, app) = rule.rsplit_once('=').ok_or_else(invalid)?;
+    let slash = route.find('/').ok_or_else(invalid)?;
+    let (host, path) = route.split_at(slash);
+    if app.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok(IngressRule {
+        host: Some(host).filter(|host| !host.is_empty()).map(String::from),
+        path: String::from(path),
+        app: String::from(app),
+    })
+}
+
+fn parse_tls(tls: &str) -> Result<IngressTls, anyhow::Error> {
+    let (hosts, secret) = tls
+        .rsplit_once('=')
+        .ok_or_else(|| anyhow!("invalid tls {:?}: expected HOST[,HOST...]=SECRET", tls))?;
+
+    Ok(IngressTls {
+        hosts: hosts.split(',').map(String::from).collect(),
+        secret: String::from(secret),
+    })
+}
+
+pub async fn run(client: &ApiClient, command: IngressCommand) -> Result<(), anyhow::Error> {
+    match command {
+        IngressCommand::Create { name, rules, tls } => {
+            let ingress = client
+                .create_ingress(&IngressSpec { name, rules, tls })
+                .await?;
+            println!("ingress/{} created", ingress.name());
+        }
+        IngressCommand::List => {
+            println!("{:<24} {:<8} {:<24} RULES", "NAME", "AGE", "TLS");
+            for ingress in client.list_ingresses().await? {
+                let age = chrono::DateTime::parse_from_rfc3339(&ingress.created)
+                    .map(|created| format_age(chrono::Utc::now().signed_duration_since(created)))
+                    .unwrap_or_default();
+                let rules: Vec<String> = ingress
+                    .spec
+                    .rules
+                    .iter()
+                    .map(|rule| {
+                        format!(
+                            "{}{}={}",
+                            rule.host.as_deref().unwrap_or(""),
+                            rule.path,
+                            rule.app
+                        )
+                    })
+                    .collect();
+                let tls: Vec<&str> = ingress
+                    .spec
+                    .tls
+                    .iter()
+                    .flat_map(|tls| tls.hosts.iter().map(String::as_str))
+                    .collect();
+                let tls = match tls.is_empty() {
+                    true => String::from("-"),
+                    false => tls.join(","),
+                };
+                println!(
+                    "{:<24} {:<8} {:<24} {}",
+                    ingress.name(),
+                    age,
+                    tls,
+                    rules.join(" ")
+                );
+            }
+        }
+        IngressCommand::Delete { name } => {
+            client.delete_ingress(&name).await?;
+            println!("ingress/{} deleted", name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use clap::Subcommand;
+
+use crate::entities::{
+    container::ContainerSpec,
+    job::{Job, JobSpec},
+};
+
+use super::{client::ApiClient, format_age};
+
+#[derive(Subcommand)]
+pub enum JobCommand {
+    /// Run a container to completion
+    Create {
+        name: String,
+        #[arg(long)]
+        image: String,
+        /// Failed attempts to retry before the job is marked Failed
+        #[arg(long, default_value_t = 6)]
+        backoff_limit: u32,
+        /// Inject every key of this secret as an environment variable
+        #[arg(long)]
+        env_from_secret: Option<String>,
+        /// Command to run instead of the image default
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
+    /// List jobs and their status
+    List,
+    /// Delete a job and its containers
+    Delete { name: String },
+}
+
+pub async fn run(client: &ApiClient, command: JobCommand) -> Result<(), anyhow::Error> {
+    match command {
+        JobCommand::Create {
+            name,
+            image,
+            backoff_limit,
+            env_from_secret,
+            command,
+        } => {
+            let spec = JobSpec {
+                template: ContainerSpec {
+                    name,
+                    image,
+                    command,
+                    env_from_secret,
+                    ..ContainerSpec::default()
+                },
+                backoff_limit,
+            };
+
+            let job = client.create_job(&spec).await?;
+            println!("job/{} created", job.name());
+        }
+        JobCommand::List => {
+            println!(
+                "{:<24} {:<10} {:<9} {:<6} AGE",
+                "NAME", "STATUS", "ATTEMPTS", "EXIT"
+            );
+            for job in client.list_jobs().await? {
+                print_row(&job);
+            }
+        }
+        JobCommand::Delete { name } => {
+            client.delete_job(&name).await?;
+            println!("job/{} deleted", name);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_row(job: &Job) {
+    let exit_code = job
+        .status
+        .attempts
+        .last()
+        .and_then(|attempt| attempt.exit_code)
+        .map_or(String::from("-"), |code| code.to_string());
+    let age = job
+        .status
+        .start_time
+        .as_ref()
+        .and_then(|start| chrono::DateTime::parse_from_rfc3339(start).ok())
+        .map_or(String::from("-"), |start| {
+            format_age(chrono::Utc::now().signed_duration_since(start))
+        });
+
+    println!(
+        "{:<24} {:<10} {:<9} {:<6} {}",
+        job.name(),
+        format!("{:?}", job.status.phase),
+        job.status.attempts.len(),
+        exit_code,
+        age
+    );
+}
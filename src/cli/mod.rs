@@ -0,0 +1,237 @@
+pub mod apply;
+pub mod audit;
+pub mod autoscaler;
+pub mod backup;
+pub mod client;
+pub mod config_map;
+pub mod cron_job;
+pub mod dashboard;
+pub mod describe;
+pub mod endpoints;
+pub mod get;
+pub mod ingress;
+pub mod job;
+pub mod node;
+pub mod quota;
+pub mod rollout;
+pub mod secret;
+pub mod token;
+pub mod top;
+pub mod volume;
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::{agent, api, entities::labels::parse_label};
+
+#[derive(Parser)]
+#[command(name = "nic8s", about = "k8s from scratch")]
+pub struct Cli {
+    #[arg(long, global = true, env = "NIC8S_API_ADDR", default_value = api::DEFAULT_ADDR)]
+    pub server: String,
+
+    #[arg(long, global = true, env = "NIC8S_GRPC_ADDR", default_value = api::grpc::DEFAULT_ADDR)]
+    pub grpc: String,
+
+    /// Bearer token for servers with auth enabled
+    #[arg(long, global = true, env = "NIC8S_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+
+    /// Path to the daemon config file (defaults to ./nic8s.toml when present)
+    #[arg(long, global = true, env = "NIC8S_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the nic8s daemon with a local node (default)
+    Serve,
+    /// Run only the control plane; containers run on registered agents
+    Server,
+    /// Run a node agent that runs containers for the control plane at --server
+    Agent {
+        /// Name the node registers under
+        #[arg(long, env = "NIC8S_NODE_NAME")]
+        name: String,
+        /// Address the agent's runtime API listens on
+        #[arg(long, default_value = agent::DEFAULT_ADDR)]
+        listen: String,
+        /// Address the control plane should use to reach this agent (defaults to --listen)
+        #[arg(long)]
+        advertise: Option<String>,
+        /// Label the node for node selectors and affinity rules, as key=value; repeatable
+        #[arg(long = "label", value_parser = parse_label)]
+        labels: Vec<(String, String)>,
+    },
+    /// Create or update the containers, jobs and cron jobs described in a TOML manifest
+    Apply {
+        #[command(flatten)]
+        manifest: apply::ManifestArgs,
+        /// Print what would change without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show what applying a manifest would create, change or delete
+    Diff {
+        #[command(flatten)]
+        manifest: apply::ManifestArgs,
+    },
+    /// Change a container's image in a manifest, keeping the rest of the file as written
+    SetImage {
+        /// Name of the container in the manifest
+        container: String,
+        image: String,
+        /// Path to the manifest
+        #[arg(short, long)]
+        file: PathBuf,
+    },
+    /// Edit a container, job or cron job of a manifest in $EDITOR, keeping the rest of the file as written
+    Edit {
+        /// Name of the container, job or cron job in the manifest
+        name: String,
+        /// Path to the manifest
+        #[arg(short, long)]
+        file: PathBuf,
+    },
+    /// Manage nodes
+    Node {
+        #[command(subcommand)]
+        command: node::NodeCommand,
+    },
+    /// List containers, or show one
+    Get {
+        /// Container id or name
+        container: Option<String>,
+        /// Only list the containers of this app
+        #[arg(long)]
+        app: Option<String>,
+        #[arg(short, long, value_enum)]
+        output: Option<get::OutputFormat>,
+        /// After listing, print each change as it happens, e.g. `web-1  Running -> Exited`
+        #[arg(short, long)]
+        watch: bool,
+    },
+    /// Show a detailed report about a container
+    Describe {
+        /// Container id or name
+        container: String,
+    },
+    /// Print a container's output
+    Logs {
+        /// Container id or name
+        container: String,
+        /// Only print the last N lines
+        #[arg(long)]
+        tail: Option<usize>,
+        /// Read the persisted logs, which include the output of earlier containers by that name
+        /// (removed containers are always read from there)
+        #[arg(long)]
+        persisted: bool,
+    },
+    /// Show which containers of each app are ready to receive traffic
+    Endpoints {
+        /// Only show this app
+        app: Option<String>,
+    },
+    /// Live terminal dashboard of managed containers
+    Dashboard,
+    /// Show the CPU, memory and network usage of containers, and of nodes when there are agents
+    Top {
+        /// Only show the containers of this app
+        #[arg(long)]
+        app: Option<String>,
+        #[arg(long, value_enum, default_value_t = top::SortBy::Cpu)]
+        sort: top::SortBy,
+        /// Seconds between refreshes
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+        /// Print the usage once instead of refreshing it
+        #[arg(long)]
+        once: bool,
+    },
+    /// Manage secrets
+    Secret {
+        #[command(subcommand)]
+        command: secret::SecretCommand,
+    },
+    /// Manage config maps mounted into containers as files
+    #[command(name = "configmap")]
+    ConfigMap {
+        #[command(subcommand)]
+        command: config_map::ConfigMapCommand,
+    },
+    /// Manage run-to-completion jobs
+    Job {
+        #[command(subcommand)]
+        command: job::JobCommand,
+    },
+    /// Manage jobs that run on a cron schedule
+    #[command(name = "cronjob")]
+    CronJob {
+        #[command(subcommand)]
+        command: cron_job::CronJobCommand,
+    },
+    /// Manage autoscalers that resize apps based on their CPU usage
+    Autoscaler {
+        #[command(subcommand)]
+        command: autoscaler::AutoscalerCommand,
+    },
+    /// Roll out new versions of apps gradually
+    Rollout {
+        #[command(subcommand)]
+        command: rollout::RolloutCommand,
+    },
+    /// Manage ingresses that route HTTP requests to apps by host and path
+    Ingress {
+        #[command(subcommand)]
+        command: ingress::IngressCommand,
+    },
+    /// Manage quotas on the resources an app's containers may request
+    Quota {
+        #[command(subcommand)]
+        command: quota::QuotaCommand,
+    },
+    /// Write the desired state in the data dir (containers, jobs, secrets, ...) to a backup file
+    Backup {
+        /// File to write, or - for stdout
+        output: PathBuf,
+    },
+    /// Rebuild the desired state in the data dir from a backup, while the daemon is stopped;
+    /// the backed up containers are created when it next starts
+    Restore {
+        backup: PathBuf,
+        /// Overwrite desired state already in the data dir
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Manage named volumes that containers claim
+    Volume {
+        #[command(subcommand)]
+        command: volume::VolumeCommand,
+    },
+    /// Manage API tokens, for servers with auth enabled
+    Token {
+        #[command(subcommand)]
+        command: token::TokenCommand,
+    },
+    /// Show the audit log of calls that changed the cluster, oldest first
+    Audit {
+        #[command(flatten)]
+        args: audit::AuditArgs,
+    },
+}
+
+pub fn format_age(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m{}s", seconds / 60, seconds % 60),
+        3600..=86399 => format!("{}h{}m", seconds / 3600, (seconds % 3600) / 60),
+        _ => format!("{}d{}h", seconds / 86400, (seconds % 86400) / 3600),
+    }
+}
@@ -0,0 +1,46 @@
+use clap::Subcommand;
+
+use crate::entities::{labels::format_labels, resource_usage::format_size};
+
+use super::{client::ApiClient, format_age};
+
+#[derive(Subcommand)]
+pub enum NodeCommand {
+    /// List the nodes containers can be scheduled on
+    List,
+    /// Remove a node that no longer runs containers
+    Delete { name: String },
+}
+
+pub async fn run(client: &ApiClient, command: NodeCommand) -> Result<(), anyhow::Error> {
+    match command {
+        NodeCommand::List => {
+            println!(
+                "{:<24} {:<10} {:<24} {:<6} {:<10} {:<12} LABELS",
+                "NAME", "STATUS", "ADDRESS", "CPUS", "MEMORY", "LAST SEEN"
+            );
+            for node in client.list_nodes().await? {
+                let last_seen = node
+                    .since_last_seen()
+                    .map(|since| format!("{} ago", format_age(since)))
+                    .unwrap_or_else(|| String::from("-"));
+                println!(
+                    "{:<24} {:<10} {:<24} {:<6} {:<10} {:<12} {}",
+                    node.name,
+                    format!("{:?}", node.status),
+                    node.address.as_deref().unwrap_or("-"),
+                    node.capacity.cpus,
+                    format_size(node.capacity.memory_bytes),
+                    last_seen,
+                    format_labels(&node.labels)
+                );
+            }
+        }
+        NodeCommand::Delete { name } => {
+            client.delete_node(&name).await?;
+            println!("node/{} deleted", name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use clap::Subcommand;
+
+use crate::entities::{resource_quota::ResourceQuotaSpec, resource_usage::format_size};
+
+use super::client::ApiClient;
+
+#[derive(Subcommand)]
+pub enum QuotaCommand {
+    /// Limit the total requests of an app's containers; creates over a limit are rejected
+    Create {
+        app: String,
+        #[arg(long)]
+        cpus: Option<f64>,
+        #[arg(long)]
+        memory_bytes: Option<u64>,
+        /// Number of containers
+        #[arg(long)]
+        containers: Option<usize>,
+    },
+    /// List quotas with what each app uses of them
+    List,
+    /// Delete a quota, lifting the app's limits
+    Delete { app: String },
+}
+
+pub async fn run(client: &ApiClient, command: QuotaCommand) -> Result<(), anyhow::Error> {
+    match command {
+        QuotaCommand::Create {
+            app,
+            cpus,
+            memory_bytes,
+            containers,
+        } => {
+            let spec = ResourceQuotaSpec {
+                app,
+                cpus,
+                memory_bytes,
+                containers,
+            };
+
+            let quota = client.create_quota(&spec).await?;
+            println!("resourcequota/{} created", quota.name());
+        }
+        QuotaCommand::List => {
+            println!("{:<24} {:<14} {:<22} CONTAINERS", "APP", "CPUS", "MEMORY");
+            for quota in client.list_quotas().await? {
+                let limit = |limit: Option<String>| limit.unwrap_or_else(|| String::from("-"));
+                let cpus = format!(
+                    "{}/{}",
+                    quota.used.cpus,
+                    limit(quota.spec.cpus.map(|cpus| cpus.to_string()))
+                );
+                let memory = format!(
+                    "{}/{}",
+                    format_size(quota.used.memory_bytes),
+                    limit(quota.spec.memory_bytes.map(format_size))
+                );
+                let containers = limit(
+                    quota
+                        .spec
+                        .containers
+                        .map(|containers| containers.to_string()),
+                );
+                println!(
+                    "{:<24} {:<14} {:<22} {}/{}",
+                    quota.name(),
+                    cpus,
+                    memory,
+                    quota.used.containers,
+                    containers
+                );
+            }
+        }
+        QuotaCommand::Delete { app } => {
+            client.delete_quota(&app).await?;
+            println!("resourcequota/{} deleted", app);
+        }
+    }
+
+    Ok(())
+}
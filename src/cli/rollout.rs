@@ -0,0 +1,169 @@
+use anyhow::anyhow;
+use clap::Subcommand;
+
+use crate::entities::{
+    container::ContainerSpec,
+    rollout::{BlueGreenStrategy, CanaryStrategy, MetricCheck, RolloutSpec, RolloutStrategy},
+};
+
+use super::{client::ApiClient, format_age};
+
+#[derive(Subcommand)]
+pub enum RolloutCommand {
+    /// Roll an app out to a new image through canaries, which replace the other replicas only
+    /// once they got through the analysis window
+    Canary {
+        app: String,
+        #[arg(long)]
+        image: String,
+        /// Canaries to run, as a percentage of the app's replicas
+        #[arg(long, default_value_t = 20)]
+        percent: u32,
+        #[arg(long, default_value_t = 300)]
+        analysis_seconds: u64,
+        /// Lowest share of analysis checks, from 0 to 1, that must find the canaries ready
+        #[arg(long)]
+        min_success_rate: Option<f64>,
+        /// Roll back unless a GET of this URL answers with a number within --metric-min and
+        /// --metric-max on every check
+        #[arg(long)]
+        metric_url: Option<String>,
+        #[arg(long, requires = "metric_url")]
+        metric_min: Option<f64>,
+        #[arg(long, requires = "metric_url")]
+        metric_max: Option<f64>,
+        /// Seconds new containers get to become ready
+        #[arg(long, default_value_t = 600)]
+        progress_deadline_seconds: u64,
+    },
+    /// Roll an app out to a new image by starting a second, full set of replicas and switching
+    /// its traffic over once all of them are ready
+    BlueGreen {
+        app: String,
+        #[arg(long)]
+        image: String,
+        /// Seconds the old set is kept after the switch, during which `rollout undo` switches
+        /// traffic back to it
+        #[arg(long, default_value_t = 300)]
+        scale_down_delay_seconds: u64,
+        /// Seconds new containers get to become ready
+        #[arg(long, default_value_t = 600)]
+        progress_deadline_seconds: u64,
+    },
+    /// Take a rollout in progress back, switching traffic back to the old replicas
+    Undo { app: String },
+    /// List rollouts
+    List,
+    /// Delete a rollout, removing its canaries if it's still in progress
+    Delete { app: String },
+}
+
+pub async fn run(client: &ApiClient, command: RolloutCommand) -> Result<(), anyhow::Error> {
+    match command {
+        RolloutCommand::Canary {
+            app,
+            image,
+            percent,
+            analysis_seconds,
+            min_success_rate,
+            metric_url,
+            metric_min,
+            metric_max,
+            progress_deadline_seconds,
+        } => {
+            let template = template(client, &app, image).await?;
+            let spec = RolloutSpec {
+                app,
+                template,
+                strategy: RolloutStrategy::Canary(CanaryStrategy {
+                    percent,
+                    analysis_seconds,
+                    min_success_rate,
+                    metric: metric_url.map(|url| MetricCheck {
+                        url,
+                        min: metric_min,
+                        max: metric_max,
+                    }),
+                }),
+                progress_deadline_seconds,
+            };
+
+            let rollout = client.create_rollout(&spec).await?;
+            println!("rollout/{} created", rollout.name());
+        }
+        RolloutCommand::BlueGreen {
+            app,
+            image,
+            scale_down_delay_seconds,
+            progress_deadline_seconds,
+        } => {
+            let template = template(client, &app, image).await?;
+            let spec = RolloutSpec {
+                app,
+                template,
+                strategy: RolloutStrategy::BlueGreen(BlueGreenStrategy {
+                    scale_down_delay_seconds,
+                }),
+                progress_deadline_seconds,
+            };
+
+            let rollout = client.create_rollout(&spec).await?;
+            println!("rollout/{} created", rollout.name());
+        }
+        RolloutCommand::Undo { app } => {
+            client.undo_rollout(&app).await?;
+            println!("rollout/{} undone", app);
+        }
+        RolloutCommand::List => {
+            println!(
+                "{:<24} {:<12} {:<24} {:<12} {:<9} AGE",
+                "APP", "STRATEGY", "IMAGE", "PHASE", "PROMOTED"
+            );
+            for rollout in client.list_rollouts().await? {
+                let strategy = match &rollout.spec.strategy {
+                    RolloutStrategy::Canary(canary) => format!("canary/{}%", canary.percent),
+                    RolloutStrategy::BlueGreen(_) => String::from("blue-green"),
+                };
+                let age = chrono::DateTime::parse_from_rfc3339(&rollout.created)
+                    .map(|created| format_age(chrono::Utc::now().signed_duration_since(created)))
+                    .unwrap_or_else(|_| String::from("<unknown>"));
+
+                println!(
+                    "{:<24} {:<12} {:<24} {:<12} {:<9} {}",
+                    rollout.name(),
+                    strategy,
+                    rollout.spec.template.image,
+                    format!("{:?}", rollout.status.phase),
+                    rollout.status.promoted.len(),
+                    age
+                );
+                if let Some(message) = &rollout.status.message {
+                    println!("  {}", message);
+                }
+            }
+        }
+        RolloutCommand::Delete { app } => {
+            client.delete_rollout(&app).await?;
+            println!("rollout/{} deleted", app);
+        }
+    }
+
+    Ok(())
+}
+
+// The app's current spec, with the new image.
+async fn template(
+    client: &ApiClient,
+    app: &str,
+    image: String,
+) -> Result<ContainerSpec, anyhow::Error> {
+    let mut template = client
+        .list_containers()
+        .await?
+        .into_iter()
+        .find(|container| container.app == app)
+        .ok_or_else(|| anyhow!("app {} has no containers to roll out", app))?
+        .spec;
+    template.image = image;
+    Ok(template)
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use clap::Subcommand;
+
+use crate::entities::secret::Secret;
+
+use super::client::ApiClient;
+
+#[derive(Subcommand)]
+pub enum SecretCommand {
+    /// Create or replace a secret
+    Create {
+        name: String,
+        /// KEY=VALUE pair, can be repeated
+        #[arg(long = "from-literal", required_unless_present = "files")]
+        literals: Vec<String>,
+        /// KEY=PATH pair whose value is the file's contents, e.g. tls.crt=cert.pem; can be
+        /// repeated
+        #[arg(long = "from-file")]
+        files: Vec<String>,
+    },
+    /// List secrets and their keys (values are never shown)
+    List,
+    /// Delete a secret
+    Delete { name: String },
+}
+
+pub async fn run(client: &ApiClient, command: SecretCommand) -> Result<(), anyhow::Error> {
+    match command {
+        SecretCommand::Create {
+            name,
+            literals,
+            files,
+        } => {
+            let mut data = BTreeMap::new();
+            for literal in literals {
+                let (key, value) = literal
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("expected KEY=VALUE, got {}", literal))?;
+                data.insert(String::from(key), String::from(value));
+            }
+            for file in files {
+                let (key, path) = file
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("expected KEY=PATH, got {}", file))?;
+                let value = tokio::fs::read_to_string(path)
+                    .await
+                    .map_err(|error| anyhow!("failed to read {}: {}", path, error))?;
+                data.insert(String::from(key), value);
+            }
+
+            let metadata = client.create_secret(&Secret { name, data }).await?;
+            println!("secret/{} created", metadata.name);
+        }
+        SecretCommand::List => {
+            println!("{:<24} {:<6} KEYS", "NAME", "DATA");
+            for secret in client.list_secrets().await? {
+                println!(
+                    "{:<24} {:<6} {}",
+                    secret.name,
+                    secret.keys.len(),
+                    secret.keys.join(",")
+                );
+            }
+        }
+        SecretCommand::Delete { name } => {
+            client.delete_secret(&name).await?;
+            println!("secret/{} deleted", name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use clap::Subcommand;
+
+use crate::entities::token::TokenSpec;
+
+use super::{client::ApiClient, format_age};
+
+#[derive(Subcommand)]
+pub enum TokenCommand {
+    /// Create an API token and print its secret, which can't be shown again
+    Create {
+        /// Also the user the token's calls are recorded under
+        name: String,
+        /// Role to grant, e.g. admin, viewer or node; can be repeated
+        #[arg(long = "role", required = true)]
+        roles: Vec<String>,
+    },
+    /// List API tokens and their roles
+    List,
+    /// Delete an API token, revoking it immediately
+    Delete { name: String },
+    /// List the roles tokens can be given and what they allow
+    Roles,
+}
+
+pub async fn run(client: &ApiClient, command: TokenCommand) -> Result<(), anyhow::Error> {
+    match command {
+        TokenCommand::Create { name, roles } => {
+            let issued = client.create_token(&TokenSpec { name, roles }).await?;
+            println!("token/{} created", issued.token.name);
+            println!("{}", issued.secret);
+        }
+        TokenCommand::List => {
+            println!("{:<24} {:<8} ROLES", "NAME", "AGE");
+            for token in client.list_tokens().await? {
+                let age = chrono::DateTime::parse_from_rfc3339(&token.created)
+                    .map(|created| format_age(chrono::Utc::now().signed_duration_since(created)))
+                    .unwrap_or_default();
+                println!("{:<24} {:<8} {}", token.name, age, token.roles.join(","));
+            }
+        }
+        TokenCommand::Delete { name } => {
+            client.delete_token(&name).await?;
+            println!("token/{} deleted", name);
+        }
+        TokenCommand::Roles => {
+            println!("{:<16} {:<32} {:<32} APPS", "ROLE", "VERBS", "RESOURCES");
+            for role in client.list_roles().await? {
+                for rule in role.rules.iter() {
+                    println!(
+                        "{:<16} {:<32} {:<32} {}",
+                        role.name,
+                        rule.verbs.join(","),
+                        rule.resources.join(","),
+                        rule.apps.join(",")
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
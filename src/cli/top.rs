@@ -0,0 +1,191 @@
+use std::{cmp::Ordering, collections::HashMap, fmt::Write, time::Duration};
+
+use clap::ValueEnum;
+
+use crate::entities::{
+    container::Container,
+    node::Node,
+    resource_usage::{format_size, ResourceUsage},
+};
+
+use super::client::ApiClient;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SortBy {
+    Name,
+    Cpu,
+    Memory,
+    /// Bytes received
+    NetRx,
+    /// Bytes sent
+    NetTx,
+}
+
+pub async fn run(
+    client: &ApiClient,
+    app: Option<&str>,
+    sort: SortBy,
+    interval: u64,
+    once: bool,
+) -> Result<(), anyhow::Error> {
+    loop {
+        let table = render(client, app, sort).await?;
+        if once {
+            print!("{}", table);
+            return Ok(());
+        }
+        // Clears the screen and moves the cursor home, so the table redraws in place.
+        print!("\x1b[2J\x1b[H{}", table);
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+async fn render(
+    client: &ApiClient,
+    app: Option<&str>,
+    sort: SortBy,
+) -> Result<String, anyhow::Error> {
+    let (containers, stats, nodes) = tokio::try_join!(
+        client.list_containers(),
+        client.stats(),
+        client.list_nodes()
+    )?;
+    let usage: HashMap<String, ResourceUsage> = stats
+        .into_iter()
+        .map(|usage| (usage.container_id.clone(), usage))
+        .collect();
+
+    let mut out = String::new();
+    // Nodes only get a table of their own when containers run on agents.
+    if nodes.iter().any(|node| node.address.is_some()) {
+        node_table(&mut out, &nodes, &containers, &usage, sort);
+        out.push('\n');
+    }
+
+    let mut containers: Vec<&Container> = containers
+        .iter()
+        .filter(|container| app.is_none_or(|app| container.app == app))
+        .collect();
+    containers.sort_by(|a, b| {
+        compare(sort, usage.get(&a.id), usage.get(&b.id)).then_with(|| a.name.cmp(&b.name))
+    });
+    let _ = writeln!(
+        out,
+        "{:<24} {:<16} {:<12} {:>7} {:>22} NET I/O",
+        "NAME", "APP", "NODE", "CPU", "MEMORY"
+    );
+    for container in containers {
+        let usage = usage.get(&container.id);
+        let _ = writeln!(
+            out,
+            "{:<24} {:<16} {:<12} {:>7} {:>22} {}",
+            container.name,
+            container.app,
+            container.node,
+            cpu(usage),
+            memory(usage),
+            network(usage)
+        );
+    }
+    Ok(out)
+}
+
+fn node_table(
+    out: &mut String,
+    nodes: &[Node],
+    containers: &[Container],
+    usage: &HashMap<String, ResourceUsage>,
+    sort: SortBy,
+) {
+    // Each node's usage is the sum of its containers', with its capacity as the limit.
+    let mut totals: Vec<(&Node, usize, ResourceUsage)> = nodes
+        .iter()
+        .map(|node| {
+            let mut total = ResourceUsage {
+                memory_limit_bytes: node.capacity.memory_bytes,
+                ..ResourceUsage::default()
+            };
+            let mut count = 0;
+            for container in containers.iter().filter(|c| c.node == node.name) {
+                count += 1;
+                if let Some(usage) = usage.get(&container.id) {
+                    total.cpu_percent += usage.cpu_percent;
+                    total.memory_bytes += usage.memory_bytes;
+                    total.net_rx_bytes += usage.net_rx_bytes;
+                    total.net_tx_bytes += usage.net_tx_bytes;
+                }
+            }
+            // Per container the CPU is a share of one core; per node it's a share of them all.
+            if node.capacity.cpus > 0.0 {
+                total.cpu_percent /= node.capacity.cpus;
+            }
+            (node, count, total)
+        })
+        .collect();
+    totals.sort_by(|a, b| {
+        compare(sort, Some(&a.2), Some(&b.2)).then_with(|| a.0.name.cmp(&b.0.name))
+    });
+
+    let _ = writeln!(
+        out,
+        "{:<24} {:<10} {:>10} {:>7} {:>22} NET I/O",
+        "NODE", "STATUS", "CONTAINERS", "CPU", "MEMORY"
+    );
+    for (node, count, total) in totals {
+        let _ = writeln!(
+            out,
+            "{:<24} {:<10} {:>10} {:>7} {:>22} {}",
+            node.name,
+            format!("{:?}", node.status),
+            count,
+            cpu(Some(&total)),
+            memory(Some(&total)),
+            network(Some(&total))
+        );
+    }
+}
+
+// Busiest first, with containers the runtime has no stats for last. Sorting by name is left to
+// the caller's tie-break.
+fn compare(sort: SortBy, a: Option<&ResourceUsage>, b: Option<&ResourceUsage>) -> Ordering {
+    let key = |usage: &ResourceUsage| match sort {
+        SortBy::Name => None,
+        SortBy::Cpu => Some(usage.cpu_percent),
+        SortBy::Memory => Some(usage.memory_bytes as f64),
+        SortBy::NetRx => Some(usage.net_rx_bytes as f64),
+        SortBy::NetTx => Some(usage.net_tx_bytes as f64),
+    };
+    match (a.and_then(key), b.and_then(key)) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn cpu(usage: Option<&ResourceUsage>) -> String {
+    usage.map_or(String::from("-"), |usage| {
+        format!("{:.1}%", usage.cpu_percent)
+    })
+}
+
+fn memory(usage: Option<&ResourceUsage>) -> String {
+    usage.map_or(String::from("-"), |usage| match usage.memory_limit_bytes {
+        0 => format_size(usage.memory_bytes),
+        limit => format!(
+            "{} / {}",
+            format_size(usage.memory_bytes),
+            format_size(limit)
+        ),
+    })
+}
+
+fn network(usage: Option<&ResourceUsage>) -> String {
+    usage.map_or(String::from("-"), |usage| {
+        format!(
+            "{} / {}",
+            format_size(usage.net_rx_bytes),
+            format_size(usage.net_tx_bytes)
+        )
+    })
+}
@@ -0,0 +1,59 @@
+use clap::Subcommand;
+
+use crate::entities::volume::VolumeSpec;
+
+use super::{client::ApiClient, format_age};
+
+#[derive(Subcommand)]
+pub enum VolumeCommand {
+    /// Create a named volume for containers to claim
+    Create {
+        name: String,
+        /// Create it on this node now, instead of on the node of the first container claiming it
+        #[arg(long)]
+        node: Option<String>,
+    },
+    /// List volumes with the containers using them
+    List,
+    /// Delete a volume and its data
+    Delete {
+        name: String,
+        /// Delete it even though containers still claim it
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+pub async fn run(client: &ApiClient, command: VolumeCommand) -> Result<(), anyhow::Error> {
+    match command {
+        VolumeCommand::Create { name, node } => {
+            let volume = client.create_volume(&VolumeSpec { name, node }).await?;
+            println!("volume/{} created", volume.name);
+        }
+        VolumeCommand::List => {
+            println!("{:<24} {:<16} {:<8} USED BY", "NAME", "NODE", "AGE");
+            for volume in client.list_volumes().await? {
+                let age = chrono::DateTime::parse_from_rfc3339(&volume.created)
+                    .map(|created| format_age(chrono::Utc::now().signed_duration_since(created)))
+                    .unwrap_or_default();
+                let used_by = match volume.used_by.is_empty() {
+                    true => String::from("-"),
+                    false => volume.used_by.join(","),
+                };
+                println!(
+                    "{:<24} {:<16} {:<8} {}",
+                    volume.name,
+                    volume.node.as_deref().unwrap_or("<unbound>"),
+                    age,
+                    used_by
+                );
+            }
+        }
+        VolumeCommand::Delete { name, force } => {
+            client.delete_volume(&name, force).await?;
+            println!("volume/{} deleted", name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use crate::{
+    admission::AdmissionChain,
+    events::recorder::EventRecorder,
+    runtime::{nodes::NodeRuntime, ContainerRuntime},
+    store::{
+        config_maps::ConfigMapStore, secrets::SecretStore, state::StateStore, volumes::VolumeStore,
+    },
+    watchers::{container_status::ContainerStatusWatcher, log_collector::LogCollector},
+};
+
+// Everything needed to create and manage containers, shared by the API and the controllers.
+#[derive(Clone)]
+pub struct Cluster {
+    // The same router as `nodes`, for callers that only need to act on existing containers.
+    pub runtime: Arc<dyn ContainerRuntime + Send + Sync>,
+    pub nodes: Arc<NodeRuntime>,
+    pub status_watcher: Arc<ContainerStatusWatcher>,
+    pub events: Arc<EventRecorder>,
+    pub state: Arc<StateStore>,
+    pub secrets: Arc<SecretStore>,
+    pub config_maps: Arc<ConfigMapStore>,
+    pub volumes: Arc<VolumeStore>,
+    pub admission: Arc<AdmissionChain>,
+    // Set when container logs are persisted.
+    pub logs: Option<Arc<LogCollector>>,
+}
@@ -0,0 +1,619 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    entities::{labels::Labels, token::Role},
+    events::event::{EventReason, EventType, ObjectKind},
+};
+
+const DEFAULT_PATH: &str = "nic8s.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub data_dir: PathBuf,
+    pub shutdown: ShutdownConfig,
+    pub secrets: SecretsConfig,
+    pub nodes: NodesConfig,
+    pub scheduler: SchedulerConfig,
+    pub gc: GcConfig,
+    pub retry: RetryConfig,
+    #[serde(deserialize_with = "runtime_config")]
+    pub runtime: RuntimeConfig,
+    pub ports: PortsConfig,
+    pub image_updates: ImageUpdatesConfig,
+    pub image_gc: ImageGcConfig,
+    pub autoscaler: AutoscalerConfig,
+    pub rollouts: RolloutConfig,
+    pub leader_election: LeaderElectionConfig,
+    pub admission: AdmissionConfig,
+    pub notifications: NotificationsConfig,
+    pub logs: LogsConfig,
+    pub ingress: IngressConfig,
+    pub auth: AuthConfig,
+    pub crash_loop: CrashLoopConfig,
+    pub drift: DriftConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            data_dir: PathBuf::from(".nic8s"),
+            shutdown: ShutdownConfig::default(),
+            secrets: SecretsConfig::default(),
+            nodes: NodesConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            gc: GcConfig::default(),
+            retry: RetryConfig::default(),
+            runtime: RuntimeConfig::default(),
+            ports: PortsConfig::default(),
+            image_updates: ImageUpdatesConfig::default(),
+            image_gc: ImageGcConfig::default(),
+            autoscaler: AutoscalerConfig::default(),
+            rollouts: RolloutConfig::default(),
+            leader_election: LeaderElectionConfig::default(),
+            admission: AdmissionConfig::default(),
+            notifications: NotificationsConfig::default(),
+            logs: LogsConfig::default(),
+            ingress: IngressConfig::default(),
+            auth: AuthConfig::default(),
+            crash_loop: CrashLoopConfig::default(),
+            drift: DriftConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    pub stop_containers: bool,
+    pub grace_period_seconds: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        ShutdownConfig {
+            stop_containers: false,
+            grace_period_seconds: 10,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SecretsConfig {
+    // Defaults to `<data_dir>/master.key`, generated on first start.
+    pub master_key_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct NodesConfig {
+    // How often agents report to the control plane.
+    pub heartbeat_interval_seconds: u64,
+    // Agents silent for longer are marked NotReady and their containers rescheduled.
+    pub heartbeat_timeout_seconds: u64,
+    // Labels of the node this process runs containers on, the local node or an agent, for node
+    // selectors and affinity topology keys.
+    pub labels: Labels,
+}
+
+impl Default for NodesConfig {
+    fn default() -> Self {
+        NodesConfig {
+            heartbeat_interval_seconds: 10,
+            heartbeat_timeout_seconds: 40,
+            labels: Labels::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    // "spread" balances containers across nodes, "bin-packing" fills nodes one at a time.
+    pub strategy: String,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig {
+            strategy: String::from("spread"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct GcConfig {
+    pub interval_seconds: u64,
+    // Orphans younger than this are left alone, which covers containers still being created.
+    pub grace_period_seconds: u64,
+    // Only log the orphans that would be removed.
+    pub dry_run: bool,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        GcConfig {
+            interval_seconds: 60,
+            grace_period_seconds: 300,
+            dry_run: false,
+        }
+    }
+}
+
+// A container restarted more than `max_restarts` times within `window_seconds` is held stopped in
+// CrashLoopBackOff before it may run again. Each further restart doubles the back-off, until the
+// container stays up for a whole window.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CrashLoopConfig {
+    pub max_restarts: usize,
+    pub window_seconds: u64,
+    pub initial_backoff_seconds: u64,
+    pub max_backoff_seconds: u64,
+}
+
+impl Default for CrashLoopConfig {
+    fn default() -> Self {
+        CrashLoopConfig {
+            max_restarts: 5,
+            window_seconds: 600,
+            initial_backoff_seconds: 10,
+            max_backoff_seconds: 300,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DriftPolicy {
+    // Only record a DriftDetected event.
+    #[default]
+    Report,
+    // Also start stopped containers again and recreate removed or changed ones.
+    Repair,
+}
+
+// Managed containers changed directly through docker, rather than through nic8s, are checked for
+// every `interval_seconds` and handled according to `policy`.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct DriftConfig {
+    pub interval_seconds: u64,
+    pub policy: DriftPolicy,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        DriftConfig {
+            interval_seconds: 30,
+            policy: DriftPolicy::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuntimeBackend {
+    #[default]
+    Docker,
+    Podman,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct RuntimeConfig {
+    // The engine this machine's containers run on.
+    pub backend: RuntimeBackend,
+    // Engine operations, such as creating or inspecting a container, run at once on each node:
+    // the limit applies to every node's engine separately, agents included, not to the cluster
+    // as a whole. The others queue. Execs, like lifecycle hooks, aren't limited.
+    pub max_concurrent_operations: usize,
+    pub docker: DockerConfig,
+    pub podman: PodmanConfig,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        RuntimeConfig {
+            backend: RuntimeBackend::default(),
+            max_concurrent_operations: 16,
+            docker: DockerConfig::default(),
+            podman: PodmanConfig::default(),
+        }
+    }
+}
+
+// `runtime = "podman"` is short for a `[runtime]` table that only picks the backend.
+fn runtime_config<'de, D: Deserializer<'de>>(deserializer: D) -> Result<RuntimeConfig, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Setting {
+        Backend(RuntimeBackend),
+        Config(RuntimeConfig),
+    }
+
+    Ok(match Setting::deserialize(deserializer)? {
+        Setting::Backend(backend) => RuntimeConfig {
+            backend,
+            ..RuntimeConfig::default()
+        },
+        Setting::Config(config) => config,
+    })
+}
+
+// The docker engine the node's containers run on, which may be on another machine. Whatever is
+// left unset comes from the environment the way the docker CLI reads it, DOCKER_HOST and the
+// current context included. Config maps are mounted from this machine's data directory, so
+// containers that mount them need an engine on the same machine.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DockerConfig {
+    // A unix://, tcp:// or ssh:// address, as in DOCKER_HOST.
+    pub host: Option<String>,
+    // A context made with `docker context create`, instead of `host`.
+    pub context: Option<String>,
+    // Verify a tcp:// engine's certificate against the ca.pem in `cert_path`, as
+    // DOCKER_TLS_VERIFY does.
+    pub tls_verify: bool,
+    // Where ca.pem, and the cert.pem and key.pem the client authenticates with, are.
+    pub cert_path: Option<PathBuf>,
+}
+
+// The podman engine, when it's the backend. As with docker, what is left unset comes from the
+// environment the way the podman CLI reads it, CONTAINER_HOST and the default connection
+// included.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PodmanConfig {
+    // A unix://, tcp:// or ssh:// address, as in CONTAINER_HOST.
+    pub host: Option<String>,
+    // A connection made with `podman system connection add`, instead of `host`.
+    pub connection: Option<String>,
+}
+
+// Transient runtime failures are retried with exponential backoff. `[retry.operations.<name>]`
+// overrides any of the settings for one operation, e.g. `wait` or `pull`.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    // Including the first try, so 1 disables retries.
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub multiplier: f64,
+    // Randomize each backoff between half and all of it.
+    pub jitter: bool,
+    pub operations: BTreeMap<String, RetryOverride>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: 3,
+            initial_backoff_ms: 200,
+            max_backoff_ms: 5000,
+            multiplier: 2.0,
+            jitter: true,
+            operations: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RetryOverride {
+    pub max_attempts: Option<u32>,
+    pub initial_backoff_ms: Option<u64>,
+    pub max_backoff_ms: Option<u64>,
+    pub multiplier: Option<f64>,
+    pub jitter: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PortsConfig {
+    // Also try binding a container's host ports before creating it, to catch ports held by
+    // processes other than nic8s containers.
+    pub host_bind_check: bool,
+}
+
+// For containers with `auto_update = true`.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ImageUpdatesConfig {
+    // How often their images are pulled to look for a newer one.
+    pub interval_seconds: u64,
+    // How long a replacement gets to become ready before the rollout stops.
+    pub ready_timeout_seconds: u64,
+}
+
+impl Default for ImageUpdatesConfig {
+    fn default() -> Self {
+        ImageUpdatesConfig {
+            interval_seconds: 300,
+            ready_timeout_seconds: 120,
+        }
+    }
+}
+
+// With `enabled`, images no container, job or cron job uses are removed from a node, oldest
+// first, once its images take up more than `high_threshold_bytes`, until they are under
+// `low_threshold_bytes`. A cron `schedule` also removes all of them at those times.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ImageGcConfig {
+    pub enabled: bool,
+    // How often disk usage is checked.
+    pub interval_seconds: u64,
+    pub high_threshold_bytes: u64,
+    pub low_threshold_bytes: u64,
+    pub schedule: Option<String>,
+    // Images never removed, e.g. "postgres:16" or "registry.example.com/*".
+    pub keep: Vec<String>,
+    // Only log the images that would be removed.
+    pub dry_run: bool,
+}
+
+impl Default for ImageGcConfig {
+    fn default() -> Self {
+        ImageGcConfig {
+            enabled: false,
+            interval_seconds: 300,
+            high_threshold_bytes: 20 * 1024 * 1024 * 1024,
+            low_threshold_bytes: 15 * 1024 * 1024 * 1024,
+            schedule: None,
+            keep: Vec::new(),
+            dry_run: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct AutoscalerConfig {
+    // How often autoscalers compare their app's CPU usage with the target.
+    pub interval_seconds: u64,
+}
+
+impl Default for AutoscalerConfig {
+    fn default() -> Self {
+        AutoscalerConfig {
+            interval_seconds: 15,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct RolloutConfig {
+    // How often rollouts take their next step, which is also how often canaries are checked
+    // while they are analyzed.
+    pub interval_seconds: u64,
+    pub metric_timeout_seconds: u64,
+}
+
+impl Default for RolloutConfig {
+    fn default() -> Self {
+        RolloutConfig {
+            interval_seconds: 5,
+            metric_timeout_seconds: 5,
+        }
+    }
+}
+
+// For running several servers against a shared data dir, of which only the lease holder runs the
+// control plane.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct LeaderElectionConfig {
+    pub enabled: bool,
+    // Defaults to `<hostname>-<pid>`; must be unique among the servers.
+    pub identity: Option<String>,
+    // How long a lease that isn't renewed stays valid, which bounds how long a failover takes.
+    pub lease_duration_seconds: u64,
+    pub renew_interval_seconds: u64,
+}
+
+impl Default for LeaderElectionConfig {
+    fn default() -> Self {
+        LeaderElectionConfig {
+            enabled: false,
+            identity: None,
+            lease_duration_seconds: 15,
+            renew_interval_seconds: 5,
+        }
+    }
+}
+
+impl LeaderElectionConfig {
+    pub fn identity(&self) -> String {
+        self.identity.clone().unwrap_or_else(|| {
+            let hostname = std::env::var("HOSTNAME")
+                .ok()
+                .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+                .map(|hostname| hostname.trim().to_string())
+                .filter(|hostname| !hostname.is_empty())
+                .unwrap_or_else(|| String::from("nic8s"));
+            format!("{}-{}", hostname, std::process::id())
+        })
+    }
+}
+
+// Plugins run in the order listed, on every container spec before it is persisted, e.g.
+//
+//   [[admission.plugins]]
+//   name = "disallow-latest-tag"
+//   apps = ["web"]
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AdmissionConfig {
+    pub plugins: Vec<AdmissionPluginConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdmissionPluginConfig {
+    // default-resources, require-resources, disallow-latest-tag or webhook.
+    pub name: String,
+    // Only admit containers of these apps; all of them when empty.
+    #[serde(default)]
+    pub apps: Vec<String>,
+    // For default-resources.
+    pub cpus: Option<f64>,
+    pub memory_bytes: Option<u64>,
+    // For webhook, which receives `{"spec": ...}` and answers `{"allowed": ..., "message": ...,
+    // "spec": ...}`.
+    pub url: Option<String>,
+    #[serde(default = "default_webhook_timeout_seconds")]
+    pub timeout_seconds: u64,
+    // Admit containers when the webhook can't be reached instead of rejecting them.
+    #[serde(default)]
+    pub ignore_failures: bool,
+}
+
+fn default_webhook_timeout_seconds() -> u64 {
+    10
+}
+
+// Events are POSTed to each webhook whose filters they pass, e.g.
+//
+//   [[notifications.webhooks]]
+//   url = "https://hooks.slack.com/services/..."
+//   format = "slack"
+//   reasons = ["Dead", "BackOff", "RolledOut"]
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    pub webhooks: Vec<NotificationWebhookConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NotificationWebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub format: NotificationFormat,
+    // Each filter matches everything when empty.
+    #[serde(default)]
+    pub types: Vec<EventType>,
+    #[serde(default)]
+    pub reasons: Vec<EventReason>,
+    #[serde(default)]
+    pub kinds: Vec<ObjectKind>,
+    #[serde(default)]
+    pub apps: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationFormat {
+    // The event itself, plus the app it belongs to.
+    #[default]
+    Json,
+    // `{"text": ...}`, for Slack incoming webhooks.
+    Slack,
+}
+
+// With `persist`, the output of managed containers is copied to `<data_dir>/logs` and kept after
+// the containers are removed or recreated.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct LogsConfig {
+    pub persist: bool,
+    pub interval_seconds: u64,
+    // A container's log file is rotated once it would grow past this.
+    pub max_file_bytes: u64,
+    // Files kept per container, the one being written included.
+    pub max_files: usize,
+    // Logs of containers that are gone are deleted after this long.
+    pub retention_hours: u64,
+}
+
+impl Default for LogsConfig {
+    fn default() -> Self {
+        LogsConfig {
+            persist: false,
+            interval_seconds: 5,
+            max_file_bytes: 10 * 1024 * 1024,
+            max_files: 5,
+            retention_hours: 7 * 24,
+        }
+    }
+}
+
+// With `enabled`, the daemon proxies HTTP requests on `addr` to the apps named by ingresses, and
+// HTTPS requests on `tls_addr` when set, using the certificates ingresses name.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct IngressConfig {
+    pub enabled: bool,
+    pub addr: String,
+    pub tls_addr: Option<String>,
+    // How long a backend gets to respond before the proxy answers 504.
+    pub timeout_seconds: u64,
+}
+
+impl Default for IngressConfig {
+    fn default() -> Self {
+        IngressConfig {
+            enabled: false,
+            addr: String::from("0.0.0.0:8080"),
+            tls_addr: None,
+            timeout_seconds: 60,
+        }
+    }
+}
+
+// With `enabled`, API calls need a bearer token whose roles allow them. Roles add to or replace
+// the built-in admin, viewer and node roles, e.g.
+//
+//   [[auth.roles]]
+//   name = "deployer"
+//   rules = [{ verbs = ["get", "list", "create", "update"], resources = ["containers", "jobs"] }]
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    pub enabled: bool,
+    pub roles: Vec<Role>,
+}
+
+impl Config {
+    pub fn master_key_file(&self) -> PathBuf {
+        self.secrets
+            .master_key_file
+            .clone()
+            .unwrap_or_else(|| self.data_dir.join("master.key"))
+    }
+
+    pub fn config_maps_dir(&self) -> PathBuf {
+        self.data_dir.join("mounts").join("configmaps")
+    }
+
+    pub fn logs_dir(&self) -> PathBuf {
+        self.data_dir.join("logs")
+    }
+
+    pub fn audit_log_file(&self) -> PathBuf {
+        self.data_dir.join("audit.log")
+    }
+
+    pub fn admin_token_file(&self) -> PathBuf {
+        self.data_dir.join("admin.token")
+    }
+
+    pub fn load(path: Option<&Path>) -> Result<Config, anyhow::Error> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None if Path::new(DEFAULT_PATH).exists() => PathBuf::from(DEFAULT_PATH),
+            None => return Ok(Config::default()),
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|error| anyhow::anyhow!("failed to read {}: {}", path.display(), error))?;
+        Ok(toml::from_str(&contents)?)
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::{
+    cluster::Cluster,
+    config::AutoscalerConfig,
+    controllers::rollout,
+    entities::{
+        autoscaler::{Autoscaler, AutoscalerSpec},
+        container::{Container, ContainerStatus},
+        rollout::{is_replica, Rollout},
+    },
+    events::event::{EventReason, ObjectKind},
+    watchers::{
+        resource_usage::ResourceUsageWatcher,
+        watcher::{Watcher, WatcherContext},
+    },
+};
+
+pub(crate) const KIND: &str = "autoscalers";
+
+// Resizes apps to hold their average CPU usage, as reported by the resource usage watcher, at a
+// target. Recommendations are kept in memory only, so the windows start over after a restart.
+pub struct AutoscalerController {
+    cluster: Cluster,
+    usage: Arc<ResourceUsageWatcher>,
+    interval: Duration,
+    // Held for a whole pass, which also keeps `delete` from racing a status update.
+    recommendations: Mutex<HashMap<String, VecDeque<(Instant, usize)>>>,
+}
+
+impl AutoscalerController {
+    pub fn new(
+        cluster: Cluster,
+        usage: Arc<ResourceUsageWatcher>,
+        config: &AutoscalerConfig,
+    ) -> Self {
+        AutoscalerController {
+            cluster,
+            usage,
+            interval: Duration::from_secs(config.interval_seconds),
+            recommendations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn create(&self, spec: AutoscalerSpec) -> Result<Autoscaler, anyhow::Error> {
+        spec.validate()?;
+        let autoscaler = Autoscaler::new(spec);
+
+        self.cluster
+            .state
+            .put(KIND, autoscaler.name(), &autoscaler)
+            .await?;
+        self.record(
+            &autoscaler,
+            EventReason::Created,
+            format!(
+                "Created autoscaler for app {} with {}-{} replicas at {}% CPU",
+                autoscaler.name(),
+                autoscaler.spec.min_replicas,
+                autoscaler.spec.max_replicas,
+                autoscaler.spec.target_cpu_percent
+            ),
+        )
+        .await;
+        Ok(autoscaler)
+    }
+
+    pub async fn get(&self, app: &str) -> Result<Option<Autoscaler>, anyhow::Error> {
+        self.cluster.state.get(KIND, app).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<Autoscaler>, anyhow::Error> {
+        let mut autoscalers: Vec<Autoscaler> = self.cluster.state.list(KIND).await?;
+        autoscalers.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok(autoscalers)
+    }
+
+    // Leaves the app at its current size.
+    pub async fn delete(&self, app: &str) -> Result<bool, anyhow::Error> {
+        let mut recommendations = self.recommendations.lock().await;
+        recommendations.remove(app);
+        self.cluster.state.delete(KIND, app).await
+    }
+
+    pub async fn check_autoscalers(&self) -> Result<(), anyhow::Error> {
+        let mut recommendations = self.recommendations.lock().await;
+        let autoscalers = self.list().await?;
+        recommendations.retain(|app, _| autoscalers.iter().any(|a| a.name() == app));
+
+        let containers = self.cluster.status_watcher.list().await;
+        let rollouts: Vec<Rollout> = self.cluster.state.list(rollout::KIND).await?;
+        let usage: HashMap<String, f64> = self
+            .usage
+            .list()
+            .await
+            .into_iter()
+            .map(|usage| (usage.container_id, usage.cpu_percent))
+            .collect();
+
+        for mut autoscaler in autoscalers {
+            // A rollout runs extra containers of the app, and replaces its replicas, until it's
+            // done.
+            if rollouts
+                .iter()
+                .any(|rollout| rollout.name() == autoscaler.spec.app && !rollout.is_finished())
+            {
+                continue;
+            }
+            let app: Vec<&Container> = containers
+                .iter()
+                .filter(|container| is_replica(container, &autoscaler.spec.app))
+                .collect();
+            // Scaling copies an existing container, so there is nothing to do until one exists.
+            if app.is_empty() {
+                continue;
+            }
+
+            let utilization = average_utilization(&app, &usage);
+            let current = app.len();
+            let recommended = match utilization {
+                Some(utilization) => autoscaler.spec.recommend(current, utilization),
+                None => current.clamp(autoscaler.spec.min_replicas, autoscaler.spec.max_replicas),
+            };
+
+            let history = recommendations
+                .entry(autoscaler.spec.app.clone())
+                .or_default();
+            let desired = stabilize(&autoscaler.spec, history, current, recommended);
+
+            autoscaler.status.current_replicas = current;
+            autoscaler.status.desired_replicas = desired;
+            autoscaler.status.current_cpu_percent = utilization;
+            if desired != current {
+                self.scale(&mut autoscaler, current, desired, utilization)
+                    .await;
+            }
+            self.cluster
+                .state
+                .put(KIND, autoscaler.name(), &autoscaler)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn scale(
+        &self,
+        autoscaler: &mut Autoscaler,
+        current: usize,
+        desired: usize,
+        utilization: Option<f64>,
+    ) {
+        let reason = match utilization {
+            Some(utilization) => format!(
+                "average CPU {:.1}% against a target of {}%",
+                utilization, autoscaler.spec.target_cpu_percent
+            ),
+            None => String::from("replica limits"),
+        };
+
+        match Container::scale(&autoscaler.spec.app, desired, &self.cluster).await {
+            Ok(containers) => {
+                autoscaler.status.current_replicas = containers.len();
+                autoscaler.status.last_scale_time = Some(chrono::Utc::now().to_rfc3339());
+                self.record(
+                    autoscaler,
+                    EventReason::Scaled,
+                    format!(
+                        "Scaled app {} from {} to {} replicas for {}",
+                        autoscaler.spec.app, current, desired, reason
+                    ),
+                )
+                .await;
+            }
+            Err(error) => {
+                self.record(
+                    autoscaler,
+                    EventReason::Failed,
+                    format!(
+                        "Failed to scale app {} from {} to {} replicas: {}",
+                        autoscaler.spec.app, current, desired, error
+                    ),
+                )
+                .await;
+            }
+        }
+    }
+
+    async fn record(&self, autoscaler: &Autoscaler, reason: EventReason, message: String) {
+        self.cluster
+            .events
+            .record(ObjectKind::Autoscaler, autoscaler.name(), reason, message)
+            .await
+    }
+}
+
+// Containers that are starting, stopping or have no stats yet are left out of the average.
+fn average_utilization(containers: &[&Container], usage: &HashMap<String, f64>) -> Option<f64> {
+    let utilizations: Vec<f64> = containers
+        .iter()
+        .filter(|container| container.get_status() == ContainerStatus::Running)
+        .filter_map(|container| {
+            let cpu_percent = usage.get(&container.id)?;
+            let cpus = container.spec.resources.cpus;
+            Some(if cpus > 0.0 {
+                cpu_percent / cpus
+            } else {
+                *cpu_percent
+            })
+        })
+        .collect();
+
+    if utilizations.is_empty() {
+        return None;
+    }
+    Some(utilizations.iter().sum::<f64>() / utilizations.len() as f64)
+}
+
+// Scales up to the lowest recommendation of the scale up window and down to the highest of the
+// scale down window, so only sustained changes in load resize the app.
+fn stabilize(
+    spec: &AutoscalerSpec,
+    history: &mut VecDeque<(Instant, usize)>,
+    current: usize,
+    recommended: usize,
+) -> usize {
+    let now = Instant::now();
+    history.push_back((now, recommended));
+
+    let up_window = Duration::from_secs(spec.scale_up_stabilization_seconds);
+    let down_window = Duration::from_secs(spec.scale_down_stabilization_seconds);
+    let longest = up_window.max(down_window);
+    while history
+        .front()
+        .is_some_and(|(time, _)| now.duration_since(*time) > longest)
+    {
+        history.pop_front();
+    }
+
+    let within = |window: Duration| {
+        history
+            .iter()
+            .filter(move |(time, _)| now.duration_since(*time) <= window)
+            .map(|(_, replicas)| *replicas)
+    };
+    let scale_up = within(up_window).min().unwrap_or(recommended);
+    let scale_down = within(down_window).max().unwrap_or(recommended);
+
+    if scale_up > current {
+        scale_up
+    } else if scale_down < current {
+        scale_down
+    } else {
+        current
+    }
+}
+
+#[async_trait]
+impl Watcher for AutoscalerController {
+    fn name(&self) -> &str {
+        "autoscaler"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        while ctx.sleep(self.interval).await {
+            if let Err(error) = self.check_autoscalers().await {
+                println!("Failed to check autoscalers: {}", error);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use tokio::{sync::Mutex, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cluster::Cluster,
+    entities::{
+        container::ContainerSpec,
+        cron_job::{ConcurrencyPolicy, CronJob, CronJobExists, CronJobRun, CronJobSpec},
+        job::{JobPhase, JobSpec},
+    },
+    events::event::{EventReason, ObjectKind},
+};
+
+use super::{job::JobController, schedule::Schedule};
+
+pub(crate) const KIND: &str = "cronjobs";
+// Active jobs are checked at least this often so the history stays current between runs.
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+type Running = HashMap<String, (CancellationToken, JoinHandle<()>)>;
+
+pub struct CronJobController {
+    cluster: Cluster,
+    jobs: Arc<JobController>,
+    shutdown: CancellationToken,
+    running: Mutex<Running>,
+}
+
+impl CronJobController {
+    pub fn new(cluster: Cluster, jobs: Arc<JobController>, shutdown: CancellationToken) -> Self {
+        CronJobController {
+            cluster,
+            jobs,
+            shutdown,
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn resume(self: &Arc<Self>) -> Result<(), anyhow::Error> {
+        let mut running = self.running.lock().await;
+        for cron_job in self.list().await? {
+            self.spawn(&mut running, cron_job);
+        }
+        Ok(())
+    }
+
+    pub async fn create(self: &Arc<Self>, mut spec: CronJobSpec) -> Result<CronJob, anyhow::Error> {
+        spec.schedule.parse::<Schedule>()?;
+        spec.job.template = self.cluster.admission.admit(spec.job.template).await?;
+        let cron_job = CronJob::new(spec);
+
+        // Held from the check until the scheduler is running, so concurrent creates of the same
+        // name can't both get through.
+        let mut running = self.running.lock().await;
+        if running.contains_key(cron_job.name()) || self.get(cron_job.name()).await?.is_some() {
+            return Err(CronJobExists(cron_job.name().to_string()).into());
+        }
+        self.cluster
+            .state
+            .put(KIND, cron_job.name(), &cron_job)
+            .await?;
+        self.record(
+            &cron_job,
+            EventReason::Created,
+            format!(
+                "Created cron job {} with schedule {:?}",
+                cron_job.name(),
+                cron_job.spec.schedule
+            ),
+        )
+        .await;
+        self.spawn(&mut running, cron_job.clone());
+        Ok(cron_job)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Option<CronJob>, anyhow::Error> {
+        self.cluster.state.get(KIND, name).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<CronJob>, anyhow::Error> {
+        let mut cron_jobs: Vec<CronJob> = self.cluster.state.list(KIND).await?;
+        cron_jobs.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok(cron_jobs)
+    }
+
+    // Deletes the cron job together with the jobs it created.
+    pub async fn delete(&self, name: &str) -> Result<bool, anyhow::Error> {
+        let running = self.running.lock().await.remove(name);
+        if let Some((token, handle)) = running {
+            token.cancel();
+            let _ = handle.await;
+        }
+
+        let Some(cron_job) = self.get(name).await? else {
+            return Ok(false);
+        };
+        for run in cron_job
+            .status
+            .active
+            .iter()
+            .chain(cron_job.status.history.iter())
+        {
+            self.jobs.delete(&run.job).await?;
+        }
+
+        self.cluster.state.delete(KIND, name).await
+    }
+
+    fn spawn(self: &Arc<Self>, running: &mut Running, cron_job: CronJob) {
+        let name = cron_job.name().to_string();
+        let token = self.shutdown.child_token();
+        let controller = self.clone();
+        let task_token = token.clone();
+
+        let handle = tokio::spawn(async move {
+            let name = cron_job.name().to_string();
+            if let Err(error) = controller.run(cron_job, &task_token).await {
+                println!("Cron job {} stopped: {}", name, error);
+            }
+        });
+
+        if let Some((token, handle)) = running.insert(name, (token, handle)) {
+            token.cancel();
+            handle.abort();
+        }
+    }
+
+    async fn run(
+        &self,
+        mut cron_job: CronJob,
+        token: &CancellationToken,
+    ) -> Result<(), anyhow::Error> {
+        let schedule: Schedule = cron_job.spec.schedule.parse()?;
+
+        loop {
+            let mut changed = self.sync_jobs(&mut cron_job).await?;
+            let now = Utc::now();
+
+            let since = cron_job
+                .status
+                .last_schedule_time
+                .as_deref()
+                .unwrap_or(&cron_job.created);
+            let since = DateTime::parse_from_rfc3339(since)?.with_timezone(&Utc);
+
+            // Only the latest due run is started; anything older was missed, typically while the
+            // daemon was down.
+            let mut due = None;
+            let mut missed = 0;
+            while let Some(next) = schedule.next_after(due.unwrap_or(since)) {
+                if next > now {
+                    break;
+                }
+                if due.is_some() {
+                    missed += 1;
+                }
+                due = Some(next);
+            }
+
+            if let Some(scheduled) = due {
+                if missed > 0 {
+                    self.record(
+                        &cron_job,
+                        EventReason::Missed,
+                        format!("Missed {} scheduled run(s)", missed),
+                    )
+                    .await;
+                }
+
+                let late = (now - scheduled).to_std().unwrap_or_default();
+                match cron_job.spec.starting_deadline_seconds {
+                    Some(deadline) if late > Duration::from_secs(deadline) => {
+                        self.record(
+                            &cron_job,
+                            EventReason::Missed,
+                            format!(
+                                "Skipped run scheduled at {}: missed the {}s starting deadline",
+                                scheduled.to_rfc3339(),
+                                deadline
+                            ),
+                        )
+                        .await
+                    }
+                    _ => self.start_run(&mut cron_job, scheduled).await?,
+                }
+
+                cron_job.status.last_schedule_time = Some(scheduled.to_rfc3339());
+                changed = true;
+            }
+
+            if changed && !token.is_cancelled() {
+                self.cluster
+                    .state
+                    .put(KIND, cron_job.name(), &cron_job)
+                    .await?;
+            }
+
+            let wait = schedule
+                .next_after(now)
+                .and_then(|next| (next - Utc::now()).to_std().ok())
+                .unwrap_or_default()
+                .min(SYNC_INTERVAL);
+
+            tokio::select! {
+                _ = token.cancelled() => return Ok(()),
+                _ = tokio::time::sleep(wait) => {}
+            }
+        }
+    }
+
+    async fn start_run(
+        &self,
+        cron_job: &mut CronJob,
+        scheduled: DateTime<Utc>,
+    ) -> Result<(), anyhow::Error> {
+        // Naming runs after the scheduled minute keeps a run from being created twice if the
+        // daemon stops between creating the job and saving the cron job.
+        let name = format!("{}-{}", cron_job.name(), scheduled.timestamp() / 60);
+        if cron_job
+            .status
+            .active
+            .iter()
+            .chain(cron_job.status.history.iter())
+            .any(|run| run.job == name)
+        {
+            return Ok(());
+        }
+
+        if !cron_job.status.active.is_empty() {
+            match cron_job.spec.concurrency_policy {
+                ConcurrencyPolicy::Allow => {}
+                ConcurrencyPolicy::Forbid => {
+                    let active: Vec<&str> = cron_job
+                        .status
+                        .active
+                        .iter()
+                        .map(|run| run.job.as_str())
+                        .collect();
+                    let message = format!(
+                        "Skipped run scheduled at {}: {} still active",
+                        scheduled.to_rfc3339(),
+                        active.join(", ")
+                    );
+                    self.record(cron_job, EventReason::Skipped, message).await;
+                    return Ok(());
+                }
+                ConcurrencyPolicy::Replace => {
+                    for run in std::mem::take(&mut cron_job.status.active) {
+                        self.record(
+                            cron_job,
+                            EventReason::Killed,
+                            format!("Replacing active job {}", run.job),
+                        )
+                        .await;
+                        self.jobs.delete(&run.job).await?;
+                    }
+                }
+            }
+        }
+
+        if self.jobs.get(&name).await?.is_none() {
+            let spec = JobSpec {
+                template: ContainerSpec {
+                    name: name.clone(),
+                    ..cron_job.spec.job.template.clone()
+                },
+                ..cron_job.spec.job.clone()
+            };
+            self.jobs.create(spec).await?;
+            self.record(
+                cron_job,
+                EventReason::Created,
+                format!("Created job {}", name),
+            )
+            .await;
+        }
+
+        cron_job.status.active.push(CronJobRun {
+            job: name,
+            scheduled_time: scheduled.to_rfc3339(),
+            phase: JobPhase::Pending,
+        });
+        Ok(())
+    }
+
+    // Moves finished jobs into the history and deletes the oldest ones beyond the history limits.
+    async fn sync_jobs(&self, cron_job: &mut CronJob) -> Result<bool, anyhow::Error> {
+        let mut changed = false;
+
+        for mut run in std::mem::take(&mut cron_job.status.active) {
+            match self.jobs.get(&run.job).await? {
+                Some(job) if job.is_finished() => {
+                    run.phase = job.status.phase;
+                    cron_job.status.history.push(run);
+                    changed = true;
+                }
+                Some(job) => {
+                    changed |= run.phase != job.status.phase;
+                    run.phase = job.status.phase;
+                    cron_job.status.active.push(run);
+                }
+                None => changed = true,
+            }
+        }
+
+        let limits = [
+            (
+                JobPhase::Complete,
+                cron_job.spec.successful_jobs_history_limit,
+            ),
+            (JobPhase::Failed, cron_job.spec.failed_jobs_history_limit),
+        ];
+        for (phase, limit) in limits {
+            let count = cron_job
+                .status
+                .history
+                .iter()
+                .filter(|run| run.phase == phase)
+                .count();
+            let mut excess = count.saturating_sub(limit);
+
+            for run in std::mem::take(&mut cron_job.status.history) {
+                if excess > 0 && run.phase == phase {
+                    excess -= 1;
+                    self.jobs.delete(&run.job).await?;
+                    changed = true;
+                } else {
+                    cron_job.status.history.push(run);
+                }
+            }
+        }
+
+        Ok(changed)
+    }
+
+    async fn record(&self, cron_job: &CronJob, reason: EventReason, message: String) {
+        self.cluster
+            .events
+            .record(ObjectKind::CronJob, cron_job.name(), reason, message)
+            .await
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use crate::{
+    cluster::Cluster,
+    config::{DriftConfig, DriftPolicy},
+    entities::{
+        container::{Container, ContainerSpec, ContainerStatus, RestartPolicy, KIND},
+        node::NodeStatus,
+    },
+    events::event::EventReason,
+    watchers::watcher::{Watcher, WatcherContext},
+};
+
+// How a managed container differs from what nic8s created, after someone changed it directly
+// through the runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Drift {
+    // Stopped while its restart policy says it should run, by something other than nic8s.
+    Stopped,
+    // Removed, with nothing else in its place.
+    Removed,
+    // Replaced by another container with the same name, such as one recreated with other
+    // environment variables.
+    Replaced { id: String },
+    // Running with a spec other than the desired one.
+    Modified,
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drift::Stopped => write!(f, "was stopped outside nic8s"),
+            Drift::Removed => write!(f, "was removed outside nic8s"),
+            Drift::Replaced { id } => write!(f, "was replaced outside nic8s by container {}", id),
+            Drift::Modified => write!(f, "no longer matches its desired spec"),
+        }
+    }
+}
+
+// Compares the managed containers on each ready node with their desired state, recording a
+// DriftDetected event for every container changed behind nic8s' back and, with the repair
+// policy, putting it back as it was. A drift has to be seen by two checks in a row, so
+// containers in the middle of being deleted or replaced by nic8s itself are left alone.
+pub struct DriftDetector {
+    cluster: Cluster,
+    interval: Duration,
+    policy: DriftPolicy,
+    // Drift seen by the last check, by container name.
+    seen: Mutex<HashMap<String, Drift>>,
+    // Drift already recorded, so it is only reported once while it lasts.
+    reported: Mutex<HashMap<String, Drift>>,
+}
+
+impl DriftDetector {
+    pub fn new(cluster: Cluster, config: &DriftConfig) -> Self {
+        DriftDetector {
+            cluster,
+            interval: Duration::from_secs(config.interval_seconds),
+            policy: config.policy,
+            seen: Mutex::new(HashMap::new()),
+            reported: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn check(&self) -> Result<(), anyhow::Error> {
+        let drifted = self.detect().await?;
+
+        let confirmed: Vec<(Container, ContainerSpec, Drift)> = {
+            let mut seen = self.seen.lock().await;
+            let previous = std::mem::take(&mut *seen);
+            drifted
+                .into_iter()
+                .filter_map(|(container, spec, drift)| {
+                    seen.insert(container.name.clone(), drift.clone());
+                    (previous.get(&container.name) == Some(&drift))
+                        .then_some((container, spec, drift))
+                })
+                .collect()
+        };
+
+        let mut reported = self.reported.lock().await;
+        reported.retain(|name, _| {
+            confirmed
+                .iter()
+                .any(|(container, ..)| container.name == *name)
+        });
+        for (container, spec, drift) in confirmed {
+            if reported.get(&container.name) == Some(&drift) {
+                continue;
+            }
+
+            let repair = self.policy == DriftPolicy::Repair;
+            let action = match (&drift, repair) {
+                (_, false) => "",
+                (Drift::Stopped, true) => ", starting it again",
+                (_, true) => ", recreating it",
+            };
+            self.cluster
+                .events
+                .record_container(
+                    &container.name,
+                    EventReason::DriftDetected,
+                    format!("Container {} {}{}", container.name, drift, action),
+                )
+                .await;
+            if !repair {
+                reported.insert(container.name.clone(), drift);
+                continue;
+            }
+
+            if let Err(error) = self.repair(&container, &spec, &drift).await {
+                self.cluster
+                    .events
+                    .record_container(
+                        &container.name,
+                        EventReason::Failed,
+                        format!("Failed to repair container {}: {}", container.name, error),
+                    )
+                    .await;
+            }
+            self.seen.lock().await.remove(&container.name);
+        }
+        Ok(())
+    }
+
+    // The tracked containers that drifted, with their desired spec. Containers on nodes that
+    // aren't ready, or that fail to list their containers, are skipped rather than taken for
+    // removed.
+    async fn detect(&self) -> Result<Vec<(Container, ContainerSpec, Drift)>, anyhow::Error> {
+        let desired: BTreeMap<String, ContainerSpec> = self
+            .cluster
+            .state
+            .list::<ContainerSpec>(KIND)
+            .await?
+            .into_iter()
+            .map(|spec| (spec.name.clone(), spec))
+            .collect();
+
+        let mut live: HashMap<String, HashMap<String, Container>> = HashMap::new();
+        for node in self.cluster.nodes.list().await {
+            if node.status != NodeStatus::Ready {
+                continue;
+            }
+            let listed = match self.cluster.nodes.node(&node.name).await {
+                Ok(runtime) => runtime.list_managed().await,
+                Err(error) => Err(error),
+            };
+            match listed {
+                Ok(containers) => {
+                    let containers = containers
+                        .into_iter()
+                        .map(|container| (container.name.clone(), container))
+                        .collect();
+                    live.insert(node.name, containers);
+                }
+                Err(error) => println!(
+                    "Failed to list containers on node {} for drift detection: {}",
+                    node.name, error
+                ),
+            }
+        }
+
+        let mut drifted = Vec::new();
+        for container in self.cluster.status_watcher.list().await {
+            let Some(spec) = desired.get(&container.name) else {
+                continue;
+            };
+            let Some(on_node) = live.get(&container.node) else {
+                continue;
+            };
+
+            let drift = match on_node.get(&container.name) {
+                Some(current) if current.id != container.id => Some(Drift::Replaced {
+                    id: current.id.clone(),
+                }),
+                Some(current) if current.spec != *spec => Some(Drift::Modified),
+                Some(current) => {
+                    let held = self
+                        .cluster
+                        .status_watcher
+                        .is_held_stopped(&container.id)
+                        .await;
+                    (!held && stopped(&container, current, spec)).then_some(Drift::Stopped)
+                }
+                // Recreated without the nic8s labels, so only found by name.
+                None => match self.cluster.nodes.node(&container.node).await {
+                    Ok(runtime) => match runtime.inspect(&container.name).await {
+                        Ok(current) if current.id != container.id => {
+                            Some(Drift::Replaced { id: current.id })
+                        }
+                        Ok(_) => None,
+                        Err(_) => Some(Drift::Removed),
+                    },
+                    Err(_) => None,
+                },
+            };
+            if let Some(drift) = drift {
+                drifted.push((container, spec.clone(), drift));
+            }
+        }
+        Ok(drifted)
+    }
+
+    async fn repair(
+        &self,
+        container: &Container,
+        spec: &ContainerSpec,
+        drift: &Drift,
+    ) -> Result<(), anyhow::Error> {
+        let runtime = self.cluster.nodes.node(&container.node).await?;
+        match drift {
+            Drift::Stopped => return runtime.start(&container.id).await,
+            Drift::Removed => {}
+            Drift::Replaced { id } => runtime.remove(id).await?,
+            Drift::Modified => runtime.remove(&container.id).await?,
+        }
+        self.cluster
+            .status_watcher
+            .remove_container(&container.id)
+            .await;
+        Container::new(spec, &self.cluster).await?;
+        Ok(())
+    }
+}
+
+// Exited containers that should be running, other than the ones nic8s holds stopped in
+// CrashLoopBackOff.
+fn stopped(tracked: &Container, current: &Container, spec: &ContainerSpec) -> bool {
+    spec.restart_policy == RestartPolicy::Always
+        && matches!(
+            current.get_status(),
+            ContainerStatus::Exited | ContainerStatus::Dead
+        )
+        && tracked.get_status() != ContainerStatus::CrashLoopBackOff
+}
+
+#[async_trait]
+impl Watcher for DriftDetector {
+    fn name(&self) -> &str {
+        "drift-detector"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        while ctx.sleep(self.interval).await {
+            if let Err(error) = self.check().await {
+                println!("Drift detection failed: {}", error);
+            }
+        }
+        Ok(())
+    }
+}
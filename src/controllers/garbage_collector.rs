@@ -0,0 +1,106 @@
+use std::{collections::HashSet, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::{
+    cluster::Cluster,
+    config::GcConfig,
+    entities::container::{Container, ContainerSpec, KIND},
+    events::event::EventReason,
+    watchers::watcher::{Watcher, WatcherContext},
+};
+
+// Removes managed containers that have no desired-state record, such as ones whose removal
+// failed on an unreachable node after they were deleted.
+pub struct GarbageCollector {
+    cluster: Cluster,
+    interval: Duration,
+    grace_period: Duration,
+    dry_run: bool,
+}
+
+impl GarbageCollector {
+    pub fn new(cluster: Cluster, config: &GcConfig) -> Self {
+        GarbageCollector {
+            cluster,
+            interval: Duration::from_secs(config.interval_seconds),
+            grace_period: Duration::from_secs(config.grace_period_seconds),
+            dry_run: config.dry_run,
+        }
+    }
+
+    pub async fn collect(&self) -> Result<(), anyhow::Error> {
+        let desired: HashSet<String> = self
+            .cluster
+            .state
+            .list::<ContainerSpec>(KIND)
+            .await?
+            .into_iter()
+            .map(|spec| spec.name)
+            .collect();
+
+        for container in self.cluster.runtime.list_managed().await? {
+            if desired.contains(&container.name) || !self.past_grace_period(&container) {
+                continue;
+            }
+
+            if self.dry_run {
+                println!(
+                    "Orphaned container {} ({}) on node {} would be removed (dry run)",
+                    container.name, container.id, container.node
+                );
+                continue;
+            }
+
+            self.cluster
+                .events
+                .record_container(
+                    &container.name,
+                    EventReason::Killed,
+                    format!("Removing orphaned container {}", container.name),
+                )
+                .await;
+            if let Err(error) = self.cluster.runtime.remove(&container.id).await {
+                println!(
+                    "Failed to remove orphaned container {}: {}",
+                    container.name, error
+                );
+                continue;
+            }
+            self.cluster
+                .status_watcher
+                .remove_container(&container.id)
+                .await;
+        }
+
+        Ok(())
+    }
+
+    fn past_grace_period(&self, container: &Container) -> bool {
+        chrono::DateTime::parse_from_rfc3339(&container.created)
+            .ok()
+            .and_then(|created| {
+                chrono::Utc::now()
+                    .signed_duration_since(created)
+                    .to_std()
+                    .ok()
+            })
+            .is_some_and(|age| age >= self.grace_period)
+    }
+}
+
+#[async_trait]
+impl Watcher for GarbageCollector {
+    fn name(&self) -> &str {
+        "garbage-collector"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        while ctx.sleep(self.interval).await {
+            if let Err(error) = self.collect().await {
+                println!("Garbage collection failed: {}", error);
+            }
+        }
+        Ok(())
+    }
+}
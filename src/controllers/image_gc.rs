@@ -0,0 +1,245 @@
+use std::{collections::HashSet, time::Duration};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::Mutex;
+
+use crate::{
+    cluster::Cluster,
+    config::ImageGcConfig,
+    entities::{
+        container::{ContainerSpec, KIND},
+        cron_job::CronJob,
+        job::Job,
+        node::NodeStatus,
+        resource_usage::format_size,
+    },
+    events::event::{EventReason, ObjectKind},
+    runtime::Image,
+    watchers::watcher::{Watcher, WatcherContext},
+};
+
+use super::{cron_job, job, schedule::Schedule};
+
+// Removes the images nothing in the desired state uses from each ready node, when the node's
+// images take up too much disk space or on a schedule.
+pub struct ImageGarbageCollector {
+    cluster: Cluster,
+    interval: Duration,
+    high_threshold_bytes: u64,
+    low_threshold_bytes: u64,
+    schedule: Option<Schedule>,
+    keep: Vec<String>,
+    dry_run: bool,
+    // When the last scheduled collection ran, or the collector started.
+    last_scheduled: Mutex<DateTime<Utc>>,
+}
+
+impl ImageGarbageCollector {
+    pub fn new(cluster: Cluster, config: &ImageGcConfig) -> Result<Self, anyhow::Error> {
+        if config.low_threshold_bytes > config.high_threshold_bytes {
+            return Err(anyhow!(
+                "image_gc.low_threshold_bytes must not be above image_gc.high_threshold_bytes"
+            ));
+        }
+        let schedule = config
+            .schedule
+            .as_deref()
+            .map(|schedule| {
+                schedule
+                    .parse::<Schedule>()
+                    .map_err(|error| anyhow!("invalid image_gc.schedule: {}", error))
+            })
+            .transpose()?;
+
+        Ok(ImageGarbageCollector {
+            cluster,
+            interval: Duration::from_secs(config.interval_seconds),
+            high_threshold_bytes: config.high_threshold_bytes,
+            low_threshold_bytes: config.low_threshold_bytes,
+            schedule,
+            keep: config.keep.clone(),
+            dry_run: config.dry_run,
+            last_scheduled: Mutex::new(Utc::now()),
+        })
+    }
+
+    // With `all`, every unused image goes, whatever the disk usage.
+    pub async fn collect(&self, all: bool) -> Result<(), anyhow::Error> {
+        let referenced = self.referenced().await?;
+        for node in self.cluster.nodes.list().await {
+            if node.status != NodeStatus::Ready {
+                continue;
+            }
+            if let Err(error) = self.collect_node(&node.name, &referenced, all).await {
+                self.cluster
+                    .events
+                    .record(
+                        ObjectKind::Node,
+                        &node.name,
+                        EventReason::Failed,
+                        format!("Image garbage collection failed: {}", error),
+                    )
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn collect_node(
+        &self,
+        node: &str,
+        referenced: &HashSet<String>,
+        all: bool,
+    ) -> Result<(), anyhow::Error> {
+        let runtime = self.cluster.nodes.node(node).await?;
+        let mut usage = runtime.image_disk_usage().await?;
+        if !all && usage <= self.high_threshold_bytes {
+            return Ok(());
+        }
+
+        // Images of containers that still exist can't be removed, whatever their spec says.
+        let in_use: HashSet<String> = runtime
+            .list_managed()
+            .await?
+            .into_iter()
+            .filter_map(|container| container.image_digest)
+            .collect();
+        let mut unused: Vec<Image> = runtime
+            .list_images()
+            .await?
+            .into_iter()
+            .filter(|image| {
+                !in_use.contains(&image.id)
+                    && !image
+                        .tags
+                        .iter()
+                        .any(|tag| referenced.contains(&normalize(tag)) || self.kept(tag))
+            })
+            .collect();
+        unused.sort_by(|a, b| (&a.created, &a.id).cmp(&(&b.created, &b.id)));
+
+        let mut removed = Vec::new();
+        let mut freed = 0;
+        for image in unused {
+            if !all && usage <= self.low_threshold_bytes {
+                break;
+            }
+            let name = image.tags.first().unwrap_or(&image.id).clone();
+            if self.dry_run {
+                println!(
+                    "Unused image {} ({}) on node {} would be removed (dry run)",
+                    name,
+                    format_size(image.size_bytes),
+                    node
+                );
+            } else if let Err(error) = runtime.remove_image(&image).await {
+                println!(
+                    "Failed to remove image {} from node {}: {}",
+                    name, node, error
+                );
+                continue;
+            } else {
+                removed.push(name);
+                freed += image.size_bytes;
+            }
+            usage = usage.saturating_sub(image.size_bytes);
+        }
+
+        if !removed.is_empty() {
+            self.cluster
+                .events
+                .record(
+                    ObjectKind::Node,
+                    node,
+                    EventReason::ImagesRemoved,
+                    format!(
+                        "Removed {} unused image(s), freeing {}: {}",
+                        removed.len(),
+                        format_size(freed),
+                        removed.join(", ")
+                    ),
+                )
+                .await;
+        }
+        Ok(())
+    }
+
+    // The images of every container, init container, job and cron job in the desired state.
+    async fn referenced(&self) -> Result<HashSet<String>, anyhow::Error> {
+        let state = &self.cluster.state;
+        let mut specs: Vec<ContainerSpec> = state.list(KIND).await?;
+        specs.extend(
+            state
+                .list::<Job>(job::KIND)
+                .await?
+                .into_iter()
+                .map(|job| job.spec.template),
+        );
+        specs.extend(
+            state
+                .list::<CronJob>(cron_job::KIND)
+                .await?
+                .into_iter()
+                .map(|cron_job| cron_job.spec.job.template),
+        );
+
+        Ok(specs
+            .iter()
+            .flat_map(|spec| {
+                std::iter::once(&spec.image)
+                    .chain(spec.init_containers.iter().map(|init| &init.image))
+            })
+            .map(|image| normalize(image))
+            .collect())
+    }
+
+    fn kept(&self, tag: &str) -> bool {
+        let tag = normalize(tag);
+        self.keep.iter().any(|keep| match keep.strip_suffix('*') {
+            Some(prefix) => tag.starts_with(prefix),
+            None => normalize(keep) == tag,
+        })
+    }
+
+    async fn scheduled_run_due(&self) -> bool {
+        let Some(schedule) = &self.schedule else {
+            return false;
+        };
+        let now = Utc::now();
+        let mut last = self.last_scheduled.lock().await;
+        if schedule.next_after(*last).is_some_and(|next| next <= now) {
+            *last = now;
+            return true;
+        }
+        false
+    }
+}
+
+// `nginx` and `nginx:latest` are the same image.
+fn normalize(image: &str) -> String {
+    let name = image.rsplit('/').next().unwrap_or(image);
+    if image.contains('@') || name.contains(':') {
+        String::from(image)
+    } else {
+        format!("{}:latest", image)
+    }
+}
+
+#[async_trait]
+impl Watcher for ImageGarbageCollector {
+    fn name(&self) -> &str {
+        "image-gc"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        while ctx.sleep(self.interval).await {
+            let all = self.scheduled_run_due().await;
+            if let Err(error) = self.collect(all).await {
+                println!("Image garbage collection failed: {}", error);
+            }
+        }
+        Ok(())
+    }
+}
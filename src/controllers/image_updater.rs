@@ -0,0 +1,160 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::{
+    cluster::Cluster,
+    config::ImageUpdatesConfig,
+    entities::container::Container,
+    events::event::{EventReason, ObjectKind},
+    watchers::watcher::{Watcher, WatcherContext},
+};
+
+const READY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Pulls the images of containers with `auto_update` and replaces the containers, one at a time,
+// when their tag has moved on to a newer image.
+pub struct ImageUpdater {
+    cluster: Cluster,
+    interval: Duration,
+    ready_timeout: Duration,
+}
+
+impl ImageUpdater {
+    pub fn new(cluster: Cluster, config: &ImageUpdatesConfig) -> Self {
+        ImageUpdater {
+            cluster,
+            interval: Duration::from_secs(config.interval_seconds),
+            ready_timeout: Duration::from_secs(config.ready_timeout_seconds),
+        }
+    }
+
+    pub async fn check_updates(&self, ctx: &WatcherContext) {
+        // Every node pulls each image once, however many containers use it there.
+        let mut images: BTreeMap<(String, String), Vec<Container>> = BTreeMap::new();
+        for container in self.cluster.status_watcher.list().await {
+            if container.spec.auto_update {
+                images
+                    .entry((container.node.clone(), container.spec.image.clone()))
+                    .or_default()
+                    .push(container);
+            }
+        }
+
+        for ((node, image), mut containers) in images {
+            let latest = match self.pull(&node, &image).await {
+                Ok(latest) => latest,
+                Err(error) => {
+                    println!(
+                        "Failed to check image {} on node {}: {}",
+                        image, node, error
+                    );
+                    continue;
+                }
+            };
+
+            containers.sort_by(|a, b| a.name.cmp(&b.name));
+            let mut replaced: BTreeMap<String, usize> = BTreeMap::new();
+            for container in containers {
+                if container.image_digest.as_deref() == Some(latest.as_str()) {
+                    continue;
+                }
+                if ctx.shutdown.is_cancelled() {
+                    return;
+                }
+                // Stop the rollout at the first replacement that doesn't become ready, leaving
+                // the remaining containers on the old image.
+                if let Err(error) = self.replace(&container, &latest).await {
+                    self.record(
+                        &container.app,
+                        EventReason::Failed,
+                        format!(
+                            "Stopped rolling out image {} on node {}: {}",
+                            image, node, error
+                        ),
+                    )
+                    .await;
+                    replaced.remove(&container.app);
+                    break;
+                }
+                *replaced.entry(container.app).or_default() += 1;
+            }
+
+            for (app, count) in replaced {
+                self.record(
+                    &app,
+                    EventReason::RolledOut,
+                    format!(
+                        "Rolled out image {} ({}) to {} containers on node {}",
+                        image, latest, count, node
+                    ),
+                )
+                .await;
+            }
+        }
+    }
+
+    async fn record(&self, app: &str, reason: EventReason, message: String) {
+        self.cluster
+            .events
+            .record(ObjectKind::App, app, reason, message)
+            .await
+    }
+
+    async fn pull(&self, node: &str, image: &str) -> Result<String, anyhow::Error> {
+        self.cluster.nodes.node(node).await?.pull(image).await
+    }
+
+    async fn replace(&self, container: &Container, latest: &str) -> Result<(), anyhow::Error> {
+        self.cluster
+            .events
+            .record_container(
+                &container.name,
+                EventReason::Pulled,
+                format!(
+                    "Image {} changed from {} to {}, replacing container {}",
+                    container.spec.image,
+                    container.image_digest.as_deref().unwrap_or("<unknown>"),
+                    latest,
+                    container.name
+                ),
+            )
+            .await;
+
+        container.delete(&self.cluster).await?;
+        let replacement = Container::new(&container.spec, &self.cluster).await?;
+        self.wait_until_ready(&replacement).await
+    }
+
+    async fn wait_until_ready(&self, container: &Container) -> Result<(), anyhow::Error> {
+        let deadline = tokio::time::Instant::now() + self.ready_timeout;
+        loop {
+            let current = self.cluster.status_watcher.find(&container.id).await;
+            if current.as_ref().is_some_and(Container::is_ready) {
+                return Ok(());
+            }
+            if current.is_none() || tokio::time::Instant::now() >= deadline {
+                return Err(anyhow::anyhow!(
+                    "container {} did not become ready within {}s",
+                    container.name,
+                    self.ready_timeout.as_secs()
+                ));
+            }
+            tokio::time::sleep(READY_POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Watcher for ImageUpdater {
+    fn name(&self) -> &str {
+        "image-updater"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        while ctx.sleep(self.interval).await {
+            self.check_updates(&ctx).await;
+        }
+        Ok(())
+    }
+}
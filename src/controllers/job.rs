@@ -0,0 +1,308 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use tokio::{sync::Mutex, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cluster::Cluster,
+    entities::{
+        container::{Container, ContainerSpec, ContainerStatus, RestartPolicy, JOB_LABEL},
+        job::{backoff_delay, Job, JobAttempt, JobPhase, JobSpec},
+    },
+    events::event::{EventReason, ObjectKind},
+};
+
+pub(crate) const KIND: &str = "jobs";
+
+// Runs each job's containers to completion on its own task. Job containers are labelled with the
+// job name instead of the managed label, so the status watcher never adopts them.
+pub struct JobController {
+    cluster: Cluster,
+    shutdown: CancellationToken,
+    running: Mutex<HashMap<String, (CancellationToken, JoinHandle<()>)>>,
+}
+
+impl JobController {
+    pub fn new(cluster: Cluster, shutdown: CancellationToken) -> Self {
+        JobController {
+            cluster,
+            shutdown,
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn resume(self: &Arc<Self>) -> Result<(), anyhow::Error> {
+        for job in self.list().await? {
+            if !job.is_finished() {
+                println!("Resuming job {}", job.name());
+                self.spawn(job).await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn create(self: &Arc<Self>, spec: JobSpec) -> Result<Job, anyhow::Error> {
+        let spec = JobSpec {
+            template: self.cluster.admission.admit(spec.template).await?,
+            ..spec
+        };
+        let job = Job::new(spec);
+
+        self.cluster.state.put(KIND, job.name(), &job).await?;
+        self.record(
+            &job,
+            EventReason::Created,
+            format!("Created job {}", job.name()),
+        )
+        .await;
+        self.spawn(job.clone()).await;
+        Ok(job)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Option<Job>, anyhow::Error> {
+        self.cluster.state.get(KIND, name).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<Job>, anyhow::Error> {
+        let mut jobs: Vec<Job> = self.cluster.state.list(KIND).await?;
+        jobs.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok(jobs)
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<bool, anyhow::Error> {
+        // Wait for the task to stop so it can't write the job back after it is deleted.
+        let running = self.running.lock().await.remove(name);
+        if let Some((token, handle)) = running {
+            token.cancel();
+            let _ = handle.await;
+        }
+
+        let Some(job) = self.get(name).await? else {
+            return Ok(false);
+        };
+        for attempt in job.status.attempts.iter() {
+            if let Some(id) = &attempt.container_id {
+                let _ = self.cluster.runtime.remove(id).await;
+            }
+        }
+
+        self.cluster.state.delete(KIND, name).await
+    }
+
+    async fn spawn(self: &Arc<Self>, job: Job) {
+        let name = job.name().to_string();
+        let token = self.shutdown.child_token();
+        let controller = self.clone();
+        let task_token = token.clone();
+
+        let handle = tokio::spawn(async move {
+            let name = job.name().to_string();
+            if let Err(error) = controller.run(job, &task_token).await {
+                println!("Job {} stopped: {}", name, error);
+            }
+            if !task_token.is_cancelled() {
+                controller.running.lock().await.remove(&name);
+            }
+        });
+
+        self.running.lock().await.insert(name, (token, handle));
+    }
+
+    async fn run(&self, mut job: Job, token: &CancellationToken) -> Result<(), anyhow::Error> {
+        loop {
+            let running = job
+                .status
+                .attempts
+                .last()
+                .filter(|attempt| attempt.finished.is_none())
+                .and_then(|attempt| attempt.container_id.clone());
+
+            let exit_code = match running {
+                Some(id) => self.start_and_wait(&id, token).await,
+                None => {
+                    if !self.back_off(&job, token).await {
+                        return Ok(());
+                    }
+                    let started = self.start_attempt(&mut job).await;
+                    self.save(&job, token).await?;
+
+                    match started {
+                        Ok(id) => self.start_and_wait(&id, token).await,
+                        Err(error) => Some(Err(error)),
+                    }
+                }
+            };
+            let Some(exit_code) = exit_code else {
+                return Ok(());
+            };
+
+            let attempt_count = job.status.attempts.len();
+            if let Some(attempt) = job.status.attempts.last_mut() {
+                attempt.finished = Some(chrono::Utc::now().to_rfc3339());
+                attempt.exit_code = exit_code.as_ref().ok().copied();
+            }
+
+            match exit_code {
+                Ok(0) => {
+                    job.status.phase = JobPhase::Complete;
+                    job.status.completion_time = Some(chrono::Utc::now().to_rfc3339());
+                    job.status.message = None;
+                    self.save(&job, token).await?;
+                    self.record(
+                        &job,
+                        EventReason::Completed,
+                        format!("Job completed after {} attempt(s)", attempt_count),
+                    )
+                    .await;
+                    return Ok(());
+                }
+                Ok(code) => job.status.message = Some(format!("Attempt exited with code {}", code)),
+                Err(error) => job.status.message = Some(format!("Attempt failed: {}", error)),
+            }
+            self.record(
+                &job,
+                EventReason::Failed,
+                job.status.message.clone().unwrap_or_default(),
+            )
+            .await;
+
+            if job.failures() > job.spec.backoff_limit {
+                job.status.phase = JobPhase::Failed;
+                job.status.completion_time = Some(chrono::Utc::now().to_rfc3339());
+                job.status.message =
+                    Some(String::from("Job has reached the specified backoff limit"));
+                self.save(&job, token).await?;
+                self.record(
+                    &job,
+                    EventReason::Failed,
+                    format!(
+                        "Job failed after {} attempt(s), backoff limit is {}",
+                        attempt_count, job.spec.backoff_limit
+                    ),
+                )
+                .await;
+                return Ok(());
+            }
+            self.save(&job, token).await?;
+        }
+    }
+
+    // Sleeps out the remaining back-off since the last failed attempt; false when cancelled.
+    async fn back_off(&self, job: &Job, token: &CancellationToken) -> bool {
+        let failures = job.failures();
+        let Some(finished) = job
+            .status
+            .attempts
+            .last()
+            .and_then(|attempt| attempt.finished.as_ref())
+        else {
+            return true;
+        };
+        if failures == 0 {
+            return true;
+        }
+
+        let elapsed = chrono::DateTime::parse_from_rfc3339(finished)
+            .ok()
+            .and_then(|finished| {
+                chrono::Utc::now()
+                    .signed_duration_since(finished)
+                    .to_std()
+                    .ok()
+            })
+            .unwrap_or_default();
+        let remaining = backoff_delay(failures).saturating_sub(elapsed);
+
+        self.record(
+            job,
+            EventReason::BackOff,
+            format!("Back-off {}s before retrying", remaining.as_secs()),
+        )
+        .await;
+
+        tokio::select! {
+            _ = token.cancelled() => false,
+            _ = tokio::time::sleep(remaining) => true,
+        }
+    }
+
+    async fn start_attempt(&self, job: &mut Job) -> Result<String, anyhow::Error> {
+        // Failed attempts are retried by the controller, not restarted in place.
+        let spec = ContainerSpec {
+            name: format!("{}-{}", job.name(), job.status.attempts.len() + 1),
+            app: Some(job.name().to_string()),
+            restart_policy: RestartPolicy::Never,
+            ..job.spec.template.clone()
+        };
+        let labels = BTreeMap::from([(String::from(JOB_LABEL), job.name().to_string())]);
+
+        let now = chrono::Utc::now().to_rfc3339();
+        job.status.phase = JobPhase::Running;
+        job.status.start_time.get_or_insert_with(|| now.clone());
+
+        let created = Container::create(&spec, labels, &self.cluster).await;
+        job.status.attempts.push(JobAttempt {
+            container_name: spec.name,
+            container_id: created.as_ref().ok().map(|container| container.id.clone()),
+            started: now,
+            finished: None,
+            exit_code: None,
+        });
+
+        created.map(|container| container.id)
+    }
+
+    // Attempt containers are created stopped and started here, after their init containers, which
+    // also covers an attempt interrupted by a restart before it started.
+    async fn start_and_wait(
+        &self,
+        id: &str,
+        token: &CancellationToken,
+    ) -> Option<Result<i64, anyhow::Error>> {
+        match self.cluster.runtime.inspect(id).await {
+            Ok(container) if container.get_status() == ContainerStatus::Created => {
+                tokio::select! {
+                    _ = token.cancelled() => return None,
+                    started = container.start(&self.cluster) => {
+                        if let Err(error) = started {
+                            return Some(Err(error));
+                        }
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(error) => return Some(Err(error)),
+        }
+
+        self.wait(id, token).await
+    }
+
+    // None when cancelled while the container is still running.
+    async fn wait(
+        &self,
+        id: &str,
+        token: &CancellationToken,
+    ) -> Option<Result<i64, anyhow::Error>> {
+        tokio::select! {
+            _ = token.cancelled() => None,
+            exit_code = self.cluster.runtime.wait(id) => Some(exit_code),
+        }
+    }
+
+    async fn save(&self, job: &Job, token: &CancellationToken) -> Result<(), anyhow::Error> {
+        if token.is_cancelled() {
+            return Ok(());
+        }
+        self.cluster.state.put(KIND, job.name(), job).await
+    }
+
+    async fn record(&self, job: &Job, reason: EventReason, message: String) {
+        self.cluster
+            .events
+            .record(ObjectKind::Job, job.name(), reason, message)
+            .await
+    }
+}
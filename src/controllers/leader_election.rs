@@ -0,0 +1,141 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::{config::LeaderElectionConfig, store::state::StateStore};
+
+const KIND: &str = "leases";
+const LEASE: &str = "control-plane";
+
+// Whoever holds an unexpired lease runs the control plane. Expiry compares the holder's clock with
+// ours, so the servers' clocks need to agree to well within the lease duration.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Lease {
+    pub holder: String,
+    pub acquired: DateTime<Utc>,
+    pub renewed: DateTime<Utc>,
+    pub duration_seconds: u64,
+}
+
+impl Lease {
+    fn expired(&self, now: DateTime<Utc>) -> bool {
+        now.signed_duration_since(self.renewed).num_seconds() >= self.duration_seconds as i64
+    }
+}
+
+// Lets several servers share a data dir with only one of them running controllers and watchers.
+// The others wait for the lease and take over once the leader stops renewing it.
+pub struct LeaderElection {
+    state: Arc<StateStore>,
+    identity: String,
+    lease_duration: Duration,
+    renew_interval: Duration,
+}
+
+impl LeaderElection {
+    pub fn new(
+        state: Arc<StateStore>,
+        config: &LeaderElectionConfig,
+    ) -> Result<Self, anyhow::Error> {
+        if config.renew_interval_seconds == 0
+            || config.renew_interval_seconds >= config.lease_duration_seconds
+        {
+            return Err(anyhow!(
+                "the leader lease renew interval ({}s) must be at least 1s and shorter than the lease duration ({}s)",
+                config.renew_interval_seconds,
+                config.lease_duration_seconds
+            ));
+        }
+
+        Ok(LeaderElection {
+            state,
+            identity: config.identity(),
+            lease_duration: Duration::from_secs(config.lease_duration_seconds),
+            renew_interval: Duration::from_secs(config.renew_interval_seconds),
+        })
+    }
+
+    // Waits until this server holds the lease.
+    pub async fn acquire(&self) {
+        let mut waiting_on = None;
+        loop {
+            match self.try_acquire().await {
+                Ok(None) => {
+                    println!("Became leader as {}", self.identity);
+                    return;
+                }
+                Ok(Some(lease)) => {
+                    if waiting_on.as_ref() != Some(&lease.holder) {
+                        println!(
+                            "Waiting for the leader lease held by {} since {}",
+                            lease.holder,
+                            lease.acquired.to_rfc3339()
+                        );
+                        waiting_on = Some(lease.holder);
+                    }
+                }
+                Err(error) => println!("Failed to acquire the leader lease: {}", error),
+            }
+            tokio::time::sleep(self.renew_interval).await;
+        }
+    }
+
+    // Renews the lease for as long as this server keeps it; returns once it is lost.
+    pub async fn hold(&self) -> anyhow::Error {
+        let mut renewed = Instant::now();
+        loop {
+            tokio::time::sleep(self.renew_interval).await;
+            match self.try_acquire().await {
+                Ok(None) => renewed = Instant::now(),
+                Ok(Some(lease)) => {
+                    return anyhow!("lost the leader lease to {}", lease.holder);
+                }
+                // Give up before the lease expires, since another server may take over then even
+                // though we can't tell.
+                Err(error) if renewed.elapsed() + self.renew_interval >= self.lease_duration => {
+                    return anyhow!("failed to renew the leader lease: {}", error);
+                }
+                Err(error) => println!("Failed to renew the leader lease: {}", error),
+            }
+        }
+    }
+
+    // Lets a waiting server take over right away instead of after the lease expires.
+    pub async fn release(&self) {
+        let released = async {
+            let _lock = self.state.lock(KIND, LEASE).await?;
+            let lease: Option<Lease> = self.state.get(KIND, LEASE).await?;
+            if lease.is_some_and(|lease| lease.holder == self.identity) {
+                self.state.delete(KIND, LEASE).await?;
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        if let Err(error) = released.await {
+            println!("Failed to release the leader lease: {}", error);
+        }
+    }
+
+    // Takes or renews the lease, unless another server holds it, in which case that lease is
+    // returned.
+    async fn try_acquire(&self) -> Result<Option<Lease>, anyhow::Error> {
+        let _lock = self.state.lock(KIND, LEASE).await?;
+        let now = Utc::now();
+
+        let acquired = match self.state.get::<Lease>(KIND, LEASE).await? {
+            Some(lease) if lease.holder == self.identity => lease.acquired,
+            Some(lease) if !lease.expired(now) => return Ok(Some(lease)),
+            _ => now,
+        };
+        let lease = Lease {
+            holder: self.identity.clone(),
+            acquired,
+            renewed: now,
+            duration_seconds: self.lease_duration.as_secs(),
+        };
+        self.state.put(KIND, LEASE, &lease).await?;
+        Ok(None)
+    }
+}
@@ -0,0 +1,11 @@
+pub mod autoscaler;
+pub mod cron_job;
+pub mod drift;
+pub mod garbage_collector;
+pub mod image_gc;
+pub mod image_updater;
+pub mod job;
+pub mod leader_election;
+pub mod rollout;
+pub mod schedule;
+pub mod work_queue;
@@ -0,0 +1,597 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::Mutex;
+
+use crate::{
+    cluster::Cluster,
+    config::RolloutConfig,
+    entities::{
+        container::Container,
+        rollout::{
+            is_replica, BlueGreenStrategy, CanaryStrategy, MetricCheck, Rollout, RolloutPhase,
+            RolloutSpec, RolloutStrategy,
+        },
+    },
+    events::event::{EventReason, ObjectKind},
+    ingress::IngressController,
+    watchers::watcher::{Watcher, WatcherContext},
+};
+
+pub(crate) const KIND: &str = "rollouts";
+
+// Moves each rollout through its phases, one step per pass. Everything a step needs is kept in
+// the rollout's status, so rollouts carry on where they were after a restart.
+pub struct RolloutController {
+    cluster: Cluster,
+    interval: Duration,
+    http: reqwest::Client,
+    // Told when a blue-green rollout moves traffic, so it doesn't wait for its next resync.
+    ingress: Option<Arc<IngressController>>,
+    // Held for a whole pass, so `delete` can't race a step.
+    lock: Mutex<()>,
+}
+
+impl RolloutController {
+    pub fn new(cluster: Cluster, config: &RolloutConfig) -> Result<Self, anyhow::Error> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.metric_timeout_seconds))
+            .build()?;
+        Ok(RolloutController {
+            cluster,
+            interval: Duration::from_secs(config.interval_seconds),
+            http,
+            ingress: None,
+            lock: Mutex::new(()),
+        })
+    }
+
+    pub fn with_ingress(mut self, ingress: Arc<IngressController>) -> Self {
+        self.ingress = Some(ingress);
+        self
+    }
+
+    pub async fn create(&self, spec: RolloutSpec) -> Result<Rollout, anyhow::Error> {
+        spec.validate()?;
+        let rollout = Rollout::new(spec);
+
+        self.cluster
+            .state
+            .put(KIND, rollout.name(), &rollout)
+            .await?;
+        let message = match &rollout.spec.strategy {
+            RolloutStrategy::Canary(canary) => format!(
+                "Started a canary rollout of app {} with {}% of its replicas and {}s of analysis",
+                rollout.name(),
+                canary.percent,
+                canary.analysis_seconds
+            ),
+            RolloutStrategy::BlueGreen(_) => {
+                format!("Started a blue-green rollout of app {}", rollout.name())
+            }
+        };
+        self.record(&rollout, EventReason::Created, message).await;
+        Ok(rollout)
+    }
+
+    pub async fn get(&self, app: &str) -> Result<Option<Rollout>, anyhow::Error> {
+        self.cluster.state.get(KIND, app).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<Rollout>, anyhow::Error> {
+        let mut rollouts: Vec<Rollout> = self.cluster.state.list(KIND).await?;
+        rollouts.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok(rollouts)
+    }
+
+    // Stops a rollout still in progress where it is: canaries are removed, leaving replicas
+    // already promoted, and of a blue-green rollout's two sets the one without traffic is.
+    pub async fn delete(&self, app: &str) -> Result<bool, anyhow::Error> {
+        let _lock = self.lock.lock().await;
+        let Some(rollout) = self.get(app).await? else {
+            return Ok(false);
+        };
+        if !rollout.is_finished() {
+            if rollout.status.switched {
+                self.remove_old_set(&rollout).await?;
+            } else {
+                self.remove_new(&rollout).await?;
+            }
+        }
+        let deleted = self.cluster.state.delete(KIND, app).await?;
+        self.traffic_changed();
+        Ok(deleted)
+    }
+
+    // Takes a rollout back, moving traffic back to the old set of a blue-green rollout right
+    // away. The next pass removes what the rollout created.
+    pub async fn undo(&self, app: &str) -> Result<Rollout, anyhow::Error> {
+        let _lock = self.lock.lock().await;
+        let mut rollout = self
+            .get(app)
+            .await?
+            .ok_or_else(|| anyhow!("rollout {} not found", app))?;
+        if !rollout.can_undo() {
+            return Err(anyhow!(
+                "rollout {} can't be undone while {:?}",
+                app,
+                rollout.status.phase
+            ));
+        }
+
+        rollout.status.switched = false;
+        enter(
+            &mut rollout,
+            RolloutPhase::RollingBack,
+            Some(String::from("undone")),
+        );
+        self.cluster
+            .state
+            .put(KIND, rollout.name(), &rollout)
+            .await?;
+        self.traffic_changed();
+        Ok(rollout)
+    }
+
+    pub async fn check_rollouts(&self) -> Result<(), anyhow::Error> {
+        let _lock = self.lock.lock().await;
+        for mut rollout in self.list().await? {
+            if rollout.is_finished() {
+                continue;
+            }
+            if let Err(error) = self.step(&mut rollout).await {
+                self.record(
+                    &rollout,
+                    EventReason::Failed,
+                    format!("Rollout of app {} failed a step: {}", rollout.name(), error),
+                )
+                .await;
+            }
+            self.cluster
+                .state
+                .put(KIND, rollout.name(), &rollout)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn step(&self, rollout: &mut Rollout) -> Result<(), anyhow::Error> {
+        match (rollout.status.phase.clone(), rollout.spec.strategy.clone()) {
+            (RolloutPhase::Progressing, RolloutStrategy::Canary(canary)) => {
+                self.progress(rollout, &canary).await
+            }
+            (RolloutPhase::Progressing, RolloutStrategy::BlueGreen(_)) => {
+                self.progress_new_set(rollout).await
+            }
+            (RolloutPhase::Analyzing, RolloutStrategy::Canary(canary)) => {
+                self.analyze(rollout, &canary).await
+            }
+            (RolloutPhase::Promoting, _) => self.promote(rollout).await,
+            (RolloutPhase::Switched, RolloutStrategy::BlueGreen(blue_green)) => {
+                self.scale_down(rollout, &blue_green).await
+            }
+            (RolloutPhase::RollingBack, _) => self.roll_back(rollout).await,
+            _ => Ok(()),
+        }
+    }
+
+    // Starts the canaries, then waits for all of them to be ready.
+    async fn progress(
+        &self,
+        rollout: &mut Rollout,
+        canary: &CanaryStrategy,
+    ) -> Result<(), anyhow::Error> {
+        if rollout.status.canaries.is_empty() {
+            let count = canary.canaries(self.replicas(rollout.name()).await.len());
+            for index in 1..=count {
+                let name = format!("{}-canary-{}", rollout.name(), index);
+                // Left behind by an earlier rollout of the app.
+                if let Some(stale) = self.cluster.status_watcher.find(&name).await {
+                    stale.delete(&self.cluster).await?;
+                }
+                if let Err(error) =
+                    Container::new(&rollout.spec.replica(&name), &self.cluster).await
+                {
+                    let message = format!("failed to create canary {}: {}", name, error);
+                    enter(rollout, RolloutPhase::RollingBack, Some(message));
+                    return Ok(());
+                }
+                rollout.status.canaries.push(name);
+            }
+            self.record(
+                rollout,
+                EventReason::Scaled,
+                format!(
+                    "Started {} canary container(s) of app {}",
+                    count,
+                    rollout.name()
+                ),
+            )
+            .await;
+            return Ok(());
+        }
+
+        if self.all_ready(&rollout.status.canaries).await {
+            self.record(
+                rollout,
+                EventReason::Ready,
+                format!(
+                    "Canaries of app {} are ready, analyzing them for {}s",
+                    rollout.name(),
+                    canary.analysis_seconds
+                ),
+            )
+            .await;
+            enter(rollout, RolloutPhase::Analyzing, None);
+        } else if elapsed(rollout) >= rollout.spec.progress_deadline_seconds {
+            let message = format!(
+                "canaries did not become ready within {}s",
+                rollout.spec.progress_deadline_seconds
+            );
+            enter(rollout, RolloutPhase::RollingBack, Some(message));
+        }
+        Ok(())
+    }
+
+    // Checks the canaries and the metric, if any, until the analysis window is over.
+    async fn analyze(
+        &self,
+        rollout: &mut Rollout,
+        canary: &CanaryStrategy,
+    ) -> Result<(), anyhow::Error> {
+        for name in rollout.status.canaries.iter() {
+            rollout.status.checks += 1;
+            if self.ready(name).await {
+                rollout.status.ready_checks += 1;
+            }
+        }
+
+        if let Some(metric) = &canary.metric {
+            let failure = match self.read_metric(metric).await {
+                Ok(value) if metric.within(value) => None,
+                Ok(value) => Some(format!(
+                    "metric {} was {}, outside {}",
+                    metric.url,
+                    value,
+                    bounds(metric)
+                )),
+                Err(error) => Some(format!("failed to read metric {}: {}", metric.url, error)),
+            };
+            if failure.is_some() {
+                enter(rollout, RolloutPhase::RollingBack, failure);
+                return Ok(());
+            }
+        }
+
+        if elapsed(rollout) < canary.analysis_seconds {
+            return Ok(());
+        }
+        let rate = rollout.status.success_rate().unwrap_or(0.0);
+        if let Some(min) = canary.min_success_rate.filter(|min| rate < *min) {
+            let message = format!(
+                "canaries were ready in {:.0}% of checks, below the required {:.0}%",
+                rate * 100.0,
+                min * 100.0
+            );
+            enter(rollout, RolloutPhase::RollingBack, Some(message));
+            return Ok(());
+        }
+
+        self.record(
+            rollout,
+            EventReason::Completed,
+            format!(
+                "Canaries of app {} passed analysis, ready in {:.0}% of checks; promoting",
+                rollout.name(),
+                rate * 100.0
+            ),
+        )
+        .await;
+        enter(rollout, RolloutPhase::Promoting, None);
+        Ok(())
+    }
+
+    // Replaces the replicas with the new spec one at a time, each once the one before it is
+    // ready, then removes the canaries.
+    async fn promote(&self, rollout: &mut Rollout) -> Result<(), anyhow::Error> {
+        if let Some(last) = rollout.status.promoted.last().cloned() {
+            if !self.ready(&last).await {
+                if elapsed(rollout) >= rollout.spec.progress_deadline_seconds {
+                    let message = format!(
+                        "replacement {} did not become ready within {}s",
+                        last, rollout.spec.progress_deadline_seconds
+                    );
+                    self.record(rollout, EventReason::Failed, message.clone())
+                        .await;
+                    enter(rollout, RolloutPhase::Failed, Some(message));
+                }
+                return Ok(());
+            }
+        }
+
+        let mut outdated: Vec<Container> = self
+            .replicas(rollout.name())
+            .await
+            .into_iter()
+            .filter(|container| !rollout.status.promoted.contains(&container.name))
+            .collect();
+        outdated.sort_by(|a, b| a.name.cmp(&b.name));
+        let Some(container) = outdated.into_iter().next() else {
+            self.remove_canaries(rollout).await?;
+            self.record(
+                rollout,
+                EventReason::RolledOut,
+                format!(
+                    "Rolled out app {} to {} replica(s)",
+                    rollout.name(),
+                    rollout.status.promoted.len()
+                ),
+            )
+            .await;
+            enter(rollout, RolloutPhase::Succeeded, None);
+            return Ok(());
+        };
+
+        container.delete(&self.cluster).await?;
+        rollout.status.promoted.push(container.name.clone());
+        // Restarts the deadline for this replacement.
+        enter(rollout, RolloutPhase::Promoting, None);
+        Container::new(&rollout.spec.replica(&container.name), &self.cluster).await?;
+        Ok(())
+    }
+
+    // Starts a set of replicas as large as the app's next to it, then waits for all of them to
+    // be ready before switching traffic over.
+    async fn progress_new_set(&self, rollout: &mut Rollout) -> Result<(), anyhow::Error> {
+        if rollout.status.replicas.is_empty() {
+            let old_set = self.replicas(rollout.name()).await;
+            // Nothing would ever become ready, so the deadline would never be checked.
+            if old_set.is_empty() {
+                let message = format!("app {} has no replicas to replace", rollout.name());
+                enter(rollout, RolloutPhase::RollingBack, Some(message));
+                return Ok(());
+            }
+            // The sets take turns on the names, so the new one never collides with the old.
+            let blue = format!("{}-blue-", rollout.name());
+            let color = if old_set.iter().any(|c| c.name.starts_with(&blue)) {
+                "green"
+            } else {
+                "blue"
+            };
+            for index in 1..=old_set.len() {
+                let name = format!("{}-{}-{}", rollout.name(), color, index);
+                if let Err(error) =
+                    Container::new(&rollout.spec.replica(&name), &self.cluster).await
+                {
+                    let message = format!("failed to create {}: {}", name, error);
+                    enter(rollout, RolloutPhase::RollingBack, Some(message));
+                    return Ok(());
+                }
+                rollout.status.replicas.push(name);
+            }
+            self.record(
+                rollout,
+                EventReason::Scaled,
+                format!(
+                    "Started {} {} replica(s) of app {}",
+                    old_set.len(),
+                    color,
+                    rollout.name()
+                ),
+            )
+            .await;
+            return Ok(());
+        }
+
+        if self.all_ready(&rollout.status.replicas).await {
+            rollout.status.switched = true;
+            self.traffic_changed();
+            self.record(
+                rollout,
+                EventReason::Switched,
+                format!(
+                    "Switched the traffic of app {} to its new replicas",
+                    rollout.name()
+                ),
+            )
+            .await;
+            enter(rollout, RolloutPhase::Switched, None);
+        } else if elapsed(rollout) >= rollout.spec.progress_deadline_seconds {
+            let message = format!(
+                "new replicas did not become ready within {}s",
+                rollout.spec.progress_deadline_seconds
+            );
+            enter(rollout, RolloutPhase::RollingBack, Some(message));
+        }
+        Ok(())
+    }
+
+    // Removes the old set once the rollout can no longer be undone.
+    async fn scale_down(
+        &self,
+        rollout: &mut Rollout,
+        blue_green: &BlueGreenStrategy,
+    ) -> Result<(), anyhow::Error> {
+        if elapsed(rollout) < blue_green.scale_down_delay_seconds {
+            return Ok(());
+        }
+        self.remove_old_set(rollout).await?;
+        self.record(
+            rollout,
+            EventReason::RolledOut,
+            format!(
+                "Rolled out app {} to {} replica(s)",
+                rollout.name(),
+                rollout.status.replicas.len()
+            ),
+        )
+        .await;
+        enter(rollout, RolloutPhase::Succeeded, None);
+        Ok(())
+    }
+
+    async fn roll_back(&self, rollout: &mut Rollout) -> Result<(), anyhow::Error> {
+        if rollout.status.switched {
+            rollout.status.switched = false;
+            self.traffic_changed();
+        }
+        self.remove_new(rollout).await?;
+        let reason = rollout.status.message.clone().unwrap_or_default();
+        self.record(
+            rollout,
+            EventReason::RolledBack,
+            format!("Rolled back app {}: {}", rollout.name(), reason),
+        )
+        .await;
+        enter(
+            rollout,
+            RolloutPhase::RolledBack,
+            rollout.status.message.clone(),
+        );
+        Ok(())
+    }
+
+    async fn remove_canaries(&self, rollout: &Rollout) -> Result<(), anyhow::Error> {
+        for name in rollout.status.canaries.iter() {
+            if let Some(canary) = self.cluster.status_watcher.find(name).await {
+                canary.delete(&self.cluster).await?;
+            }
+        }
+        Ok(())
+    }
+
+    // The containers the rollout started, which a roll back removes again.
+    async fn remove_new(&self, rollout: &Rollout) -> Result<(), anyhow::Error> {
+        self.remove_canaries(rollout).await?;
+        for name in rollout.status.replicas.iter() {
+            if let Some(replica) = self.cluster.status_watcher.find(name).await {
+                replica.delete(&self.cluster).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn remove_old_set(&self, rollout: &Rollout) -> Result<(), anyhow::Error> {
+        for container in self.replicas(rollout.name()).await {
+            if !rollout.status.replicas.contains(&container.name) {
+                container.delete(&self.cluster).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn traffic_changed(&self) {
+        if let Some(ingress) = &self.ingress {
+            ingress.endpoints_changed();
+        }
+    }
+
+    async fn read_metric(&self, metric: &MetricCheck) -> Result<f64, anyhow::Error> {
+        let body = self
+            .http
+            .get(&metric.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        body.trim()
+            .parse()
+            .map_err(|_| anyhow!("expected a number, got {:?}", body.trim()))
+    }
+
+    async fn replicas(&self, app: &str) -> Vec<Container> {
+        self.cluster
+            .status_watcher
+            .list()
+            .await
+            .into_iter()
+            .filter(|container| is_replica(container, app))
+            .collect()
+    }
+
+    async fn ready(&self, name: &str) -> bool {
+        self.cluster
+            .status_watcher
+            .find(name)
+            .await
+            .is_some_and(|container| container.is_ready())
+    }
+
+    async fn all_ready(&self, names: &[String]) -> bool {
+        for name in names {
+            if !self.ready(name).await {
+                return false;
+            }
+        }
+        true
+    }
+
+    async fn record(&self, rollout: &Rollout, reason: EventReason, message: String) {
+        self.cluster
+            .events
+            .record(ObjectKind::Rollout, rollout.name(), reason, message)
+            .await
+    }
+}
+
+// The containers that should get traffic, leaving out the set of a blue-green rollout that
+// isn't serving.
+pub async fn serving(cluster: &Cluster) -> Result<Vec<Container>, anyhow::Error> {
+    let rollouts: Vec<Rollout> = cluster.state.list(KIND).await?;
+    Ok(cluster
+        .status_watcher
+        .list()
+        .await
+        .into_iter()
+        .filter(|container| {
+            rollouts
+                .iter()
+                .filter(|rollout| rollout.name() == container.app)
+                .all(|rollout| rollout.serves(&container.name))
+        })
+        .collect())
+}
+
+fn enter(rollout: &mut Rollout, phase: RolloutPhase, message: Option<String>) {
+    rollout.status.phase = phase;
+    rollout.status.phase_started = Utc::now().to_rfc3339();
+    rollout.status.message = message;
+}
+
+// Seconds since the current phase started.
+fn elapsed(rollout: &Rollout) -> u64 {
+    DateTime::parse_from_rfc3339(&rollout.status.phase_started).map_or(0, |started| {
+        Utc::now()
+            .signed_duration_since(started)
+            .num_seconds()
+            .max(0) as u64
+    })
+}
+
+fn bounds(metric: &MetricCheck) -> String {
+    match (metric.min, metric.max) {
+        (Some(min), Some(max)) => format!("{}..{}", min, max),
+        (Some(min), None) => format!(">= {}", min),
+        (None, Some(max)) => format!("<= {}", max),
+        (None, None) => String::from("any value"),
+    }
+}
+
+#[async_trait]
+impl Watcher for RolloutController {
+    fn name(&self) -> &str {
+        "rollout"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        while ctx.sleep(self.interval).await {
+            if let Err(error) = self.check_rollouts().await {
+                println!("Failed to check rollouts: {}", error);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Datelike, Days, DurationRound, TimeDelta, TimeZone, Timelike, Utc};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+// A standard five-field cron expression (minute hour day-of-month month day-of-week), evaluated
+// in UTC. Each field is stored as a bitset of the values it matches.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    // Like cron, when both day fields are restricted a day matching either one fires.
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let expanded = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            expression => expression,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(anyhow!(
+                "invalid schedule {:?}: expected 5 fields, got {}",
+                expression,
+                fields.len()
+            ));
+        }
+
+        let field = |index: usize, min: u32, max: u32, names: &[&str], offset: u32| {
+            parse_field(fields[index], min, max, names, offset)
+                .map_err(|error| anyhow!("invalid schedule {:?}: {}", expression, error))
+        };
+
+        // Sunday can be written as 0 or 7.
+        let mut days_of_week = field(4, 0, 7, &WEEKDAYS, 0)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        let schedule = Schedule {
+            minutes: field(0, 0, 59, &[], 0)?,
+            hours: field(1, 0, 23, &[], 0)?,
+            days_of_month: field(2, 1, 31, &[], 0)?,
+            months: field(3, 1, 12, &MONTHS, 1)?,
+            days_of_week,
+            any_day_of_month: fields[2].starts_with('*'),
+            any_day_of_week: fields[4].starts_with('*'),
+        };
+
+        if schedule.next_after(Utc::now()).is_none() {
+            return Err(anyhow!("schedule {:?} never fires", expression));
+        }
+        Ok(schedule)
+    }
+}
+
+fn parse_value(value: &str, names: &[&str], offset: u32) -> Result<u32, anyhow::Error> {
+    if let Some(index) = names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(value))
+    {
+        return Ok(index as u32 + offset);
+    }
+    value
+        .parse()
+        .map_err(|_| anyhow!("{:?} is not a number", value))
+}
+
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+    offset: u32,
+) -> Result<u64, anyhow::Error> {
+    let mut bits = 0;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(parse_value(step, &[], 0)?)),
+            None => (part, None),
+        };
+
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (
+                parse_value(start, names, offset)?,
+                parse_value(end, names, offset)?,
+            ),
+            // `5/15` means every 15 starting at 5.
+            None if step.is_some() => (parse_value(range, names, offset)?, max),
+            None => {
+                let value = parse_value(range, names, offset)?;
+                (value, value)
+            }
+        };
+
+        if start < min || end > max || start > end {
+            return Err(anyhow!("{:?} is outside {}-{}", part, min, max));
+        }
+        if step == Some(0) {
+            return Err(anyhow!("{:?} has a zero step", part));
+        }
+
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+fn contains(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+impl Schedule {
+    fn matches_day(&self, time: &DateTime<Utc>) -> bool {
+        let day_of_month = contains(self.days_of_month, time.day());
+        let day_of_week = contains(self.days_of_week, time.weekday().num_days_from_sunday());
+
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+
+    // The first time strictly after `after` that the schedule fires.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.duration_trunc(TimeDelta::minutes(1)).ok()? + TimeDelta::minutes(1);
+        // Long enough to reach the next February 29th.
+        let limit = time + TimeDelta::days(366 * 8);
+
+        while time < limit {
+            if !contains(self.months, time.month()) {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+            } else if !self.matches_day(&time) {
+                time = (time.date_naive() + Days::new(1))
+                    .and_hms_opt(0, 0, 0)?
+                    .and_utc();
+            } else if !contains(self.hours, time.hour()) {
+                time = time.duration_trunc(TimeDelta::hours(1)).ok()? + TimeDelta::hours(1);
+            } else if !contains(self.minutes, time.minute()) {
+                time += TimeDelta::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+    time::Duration,
+};
+
+use tokio::{
+    sync::{Mutex, Notify},
+    time::Instant,
+};
+
+// Keys waiting to be reconciled, like the work queues of Kubernetes controllers. A key is queued
+// at most once and never handed out twice at the same time, and keys that failed are held back
+// with an exponential backoff instead of being retried on every pass.
+pub struct WorkQueue<K> {
+    state: Mutex<QueueState<K>>,
+    notify: Notify,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+struct QueueState<K> {
+    queue: VecDeque<K>,
+    // Keys that need processing: the queued ones and those added again while being processed.
+    dirty: HashSet<K>,
+    processing: HashSet<K>,
+    // Keys backing off after a failure, with the time they are queued again.
+    waiting: HashMap<K, Instant>,
+    failures: HashMap<K, u32>,
+}
+
+impl<K: Clone + Eq + Hash> QueueState<K> {
+    fn enqueue(&mut self, key: K) -> bool {
+        if !self.dirty.insert(key.clone()) || self.processing.contains(&key) {
+            return false;
+        }
+        self.queue.push_back(key);
+        true
+    }
+
+    fn promote(&mut self, now: Instant) {
+        let ready: Vec<K> = self
+            .waiting
+            .iter()
+            .filter(|(_, at)| **at <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in ready {
+            self.waiting.remove(&key);
+            self.enqueue(key);
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash> WorkQueue<K> {
+    pub fn new(base_delay: Duration, max_delay: Duration) -> Self {
+        WorkQueue {
+            state: Mutex::new(QueueState {
+                queue: VecDeque::new(),
+                dirty: HashSet::new(),
+                processing: HashSet::new(),
+                waiting: HashMap::new(),
+                failures: HashMap::new(),
+            }),
+            notify: Notify::new(),
+            base_delay,
+            max_delay,
+        }
+    }
+
+    // Does nothing for keys that are already queued or backing off, so periodic resyncs can add
+    // every key without bypassing the backoff.
+    pub async fn add(&self, key: K) {
+        let mut state = self.state.lock().await;
+        if state.waiting.contains_key(&key) {
+            return;
+        }
+        if state.enqueue(key) {
+            self.notify.notify_one();
+        }
+    }
+
+    // Queues the key again once its backoff, which doubles with each failure, has passed.
+    pub async fn add_rate_limited(&self, key: K) -> Duration {
+        let mut state = self.state.lock().await;
+        let failures = state.failures.entry(key.clone()).or_default();
+        let delay = self.backoff(*failures);
+        *failures += 1;
+
+        // A key added again while it was being processed would otherwise be queued by `done`.
+        if state.processing.contains(&key) {
+            state.dirty.remove(&key);
+        }
+        let ready = Instant::now() + delay;
+        state
+            .waiting
+            .entry(key)
+            .and_modify(|at| *at = (*at).min(ready))
+            .or_insert(ready);
+        self.notify.notify_one();
+        delay
+    }
+
+    // Resets the backoff of a key once it was processed successfully or went away.
+    pub async fn forget<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.state.lock().await.failures.remove(key);
+    }
+
+    // Failures since the key was last forgotten.
+    pub async fn failures<Q>(&self, key: &Q) -> u32
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.state
+            .lock()
+            .await
+            .failures
+            .get(key)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    // Waits for the next key, which must be handed back with `done` once it is processed.
+    pub async fn get(&self) -> K {
+        loop {
+            let next_ready = {
+                let mut state = self.state.lock().await;
+                state.promote(Instant::now());
+                if let Some(key) = state.queue.pop_front() {
+                    state.dirty.remove(&key);
+                    state.processing.insert(key.clone());
+                    if !state.queue.is_empty() {
+                        self.notify.notify_one();
+                    }
+                    return key;
+                }
+                state.waiting.values().min().copied()
+            };
+
+            match next_ready {
+                Some(at) => {
+                    tokio::select! {
+                        _ = self.notify.notified() => {}
+                        _ = tokio::time::sleep_until(at) => {}
+                    }
+                }
+                None => self.notify.notified().await,
+            }
+        }
+    }
+
+    pub async fn done(&self, key: &K) {
+        let mut state = self.state.lock().await;
+        state.processing.remove(key);
+        if state.dirty.contains(key) {
+            state.queue.push_back(key.clone());
+            self.notify.notify_one();
+        }
+    }
+
+    fn backoff(&self, failures: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(failures))
+            .min(self.max_delay)
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+// Sent by the CLI with every request; not authenticated, so it names who claims to act.
+pub const USER_HEADER: &str = "x-nic8s-user";
+
+// One mutating API call and how it ended.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub user: String,
+    // The address the call came from.
+    pub source: String,
+    // create, update, delete or the action a call names, such as restart or scale.
+    pub operation: String,
+    pub resource: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    // The HTTP status, or the gRPC one for calls made over gRPC.
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AuditQuery {
+    pub resource: Option<String>,
+    pub name: Option<String>,
+    pub user: Option<String>,
+    // The most recent entries, when set.
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.resource
+            .as_ref()
+            .is_none_or(|resource| &entry.resource == resource)
+            && self
+                .name
+                .as_ref()
+                .is_none_or(|name| entry.name.as_ref() == Some(name))
+            && self.user.as_ref().is_none_or(|user| &entry.user == user)
+    }
+}
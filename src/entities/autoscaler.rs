@@ -0,0 +1,95 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+// Changes of less than this fraction of the target are ignored, so small fluctuations around
+// the target don't scale the app back and forth.
+const TOLERANCE: f64 = 0.1;
+
+fn default_min_replicas() -> usize {
+    1
+}
+
+fn default_scale_down_stabilization_seconds() -> u64 {
+    300
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AutoscalerSpec {
+    // The app whose containers are scaled, which also names the autoscaler.
+    pub app: String,
+    #[serde(default = "default_min_replicas")]
+    pub min_replicas: usize,
+    pub max_replicas: usize,
+    // Average CPU usage to hold across the app's containers, as a percentage of each container's
+    // requested CPUs, or of one CPU for containers without a request.
+    pub target_cpu_percent: f64,
+    // Scaling uses the lowest recommendation within the scale up window and the highest within
+    // the scale down window, so a short spike or dip doesn't resize the app.
+    #[serde(default)]
+    pub scale_up_stabilization_seconds: u64,
+    #[serde(default = "default_scale_down_stabilization_seconds")]
+    pub scale_down_stabilization_seconds: u64,
+}
+
+impl AutoscalerSpec {
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.min_replicas == 0 {
+            return Err(anyhow!("min replicas must be at least 1"));
+        }
+        if self.max_replicas < self.min_replicas {
+            return Err(anyhow!(
+                "max replicas ({}) must not be lower than min replicas ({})",
+                self.max_replicas,
+                self.min_replicas
+            ));
+        }
+        if !self.target_cpu_percent.is_finite() || self.target_cpu_percent <= 0.0 {
+            return Err(anyhow!(
+                "target CPU percent must be positive, got {}",
+                self.target_cpu_percent
+            ));
+        }
+        Ok(())
+    }
+
+    // The replica count that brings the average utilization back to the target.
+    pub fn recommend(&self, replicas: usize, utilization: f64) -> usize {
+        let ratio = utilization / self.target_cpu_percent;
+        let recommended = if (ratio - 1.0).abs() <= TOLERANCE {
+            replicas
+        } else {
+            (replicas as f64 * ratio).ceil() as usize
+        };
+        recommended.clamp(self.min_replicas, self.max_replicas)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AutoscalerStatus {
+    pub current_replicas: usize,
+    pub desired_replicas: usize,
+    // Average over the containers with metrics; None until the first stats come in.
+    pub current_cpu_percent: Option<f64>,
+    pub last_scale_time: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Autoscaler {
+    pub spec: AutoscalerSpec,
+    pub created: String,
+    pub status: AutoscalerStatus,
+}
+
+impl Autoscaler {
+    pub fn new(spec: AutoscalerSpec) -> Self {
+        Autoscaler {
+            spec,
+            created: chrono::Utc::now().to_rfc3339(),
+            status: AutoscalerStatus::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.spec.app
+    }
+}
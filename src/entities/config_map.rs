@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{cluster::Cluster, entities::container::Container, events::event::EventReason};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConfigMap {
+    pub name: String,
+    // Each key becomes a file of the same name in the mounted directory.
+    pub data: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConfigMapMount {
+    pub name: String,
+    pub mount_path: String,
+    // Files are always updated in place; this also restarts the container so it rereads them.
+    #[serde(default)]
+    pub restart_on_change: bool,
+}
+
+impl ConfigMap {
+    pub async fn restart_consumers(
+        &self,
+        cluster: &Cluster,
+    ) -> Result<Vec<Container>, anyhow::Error> {
+        let consumers: Vec<Container> = cluster
+            .status_watcher
+            .list()
+            .await
+            .into_iter()
+            .filter(|container| {
+                container
+                    .spec
+                    .config_maps
+                    .iter()
+                    .any(|mount| mount.name == self.name && mount.restart_on_change)
+            })
+            .collect();
+
+        for container in consumers.iter() {
+            cluster
+                .events
+                .record_container(
+                    &container.name,
+                    EventReason::Killed,
+                    format!(
+                        "Restarting container {}: config map {} changed",
+                        container.name, self.name
+                    ),
+                )
+                .await;
+            cluster.status_watcher.release(&container.id).await;
+            cluster.runtime.restart(&container.id).await?;
+        }
+
+        Ok(consumers)
+    }
+}
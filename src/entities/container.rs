@@ -1,9 +1,46 @@
-use anyhow::{anyhow, Ok};
-use tokio::process::Command;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    time::{Duration, Instant},
+};
 
-use crate::watchers::{container_status::ContainerStatusWatcher, watchers::Watchers};
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-#[derive(Clone, PartialEq, Debug)]
+use crate::{
+    cluster::Cluster,
+    entities::{
+        config_map::ConfigMapMount,
+        job::backoff_delay,
+        labels::{LabelSelector, Labels},
+        lifecycle::{HookPoint, Lifecycle},
+        ports::{HostPorts, PortConflict},
+        rollout::is_replica,
+        volume::VolumeClaim,
+    },
+    events::event::EventReason,
+    runtime::{ContainerRuntime, RunOptions},
+};
+
+pub const MANAGED_LABEL: &str = "nic8s.managed";
+pub const APP_LABEL: &str = "nic8s.app";
+pub const JOB_LABEL: &str = "nic8s.job";
+pub const SPEC_LABEL: &str = "nic8s.spec";
+pub const INIT_LABEL: &str = "nic8s.init";
+// Specs of the managed containers that should exist, by name, so orphans can be told apart.
+pub const KIND: &str = "containers";
+
+pub const DEFAULT_STOP_SIGNAL: &str = "SIGTERM";
+pub const DEFAULT_TERMINATION_GRACE_PERIOD: Duration = Duration::from_secs(10);
+// Sent once the grace period is over; it can't be caught.
+pub const KILL_SIGNAL: &str = "SIGKILL";
+const DEPENDENCY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// How long a container waits for a dependency that doesn't exist to be created before it gives
+// up; it can only be created after the container when something deletes and recreates it.
+const MISSING_DEPENDENCY_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum ContainerStatus {
     Created,
     Running,
@@ -11,63 +48,948 @@ pub enum ContainerStatus {
     Exited,
     Paused,
     Dead,
+    // Restarted too often lately; stopped until its back-off has passed.
+    CrashLoopBackOff,
     Unknown,
 }
 
-#[derive(Clone)]
+impl From<&str> for ContainerStatus {
+    fn from(status: &str) -> Self {
+        match status {
+            "created" => ContainerStatus::Created,
+            "running" => ContainerStatus::Running,
+            "restarting" => ContainerStatus::Restarting,
+            "exited" => ContainerStatus::Exited,
+            "paused" => ContainerStatus::Paused,
+            "dead" => ContainerStatus::Dead,
+            // Podman's names for states docker has no word of its own for.
+            "configured" | "initialized" => ContainerStatus::Created,
+            "stopping" => ContainerStatus::Running,
+            "stopped" => ContainerStatus::Exited,
+            _ => ContainerStatus::Unknown,
+        }
+    }
+}
+
+// What the scheduler reserves on a node for the container.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceRequests {
+    #[serde(default)]
+    pub cpus: f64,
+    #[serde(default)]
+    pub memory_bytes: u64,
+}
+
+impl ResourceRequests {
+    pub fn is_empty(&self) -> bool {
+        self.cpus == 0.0 && self.memory_bytes == 0
+    }
+}
+
+// A rule on which containers must, or must not, run in the same topology domain as this one.
+// Nodes are in the same domain when they have the same value for `topology_key`; without it,
+// each node is its own domain.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AffinityTerm {
+    #[serde(flatten)]
+    pub selector: LabelSelector,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topology_key: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum RestartPolicy {
+    #[default]
+    Always,
+    OnFailure,
+    Never,
+}
+
+// Runs to completion, in order with the others, before the main container starts.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InitContainer {
+    pub name: String,
+    pub image: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+}
+
+// Another container this one only starts after, once that one meets `condition`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub name: String,
+    #[serde(default)]
+    pub condition: DependencyCondition,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyCondition {
+    // Running.
+    #[default]
+    Started,
+    // Running and passing its readiness probe, if it has one.
+    Healthy,
+}
+
+impl DependencyCondition {
+    pub fn is_met(&self, container: &Container) -> bool {
+        match self {
+            DependencyCondition::Started => container.status == ContainerStatus::Running,
+            DependencyCondition::Healthy => container.is_ready(),
+        }
+    }
+}
+
+impl fmt::Display for DependencyCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyCondition::Started => write!(f, "started"),
+            DependencyCondition::Healthy => write!(f, "healthy"),
+        }
+    }
+}
+
+// Runs `command` inside the container through docker's health check; the container only counts
+// as ready, and receives traffic for its app, once the command succeeds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Probe {
+    pub command: String,
+    #[serde(default)]
+    pub initial_delay_seconds: u64,
+    #[serde(default = "default_period_seconds")]
+    pub period_seconds: u64,
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+fn default_period_seconds() -> u64 {
+    10
+}
+
+fn default_timeout_seconds() -> u64 {
+    1
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContainerSpec {
+    pub name: String,
+    pub image: String,
+    #[serde(default)]
+    pub ports: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_from_secret: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub config_maps: Vec<ConfigMapMount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<VolumeClaim>,
+    // Matched by the affinity rules of other containers, together with an `app` label.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: Labels,
+    // Pins the container to a node instead of letting the scheduler pick one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    // Only nodes with all of these labels are considered.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub node_selector: Labels,
+    // The container is only placed next to containers matching each of these rules, and never
+    // next to containers matching any of the anti-affinity ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub affinity: Vec<AffinityTerm>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anti_affinity: Vec<AffinityTerm>,
+    #[serde(default, skip_serializing_if = "ResourceRequests::is_empty")]
+    pub resources: ResourceRequests,
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub init_containers: Vec<InitContainer>,
+    // Started, in order, once each of these is; init containers only run after that.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<Dependency>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readiness_probe: Option<Probe>,
+    // Replace the container when its image tag points at a newer image in the registry.
+    #[serde(default)]
+    pub auto_update: bool,
+    // How long the container gets to exit after its stop signal before it is killed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub termination_grace_period_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_signal: Option<String>,
+    #[serde(default, skip_serializing_if = "Lifecycle::is_empty")]
+    pub lifecycle: Lifecycle,
+}
+
+impl ContainerSpec {
+    pub fn app_name(&self) -> &str {
+        self.app.as_deref().unwrap_or(&self.name)
+    }
+
+    // The labels affinity rules match against.
+    pub fn all_labels(&self) -> Labels {
+        let mut labels = self.labels.clone();
+        labels.insert(String::from("app"), self.app_name().to_string());
+        labels
+    }
+
+    pub fn stop_signal(&self) -> &str {
+        self.stop_signal.as_deref().unwrap_or(DEFAULT_STOP_SIGNAL)
+    }
+
+    pub fn termination_grace_period(&self) -> Duration {
+        self.termination_grace_period_seconds
+            .map_or(DEFAULT_TERMINATION_GRACE_PERIOD, Duration::from_secs)
+    }
+
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        for term in self.affinity.iter().chain(self.anti_affinity.iter()) {
+            term.selector.validate()?;
+        }
+        for hook in [&self.lifecycle.post_start, &self.lifecycle.pre_stop]
+            .into_iter()
+            .flatten()
+        {
+            hook.validate()?;
+        }
+        if self
+            .depends_on
+            .iter()
+            .any(|dependency| dependency.name == self.name)
+        {
+            return Err(anyhow!("container {} depends on itself", self.name));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DependencyError {
+    // The names along the cycle, the first one repeated at the end.
+    #[error("dependency cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+    #[error("container {container} depends on {dependency}, which does not exist")]
+    Missing {
+        container: String,
+        dependency: String,
+    },
+}
+
+// Orders the specs so each comes after those among them it depends on, keeping their order
+// otherwise. Fails on a dependency cycle, or a dependency that is neither among the specs nor one
+// of the containers `exists` knows about.
+pub fn dependency_order<'a>(
+    specs: &'a [ContainerSpec],
+    exists: &dyn Fn(&str) -> bool,
+) -> Result<Vec<&'a ContainerSpec>, DependencyError> {
+    let by_name: HashMap<&str, &ContainerSpec> = specs
+        .iter()
+        .map(|spec| (spec.name.as_str(), spec))
+        .collect();
+    let mut ordered = Vec::new();
+    let mut done = HashSet::new();
+    let mut path = Vec::new();
+    for spec in specs {
+        visit(spec, &by_name, exists, &mut done, &mut path, &mut ordered)?;
+    }
+    Ok(ordered)
+}
+
+fn visit<'a>(
+    spec: &'a ContainerSpec,
+    by_name: &HashMap<&str, &'a ContainerSpec>,
+    exists: &dyn Fn(&str) -> bool,
+    done: &mut HashSet<&'a str>,
+    path: &mut Vec<&'a str>,
+    ordered: &mut Vec<&'a ContainerSpec>,
+) -> Result<(), DependencyError> {
+    if done.contains(spec.name.as_str()) {
+        return Ok(());
+    }
+    if let Some(start) = path.iter().position(|name| *name == spec.name) {
+        let mut cycle: Vec<String> = path[start..].iter().map(|name| name.to_string()).collect();
+        cycle.push(spec.name.clone());
+        return Err(DependencyError::Cycle(cycle));
+    }
+
+    path.push(&spec.name);
+    for dependency in spec.depends_on.iter() {
+        match by_name.get(dependency.name.as_str()) {
+            Some(dependency) => visit(dependency, by_name, exists, done, path, ordered)?,
+            None if exists(&dependency.name) => {}
+            None => {
+                return Err(DependencyError::Missing {
+                    container: spec.name.clone(),
+                    dependency: dependency.name.clone(),
+                })
+            }
+        }
+    }
+    path.pop();
+    done.insert(&spec.name);
+    ordered.push(spec);
+    Ok(())
+}
+
+// How a container last stopped, as the runtime reports it while the container is down.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Termination {
+    pub exit_code: i64,
+    #[serde(default)]
+    pub oom_killed: bool,
+    pub finished_at: String,
+}
+
+impl Termination {
+    pub fn reason(&self) -> &'static str {
+        match (self.oom_killed, self.exit_code) {
+            (true, _) => "OOMKilled",
+            (false, 0) => "Completed",
+            (false, _) => "Error",
+        }
+    }
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Reason: {}, ExitCode: {}", self.reason(), self.exit_code)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Container {
     pub id: String,
     pub name: String,
-    pub image: String,
+    pub app: String,
+    pub spec: ContainerSpec,
+    #[serde(default)]
+    pub node: String,
     pub created: String,
-    pub ports: String,
-    status: &'static ContainerStatus,
+    #[serde(default)]
+    pub started_at: Option<String>,
+    #[serde(default)]
+    pub restart_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_restart: Option<String>,
+    // When a container in CrashLoopBackOff is started again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub back_off_until: Option<String>,
+    // Kept once the container runs again, so the reason for a restart stays visible.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_termination: Option<Termination>,
+    #[serde(default)]
+    pub health: Option<String>,
+    // The ID of the image the container was created from, which the tag in the spec may no
+    // longer point at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_digest: Option<String>,
+    pub(crate) status: ContainerStatus,
 }
 
 impl Container {
-    pub async fn new(
-        name: &str,
-        ports: &str,
-        image: &str,
-        status_watcher: &ContainerStatusWatcher,
+    pub async fn new(spec: &ContainerSpec, cluster: &Cluster) -> Result<Container, anyhow::Error> {
+        let spec = &cluster.admission.admit(spec.clone()).await?;
+        let labels = BTreeMap::from([(String::from(MANAGED_LABEL), String::from("true"))]);
+        let container = Container::create(spec, labels, cluster).await?;
+
+        cluster.state.put(KIND, &spec.name, spec).await?;
+        cluster
+            .status_watcher
+            .add_container(container.clone())
+            .await;
+
+        if container.spec.init_containers.is_empty() && container.spec.depends_on.is_empty() {
+            container.start(cluster).await?;
+        } else {
+            container.start_in_background(cluster);
+        }
+        Ok(container)
+    }
+
+    // Stops the container gracefully, then removes it and its desired-state record.
+    pub async fn delete(&self, cluster: &Cluster) -> Result<(), anyhow::Error> {
+        self.stop_gracefully(cluster).await?;
+        if let Some(logs) = &cluster.logs {
+            logs.flush(self).await;
+        }
+        cluster.runtime.remove(&self.id).await?;
+        cluster.status_watcher.remove_container(&self.id).await;
+        cluster.state.delete(KIND, &self.name).await?;
+        Ok(())
+    }
+
+    // Runs the pre_stop hook, then sends a running container its stop signal and gives it the
+    // grace period to exit before killing it, recording an event for each step. Only a pre_stop
+    // hook that fails with the fail policy keeps it from being removed.
+    async fn stop_gracefully(&self, cluster: &Cluster) -> Result<(), anyhow::Error> {
+        let runtime = &cluster.runtime;
+        let events = &cluster.events;
+        let running = runtime
+            .inspect(&self.id)
+            .await
+            .is_ok_and(|current| current.get_status() == ContainerStatus::Running);
+        if !running {
+            return Ok(());
+        }
+        if let Some(hook) = &self.spec.lifecycle.pre_stop {
+            hook.run(HookPoint::PreStop, self, runtime.as_ref(), cluster)
+                .await?;
+        }
+
+        let signal = self.spec.stop_signal();
+        let grace_period = self.spec.termination_grace_period();
+        events
+            .record_container(
+                &self.name,
+                EventReason::Stopping,
+                format!(
+                    "Sending {} to container {}, killing it if it is still running in {}s",
+                    signal,
+                    self.name,
+                    grace_period.as_secs()
+                ),
+            )
+            .await;
+        if let Err(error) = runtime.kill(&self.id, signal).await {
+            println!(
+                "Failed to send {} to container {}: {}",
+                signal, self.name, error
+            );
+            return Ok(());
+        }
+
+        match tokio::time::timeout(grace_period, runtime.wait(&self.id)).await {
+            Ok(Ok(code)) => {
+                events
+                    .record_container(
+                        &self.name,
+                        EventReason::Stopped,
+                        format!("Container {} exited with code {}", self.name, code),
+                    )
+                    .await;
+            }
+            Ok(Err(error)) => {
+                println!("Failed to wait for container {}: {}", self.name, error);
+            }
+            Err(_) => {
+                events
+                    .record_container(
+                        &self.name,
+                        EventReason::ForceKilled,
+                        format!(
+                            "Container {} did not exit within {}s of {}, killing it",
+                            self.name,
+                            grace_period.as_secs(),
+                            signal
+                        ),
+                    )
+                    .await;
+                if let Err(error) = runtime.kill(&self.id, KILL_SIGNAL).await {
+                    println!("Failed to kill container {}: {}", self.name, error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Pulls and creates the container, stopped, without handing it to the status watcher, for
+    // callers such as the job controller that track the container themselves.
+    pub async fn create(
+        spec: &ContainerSpec,
+        labels: BTreeMap<String, String>,
+        cluster: &Cluster,
     ) -> Result<Container, anyhow::Error> {
-        let mut command = Command::new("docker");
-
-        command
-            .arg("run")
-            .arg("-d")
-            .arg("--name")
-            .arg(String::from(name))
-            .arg("-p")
-            .arg(String::from(ports))
-            .arg(String::from(image));
-
-        let out = command.output().await?;
-
-        if !out.status.success() {
-            return Err(anyhow!(
-                "failed to execute process: {}\n{}",
-                out.status,
-                String::from_utf8_lossy(&out.stderr)
-            ));
-        }
-
-        let container_id = String::from_utf8_lossy(&out.stdout).trim().to_string();
-        println!("Container ID: {}", container_id);
-        let container = Container {
-            id: container_id,
-            name: String::from(name),
-            image: String::from(image),
-            created: chrono::Local::now().to_string(),
-            ports: String::from(ports),
-            status: &ContainerStatus::Created,
+        let events = &cluster.events;
+        // Claimed volumes that already live on a node pin the container there.
+        let node = match cluster.volumes.node_for(spec).await? {
+            Some(node) => {
+                let pinned = ContainerSpec {
+                    node: Some(node),
+                    ..spec.clone()
+                };
+                cluster.nodes.schedule(&pinned).await?
+            }
+            None => cluster.nodes.schedule(spec).await?,
+        };
+        let runtime = cluster.nodes.node(&node).await?;
+        if let Err(conflict) = check_port_conflicts(spec, &node, cluster).await {
+            events
+                .record_container(&spec.name, EventReason::Failed, conflict.to_string())
+                .await;
+            return Err(conflict.into());
+        }
+        events
+            .record_container(
+                &spec.name,
+                EventReason::Scheduled,
+                format!("Assigned container {} to node {}", spec.name, node),
+            )
+            .await;
+        let mut mounts = cluster.config_maps.mounts_for(spec).await?;
+        mounts.extend(cluster.volumes.mounts_for(spec, &node).await?);
+        let options = RunOptions {
+            env: cluster.secrets.env_for(spec).await?,
+            mounts,
+            labels,
+            volumes_from: None,
         };
 
-        status_watcher.add_container(container.clone()).await;
+        pull(runtime.as_ref(), &spec.name, &spec.image, cluster).await?;
+
+        let mut container = match runtime.create(spec, &options).await {
+            Ok(container) => container,
+            Err(error) => {
+                events
+                    .record_container(
+                        &spec.name,
+                        EventReason::Failed,
+                        format!("Failed to create container: {}", error),
+                    )
+                    .await;
+                return Err(error);
+            }
+        };
+        container.node = node.clone();
+        cluster.nodes.assign(&container).await;
+
+        events
+            .record_container(
+                &container.name,
+                EventReason::Created,
+                format!("Created container {}", container.name),
+            )
+            .await;
         Ok(container)
     }
 
+    // Fails when `spec` depends on a container that isn't in the cluster, or creating it would
+    // close a dependency cycle with them; either would leave containers waiting forever.
+    pub async fn check_dependencies(
+        spec: &ContainerSpec,
+        cluster: &Cluster,
+    ) -> Result<(), anyhow::Error> {
+        if spec.depends_on.is_empty() {
+            return Ok(());
+        }
+        let mut specs: Vec<ContainerSpec> = cluster
+            .status_watcher
+            .list()
+            .await
+            .into_iter()
+            .filter(|container| container.name != spec.name)
+            .map(|container| container.spec)
+            .collect();
+        specs.push(spec.clone());
+        dependency_order(&specs, &|_| false)?;
+        Ok(())
+    }
+
+    // Waits for the dependencies, runs the init containers in order and then starts the
+    // container. An init container that fails is retried with a back-off unless the restart
+    // policy is Never.
+    pub async fn start(&self, cluster: &Cluster) -> Result<(), anyhow::Error> {
+        let runtime = cluster.nodes.node(&self.node).await?;
+        self.wait_for_dependencies(runtime.as_ref(), cluster)
+            .await?;
+
+        for init in self.spec.init_containers.iter() {
+            let mut failures = 0;
+            loop {
+                let failure = match self
+                    .run_init_container(init, runtime.as_ref(), cluster)
+                    .await
+                {
+                    Ok(0) => break,
+                    Ok(code) => format!("exited with code {}", code),
+                    Err(error) => format!("failed: {}", error),
+                };
+                failures += 1;
+
+                let message = format!("Init container {} {}", init.name, failure);
+                cluster
+                    .events
+                    .record_container(&self.name, EventReason::Failed, message.clone())
+                    .await;
+                if self.spec.restart_policy == RestartPolicy::Never {
+                    return Err(anyhow!("{}", message));
+                }
+
+                let delay = backoff_delay(failures);
+                cluster
+                    .events
+                    .record_container(
+                        &self.name,
+                        EventReason::BackOff,
+                        format!(
+                            "Back-off {}s before restarting init container {}",
+                            delay.as_secs(),
+                            init.name
+                        ),
+                    )
+                    .await;
+                tokio::time::sleep(delay).await;
+
+                // Stop retrying if the container was deleted meanwhile.
+                runtime.inspect(&self.id).await?;
+            }
+        }
+
+        cluster.status_watcher.release(&self.id).await;
+        if let Err(error) = runtime.start(&self.id).await {
+            cluster
+                .events
+                .record_container(
+                    &self.name,
+                    EventReason::Failed,
+                    format!("Failed to start container: {}", error),
+                )
+                .await;
+            return Err(error);
+        }
+
+        if let Some(hook) = &self.spec.lifecycle.post_start {
+            let ran = hook
+                .run(HookPoint::PostStart, self, runtime.as_ref(), cluster)
+                .await;
+            if let Err(error) = ran {
+                cluster.status_watcher.hold_stopped(&self.id).await;
+                if let Err(error) = runtime.stop(&self.id, Some(Duration::ZERO)).await {
+                    println!("Failed to stop container {}: {}", self.name, error);
+                }
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    // Dependencies are waited for one after the other, through the status watcher, recording
+    // what the container waits for once per dependency. One that doesn't exist is given up on
+    // after a while.
+    async fn wait_for_dependencies(
+        &self,
+        runtime: &(dyn ContainerRuntime + Send + Sync),
+        cluster: &Cluster,
+    ) -> Result<(), anyhow::Error> {
+        for dependency in self.spec.depends_on.iter() {
+            let mut waiting = false;
+            let mut missing_since: Option<Instant> = None;
+            loop {
+                let found = cluster.status_watcher.find(&dependency.name).await;
+                match &found {
+                    Some(container) if dependency.condition.is_met(container) => break,
+                    Some(_) => missing_since = None,
+                    None => {
+                        let since = *missing_since.get_or_insert_with(Instant::now);
+                        if since.elapsed() >= MISSING_DEPENDENCY_TIMEOUT {
+                            let message = format!(
+                                "Gave up waiting for container {}, which does not exist",
+                                dependency.name
+                            );
+                            cluster
+                                .events
+                                .record_container(&self.name, EventReason::Failed, message.clone())
+                                .await;
+                            return Err(anyhow!("{}", message));
+                        }
+                    }
+                }
+                if !waiting {
+                    waiting = true;
+                    let message = match found {
+                        Some(_) => format!(
+                            "Waiting for container {} to be {}",
+                            dependency.name, dependency.condition
+                        ),
+                        None => format!(
+                            "Waiting for container {} to be {}; it does not exist",
+                            dependency.name, dependency.condition
+                        ),
+                    };
+                    cluster
+                        .events
+                        .record_container(&self.name, EventReason::Waiting, message)
+                        .await;
+                }
+                tokio::time::sleep(DEPENDENCY_CHECK_INTERVAL).await;
+
+                // Stop waiting if the container was deleted meanwhile.
+                runtime.inspect(&self.id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn start_in_background(&self, cluster: &Cluster) {
+        let container = self.clone();
+        let cluster = cluster.clone();
+
+        tokio::spawn(async move {
+            if let Err(error) = container.start(&cluster).await {
+                println!("Failed to start container {}: {}", container.name, error);
+            }
+        });
+    }
+
+    // Returns the init container's exit code. It shares the main container's volumes, mounts and
+    // environment, and is removed once it exits.
+    async fn run_init_container(
+        &self,
+        init: &InitContainer,
+        runtime: &(dyn ContainerRuntime + Send + Sync),
+        cluster: &Cluster,
+    ) -> Result<i64, anyhow::Error> {
+        let spec = ContainerSpec {
+            name: format!("{}-init-{}", self.name, init.name),
+            image: init.image.clone(),
+            command: init.command.clone(),
+            app: Some(self.app.clone()),
+            restart_policy: RestartPolicy::Never,
+            ..ContainerSpec::default()
+        };
+        let options = RunOptions {
+            env: cluster.secrets.env_for(&self.spec).await?,
+            mounts: cluster.config_maps.mounts_for(&self.spec).await?,
+            labels: BTreeMap::from([(String::from(INIT_LABEL), self.name.clone())]),
+            volumes_from: Some(self.id.clone()),
+        };
+
+        // Left behind when the daemon stopped while the init container was running.
+        let _ = runtime.remove(&spec.name).await;
+
+        pull(runtime, &self.name, &init.image, cluster).await?;
+        let container = runtime.run(&spec, &options).await?;
+        cluster
+            .events
+            .record_container(
+                &self.name,
+                EventReason::Started,
+                format!("Started init container {}", init.name),
+            )
+            .await;
+
+        let exit_code = runtime.wait(&container.id).await;
+        let _ = runtime.remove(&container.id).await;
+        if exit_code.as_ref().is_ok_and(|code| *code == 0) {
+            cluster
+                .events
+                .record_container(
+                    &self.name,
+                    EventReason::Completed,
+                    format!("Init container {} completed", init.name),
+                )
+                .await;
+        }
+        exit_code
+    }
+
+    pub async fn adopt_all(cluster: &Cluster) -> Result<Vec<Container>, anyhow::Error> {
+        let containers = cluster.runtime.list_managed().await?;
+
+        // Containers created before desired state was recorded are recorded as they are, so the
+        // garbage collector doesn't take them for orphans.
+        if !cluster.state.has_kind(KIND).await? {
+            for container in containers.iter() {
+                cluster
+                    .state
+                    .put(KIND, &container.name, &container.spec)
+                    .await?;
+            }
+        }
+
+        for container in containers.iter() {
+            println!(
+                "Adopted container {} ({}) node: {} image: {} ports: {} created: {}",
+                container.name,
+                container.id,
+                container.node,
+                container.spec.image,
+                container.spec.ports,
+                container.created
+            );
+
+            cluster
+                .status_watcher
+                .add_container(container.clone())
+                .await;
+
+            // Created but never started, usually because the daemon stopped during init.
+            if container.status == ContainerStatus::Created {
+                container.start_in_background(cluster);
+            }
+        }
+
+        Ok(containers)
+    }
+
+    // Creates the containers desired state records but no node runs, as after restoring a
+    // backup on a new host.
+    pub async fn recreate_missing(
+        cluster: &Cluster,
+        existing: &[Container],
+    ) -> Result<Vec<Container>, anyhow::Error> {
+        let specs: Vec<ContainerSpec> = cluster.state.list(KIND).await?;
+        let mut created = Vec::new();
+        for spec in specs
+            .iter()
+            .filter(|spec| !existing.iter().any(|container| container.name == spec.name))
+        {
+            match Container::new(spec, cluster).await {
+                Ok(container) => {
+                    println!("Recreated container {} from desired state", spec.name);
+                    created.push(container);
+                }
+                Err(error) => println!("Failed to recreate container {}: {}", spec.name, error),
+            }
+        }
+        Ok(created)
+    }
+
+    pub async fn scale(
+        app: &str,
+        replicas: usize,
+        cluster: &Cluster,
+    ) -> Result<Vec<Container>, anyhow::Error> {
+        let mut current: Vec<Container> = cluster
+            .status_watcher
+            .list()
+            .await
+            .into_iter()
+            .filter(|container| is_replica(container, app))
+            .collect();
+        current.sort_by(|a, b| (a.name.len(), &a.name).cmp(&(b.name.len(), &b.name)));
+
+        let template = current
+            .first()
+            .ok_or_else(|| anyhow!("no containers found for app {}", app))?
+            .spec
+            .clone();
+
+        let mut index = 1;
+        while current.len() < replicas {
+            let name = format!("{}-{}", app, index);
+            index += 1;
+            if current.iter().any(|container| container.name == name) {
+                continue;
+            }
+
+            let spec = ContainerSpec {
+                name,
+                app: Some(String::from(app)),
+                ..template.clone()
+            };
+            current.push(Container::new(&spec, cluster).await?);
+        }
+
+        while current.len() > replicas {
+            if let Some(container) = current.pop() {
+                cluster
+                    .events
+                    .record_container(
+                        &container.name,
+                        EventReason::Killed,
+                        format!("Killing container {} to scale {} down", container.name, app),
+                    )
+                    .await;
+                container.delete(cluster).await?;
+            }
+        }
+
+        Ok(current)
+    }
+
+    pub fn uptime(&self) -> Option<chrono::Duration> {
+        if self.status != ContainerStatus::Running {
+            return None;
+        }
+
+        let started_at = chrono::DateTime::parse_from_rfc3339(self.started_at.as_ref()?).ok()?;
+        Some(chrono::Utc::now().signed_duration_since(started_at))
+    }
+
     pub fn get_status(&self) -> ContainerStatus {
         self.status.clone()
     }
+
+    pub fn set_status(&mut self, status: ContainerStatus) {
+        self.status = status;
+    }
+
+    // Running, and past its readiness probe if it has one. Docker reports "starting" until the
+    // probe first passes and "unhealthy" once it keeps failing.
+    pub fn is_ready(&self) -> bool {
+        self.status == ContainerStatus::Running
+            && self
+                .health
+                .as_deref()
+                .is_none_or(|health| health == "healthy")
+    }
+}
+
+// Docker only notices a taken host port when the container starts, without saying who holds it.
+async fn check_port_conflicts(
+    spec: &ContainerSpec,
+    node: &str,
+    cluster: &Cluster,
+) -> Result<(), PortConflict> {
+    let Some(requested) = HostPorts::parse(&spec.ports) else {
+        return Ok(());
+    };
+
+    for other in cluster.status_watcher.list().await {
+        if other.node != node || other.name == spec.name {
+            continue;
+        }
+        if HostPorts::parse(&other.spec.ports).is_some_and(|ports| ports.overlaps(&requested)) {
+            return Err(PortConflict::Container {
+                container: spec.name.clone(),
+                ports: requested,
+                other: other.name,
+                node: String::from(node),
+            });
+        }
+    }
+    Ok(())
+}
+
+async fn pull(
+    runtime: &(dyn ContainerRuntime + Send + Sync),
+    container: &str,
+    image: &str,
+    cluster: &Cluster,
+) -> Result<(), anyhow::Error> {
+    match runtime.pull_if_missing(image).await {
+        Ok(true) => {
+            cluster
+                .events
+                .record_container(
+                    container,
+                    EventReason::Pulled,
+                    format!("Successfully pulled image {}", image),
+                )
+                .await;
+            Ok(())
+        }
+        Ok(false) => Ok(()),
+        Err(error) => {
+            cluster
+                .events
+                .record_container(
+                    container,
+                    EventReason::Failed,
+                    format!("Failed to pull image {}: {}", image, error),
+                )
+                .await;
+            Err(error)
+        }
+    }
 }
@@ -0,0 +1,99 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::entities::job::{JobPhase, JobSpec};
+
+#[derive(Debug, Error)]
+#[error("cron job {0} already exists")]
+pub struct CronJobExists(pub String);
+
+fn default_successful_jobs_history_limit() -> usize {
+    3
+}
+
+fn default_failed_jobs_history_limit() -> usize {
+    1
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ConcurrencyPolicy {
+    // Start a new job even when the previous one is still running.
+    #[default]
+    Allow,
+    // Skip the run while a previous job is still running.
+    Forbid,
+    // Delete running jobs and start the new one.
+    Replace,
+}
+
+impl FromStr for ConcurrencyPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy.to_ascii_lowercase().as_str() {
+            "allow" => Ok(ConcurrencyPolicy::Allow),
+            "forbid" => Ok(ConcurrencyPolicy::Forbid),
+            "replace" => Ok(ConcurrencyPolicy::Replace),
+            _ => Err(anyhow!(
+                "invalid concurrency policy {:?}: use Allow, Forbid or Replace",
+                policy
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CronJobSpec {
+    // The job template; its name is the cron job name and runs are named `<name>-<minute>`.
+    #[serde(flatten)]
+    pub job: JobSpec,
+    pub schedule: String,
+    #[serde(default)]
+    pub concurrency_policy: ConcurrencyPolicy,
+    // Runs missed by more than this (e.g. during daemon downtime) are skipped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub starting_deadline_seconds: Option<u64>,
+    #[serde(default = "default_successful_jobs_history_limit")]
+    pub successful_jobs_history_limit: usize,
+    #[serde(default = "default_failed_jobs_history_limit")]
+    pub failed_jobs_history_limit: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CronJobRun {
+    pub job: String,
+    pub scheduled_time: String,
+    pub phase: JobPhase,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CronJobStatus {
+    pub last_schedule_time: Option<String>,
+    pub active: Vec<CronJobRun>,
+    // Finished runs, oldest first, trimmed to the history limits.
+    pub history: Vec<CronJobRun>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CronJob {
+    pub spec: CronJobSpec,
+    pub created: String,
+    pub status: CronJobStatus,
+}
+
+impl CronJob {
+    pub fn new(spec: CronJobSpec) -> Self {
+        CronJob {
+            spec,
+            created: chrono::Utc::now().to_rfc3339(),
+            status: CronJobStatus::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.spec.job.template.name
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::container::Container;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Endpoint {
+    pub container: String,
+    pub id: String,
+    pub node: String,
+    pub ports: String,
+}
+
+// The containers serving an app. Only `ready` ones should receive traffic; the others are
+// starting, failing their readiness probe or not running.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Endpoints {
+    pub app: String,
+    pub ready: Vec<Endpoint>,
+    pub not_ready: Vec<Endpoint>,
+}
+
+impl Endpoints {
+    // One entry per app, sorted by app and then container name.
+    pub fn from_containers(containers: &[Container]) -> Vec<Endpoints> {
+        let mut containers: Vec<&Container> = containers.iter().collect();
+        containers.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut apps: BTreeMap<&str, Endpoints> = BTreeMap::new();
+        for container in containers {
+            let endpoints = apps.entry(&container.app).or_insert_with(|| Endpoints {
+                app: container.app.clone(),
+                ready: Vec::new(),
+                not_ready: Vec::new(),
+            });
+            let endpoint = Endpoint {
+                container: container.name.clone(),
+                id: container.id.clone(),
+                node: container.node.clone(),
+                ports: container.spec.ports.clone(),
+            };
+            if container.is_ready() {
+                endpoints.ready.push(endpoint);
+            } else {
+                endpoints.not_ready.push(endpoint);
+            }
+        }
+        apps.into_values().collect()
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+fn default_path() -> String {
+    String::from("/")
+}
+
+// Sends requests for `host`, or for any host when it is left out, whose path starts with `path`
+// to the ready containers of `app`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IngressRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(default = "default_path")]
+    pub path: String,
+    pub app: String,
+}
+
+impl IngressRule {
+    // Paths match whole segments, so `/api` matches `/api` and `/api/users` but not `/apis`.
+    pub fn matches_path(&self, path: &str) -> bool {
+        let prefix = self.path.trim_end_matches('/');
+        match path.strip_prefix(prefix) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/'),
+            None => false,
+        }
+    }
+
+    pub fn matches_host(&self, host: &str) -> bool {
+        self.host
+            .as_ref()
+            .is_none_or(|rule| rule.eq_ignore_ascii_case(host))
+    }
+}
+
+// Keys of the secret that holds a certificate chain and its private key, both PEM encoded.
+pub const TLS_CERT: &str = "tls.crt";
+pub const TLS_KEY: &str = "tls.key";
+
+// Terminates TLS for `hosts` with the certificate in `secret`. A host may be a wildcard like
+// `*.example.com`, which covers a single label.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IngressTls {
+    pub hosts: Vec<String>,
+    pub secret: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IngressSpec {
+    pub name: String,
+    pub rules: Vec<IngressRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tls: Vec<IngressTls>,
+}
+
+impl IngressSpec {
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.rules.is_empty() {
+            return Err(anyhow!("ingress {} has no rules", self.name));
+        }
+        let mut seen = HashSet::new();
+        for rule in self.rules.iter() {
+            if !rule.path.starts_with('/') {
+                return Err(anyhow!("path {:?} must start with /", rule.path));
+            }
+            if rule.host.as_ref().is_some_and(|host| host.is_empty()) {
+                return Err(anyhow!(
+                    "host must not be empty; leave it out to match any host"
+                ));
+            }
+            let host = rule.host.as_deref().unwrap_or("*");
+            if !seen.insert((host.to_ascii_lowercase(), rule.path.trim_end_matches('/'))) {
+                return Err(anyhow!("{}{} is routed more than once", host, rule.path));
+            }
+        }
+
+        let mut hosts = HashSet::new();
+        for tls in self.tls.iter() {
+            if tls.secret.is_empty() {
+                return Err(anyhow!("tls for {:?} names no secret", tls.hosts));
+            }
+            if tls.hosts.is_empty() {
+                return Err(anyhow!("tls with secret {} has no hosts", tls.secret));
+            }
+            for host in tls.hosts.iter() {
+                let name = host.strip_prefix("*.").unwrap_or(host);
+                if name.is_empty() || name.contains('*') {
+                    return Err(anyhow!(
+                        "invalid tls host {:?}: expected a name like example.com or *.example.com",
+                        host
+                    ));
+                }
+                if !hosts.insert(host.to_ascii_lowercase()) {
+                    return Err(anyhow!("tls host {} has more than one secret", host));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Ingress {
+    pub spec: IngressSpec,
+    pub created: String,
+}
+
+impl Ingress {
+    pub fn new(spec: IngressSpec) -> Self {
+        Ingress {
+            spec,
+            created: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.spec.name
+    }
+}
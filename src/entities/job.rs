@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entities::container::ContainerSpec;
+
+const DEFAULT_BACKOFF_LIMIT: u32 = 6;
+
+fn default_backoff_limit() -> u32 {
+    DEFAULT_BACKOFF_LIMIT
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JobSpec {
+    // The template's name is the job name; attempts run as `<name>-<attempt>`.
+    #[serde(flatten)]
+    pub template: ContainerSpec,
+    // Number of failed attempts retried before the job is marked Failed.
+    #[serde(default = "default_backoff_limit")]
+    pub backoff_limit: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum JobPhase {
+    Pending,
+    Running,
+    Complete,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JobAttempt {
+    pub container_name: String,
+    // None when the container could not be created at all.
+    pub container_id: Option<String>,
+    pub started: String,
+    pub finished: Option<String>,
+    pub exit_code: Option<i64>,
+}
+
+impl JobAttempt {
+    pub fn failed(&self) -> bool {
+        self.finished.is_some() && self.exit_code != Some(0)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub phase: JobPhase,
+    pub attempts: Vec<JobAttempt>,
+    pub start_time: Option<String>,
+    pub completion_time: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Job {
+    pub spec: JobSpec,
+    pub status: JobStatus,
+}
+
+impl Job {
+    pub fn new(spec: JobSpec) -> Self {
+        Job {
+            spec,
+            status: JobStatus {
+                phase: JobPhase::Pending,
+                attempts: Vec::new(),
+                start_time: None,
+                completion_time: None,
+                message: None,
+            },
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.spec.template.name
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status.phase, JobPhase::Complete | JobPhase::Failed)
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.status
+            .attempts
+            .iter()
+            .filter(|attempt| attempt.failed())
+            .count() as u32
+    }
+}
+
+// 10s, 20s, 40s, ... capped at six minutes, like Kubernetes.
+pub fn backoff_delay(failures: u32) -> Duration {
+    let base = Duration::from_secs(10);
+    let max = Duration::from_secs(360);
+
+    base.saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(max)
+}
@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+pub type Labels = BTreeMap<String, String>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Operator {
+    In,
+    NotIn,
+    Exists,
+    DoesNotExist,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LabelExpression {
+    pub key: String,
+    pub operator: Operator,
+    // The values In and NotIn compare against; Exists and DoesNotExist take none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+impl LabelExpression {
+    pub fn matches(&self, labels: &Labels) -> bool {
+        let value = labels.get(&self.key);
+        match self.operator {
+            Operator::In => value.is_some_and(|value| self.values.contains(value)),
+            Operator::NotIn => value.is_none_or(|value| !self.values.contains(value)),
+            Operator::Exists => value.is_some(),
+            Operator::DoesNotExist => value.is_none(),
+        }
+    }
+}
+
+// Matches labels that have all of `match_labels` and satisfy all of `match_expressions`. An
+// empty selector matches everything.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LabelSelector {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub match_labels: Labels,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub match_expressions: Vec<LabelExpression>,
+}
+
+impl LabelSelector {
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        for expression in self.match_expressions.iter() {
+            match expression.operator {
+                Operator::In | Operator::NotIn if expression.values.is_empty() => {
+                    return Err(anyhow!(
+                        "the {:?} expression on label {} needs values",
+                        expression.operator,
+                        expression.key
+                    ));
+                }
+                Operator::Exists | Operator::DoesNotExist if !expression.values.is_empty() => {
+                    return Err(anyhow!(
+                        "the {:?} expression on label {} takes no values",
+                        expression.operator,
+                        expression.key
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, labels: &Labels) -> bool {
+        self.match_labels
+            .iter()
+            .all(|(key, value)| labels.get(key) == Some(value))
+            && self
+                .match_expressions
+                .iter()
+                .all(|expression| expression.matches(labels))
+    }
+}
+
+// Parses `key=value` pairs, as given on the command line.
+pub fn parse_label(label: &str) -> Result<(String, String), anyhow::Error> {
+    match label.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((String::from(key), String::from(value))),
+        _ => Err(anyhow!("invalid label {:?}: expected key=value", label)),
+    }
+}
+
+pub fn format_labels(labels: &Labels) -> String {
+    labels
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(",")
+}
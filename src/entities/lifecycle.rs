@@ -0,0 +1,163 @@
+use std::{fmt, time::Duration};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cluster::Cluster, entities::container::Container, events::event::EventReason,
+    runtime::ContainerRuntime,
+};
+
+// Hooks nic8s runs around the container's life. `post_start` runs once nic8s has started the
+// container, e.g. to warm a cache, and `pre_stop` before it sends the stop signal, e.g. to take
+// the container out of an external load balancer. Restarts by the runtime itself skip them.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lifecycle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_start: Option<LifecycleHook>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_stop: Option<LifecycleHook>,
+}
+
+impl Lifecycle {
+    pub fn is_empty(&self) -> bool {
+        self.post_start.is_none() && self.pre_stop.is_none()
+    }
+}
+
+// Either `exec`, a command run inside the container, or `http`, a request nic8s makes itself.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LifecycleHook {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exec: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpHook>,
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    #[serde(default)]
+    pub on_failure: HookFailurePolicy,
+}
+
+fn default_timeout_seconds() -> u64 {
+    10
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HttpHook {
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+}
+
+fn default_method() -> String {
+    String::from("GET")
+}
+
+// What a failed or timed out hook does to the step it belongs to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookFailurePolicy {
+    // Records the failure and carries on.
+    #[default]
+    Ignore,
+    // A failed `post_start` stops the container again; a failed `pre_stop` keeps it running
+    // and fails the delete or scale-down.
+    Fail,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HookPoint {
+    PostStart,
+    PreStop,
+}
+
+impl fmt::Display for HookPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookPoint::PostStart => write!(f, "post_start"),
+            HookPoint::PreStop => write!(f, "pre_stop"),
+        }
+    }
+}
+
+impl LifecycleHook {
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        match (self.exec.is_empty(), &self.http) {
+            (true, None) => Err(anyhow!("a lifecycle hook needs either exec or http")),
+            (false, Some(_)) => Err(anyhow!("a lifecycle hook takes exec or http, not both")),
+            (true, Some(http)) => {
+                reqwest::Method::from_bytes(http.method.as_bytes())
+                    .map_err(|_| anyhow!("invalid hook method {:?}", http.method))?;
+                reqwest::Url::parse(&http.url)
+                    .map_err(|error| anyhow!("invalid hook url {:?}: {}", http.url, error))?;
+                Ok(())
+            }
+            (false, None) => Ok(()),
+        }
+    }
+
+    // Runs the hook against `container`, recording a HookFailed event when it fails. The error
+    // is only returned when the hook's failure policy says the step should fail with it.
+    pub async fn run(
+        &self,
+        point: HookPoint,
+        container: &Container,
+        runtime: &(dyn ContainerRuntime + Send + Sync),
+        cluster: &Cluster,
+    ) -> Result<(), anyhow::Error> {
+        let timeout = Duration::from_secs(self.timeout_seconds);
+        let error = match tokio::time::timeout(timeout, self.call(&container.id, runtime)).await {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(error)) => error,
+            Err(_) => anyhow!("timed out after {}s", self.timeout_seconds),
+        };
+
+        let action = match (self.on_failure, point) {
+            (HookFailurePolicy::Ignore, _) => "",
+            (HookFailurePolicy::Fail, HookPoint::PostStart) => ", stopping it",
+            (HookFailurePolicy::Fail, HookPoint::PreStop) => ", keeping it running",
+        };
+        cluster
+            .events
+            .record_container(
+                &container.name,
+                EventReason::HookFailed,
+                format!(
+                    "{} hook of container {} failed: {}{}",
+                    point, container.name, error, action
+                ),
+            )
+            .await;
+        match self.on_failure {
+            HookFailurePolicy::Ignore => Ok(()),
+            HookFailurePolicy::Fail => Err(anyhow!("{} hook failed: {}", point, error)),
+        }
+    }
+
+    async fn call(
+        &self,
+        id: &str,
+        runtime: &(dyn ContainerRuntime + Send + Sync),
+    ) -> Result<(), anyhow::Error> {
+        if let Some(http) = &self.http {
+            let method = reqwest::Method::from_bytes(http.method.as_bytes())?;
+            reqwest::Client::new()
+                .request(method, &http.url)
+                .send()
+                .await?
+                .error_for_status()?;
+            return Ok(());
+        }
+
+        let output = runtime.exec(id, &self.exec).await?;
+        if output.exit_code != 0 {
+            return Err(anyhow!(
+                "{} exited with code {}: {}",
+                self.exec.join(" "),
+                output.exit_code,
+                output.output.trim()
+            ));
+        }
+        Ok(())
+    }
+}
@@ -1 +1,18 @@
-pub mod container;
\ No newline at end of file
+pub mod audit;
+pub mod autoscaler;
+pub mod config_map;
+pub mod container;
+pub mod cron_job;
+pub mod endpoints;
+pub mod ingress;
+pub mod job;
+pub mod labels;
+pub mod lifecycle;
+pub mod node;
+pub mod ports;
+pub mod resource_quota;
+pub mod resource_usage;
+pub mod rollout;
+pub mod secret;
+pub mod token;
+pub mod volume;
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+use crate::runtime::nodes::LOCAL_NODE;
+
+use super::labels::Labels;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum NodeStatus {
+    Ready,
+    NotReady,
+    // Not heard from since the control plane started.
+    #[default]
+    Unknown,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeCapacity {
+    pub cpus: f64,
+    pub memory_bytes: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Node {
+    pub name: String,
+    // Where the agent's runtime API listens; None for the control plane's own docker.
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub capacity: NodeCapacity,
+    #[serde(default, skip_serializing_if = "Labels::is_empty")]
+    pub labels: Labels,
+    #[serde(default)]
+    pub status: NodeStatus,
+    #[serde(default)]
+    pub registered: String,
+    #[serde(default)]
+    pub last_seen: String,
+    // The bearer secret the agent's runtime API expects. Agents send it when they register; it
+    // is never listed back.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl Node {
+    pub fn local(capacity: NodeCapacity, labels: Labels) -> Self {
+        Node {
+            name: String::from(LOCAL_NODE),
+            address: None,
+            capacity,
+            labels,
+            status: NodeStatus::Ready,
+            registered: chrono::Utc::now().to_rfc3339(),
+            // The local node sends no heartbeats.
+            last_seen: String::new(),
+            token: None,
+        }
+    }
+
+    pub fn is_schedulable(&self) -> bool {
+        self.status != NodeStatus::NotReady
+    }
+
+    pub fn since_last_seen(&self) -> Option<chrono::Duration> {
+        let last_seen = chrono::DateTime::parse_from_rfc3339(&self.last_seen).ok()?;
+        Some(chrono::Utc::now().signed_duration_since(last_seen))
+    }
+}
@@ -0,0 +1,95 @@
+use std::{fmt, io, net::IpAddr, ops::RangeInclusive};
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PortConflict {
+    #[error("host port {ports} for container {container} is already used by container {other} on node {node}")]
+    Container {
+        container: String,
+        ports: HostPorts,
+        other: String,
+        node: String,
+    },
+    #[error("host port {port}/{protocol} for container {container} is already in use on the host: {source}")]
+    Host {
+        container: String,
+        port: u16,
+        protocol: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("host port {ports} for container {container} is below {lowest}, the lowest a rootless engine can bind; use a higher port or lower net.ipv4.ip_unprivileged_port_start")]
+    Privileged {
+        container: String,
+        ports: HostPorts,
+        lowest: u16,
+    },
+}
+
+// The host side of a `-p` mapping. Mappings without a host port, like `80` or
+// `127.0.0.1::80`, get a free port from docker and never conflict.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HostPorts {
+    pub ip: Option<IpAddr>,
+    pub ports: RangeInclusive<u16>,
+    pub protocol: String,
+}
+
+impl HostPorts {
+    pub fn parse(ports: &str) -> Option<HostPorts> {
+        let (mapping, protocol) = ports.split_once('/').unwrap_or((ports, "tcp"));
+        let mut parts = mapping.rsplitn(3, ':');
+        let _container_port = parts.next()?;
+        let host_port = parts.next().filter(|host_port| !host_port.is_empty())?;
+        let ip = match parts.next() {
+            Some(ip) => Some(
+                ip.trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .parse()
+                    .ok()?,
+            ),
+            None => None,
+        };
+
+        let ports = match host_port.split_once('-') {
+            Some((start, end)) => start.parse().ok()?..=end.parse().ok()?,
+            None => {
+                let port = host_port.parse().ok()?;
+                port..=port
+            }
+        };
+        Some(HostPorts {
+            ip: ip.filter(|ip: &IpAddr| !ip.is_unspecified()),
+            ports,
+            protocol: String::from(protocol),
+        })
+    }
+
+    // Ports bound on all addresses clash with the same ports on any address.
+    pub fn overlaps(&self, other: &HostPorts) -> bool {
+        self.protocol == other.protocol
+            && self.ports.start() <= other.ports.end()
+            && other.ports.start() <= self.ports.end()
+            && (self.ip.is_none() || other.ip.is_none() || self.ip == other.ip)
+    }
+}
+
+impl fmt::Display for HostPorts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ip) = self.ip {
+            write!(f, "{}:", ip)?;
+        }
+        if self.ports.start() == self.ports.end() {
+            write!(f, "{}/{}", self.ports.start(), self.protocol)
+        } else {
+            write!(
+                f,
+                "{}-{}/{}",
+                self.ports.start(),
+                self.ports.end(),
+                self.protocol
+            )
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use super::resource_usage::format_size;
+
+// Apps are the unit quotas apply to; a limit left out is unlimited.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResourceQuotaSpec {
+    pub app: String,
+    // Totals of the requests of the app's containers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub containers: Option<usize>,
+}
+
+impl ResourceQuotaSpec {
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.cpus.is_none() && self.memory_bytes.is_none() && self.containers.is_none() {
+            return Err(anyhow!(
+                "a resource quota needs at least one of cpus, memory_bytes or containers"
+            ));
+        }
+        if let Some(cpus) = self.cpus {
+            if !cpus.is_finite() || cpus < 0.0 {
+                return Err(anyhow!("cpus must not be negative, got {}", cpus));
+            }
+        }
+        Ok(())
+    }
+
+    // Why `used` is over the quota, or None when it fits.
+    pub fn exceeded(&self, used: &QuotaUsage) -> Option<String> {
+        let mut exceeded = Vec::new();
+        // Sums of fractional CPUs pick up rounding errors, so a total that is equal to the quota
+        // is allowed.
+        if let Some(cpus) = self.cpus.filter(|cpus| used.cpus > cpus + 1e-9) {
+            exceeded.push(format!("{:.2} of {} CPUs", used.cpus, cpus));
+        }
+        if let Some(memory_bytes) = self
+            .memory_bytes
+            .filter(|memory_bytes| used.memory_bytes > *memory_bytes)
+        {
+            exceeded.push(format!(
+                "{} of {} memory",
+                format_size(used.memory_bytes),
+                format_size(memory_bytes)
+            ));
+        }
+        if let Some(containers) = self
+            .containers
+            .filter(|containers| used.containers > *containers)
+        {
+            exceeded.push(format!("{} of {} containers", used.containers, containers));
+        }
+
+        if exceeded.is_empty() {
+            None
+        } else {
+            Some(exceeded.join(", "))
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    pub cpus: f64,
+    pub memory_bytes: u64,
+    pub containers: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResourceQuota {
+    pub spec: ResourceQuotaSpec,
+    pub created: String,
+    // What the app's containers request right now, filled in when the quota is read.
+    #[serde(default)]
+    pub used: QuotaUsage,
+}
+
+impl ResourceQuota {
+    pub fn new(spec: ResourceQuotaSpec) -> Self {
+        ResourceQuota {
+            spec,
+            created: chrono::Utc::now().to_rfc3339(),
+            used: QuotaUsage::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.spec.app
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::container::{Container, ContainerSpec};
+
+fn default_canary_percent() -> u32 {
+    20
+}
+
+fn default_analysis_seconds() -> u64 {
+    300
+}
+
+fn default_scale_down_delay_seconds() -> u64 {
+    300
+}
+
+fn default_progress_deadline_seconds() -> u64 {
+    600
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RolloutSpec {
+    // The app being rolled out, which also names the rollout.
+    pub app: String,
+    // The spec every replica of the app ends up with. Its name is ignored, as replicas keep
+    // their own.
+    pub template: ContainerSpec,
+    pub strategy: RolloutStrategy,
+    // How long new containers get to become ready before the rollout gives up on them.
+    #[serde(default = "default_progress_deadline_seconds")]
+    pub progress_deadline_seconds: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RolloutStrategy {
+    // Runs a share of the replicas on the new spec next to the old ones, and only replaces the
+    // rest once those canaries got through an analysis window.
+    Canary(CanaryStrategy),
+    // Starts a second, full set of replicas on the new spec and, once all of them are ready,
+    // switches the app's traffic from the old set to the new one at once.
+    BlueGreen(BlueGreenStrategy),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CanaryStrategy {
+    // Canaries to run, as a percentage of the app's replicas, rounded up to at least one.
+    #[serde(default = "default_canary_percent")]
+    pub percent: u32,
+    #[serde(default = "default_analysis_seconds")]
+    pub analysis_seconds: u64,
+    // Lowest share of analysis checks that must find the canaries ready, from 0 to 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_success_rate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric: Option<MetricCheck>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlueGreenStrategy {
+    // How long the old set is kept after the switch, during which the rollout can be undone.
+    #[serde(default = "default_scale_down_delay_seconds")]
+    pub scale_down_delay_seconds: u64,
+}
+
+impl CanaryStrategy {
+    pub fn canaries(&self, replicas: usize) -> usize {
+        (replicas * self.percent as usize).div_ceil(100).max(1)
+    }
+}
+
+// An external metric, such as an error rate, read on every analysis check: a GET of `url` has
+// to answer with a number within `min` and `max` or the rollout is rolled back.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MetricCheck {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+impl MetricCheck {
+    pub fn within(&self, value: f64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+impl RolloutSpec {
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        match &self.strategy {
+            RolloutStrategy::Canary(canary) => {
+                if canary.percent == 0 || canary.percent > 100 {
+                    return Err(anyhow!(
+                        "canary percent must be between 1 and 100, got {}",
+                        canary.percent
+                    ));
+                }
+                if let Some(rate) = canary.min_success_rate {
+                    if !(0.0..=1.0).contains(&rate) {
+                        return Err(anyhow!(
+                            "min success rate must be between 0 and 1, got {}",
+                            rate
+                        ));
+                    }
+                }
+                if let Some(metric) = &canary.metric {
+                    reqwest::Url::parse(&metric.url).map_err(|error| {
+                        anyhow!("invalid metric url {:?}: {}", metric.url, error)
+                    })?;
+                }
+            }
+            RolloutStrategy::BlueGreen(_) => {}
+        }
+        self.template.validate()
+    }
+
+    // The template as the replica or canary named `name` runs it.
+    pub fn replica(&self, name: &str) -> ContainerSpec {
+        ContainerSpec {
+            name: String::from(name),
+            app: Some(self.app.clone()),
+            ..self.template.clone()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RolloutPhase {
+    // Canaries are starting.
+    Progressing,
+    // Canaries are running and being checked.
+    Analyzing,
+    // The canaries passed; the other replicas are being replaced one at a time.
+    Promoting,
+    // Traffic goes to the new set of a blue-green rollout; the old set is kept until the scale
+    // down delay is over.
+    Switched,
+    // The canaries failed and are being removed.
+    RollingBack,
+    Succeeded,
+    RolledBack,
+    // A replacement didn't become ready while promoting; the app is left as it is.
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RolloutStatus {
+    pub phase: RolloutPhase,
+    // When the current phase started, for the analysis window and the progress deadline.
+    pub phase_started: String,
+    #[serde(default)]
+    pub canaries: Vec<String>,
+    // Replicas already replaced while promoting.
+    #[serde(default)]
+    pub promoted: Vec<String>,
+    // The new set of a blue-green rollout, and whether traffic was switched to it.
+    #[serde(default)]
+    pub replicas: Vec<String>,
+    #[serde(default)]
+    pub switched: bool,
+    // Canary checks made during analysis, and how many found the canary ready.
+    #[serde(default)]
+    pub checks: u32,
+    #[serde(default)]
+    pub ready_checks: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl RolloutStatus {
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.checks > 0).then(|| self.ready_checks as f64 / self.checks as f64)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rollout {
+    pub spec: RolloutSpec,
+    pub created: String,
+    pub status: RolloutStatus,
+}
+
+impl Rollout {
+    pub fn new(spec: RolloutSpec) -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+        Rollout {
+            spec,
+            created: now.clone(),
+            status: RolloutStatus {
+                phase: RolloutPhase::Progressing,
+                phase_started: now,
+                canaries: Vec::new(),
+                promoted: Vec::new(),
+                replicas: Vec::new(),
+                switched: false,
+                checks: 0,
+                ready_checks: 0,
+                message: None,
+            },
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.spec.app
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status.phase,
+            RolloutPhase::Succeeded | RolloutPhase::RolledBack | RolloutPhase::Failed
+        )
+    }
+
+    // Canaries can only be taken back until they are promoted; a blue-green rollout can be
+    // undone until the old set is gone.
+    pub fn can_undo(&self) -> bool {
+        !self.is_finished() && self.status.phase != RolloutPhase::Promoting
+    }
+
+    // Whether the app's container named `container` should get traffic. While a blue-green
+    // rollout is in progress only one of its two sets does.
+    pub fn serves(&self, container: &str) -> bool {
+        if self.is_finished() || !matches!(self.spec.strategy, RolloutStrategy::BlueGreen(_)) {
+            return true;
+        }
+        self.status.replicas.iter().any(|name| name == container) == self.status.switched
+    }
+}
+
+// The app's containers other than its canaries, which rollouts replace and scaling resizes.
+pub fn is_replica(container: &Container, app: &str) -> bool {
+    container.app == app && !container.name.starts_with(&format!("{}-canary-", app))
+}
@@ -0,0 +1,26 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Secret {
+    pub name: String,
+    pub data: BTreeMap<String, String>,
+}
+
+// Values are never printed, so a stray `{:?}` can't leak them into the logs.
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Secret")
+            .field("name", &self.name)
+            .field("keys", &self.data.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SecretMetadata {
+    pub name: String,
+    pub keys: Vec<String>,
+    pub created: String,
+}
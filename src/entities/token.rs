@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+// Matches every verb, resource or app in a rule.
+pub const ANY: &str = "*";
+
+// What the API lets a caller do, by resource: `get`, `list` and `watch` read, `create`,
+// `update` and `delete` change things. Actions like restarting a container or scaling an app
+// count as updates.
+pub const VERBS: [&str; 6] = ["get", "list", "watch", "create", "update", "delete"];
+
+#[derive(Debug, Error)]
+pub enum TokenError {
+    #[error("token {0} already exists")]
+    Exists(String),
+    #[error("role {0} does not exist")]
+    UnknownRole(String),
+    #[error("a token needs at least one role")]
+    NoRoles,
+}
+
+// Grants the verbs on the resources, e.g. `containers` or `secrets`, of the apps. A rule limited
+// to some apps only covers calls about one of them, like those on its containers or its rollout;
+// calls across apps, such as listing every container, need a rule for all apps.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub verbs: Vec<String>,
+    pub resources: Vec<String>,
+    #[serde(default = "all_apps")]
+    pub apps: Vec<String>,
+}
+
+fn all_apps() -> Vec<String> {
+    vec![String::from(ANY)]
+}
+
+impl PolicyRule {
+    // `app` is the app the call is about, if it is about one.
+    pub fn allows(&self, verb: &str, resource: &str, app: Option<&str>) -> bool {
+        let matches = |values: &[String], value: &str| {
+            values
+                .iter()
+                .any(|allowed| allowed == ANY || allowed == value)
+        };
+        let app_allowed = self.apps.iter().any(|allowed| allowed == ANY)
+            || app.is_some_and(|app| matches(&self.apps, app));
+        matches(&self.verbs, verb) && matches(&self.resources, resource) && app_allowed
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Role {
+    pub name: String,
+    pub rules: Vec<PolicyRule>,
+}
+
+impl Role {
+    pub fn allows(&self, verb: &str, resource: &str, app: Option<&str>) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.allows(verb, resource, app))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TokenSpec {
+    // Also the user the token's calls are recorded under.
+    pub name: String,
+    pub roles: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Token {
+    pub name: String,
+    pub roles: Vec<String>,
+    pub created: String,
+}
+
+// A token as handed out once, on creation; only a hash of the secret is kept.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IssuedToken {
+    pub token: Token,
+    // What clients send as `Authorization: Bearer <secret>`.
+    pub secret: String,
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum VolumeError {
+    #[error("volume {0} already exists")]
+    Exists(String),
+    #[error("volume {name} is mounted by {}; use --force to delete it anyway", containers.join(", "))]
+    InUse {
+        name: String,
+        containers: Vec<String>,
+    },
+    #[error("volume {volume} claimed by container {container} not found")]
+    NotFound { volume: String, container: String },
+}
+
+// Mounts a volume into a container, which then only runs on the volume's node.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VolumeClaim {
+    pub name: String,
+    pub mount_path: String,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VolumeSpec {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+}
+
+// A named docker volume. It lives on one node: the one given when creating it or, failing that,
+// the one its first claiming container is scheduled to.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Volume {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    pub created: String,
+    // The containers claiming the volume, filled in when read.
+    #[serde(default)]
+    pub used_by: Vec<String>,
+}
+
+impl Volume {
+    pub fn new(spec: VolumeSpec) -> Self {
+        Volume {
+            name: spec.name,
+            node: spec.node,
+            created: chrono::Utc::now().to_rfc3339(),
+            used_by: Vec::new(),
+        }
+    }
+}
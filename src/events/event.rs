@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EventType {
+    Normal,
+    Warning,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EventReason {
+    Scheduled,
+    Created,
+    Pulled,
+    Started,
+    Exited,
+    Dead,
+    Unhealthy,
+    Killed,
+    Failed,
+    Completed,
+    BackOff,
+    Skipped,
+    Missed,
+    NodeReady,
+    NodeNotReady,
+    Rescheduled,
+    Ready,
+    NotReady,
+    Scaled,
+    RolledOut,
+    RolledBack,
+    Switched,
+    DriftDetected,
+    ImagesRemoved,
+    OOMKilled,
+    Stopping,
+    Stopped,
+    ForceKilled,
+    HookFailed,
+    Waiting,
+}
+
+impl EventReason {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            EventReason::Dead
+            | EventReason::Unhealthy
+            | EventReason::Failed
+            | EventReason::BackOff
+            | EventReason::Missed
+            | EventReason::NodeNotReady
+            | EventReason::Rescheduled
+            | EventReason::NotReady
+            | EventReason::DriftDetected
+            | EventReason::OOMKilled
+            | EventReason::ForceKilled
+            | EventReason::HookFailed
+            | EventReason::RolledBack => EventType::Warning,
+            _ => EventType::Normal,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ObjectKind {
+    Container,
+    Job,
+    CronJob,
+    Node,
+    Autoscaler,
+    App,
+    Rollout,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ObjectReference {
+    pub kind: ObjectKind,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Event {
+    pub object: ObjectReference,
+    #[serde(rename = "type")]
+    pub event_type: EventType,
+    pub reason: EventReason,
+    pub message: String,
+    pub timestamp: DateTime<Utc>,
+}
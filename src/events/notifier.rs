@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    cluster::Cluster,
+    config::{NotificationFormat, NotificationWebhookConfig},
+    watchers::watcher::{Watcher, WatcherContext},
+};
+
+use super::event::{Event, ObjectKind};
+
+#[derive(Serialize)]
+struct Notification<'a> {
+    #[serde(flatten)]
+    event: &'a Event,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    app: Option<&'a str>,
+}
+
+// POSTs the recorded events that pass a webhook's filters to its URL, as they happen.
+pub struct Notifier {
+    cluster: Cluster,
+    webhooks: Vec<NotificationWebhookConfig>,
+    http: reqwest::Client,
+}
+
+impl Notifier {
+    pub fn new(
+        cluster: Cluster,
+        webhooks: Vec<NotificationWebhookConfig>,
+    ) -> Result<Self, anyhow::Error> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        Ok(Notifier {
+            cluster,
+            webhooks,
+            http,
+        })
+    }
+
+    async fn notify(&self, event: &Event) {
+        let app = self.app(event).await;
+
+        for webhook in self.webhooks.iter() {
+            if !matches(webhook, event, app.as_deref()) {
+                continue;
+            }
+
+            let body = match webhook.format {
+                NotificationFormat::Json => serde_json::to_value(Notification {
+                    event,
+                    app: app.as_deref(),
+                })
+                .unwrap_or_default(),
+                NotificationFormat::Slack => json!({ "text": slack_text(event, app.as_deref()) }),
+            };
+            let sent = self
+                .http
+                .post(&webhook.url)
+                .json(&body)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(error) = sent {
+                println!(
+                    "Failed to notify {} of {:?} event for {}: {}",
+                    webhook.url, event.reason, event.object.name, error
+                );
+            }
+        }
+    }
+
+    async fn app(&self, event: &Event) -> Option<String> {
+        match event.object.kind {
+            ObjectKind::App | ObjectKind::Autoscaler | ObjectKind::Rollout => {
+                Some(event.object.name.clone())
+            }
+            // Containers that aren't tracked yet, as while they are being created, are taken to
+            // be their own app.
+            ObjectKind::Container => Some(
+                self.cluster
+                    .status_watcher
+                    .find(&event.object.name)
+                    .await
+                    .map_or_else(|| event.object.name.clone(), |container| container.app),
+            ),
+            _ => None,
+        }
+    }
+}
+
+// Empty filters match everything; events without an app never match an app filter.
+fn matches(webhook: &NotificationWebhookConfig, event: &Event, app: Option<&str>) -> bool {
+    (webhook.types.is_empty() || webhook.types.contains(&event.event_type))
+        && (webhook.reasons.is_empty() || webhook.reasons.contains(&event.reason))
+        && (webhook.kinds.is_empty() || webhook.kinds.contains(&event.object.kind))
+        && (webhook.apps.is_empty()
+            || app.is_some_and(|app| webhook.apps.iter().any(|filter| filter == app)))
+}
+
+fn slack_text(event: &Event, app: Option<&str>) -> String {
+    let app = app
+        .filter(|app| *app != event.object.name)
+        .map(|app| format!(" (app {})", app))
+        .unwrap_or_default();
+    format!(
+        "*{:?}* {:?} {:?}/{}{}: {}",
+        event.event_type, event.reason, event.object.kind, event.object.name, app, event.message
+    )
+}
+
+#[async_trait]
+impl Watcher for Notifier {
+    fn name(&self) -> &str {
+        "notifier"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        let mut events = self.cluster.events.subscribe();
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = ctx.shutdown.cancelled() => return Ok(()),
+            };
+            match event {
+                Ok(event) => self.notify(&event).await,
+                Err(RecvError::Lagged(missed)) => {
+                    println!("Notifier fell behind, skipped {} events", missed)
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+}
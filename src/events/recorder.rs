@@ -0,0 +1,82 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use tokio::sync::{broadcast, Mutex};
+
+use super::event::{Event, EventReason, ObjectKind, ObjectReference};
+
+const DEFAULT_CAPACITY: usize = 1024;
+const SUBSCRIBER_CAPACITY: usize = 256;
+
+pub struct EventRecorder {
+    events: Arc<Mutex<VecDeque<Event>>>,
+    capacity: usize,
+    subscribers: broadcast::Sender<Event>,
+}
+
+impl Default for EventRecorder {
+    fn default() -> Self {
+        EventRecorder::new()
+    }
+}
+
+impl EventRecorder {
+    pub fn new() -> Self {
+        EventRecorder::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (subscribers, _) = broadcast::channel(SUBSCRIBER_CAPACITY);
+
+        EventRecorder {
+            events: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+            subscribers,
+        }
+    }
+
+    // Receives the events recorded from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.subscribers.subscribe()
+    }
+
+    pub async fn record(&self, kind: ObjectKind, name: &str, reason: EventReason, message: String) {
+        let event = Event {
+            object: ObjectReference {
+                kind,
+                name: String::from(name),
+            },
+            event_type: reason.event_type(),
+            reason,
+            message,
+            timestamp: chrono::Utc::now(),
+        };
+        println!(
+            "Event {:?} {} {:?}: {}",
+            event.object.kind, event.object.name, event.reason, event.message
+        );
+
+        // Sending only fails when nobody is subscribed, which is fine.
+        let _ = self.subscribers.send(event.clone());
+
+        let mut events = self.events.lock().await;
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    pub async fn record_container(&self, name: &str, reason: EventReason, message: String) {
+        self.record(ObjectKind::Container, name, reason, message)
+            .await
+    }
+
+    pub async fn list(&self, name: Option<&str>) -> Vec<Event> {
+        self.events
+            .lock()
+            .await
+            .iter()
+            .filter(|event| name.is_none_or(|name| event.object.name == name))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,310 @@
+pub mod proxy;
+pub mod tls;
+
+use std::{
+    collections::HashMap,
+    future::IntoFuture,
+    net::SocketAddr,
+    pin::pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use axum::Router;
+use tokio::{net::TcpListener, sync::Notify};
+
+use crate::{
+    cluster::Cluster,
+    config::IngressConfig,
+    controllers::rollout,
+    entities::{
+        endpoints::Endpoints,
+        ingress::{Ingress, IngressRule, IngressSpec},
+        node::Node,
+        ports::HostPorts,
+    },
+    runtime::nodes::LOCAL_NODE,
+    watchers::watcher::{Watcher, WatcherContext},
+};
+
+use self::{proxy::Proxy, tls::Certificates};
+
+pub(crate) const KIND: &str = "ingresses";
+// Routes are also rebuilt this often, to pick up node address changes.
+const RESYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+// A rule with the addresses of its app's ready containers.
+pub struct Route {
+    pub ingress: String,
+    pub rule: IngressRule,
+    pub backends: Vec<String>,
+    next: AtomicUsize,
+}
+
+impl Route {
+    // Round robin over the backends.
+    pub fn backend(&self) -> Option<&str> {
+        if self.backends.is_empty() {
+            return None;
+        }
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        Some(&self.backends[next % self.backends.len()])
+    }
+}
+
+#[derive(Default)]
+pub struct RouteTable {
+    // Rules for a host before the ones for any host, longer paths first.
+    routes: Vec<Route>,
+}
+
+impl RouteTable {
+    pub fn new(ingresses: &[Ingress], endpoints: &[Endpoints], nodes: &[Node]) -> Self {
+        let mut routes: Vec<Route> = ingresses
+            .iter()
+            .flat_map(|ingress| {
+                ingress.spec.rules.iter().map(|rule| Route {
+                    ingress: ingress.name().to_string(),
+                    rule: rule.clone(),
+                    backends: backends(&rule.app, endpoints, nodes),
+                    next: AtomicUsize::new(0),
+                })
+            })
+            .collect();
+        routes.sort_by(|a, b| {
+            b.rule
+                .host
+                .is_some()
+                .cmp(&a.rule.host.is_some())
+                .then(b.rule.path.len().cmp(&a.rule.path.len()))
+        });
+        RouteTable { routes }
+    }
+
+    pub fn find(&self, host: &str, path: &str) -> Option<&Route> {
+        self.routes
+            .iter()
+            .find(|route| route.rule.matches_host(host) && route.rule.matches_path(path))
+    }
+}
+
+// Containers are reached on the host port they publish, on the address of their node. Those
+// without a fixed host port can't be proxied to.
+fn backends(app: &str, endpoints: &[Endpoints], nodes: &[Node]) -> Vec<String> {
+    let Some(endpoints) = endpoints.iter().find(|endpoints| endpoints.app == app) else {
+        return Vec::new();
+    };
+    endpoints
+        .ready
+        .iter()
+        .filter_map(|endpoint| {
+            let ports = HostPorts::parse(&endpoint.ports)?;
+            let host = match ports.ip {
+                Some(ip) => ip.to_string(),
+                None if endpoint.node.is_empty() || endpoint.node == LOCAL_NODE => {
+                    String::from("127.0.0.1")
+                }
+                None => {
+                    let address = nodes
+                        .iter()
+                        .find(|node| node.name == endpoint.node)?
+                        .address
+                        .as_ref()?;
+                    let (host, _) = address.rsplit_once(':')?;
+                    host.to_string()
+                }
+            };
+            let address = match host.contains(':') {
+                true => format!("[{}]:{}", host, ports.ports.start()),
+                false => format!("{}:{}", host, ports.ports.start()),
+            };
+            Some(address)
+        })
+        .collect()
+}
+
+// Stores ingresses and, as a watcher, serves them: an HTTP reverse proxy that routes requests
+// by host and path to the ready containers of the app a rule names, and terminates TLS for the
+// hosts they have certificates for. Routes and certificates are rebuilt whenever ingresses,
+// secrets or container statuses change.
+pub struct IngressController {
+    cluster: Cluster,
+    addr: String,
+    tls_addr: Option<String>,
+    proxy: Arc<Proxy>,
+    certificates: Arc<Certificates>,
+    // Certificate problems of the last reload, so each is logged once rather than on every one.
+    certificate_errors: Mutex<Vec<String>>,
+    changed: Notify,
+}
+
+impl IngressController {
+    pub fn new(cluster: Cluster, config: &IngressConfig) -> Self {
+        IngressController {
+            cluster,
+            addr: config.addr.clone(),
+            tls_addr: config.tls_addr.clone(),
+            proxy: Arc::new(Proxy::new(Duration::from_secs(config.timeout_seconds))),
+            certificates: Arc::new(Certificates::default()),
+            certificate_errors: Mutex::new(Vec::new()),
+            changed: Notify::new(),
+        }
+    }
+
+    pub async fn create(&self, spec: IngressSpec) -> Result<Ingress, anyhow::Error> {
+        spec.validate()?;
+        let ingress = Ingress::new(spec);
+        self.cluster
+            .state
+            .put(KIND, ingress.name(), &ingress)
+            .await?;
+        self.changed.notify_one();
+        Ok(ingress)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Option<Ingress>, anyhow::Error> {
+        self.cluster.state.get(KIND, name).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<Ingress>, anyhow::Error> {
+        let mut ingresses: Vec<Ingress> = self.cluster.state.list(KIND).await?;
+        ingresses.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok(ingresses)
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<bool, anyhow::Error> {
+        let deleted = self.cluster.state.delete(KIND, name).await?;
+        self.changed.notify_one();
+        Ok(deleted)
+    }
+
+    // Certificates live in secrets, which ingresses don't own.
+    pub fn secrets_changed(&self) {
+        self.changed.notify_one();
+    }
+
+    // For changes to which containers serve an app that don't come with a status change.
+    pub fn endpoints_changed(&self) {
+        self.changed.notify_one();
+    }
+
+    pub async fn reload(&self) -> Result<(), anyhow::Error> {
+        let ingresses = self.list().await?;
+        let containers = rollout::serving(&self.cluster).await?;
+        let endpoints = Endpoints::from_containers(&containers);
+        let nodes = self.cluster.nodes.list().await;
+        self.proxy
+            .set_routes(RouteTable::new(&ingresses, &endpoints, &nodes));
+        self.reload_certificates(&ingresses).await?;
+        Ok(())
+    }
+
+    // A host whose certificate can't be loaded is left out rather than failing the others.
+    async fn reload_certificates(&self, ingresses: &[Ingress]) -> Result<(), anyhow::Error> {
+        let mut keys = HashMap::new();
+        let mut by_host = HashMap::new();
+        let mut errors = Vec::new();
+        for ingress in ingresses.iter() {
+            for tls in ingress.spec.tls.iter() {
+                if !keys.contains_key(&tls.secret) {
+                    let key = match self.cluster.secrets.get(&tls.secret).await? {
+                        Some(secret) => tls::certified_key(&secret).map_err(|e| e.to_string()),
+                        None => Err(format!("secret {} not found", tls.secret)),
+                    };
+                    keys.insert(tls.secret.clone(), key);
+                }
+                match &keys[&tls.secret] {
+                    Ok(key) => {
+                        for host in tls.hosts.iter() {
+                            by_host.insert(host.to_ascii_lowercase(), key.clone());
+                        }
+                    }
+                    Err(error) => errors.push(format!(
+                        "no certificate for {} of ingress {}: {}",
+                        tls.hosts.join(","),
+                        ingress.name(),
+                        error
+                    )),
+                }
+            }
+        }
+        self.certificates.set(by_host);
+
+        let mut reported = self.certificate_errors.lock().unwrap();
+        for error in errors.iter().filter(|error| !reported.contains(error)) {
+            println!("{}", error);
+        }
+        *reported = errors;
+        Ok(())
+    }
+
+    async fn reload_or_log(&self) {
+        if let Err(error) = self.reload().await {
+            println!("Failed to reload ingress routes: {}", error);
+        }
+    }
+}
+
+#[async_trait]
+impl Watcher for IngressController {
+    fn name(&self) -> &str {
+        "ingress"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        let listener = TcpListener::bind(&self.addr).await?;
+        println!("Ingress listening on {}", listener.local_addr()?);
+        self.reload_or_log().await;
+
+        let router = Router::new()
+            .fallback(proxy::handle)
+            .with_state(self.proxy.clone());
+        let tls_server = match &self.tls_addr {
+            Some(addr) => {
+                let listener = TcpListener::bind(addr).await?;
+                println!("Ingress listening for TLS on {}", listener.local_addr()?);
+                let acceptor = tls::acceptor(self.certificates.clone())?;
+                Some(tls::serve(
+                    listener,
+                    acceptor,
+                    router.clone(),
+                    ctx.shutdown.clone(),
+                ))
+            }
+            None => None,
+        };
+        let http_server = axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(ctx.shutdown.clone().cancelled_owned())
+        .into_future();
+        let mut server = pin!(async move {
+            match tls_server {
+                Some(tls_server) => {
+                    tokio::try_join!(async { Ok(http_server.await?) }, tls_server)?;
+                }
+                None => http_server.await?,
+            }
+            Ok::<_, anyhow::Error>(())
+        });
+
+        let mut events = self.cluster.status_watcher.subscribe();
+        loop {
+            tokio::select! {
+                result = &mut server => {
+                    println!("Ingress stopped");
+                    return result;
+                }
+                _ = events.recv() => {}
+                _ = self.changed.notified() => {}
+                _ = tokio::time::sleep(RESYNC_INTERVAL) => {}
+            }
+            self.reload_or_log().await;
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{
+        header::{self, HeaderName, HeaderValue},
+        StatusCode, Uri,
+    },
+    response::{IntoResponse, Response},
+};
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client},
+    rt::TokioExecutor,
+};
+
+use super::RouteTable;
+
+// Headers that only apply to one connection, which a proxy must not pass on.
+const HOP_BY_HOP: [&str; 7] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+// Marks requests that arrived over TLS.
+#[derive(Clone, Copy)]
+pub struct Https;
+
+pub struct Proxy {
+    routes: RwLock<Arc<RouteTable>>,
+    client: Client<HttpConnector, Body>,
+    timeout: Duration,
+}
+
+impl Proxy {
+    pub fn new(timeout: Duration) -> Self {
+        Proxy {
+            routes: RwLock::new(Arc::new(RouteTable::default())),
+            client: Client::builder(TokioExecutor::new()).build_http(),
+            timeout,
+        }
+    }
+
+    pub fn routes(&self) -> Arc<RouteTable> {
+        self.routes.read().unwrap().clone()
+    }
+
+    pub fn set_routes(&self, routes: RouteTable) {
+        *self.routes.write().unwrap() = Arc::new(routes);
+    }
+}
+
+// The port, if any, is not part of what rules match on.
+fn strip_port(host: &str) -> &str {
+    match host.rsplit_once(':') {
+        Some((hostname, port))
+            if !port.is_empty()
+                && port.bytes().all(|byte| byte.is_ascii_digit())
+                && (!hostname.contains(':') || hostname.ends_with(']')) =>
+        {
+            hostname
+        }
+        _ => host,
+    }
+}
+
+pub async fn handle(
+    State(proxy): State<Arc<Proxy>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request,
+) -> Response {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .or_else(|| request.uri().host())
+        .unwrap_or_default()
+        .to_string();
+    let hostname = strip_port(&host);
+    let proto = match request.extensions().get::<Https>() {
+        Some(_) => "https",
+        None => "http",
+    };
+
+    let routes = proxy.routes();
+    let Some(route) = routes.find(hostname, request.uri().path()) else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("no ingress rule for {}{}\n", hostname, request.uri().path()),
+        )
+            .into_response();
+    };
+    let Some(backend) = route.backend() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!(
+                "app {} of ingress {} has no ready containers\n",
+                route.rule.app, route.ingress
+            ),
+        )
+            .into_response();
+    };
+
+    let path = request
+        .uri()
+        .path_and_query()
+        .map_or("/", |path| path.as_str());
+    let uri: Uri = match format!("http://{}{}", backend, path).parse() {
+        Ok(uri) => uri,
+        Err(error) => return (StatusCode::BAD_GATEWAY, error.to_string()).into_response(),
+    };
+    *request.uri_mut() = uri;
+
+    let headers = request.headers_mut();
+    for name in HOP_BY_HOP {
+        headers.remove(name);
+    }
+    let forwarded_for = match headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(earlier) => format!("{}, {}", earlier, peer.ip()),
+        None => peer.ip().to_string(),
+    };
+    let forwarded = [
+        ("x-forwarded-for", forwarded_for),
+        ("x-forwarded-host", host.clone()),
+        ("x-forwarded-proto", String::from(proto)),
+    ];
+    for (name, value) in forwarded {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(HeaderName::from_static(name), value);
+        }
+    }
+
+    match tokio::time::timeout(proxy.timeout, proxy.client.request(request)).await {
+        Ok(Ok(response)) => {
+            let mut response = response.map(Body::new);
+            for name in HOP_BY_HOP {
+                response.headers_mut().remove(name);
+            }
+            response
+        }
+        Ok(Err(error)) => (
+            StatusCode::BAD_GATEWAY,
+            format!(
+                "failed to reach {} for app {}: {}\n",
+                backend, route.rule.app, error
+            ),
+        )
+            .into_response(),
+        Err(_) => (
+            StatusCode::GATEWAY_TIMEOUT,
+            format!("app {} did not respond in time\n", route.rule.app),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use axum::{extract::ConnectInfo, Router};
+use hyper::{body::Incoming, server::conn::http1, service::service_fn, Request};
+use hyper_util::{rt::TokioIo, server::graceful::GracefulShutdown};
+use tokio::net::TcpListener;
+use tokio_rustls::{
+    rustls::{
+        crypto::ring,
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+        server::{ClientHello, ResolvesServerCert},
+        sign::CertifiedKey,
+        ServerConfig,
+    },
+    TlsAcceptor,
+};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+
+use crate::entities::{
+    ingress::{TLS_CERT, TLS_KEY},
+    secret::Secret,
+};
+
+use super::proxy::Https;
+
+// A client that hasn't finished its handshake by then is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The certificate for each TLS host of every ingress, picked by the name the client asks for
+// (SNI). Replaced as a whole whenever ingresses or their secrets change, so rotated certificates
+// are served to new connections without a restart.
+#[derive(Debug, Default)]
+pub struct Certificates {
+    by_host: RwLock<HashMap<String, Arc<CertifiedKey>>>,
+}
+
+impl Certificates {
+    pub fn set(&self, by_host: HashMap<String, Arc<CertifiedKey>>) {
+        *self.by_host.write().unwrap() = by_host;
+    }
+
+    // An exact host wins over a wildcard covering it.
+    pub fn find(&self, name: &str) -> Option<Arc<CertifiedKey>> {
+        let name = name.to_ascii_lowercase();
+        let by_host = self.by_host.read().unwrap();
+        if let Some(key) = by_host.get(&name) {
+            return Some(key.clone());
+        }
+        let (_, parent) = name.split_once('.')?;
+        by_host.get(&format!("*.{}", parent)).cloned()
+    }
+}
+
+impl ResolvesServerCert for Certificates {
+    fn resolve(&self, hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.find(hello.server_name()?)
+    }
+}
+
+pub fn certified_key(secret: &Secret) -> Result<Arc<CertifiedKey>, anyhow::Error> {
+    let pem = |key: &str| {
+        secret
+            .data
+            .get(key)
+            .ok_or_else(|| anyhow!("secret {} has no {} key", secret.name, key))
+    };
+    let chain = CertificateDer::pem_slice_iter(pem(TLS_CERT)?.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| anyhow!("invalid {} in secret {}: {}", TLS_CERT, secret.name, error))?;
+    if chain.is_empty() {
+        return Err(anyhow!(
+            "{} in secret {} holds no certificates",
+            TLS_CERT,
+            secret.name
+        ));
+    }
+    let key = PrivateKeyDer::from_pem_slice(pem(TLS_KEY)?.as_bytes())
+        .map_err(|error| anyhow!("invalid {} in secret {}: {}", TLS_KEY, secret.name, error))?;
+    let key = ring::sign::any_supported_type(&key)
+        .map_err(|error| anyhow!("invalid {} in secret {}: {}", TLS_KEY, secret.name, error))?;
+
+    let certified = CertifiedKey::new(chain, key);
+    certified.keys_match().map_err(|error| {
+        anyhow!(
+            "{} and {} in secret {} don't match: {}",
+            TLS_CERT,
+            TLS_KEY,
+            secret.name,
+            error
+        )
+    })?;
+    Ok(Arc::new(certified))
+}
+
+pub fn acceptor(certificates: Arc<Certificates>) -> Result<TlsAcceptor, anyhow::Error> {
+    let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_cert_resolver(certificates);
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+// Serves the router over TLS until shutdown, then waits for open connections to finish.
+pub async fn serve(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    router: Router,
+    shutdown: CancellationToken,
+) -> Result<(), anyhow::Error> {
+    let graceful = GracefulShutdown::new();
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(error) => {
+                    println!("Failed to accept ingress TLS connection: {}", error);
+                    continue;
+                }
+            },
+            _ = shutdown.cancelled() => break,
+        };
+
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        let watcher = graceful.watcher();
+        tokio::spawn(async move {
+            let stream =
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => stream,
+                    // Clients asking for a host without a certificate end up here, as do
+                    // scanners; neither is worth a log line.
+                    _ => return,
+                };
+            let service = service_fn(move |mut request: Request<Incoming>| {
+                request.extensions_mut().insert(ConnectInfo(peer));
+                request.extensions_mut().insert(Https);
+                router.clone().oneshot(request)
+            });
+            let connection = http1::Builder::new().serve_connection(TokioIo::new(stream), service);
+            let _ = watcher.watch(connection).await;
+        });
+    }
+
+    graceful.shutdown().await;
+    Ok(())
+}
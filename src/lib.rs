@@ -0,0 +1,66 @@
+//! A small container orchestrator in the spirit of Kubernetes, on top of docker.
+//!
+//! The `nic8s` binary is a thin CLI over this crate. Other programs can embed the control plane
+//! with [`Nic8s::builder`]:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), anyhow::Error> {
+//! let config = nic8s::config::Config::load(None)?;
+//! nic8s::Nic8s::builder()
+//!     .config(config)
+//!     .api_addr("127.0.0.1:7443")
+//!     .build()
+//!     .run(nic8s::shutdown_signal())
+//!     .await
+//! # }
+//! ```
+//!
+//! Containers are described by [`entities::container::ContainerSpec`] and run through a
+//! [`runtime::ContainerRuntime`]; the [`watchers`] and [`controllers`] reconcile them.
+
+pub mod admission;
+pub mod agent;
+pub mod api;
+pub mod auth;
+pub mod cli;
+pub mod cluster;
+pub mod config;
+pub mod controllers;
+pub mod entities;
+pub mod events;
+pub mod ingress;
+pub mod manifest;
+pub mod runtime;
+pub mod scheduler;
+mod server;
+pub mod store;
+pub mod watchers;
+
+use tokio::signal;
+
+pub use server::{Nic8s, Nic8sBuilder};
+
+/// Completes on Ctrl-C, or SIGTERM on unix.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
@@ -1,29 +1,103 @@
-mod entities;
-mod watchers;
-use std::{collections::HashMap, sync::Arc, thread, time::Duration};
-
-use tokio::{sync::Mutex, task};
-use watchers::{container_status::ContainerStatusWatcher, watchers::Watchers};
-
-use crate::entities::container::Container;
+use clap::Parser;
+use nic8s::{
+    agent,
+    cli::{self, client::ApiClient, Cli, Command},
+    config::Config,
+    Nic8s,
+};
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
-    let status_watcher = Arc::new(ContainerStatusWatcher {
-        containers: Arc::new(Mutex::new(HashMap::new())),
-    });
-    let watchers = Watchers::new(status_watcher.clone());
-
-    Container::new("nginx", "80", "nginx", &status_watcher).await?;
+    let cli = Cli::parse();
+    let client = || ApiClient::new(&cli.server).with_token(cli.token.as_deref());
 
-    let clone_watchers = watchers.clone();
-    let container_status_checker_task = task::spawn(async move {
-        loop {
-            clone_watchers.container_status_watcher.check_status().await;
-            thread::sleep(Duration::from_secs(1))
+    match cli.command {
+        None | Some(Command::Serve) => {
+            let config = Config::load(cli.config.as_deref())?;
+            serve(&cli, config, true).await
+        }
+        Some(Command::Server) => {
+            let config = Config::load(cli.config.as_deref())?;
+            serve(&cli, config, false).await
+        }
+        Some(Command::Agent {
+            name,
+            listen,
+            advertise,
+            labels,
+        }) => {
+            let mut config = Config::load(cli.config.as_deref())?;
+            config.nodes.labels.extend(labels);
+            agent::run(client(), name, &listen, advertise, config).await
+        }
+        Some(Command::Apply { manifest, dry_run }) => {
+            cli::apply::run(&client(), manifest, dry_run).await
         }
-    });
+        Some(Command::Diff { manifest }) => cli::apply::diff(&client(), manifest).await,
+        Some(Command::SetImage {
+            container,
+            image,
+            file,
+        }) => cli::apply::set_image(&file, &container, &image),
+        Some(Command::Edit { name, file }) => cli::apply::edit(&file, &name),
+        Some(Command::Node { command }) => cli::node::run(&client(), command).await,
+        Some(Command::Get {
+            container,
+            app,
+            output,
+            watch,
+        }) => {
+            let (container, app) = (container.as_deref(), app.as_deref());
+            cli::get::run(&client(), container, app, output, watch).await
+        }
+        Some(Command::Describe { container }) => cli::describe::run(&client(), &container).await,
+        Some(Command::Logs {
+            container,
+            tail,
+            persisted,
+        }) => {
+            let client = client();
+            print!("{}", client.logs(&container, tail, persisted).await?);
+            Ok(())
+        }
+        Some(Command::Endpoints { app }) => cli::endpoints::run(&client(), app.as_deref()).await,
+        Some(Command::Top {
+            app,
+            sort,
+            interval,
+            once,
+        }) => cli::top::run(&client(), app.as_deref(), sort, interval, once).await,
+        Some(Command::Dashboard) => cli::dashboard::run(client(), &cli.grpc).await,
+        Some(Command::Secret { command }) => cli::secret::run(&client(), command).await,
+        Some(Command::ConfigMap { command }) => cli::config_map::run(&client(), command).await,
+        Some(Command::Job { command }) => cli::job::run(&client(), command).await,
+        Some(Command::CronJob { command }) => cli::cron_job::run(&client(), command).await,
+        Some(Command::Autoscaler { command }) => cli::autoscaler::run(&client(), command).await,
+        Some(Command::Rollout { command }) => cli::rollout::run(&client(), command).await,
+        Some(Command::Ingress { command }) => cli::ingress::run(&client(), command).await,
+        Some(Command::Quota { command }) => cli::quota::run(&client(), command).await,
+        Some(Command::Backup { output }) => {
+            let config = Config::load(cli.config.as_deref())?;
+            cli::backup::backup(&config, &output).await
+        }
+        Some(Command::Restore { backup, replace }) => {
+            let config = Config::load(cli.config.as_deref())?;
+            cli::backup::restore(&config, &backup, replace).await
+        }
+        Some(Command::Volume { command }) => cli::volume::run(&client(), command).await,
+        Some(Command::Token { command }) => cli::token::run(&client(), command).await,
+        Some(Command::Audit { args }) => cli::audit::run(&client(), args).await,
+    }
+}
 
-    container_status_checker_task.await?;
-    Ok(())
+// With `local_node` the daemon also runs containers on its own docker, as the "local" node.
+async fn serve(cli: &Cli, config: Config, local_node: bool) -> Result<(), anyhow::Error> {
+    Nic8s::builder()
+        .config(config)
+        .api_addr(&cli.server)
+        .grpc_addr(&cli.grpc)
+        .local_node(local_node)
+        .build()
+        .run(nic8s::shutdown_signal())
+        .await
 }
@@ -0,0 +1,223 @@
+use std::{io::Write, ops::Range, path::Path};
+
+use anyhow::anyhow;
+use toml::{
+    de::{DeTable, DeValue},
+    Spanned, Value,
+};
+
+use super::{child, FieldPath, ManifestFile, Segment};
+
+// Edits change the text of the manifest in place: only the value at the path is rewritten, or a
+// line added for a missing one, so comments, blank lines, key order and quoting elsewhere stay as
+// written.
+impl ManifestFile {
+    // Where the entry named `name` in the `[[kind]]` tables is, like `containers[2]`. Only this
+    // file is searched, not the ones it includes.
+    pub fn find(&self, kind: &str, name: &str) -> Result<FieldPath, anyhow::Error> {
+        let root = DeTable::parse(&self.contents).map_err(|error| self.error(error))?;
+        let index = match root.get_ref().get(kind).map(|value| value.get_ref()) {
+            Some(DeValue::Array(entries)) => entries.iter().position(|entry| {
+                matches!(
+                    entry.get_ref().get("name").map(|name| name.get_ref()),
+                    Some(DeValue::String(entry)) if entry == name
+                )
+            }),
+            _ => None,
+        };
+        index
+            .map(|index| FieldPath::default().field(kind).index(index))
+            .ok_or_else(|| anyhow!("{}: no {} named {}", self.path.display(), kind, name))
+    }
+
+    // Replaces the value at `path`, or adds it to its table when it's missing.
+    pub fn set(&mut self, path: &FieldPath, value: &Value) -> Result<(), anyhow::Error> {
+        let (range, text) = {
+            let root = DeTable::parse(&self.contents).map_err(|error| self.error(error))?;
+            let span = root.span();
+            let root = Spanned::new(span, DeValue::Table(root.into_inner()));
+            let Some((last, parents)) = path.0.split_last() else {
+                return Err(anyhow!("can't replace the whole manifest"));
+            };
+
+            let not_found = || anyhow!("{}: {} not found", self.path.display(), path);
+            let mut table = &root;
+            for segment in parents {
+                table = child(table, segment).ok_or_else(not_found)?;
+            }
+            match (child(table, last), last) {
+                (Some(existing), _) => self.replacement(path, existing, value)?,
+                (None, Segment::Field(key)) => self.insertion(path, table, key, value)?,
+                (None, Segment::Index(_)) => return Err(not_found()),
+            }
+        };
+        self.contents.replace_range(range, &text);
+        Ok(())
+    }
+
+    // The text of the entry at `path`, like `containers[0]`: from its `[[containers]]` header to
+    // the end of the line of its last value, tables like `[containers.resources]` included. An
+    // entry written inline is just its braces.
+    pub fn section(&self, path: &FieldPath) -> Result<Range<usize>, anyhow::Error> {
+        let root = DeTable::parse(&self.contents).map_err(|error| self.error(error))?;
+        let span = root.span();
+        let root = Spanned::new(span, DeValue::Table(root.into_inner()));
+        let mut value = &root;
+        for segment in path.0.iter() {
+            value = child(value, segment)
+                .ok_or_else(|| anyhow!("{}: {} not found", self.path.display(), path))?;
+        }
+        if !self.is_header(value) {
+            return Ok(value.span());
+        }
+
+        let start = value.span().start;
+        let end = self.last(value).unwrap_or(value.span().end);
+        let end = self.contents[end..]
+            .find('\n')
+            .map_or(self.contents.len(), |newline| end + newline + 1);
+        Ok(start..end)
+    }
+
+    pub fn write(&self) -> Result<(), anyhow::Error> {
+        // A fresh file next to the manifest, with its permissions, renamed over it once written.
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        tmp.write_all(self.contents.as_bytes())?;
+        tmp.as_file()
+            .set_permissions(std::fs::metadata(&self.path)?.permissions())?;
+        tmp.as_file().sync_all()?;
+        tmp.persist(&self.path)?;
+        Ok(())
+    }
+
+    fn replacement(
+        &self,
+        path: &FieldPath,
+        existing: &Spanned<DeValue>,
+        value: &Value,
+    ) -> Result<(Range<usize>, String), anyhow::Error> {
+        if self.is_header(existing) {
+            return Err(anyhow!(
+                "{}: {} is a table; set the values in it instead",
+                self.location(existing.span()),
+                path
+            ));
+        }
+        let written = &self.contents[existing.span()];
+        let text = match value {
+            // Strings written with single quotes keep them when the new one can have them.
+            Value::String(string)
+                if written.starts_with('\'')
+                    && !written.starts_with("'''")
+                    && !string.contains(['\'', '\n']) =>
+            {
+                format!("'{}'", string)
+            }
+            value => value.to_string(),
+        };
+        Ok((existing.span(), text))
+    }
+
+    // The key goes on a line of its own after the table's last one, so it lands in the table
+    // rather than in a table that follows it, or inside the braces of an inline table.
+    fn insertion(
+        &self,
+        path: &FieldPath,
+        table: &Spanned<DeValue>,
+        key: &str,
+        value: &Value,
+    ) -> Result<(Range<usize>, String), anyhow::Error> {
+        let DeValue::Table(entries) = table.get_ref() else {
+            return Err(anyhow!("{}: {} not found", self.path.display(), path));
+        };
+        let entry = format!("{} = {}", self::key(key), value);
+
+        let span = table.span();
+        if self.contents[span.clone()].starts_with('{') {
+            if entries.is_empty() {
+                return Ok((span, format!("{{ {} }}", entry)));
+            }
+            let end = span.start + self.contents[span.start..span.end - 1].trim_end().len();
+            return Ok((end..end, format!(", {}", entry)));
+        }
+        if !span.is_empty() && !self.is_header(table) {
+            return Err(anyhow!(
+                "{}: can't add {} to a table written with dotted keys",
+                self.location(span),
+                path
+            ));
+        }
+
+        // A table with no keys yet takes it on the line after its header; the top-level one, on
+        // the first line of the file.
+        let end = entries
+            .values()
+            .filter_map(|value| self.end(value))
+            .max()
+            .or((!span.is_empty()).then_some(span.end));
+        let at = match end {
+            Some(end) => self.contents[end..]
+                .find('\n')
+                .map_or(self.contents.len(), |newline| end + newline + 1),
+            None => 0,
+        };
+        if at == self.contents.len() && !self.contents.is_empty() && !self.contents.ends_with('\n')
+        {
+            return Ok((at..at, format!("\n{}\n", entry)));
+        }
+        Ok((at..at, format!("{}\n", entry)))
+    }
+
+    // Where the value stops being written, as far as it belongs to its table's own lines: tables
+    // with a header of their own are written elsewhere.
+    fn end(&self, value: &Spanned<DeValue>) -> Option<usize> {
+        if self.is_header(value) {
+            return None;
+        }
+        match value.get_ref() {
+            DeValue::Table(entries) if !self.contents[value.span()].starts_with('{') => {
+                entries.values().filter_map(|value| self.end(value)).max()
+            }
+            _ => Some(value.span().end),
+        }
+    }
+
+    // Where the last value in the table is written, wherever its sub-tables are.
+    fn last(&self, value: &Spanned<DeValue>) -> Option<usize> {
+        match value.get_ref() {
+            DeValue::Table(entries) if !self.contents[value.span()].starts_with('{') => {
+                entries.values().filter_map(|value| self.last(value)).max()
+            }
+            DeValue::Array(entries) if self.is_header(value) => {
+                entries.iter().filter_map(|value| self.last(value)).max()
+            }
+            _ => Some(value.span().end),
+        }
+    }
+
+    // `[table]` and `[[array]]` headers start their line; arrays written inline follow a `=`.
+    fn is_header(&self, value: &Spanned<DeValue>) -> bool {
+        let start = value.span().start;
+        let line_start = self.contents[..start]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        self.contents[start..].starts_with('[')
+            && self.contents[line_start..start].trim().is_empty()
+    }
+}
+
+fn key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if bare {
+        String::from(key)
+    } else {
+        Value::String(String::from(key)).to_string()
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+
+use crate::entities::container::{dependency_order, DependencyError};
+
+use super::{report, FieldPath, LoadOptions, Location, Manifest, ManifestFile};
+
+pub const INCLUDE_DIR: &str = "nic8s.d";
+
+pub fn load(path: &Path, options: &LoadOptions) -> Result<Manifest, anyhow::Error> {
+    let mut loader = Loader {
+        options,
+        files: Vec::new(),
+        loaded: HashSet::new(),
+    };
+    loader.load(path, None, &mut Vec::new())?;
+    loader.merge(path)
+}
+
+struct Loader<'a> {
+    options: &'a LoadOptions,
+    files: Vec<(ManifestFile, Manifest)>,
+    loaded: HashSet<PathBuf>,
+}
+
+impl Loader<'_> {
+    // `stack` holds the files currently being loaded, to catch include cycles. A file reached
+    // twice through different includes is only loaded once.
+    fn load(
+        &mut self,
+        path: &Path,
+        included_at: Option<Location>,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<(), anyhow::Error> {
+        let at = |message: String| match &included_at {
+            Some(location) => anyhow!("{}: {}", location, message),
+            None => anyhow!(message),
+        };
+
+        let canonical = path
+            .canonicalize()
+            .map_err(|error| at(format!("failed to read {}: {}", path.display(), error)))?;
+        if let Some(start) = stack.iter().position(|loading| *loading == canonical) {
+            let cycle: Vec<String> = stack[start..]
+                .iter()
+                .chain([&canonical])
+                .map(|path| path.display().to_string())
+                .collect();
+            return Err(at(format!("include cycle: {}", cycle.join(" -> "))));
+        }
+        if !self.loaded.insert(canonical.clone()) {
+            return Ok(());
+        }
+
+        let file = ManifestFile::read(path)?;
+        let (manifest, includes) = file.parse(self.options)?;
+        file.validate(&manifest)?;
+
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let mut paths = Vec::new();
+        for include in includes {
+            let location = file.location(include.span());
+            let target = dir.join(include.get_ref());
+            if target.is_dir() {
+                paths.extend(
+                    toml_files(&target)?
+                        .into_iter()
+                        .map(|path| (path, Some(location.clone()))),
+                );
+            } else {
+                paths.push((target, Some(location)));
+            }
+        }
+        if stack.is_empty() {
+            let conventional = dir.join(INCLUDE_DIR);
+            if conventional.is_dir() {
+                paths.extend(
+                    toml_files(&conventional)?
+                        .into_iter()
+                        .map(|path| (path, None)),
+                );
+            }
+        }
+
+        self.files.push((file, manifest));
+        stack.push(canonical);
+        for (path, included_at) in paths {
+            self.load(&path, included_at, stack)?;
+        }
+        stack.pop();
+        Ok(())
+    }
+
+    // Objects of the same kind and name may only be defined once across all files. Containers
+    // may depend on ones in other files, so their dependencies are only followed once merged;
+    // those on containers outside the manifest are checked against the cluster when it's applied.
+    fn merge(self, root: &Path) -> Result<Manifest, anyhow::Error> {
+        let mut merged = Manifest::default();
+        let mut defined: HashMap<(&str, String), Location> = HashMap::new();
+        let mut depends_on: HashMap<String, Location> = HashMap::new();
+        let mut problems = Vec::new();
+
+        let mut define = |file: &ManifestFile, field: &'static str, index: usize, name: &str| {
+            let location = file.locate(&FieldPath::default().field(field).index(index));
+            match defined.entry((field, String::from(name))) {
+                Entry::Occupied(entry) => {
+                    let kind = field.trim_end_matches('s').replace('_', " ");
+                    problems.push((
+                        location,
+                        format!("{} {} is already defined at {}", kind, name, entry.get()),
+                    ));
+                    false
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(location);
+                    true
+                }
+            }
+        };
+
+        for (file, manifest) in self.files {
+            for (index, spec) in manifest.containers.into_iter().enumerate() {
+                if define(&file, "containers", index, &spec.name) {
+                    if !spec.depends_on.is_empty() {
+                        let path = FieldPath::default()
+                            .field("containers")
+                            .index(index)
+                            .field("depends_on");
+                        depends_on.insert(spec.name.clone(), file.locate(&path));
+                    }
+                    merged.containers.push(spec);
+                }
+            }
+            for (index, spec) in manifest.jobs.into_iter().enumerate() {
+                if define(&file, "jobs", index, &spec.template.name) {
+                    merged.jobs.push(spec);
+                }
+            }
+            for (index, spec) in manifest.cron_jobs.into_iter().enumerate() {
+                if define(&file, "cron_jobs", index, &spec.job.template.name) {
+                    merged.cron_jobs.push(spec);
+                }
+            }
+        }
+
+        // Containers in a cycle would each wait for the next to start, forever.
+        if let Err(DependencyError::Cycle(cycle)) = dependency_order(&merged.containers, &|_| true)
+        {
+            if let Some(location) = depends_on.remove(&cycle[0]) {
+                problems.push((location, DependencyError::Cycle(cycle).to_string()));
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(report(root, problems));
+        }
+        Ok(merged)
+    }
+}
+
+fn toml_files(dir: &Path) -> Result<Vec<PathBuf>, anyhow::Error> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|error| anyhow!("failed to read {}: {}", dir.display(), error))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
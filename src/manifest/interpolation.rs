@@ -0,0 +1,126 @@
+use std::{borrow::Cow, ops::Range};
+
+use toml::{
+    de::{DeTable, DeValue},
+    Spanned,
+};
+
+pub struct Unset {
+    pub span: Range<usize>,
+    pub variable: String,
+}
+
+pub struct Interpolated {
+    // Variables that were neither set nor given a default and expanded to an empty string.
+    pub unset: Vec<Unset>,
+    pub errors: Vec<(Range<usize>, String)>,
+}
+
+// Expands `${VAR}` and `${VAR:-default}` in every string value of the document, leaving keys
+// alone. `$$` is a literal `$`, so `$${VAR}` stays as written.
+pub fn interpolate(table: &mut DeTable, lookup: &dyn Fn(&str) -> Option<String>) -> Interpolated {
+    let mut interpolated = Interpolated {
+        unset: Vec::new(),
+        errors: Vec::new(),
+    };
+    for (_, value) in table.iter_mut() {
+        interpolate_value(value, lookup, &mut interpolated);
+    }
+    interpolated
+}
+
+fn interpolate_value(
+    value: &mut Spanned<DeValue>,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    interpolated: &mut Interpolated,
+) {
+    let span = value.span();
+    match value.get_mut() {
+        DeValue::String(string) => match expand(string, lookup) {
+            Ok((expanded, unset)) => {
+                interpolated
+                    .unset
+                    .extend(unset.into_iter().map(|variable| Unset {
+                        span: span.clone(),
+                        variable,
+                    }));
+                *string = Cow::Owned(expanded);
+            }
+            Err(error) => interpolated.errors.push((span, error)),
+        },
+        DeValue::Array(array) => {
+            for value in array.iter_mut() {
+                interpolate_value(value, lookup, interpolated);
+            }
+        }
+        DeValue::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                interpolate_value(value, lookup, interpolated);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn expand(
+    input: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<(String, Vec<String>), String> {
+    let mut output = String::with_capacity(input.len());
+    let mut unset = Vec::new();
+    let mut rest = input;
+
+    while let Some(dollar) = rest.find('$') {
+        output.push_str(&rest[..dollar]);
+        rest = &rest[dollar + 1..];
+
+        if let Some(after) = rest.strip_prefix('$') {
+            output.push('$');
+            rest = after;
+            continue;
+        }
+        let Some(after) = rest.strip_prefix('{') else {
+            output.push('$');
+            continue;
+        };
+
+        let close = after
+            .find('}')
+            .ok_or_else(|| format!("unterminated variable in {:?}", input))?;
+        let expression = &after[..close];
+        rest = &after[close + 1..];
+
+        let (variable, default) = match expression.split_once(":-") {
+            Some((variable, default)) => (variable, Some(default)),
+            None => (expression, None),
+        };
+        if !is_variable_name(variable) {
+            return Err(format!(
+                "invalid variable {:?} in {:?}, use ${{NAME}} or ${{NAME:-default}}",
+                expression, input
+            ));
+        }
+
+        // Like the shell, an empty variable also falls back to the default.
+        match (lookup(variable).filter(|value| !value.is_empty()), default) {
+            (Some(value), _) => output.push_str(&value),
+            (None, Some(default)) => output.push_str(default),
+            (None, None) => {
+                if lookup(variable).is_none() {
+                    unset.push(String::from(variable));
+                }
+            }
+        }
+    }
+    output.push_str(rest);
+
+    Ok((output, unset))
+}
+
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
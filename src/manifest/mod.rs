@@ -0,0 +1,258 @@
+pub mod edit;
+pub mod include;
+pub mod interpolation;
+pub mod plan;
+pub mod template;
+pub mod validation;
+
+use std::{
+    fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use toml::{
+    de::{DeTable, DeValue},
+    Spanned,
+};
+
+use crate::entities::{container::ContainerSpec, cron_job::CronJobSpec, job::JobSpec};
+
+// Everything `nic8s apply` creates, as TOML with a `[[containers]]`, `[[jobs]]` or
+// `[[cron_jobs]]` table per object. A manifest can pull in other files with a top-level
+// `include = ["web.toml", "services/"]`, and the one passed to apply also loads `nic8s.d/*.toml`
+// next to it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Manifest {
+    pub containers: Vec<ContainerSpec>,
+    pub jobs: Vec<JobSpec>,
+    pub cron_jobs: Vec<CronJobSpec>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LoadOptions {
+    // Fail on `${VAR}` references to unset variables instead of expanding them to "".
+    pub strict: bool,
+    // What `{{ value }}`, `when` and `for_each` are rendered with.
+    pub values: template::Values,
+}
+
+impl Manifest {
+    // Parses and validates the file and everything it includes, reporting every problem against
+    // its place in the file it came from.
+    pub fn load(path: &Path, options: &LoadOptions) -> Result<Manifest, anyhow::Error> {
+        include::load(path, options)
+    }
+}
+
+pub struct ManifestFile {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+impl ManifestFile {
+    pub fn read(path: &Path) -> Result<Self, anyhow::Error> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| anyhow!("failed to read {}: {}", path.display(), error))?;
+        Ok(ManifestFile {
+            path: path.to_path_buf(),
+            contents,
+        })
+    }
+
+    // Templates are rendered and variables expanded in the parsed values, so spans still point at
+    // the text as written. Returns the manifest and the paths it includes, as written.
+    pub fn parse(
+        &self,
+        options: &LoadOptions,
+    ) -> Result<(Manifest, Vec<Spanned<String>>), anyhow::Error> {
+        let mut root = DeTable::parse(&self.contents).map_err(|error| self.error(error))?;
+
+        let rendered = template::render(root.get_mut(), &options.values);
+        let interpolated =
+            interpolation::interpolate(root.get_mut(), &|variable| std::env::var(variable).ok());
+        let mut problems: Vec<(Location, String)> = rendered
+            .into_iter()
+            .chain(interpolated.errors)
+            .map(|(span, message)| (self.location(span), message))
+            .collect();
+        for unset in interpolated.unset {
+            let location = self.location(unset.span);
+            if options.strict {
+                problems.push((location, format!("variable {} is not set", unset.variable)));
+            } else {
+                eprintln!(
+                    "warning: {}: variable {} is not set, using an empty string",
+                    location, unset.variable
+                );
+            }
+        }
+        if !problems.is_empty() {
+            problems.sort_by_key(|(location, _)| (location.line, location.column));
+            return Err(report(&self.path, problems));
+        }
+
+        let includes = match root.get_mut().remove("include") {
+            Some(include) => self.includes(include)?,
+            None => Vec::new(),
+        };
+        let manifest = Manifest::deserialize(toml::de::Deserializer::from(root))
+            .map_err(|error| self.error(error))?;
+        Ok((manifest, includes))
+    }
+
+    pub fn validate(&self, manifest: &Manifest) -> Result<(), anyhow::Error> {
+        let violations = validation::validate(manifest);
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        Err(report(
+            &self.path,
+            violations
+                .iter()
+                .map(|violation| {
+                    let message = format!("{}: {}", violation.path, violation.message);
+                    (self.locate(&violation.path), message)
+                })
+                .collect(),
+        ))
+    }
+
+    fn includes(&self, include: Spanned<DeValue>) -> Result<Vec<Spanned<String>>, anyhow::Error> {
+        let span = include.span();
+        let invalid = || {
+            anyhow!(
+                "{}: include must be an array of file or directory paths",
+                self.location(span.clone())
+            )
+        };
+
+        let DeValue::Array(paths) = include.into_inner() else {
+            return Err(invalid());
+        };
+        paths
+            .into_iter()
+            .map(|path| {
+                let span = path.span();
+                match path.into_inner() {
+                    DeValue::String(path) => Ok(Spanned::new(span, path.into_owned())),
+                    _ => Err(invalid()),
+                }
+            })
+            .collect()
+    }
+
+    // The value at `path`, or the closest enclosing table when the value is missing.
+    pub fn locate(&self, path: &FieldPath) -> Location {
+        let Ok(root) = DeTable::parse(&self.contents) else {
+            return self.location(0..0);
+        };
+        let span = root.span();
+        let root = Spanned::new(span, DeValue::Table(root.into_inner()));
+
+        let mut value = &root;
+        for segment in path.0.iter() {
+            match child(value, segment) {
+                Some(next) => value = next,
+                None => break,
+            }
+        }
+        self.location(value.span())
+    }
+
+    fn error(&self, error: toml::de::Error) -> anyhow::Error {
+        let message = error.message().trim_end();
+        match error.span() {
+            Some(span) => anyhow!("{}: {}", self.location(span), message),
+            None => anyhow!("{}: {}", self.path.display(), message),
+        }
+    }
+
+    pub fn location(&self, span: Range<usize>) -> Location {
+        let before = &self.contents[..span.start.min(self.contents.len())];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+
+        Location {
+            file: self.path.clone(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+fn child<'a>(
+    value: &'a Spanned<DeValue<'a>>,
+    segment: &Segment,
+) -> Option<&'a Spanned<DeValue<'a>>> {
+    match segment {
+        Segment::Field(name) => value.get_ref().get(name.as_str()),
+        Segment::Index(index) => value.get_ref().get(*index),
+    }
+}
+
+fn report(path: &Path, problems: Vec<(Location, String)>) -> anyhow::Error {
+    let mut report = String::new();
+    for (location, message) in problems.iter() {
+        report.push_str(&format!("{}: {}\n", location, message));
+    }
+    anyhow!(
+        "{}{} error(s) in {}",
+        report,
+        problems.len(),
+        path.display()
+    )
+}
+
+#[derive(Clone, Debug)]
+pub struct Location {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file.display(), self.line, self.column)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+// Where a value sits in the manifest, like `containers[0].ports`.
+#[derive(Clone, Debug, Default)]
+pub struct FieldPath(Vec<Segment>);
+
+impl FieldPath {
+    pub fn field(&self, name: &str) -> FieldPath {
+        let mut path = self.clone();
+        path.0.push(Segment::Field(String::from(name)));
+        path
+    }
+
+    pub fn index(&self, index: usize) -> FieldPath {
+        let mut path = self.clone();
+        path.0.push(Segment::Index(index));
+        path
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (position, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Field(name) if position == 0 => write!(f, "{}", name)?,
+                Segment::Field(name) => write!(f, ".{}", name)?,
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
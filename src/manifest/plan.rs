@@ -0,0 +1,250 @@
+use std::{collections::HashSet, fmt};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::entities::{
+    container::{dependency_order, Container, ContainerSpec, DependencyError},
+    cron_job::{CronJob, CronJobSpec},
+    job::{Job, JobSpec},
+};
+
+use super::Manifest;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Container,
+    Job,
+    CronJob,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Container => write!(f, "container"),
+            Kind::Job => write!(f, "job"),
+            Kind::CronJob => write!(f, "cronjob"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Create,
+    Update,
+    Unchanged,
+    // Jobs and cron jobs can't be updated; they have to be deleted for changes to apply.
+    Immutable,
+    Delete,
+}
+
+pub enum Desired {
+    Container(ContainerSpec),
+    Job(JobSpec),
+    CronJob(CronJobSpec),
+}
+
+pub struct FieldChange {
+    pub path: String,
+    pub live: Option<Value>,
+    pub desired: Option<Value>,
+}
+
+pub struct Change {
+    pub kind: Kind,
+    pub name: String,
+    pub action: Action,
+    pub fields: Vec<FieldChange>,
+    // None for deletions.
+    pub desired: Option<Desired>,
+}
+
+pub struct LiveState {
+    pub containers: Vec<Container>,
+    pub jobs: Vec<Job>,
+    pub cron_jobs: Vec<CronJob>,
+}
+
+// Fails when a container depends on one that is neither in the manifest nor in the cluster, or
+// only in the cluster while `prune` would delete it.
+pub fn check_dependencies(
+    manifest: &Manifest,
+    live: &LiveState,
+    prune: bool,
+) -> Result<(), DependencyError> {
+    let live: HashSet<&str> = live
+        .containers
+        .iter()
+        .map(|container| container.name.as_str())
+        .collect();
+    dependency_order(&manifest.containers, &|name| !prune && live.contains(name))?;
+    Ok(())
+}
+
+// Compares the manifest against what is running, with containers after the ones they depend
+// on. With `prune`, objects missing from the manifest are deleted; jobs started by a cron job
+// belong to it and are left alone.
+pub fn plan(manifest: &Manifest, live: &LiveState, prune: bool) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    // Loading rejects cycles and `check_dependencies` missing containers, so the order only falls
+    // back to the manifest's without them.
+    let containers = dependency_order(&manifest.containers, &|_| true)
+        .unwrap_or_else(|_| manifest.containers.iter().collect());
+    for spec in containers {
+        let existing = live
+            .containers
+            .iter()
+            .find(|container| container.name == spec.name);
+        changes.push(compare(
+            Kind::Container,
+            &spec.name,
+            existing.map(|container| &container.spec),
+            spec,
+            Action::Update,
+            Desired::Container(spec.clone()),
+        ));
+    }
+    for spec in manifest.jobs.iter() {
+        let name = &spec.template.name;
+        let existing = live.jobs.iter().find(|job| job.name() == name);
+        changes.push(compare(
+            Kind::Job,
+            name,
+            existing.map(|job| &job.spec),
+            spec,
+            Action::Immutable,
+            Desired::Job(spec.clone()),
+        ));
+    }
+    for spec in manifest.cron_jobs.iter() {
+        let name = &spec.job.template.name;
+        let existing = live
+            .cron_jobs
+            .iter()
+            .find(|cron_job| cron_job.name() == name);
+        changes.push(compare(
+            Kind::CronJob,
+            name,
+            existing.map(|cron_job| &cron_job.spec),
+            spec,
+            Action::Immutable,
+            Desired::CronJob(spec.clone()),
+        ));
+    }
+
+    if prune {
+        let desired: HashSet<(Kind, &str)> = changes
+            .iter()
+            .map(|change| (change.kind, change.name.as_str()))
+            .collect();
+        let owned: HashSet<&str> = live
+            .cron_jobs
+            .iter()
+            .flat_map(|cron_job| {
+                cron_job
+                    .status
+                    .active
+                    .iter()
+                    .chain(&cron_job.status.history)
+            })
+            .map(|run| run.job.as_str())
+            .collect();
+
+        let live_names = live
+            .containers
+            .iter()
+            .map(|container| (Kind::Container, container.name.as_str()))
+            .chain(
+                live.jobs
+                    .iter()
+                    .filter(|job| !owned.contains(job.name()))
+                    .map(|job| (Kind::Job, job.name())),
+            )
+            .chain(
+                live.cron_jobs
+                    .iter()
+                    .map(|cron_job| (Kind::CronJob, cron_job.name())),
+            );
+        let deletions: Vec<Change> = live_names
+            .filter(|object| !desired.contains(object))
+            .map(|(kind, name)| Change {
+                kind,
+                name: String::from(name),
+                action: Action::Delete,
+                fields: Vec::new(),
+                desired: None,
+            })
+            .collect();
+        changes.extend(deletions);
+    }
+
+    changes
+}
+
+fn compare<T: Serialize + PartialEq>(
+    kind: Kind,
+    name: &str,
+    live: Option<&T>,
+    desired: &T,
+    changed: Action,
+    object: Desired,
+) -> Change {
+    let (action, fields) = match live {
+        None => (Action::Create, Vec::new()),
+        Some(live) if live == desired => (Action::Unchanged, Vec::new()),
+        Some(live) => (changed, diff(live, desired)),
+    };
+
+    Change {
+        kind,
+        name: String::from(name),
+        action,
+        fields,
+        desired: Some(object),
+    }
+}
+
+// Field-level differences between two specs, keyed by dotted path like `resources.cpus`.
+pub fn diff<T: Serialize>(live: &T, desired: &T) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_values(
+        "",
+        serde_json::to_value(live).ok().as_ref(),
+        serde_json::to_value(desired).ok().as_ref(),
+        &mut changes,
+    );
+    changes
+}
+
+fn diff_values(
+    path: &str,
+    live: Option<&Value>,
+    desired: Option<&Value>,
+    changes: &mut Vec<FieldChange>,
+) {
+    if live == desired {
+        return;
+    }
+
+    if let (Some(Value::Object(live)), Some(Value::Object(desired))) = (live, desired) {
+        let mut keys: Vec<&String> = live.keys().chain(desired.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            let path = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            diff_values(&path, live.get(key), desired.get(key), changes);
+        }
+        return;
+    }
+
+    changes.push(FieldChange {
+        path: String::from(path),
+        live: live.cloned(),
+        desired: desired.cloned(),
+    });
+}
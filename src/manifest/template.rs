@@ -0,0 +1,392 @@
+use std::{
+    borrow::Cow,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use toml::{
+    de::{DeArray, DeTable, DeValue},
+    Spanned, Table, Value,
+};
+
+// Picked up next to the manifest passed to apply when no `--values` file is given.
+pub const VALUES_FILE: &str = "values.toml";
+
+// Settings a manifest is rendered with, so one manifest can serve dev, staging and prod.
+#[derive(Clone, Debug, Default)]
+pub struct Values(Table);
+
+impl Values {
+    // Later files override earlier ones key by key, and `--set key=value` overrides them all.
+    pub fn load(
+        manifest: &Path,
+        files: &[PathBuf],
+        sets: &[String],
+    ) -> Result<Values, anyhow::Error> {
+        let mut files = files.to_vec();
+        if files.is_empty() {
+            let conventional = manifest
+                .parent()
+                .unwrap_or(Path::new("."))
+                .join(VALUES_FILE);
+            if conventional.is_file() {
+                files.push(conventional);
+            }
+        }
+
+        let mut values = Values::default();
+        for file in files.iter() {
+            let contents = std::fs::read_to_string(file)
+                .map_err(|error| anyhow!("failed to read {}: {}", file.display(), error))?;
+            let table: Table = toml::from_str(&contents)
+                .map_err(|error| anyhow!("{}: {}", file.display(), error.message().trim_end()))?;
+            merge(&mut values.0, table);
+        }
+        for set in sets.iter() {
+            values.set(set)?;
+        }
+        Ok(values)
+    }
+
+    // `image.tag=1.2`, where the value is a bool or integer only when it is written exactly as one,
+    // and a string otherwise: `1.10` stays `1.10` rather than becoming the float 1.1.
+    pub fn set(&mut self, assignment: &str) -> Result<(), anyhow::Error> {
+        let (key, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid --set {:?}, use key=value", assignment))?;
+        let path: Vec<&str> = key.trim().split('.').collect();
+        if path.iter().any(|segment| !is_name(segment)) {
+            return Err(anyhow!("invalid --set key {:?}", key));
+        }
+        let value = match value {
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            value => match value.parse::<i64>() {
+                Ok(integer) if integer.to_string() == value => Value::Integer(integer),
+                _ => Value::String(String::from(value)),
+            },
+        };
+
+        let (last, parents) = path.split_last().expect("split always yields a segment");
+        let mut table = &mut self.0;
+        for segment in parents {
+            let entry = table
+                .entry(String::from(*segment))
+                .or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            table = entry.as_table_mut().expect("just made a table");
+        }
+        table.insert(String::from(*last), value);
+        Ok(())
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        let mut segments = path.split('.');
+        let mut value = self.0.get(segments.next()?)?;
+        for segment in segments {
+            value = value.as_table()?.get(segment)?;
+        }
+        Some(value)
+    }
+}
+
+fn merge(into: &mut Table, from: Table) {
+    for (key, value) in from {
+        match (into.get_mut(&key), value) {
+            (Some(Value::Table(into)), Value::Table(from)) => merge(into, from),
+            (_, value) => {
+                into.insert(key, value);
+            }
+        }
+    }
+}
+
+// Renders the manifest against `values`:
+//
+// - `{{ image.tag }}` in a string value is replaced with the value at that path;
+// - a table in an array, like `[[containers]]`, with `when = "env == 'prod'"` is dropped unless
+//   the condition holds. A bare `when = "debug"` checks the value is set and truthy, `!debug`
+//   the opposite;
+// - `for_each = "port in ports"` or `for_each = "i in 1..=replicas"` repeats the table once per
+//   item, with the item available as `{{ port }}` or `{{ i }}`.
+//
+// Returns the problems found, against the span of the value they were found in.
+pub fn render(table: &mut DeTable, values: &Values) -> Vec<(Range<usize>, String)> {
+    let mut renderer = Renderer {
+        values,
+        bindings: Vec::new(),
+        errors: Vec::new(),
+    };
+    for (_, value) in table.iter_mut() {
+        renderer.value(value);
+    }
+    renderer.errors
+}
+
+struct Renderer<'v> {
+    values: &'v Values,
+    // Loop variables, innermost last.
+    bindings: Vec<(String, Value)>,
+    errors: Vec<(Range<usize>, String)>,
+}
+
+impl Renderer<'_> {
+    fn value(&mut self, value: &mut Spanned<DeValue>) {
+        let span = value.span();
+        match value.get_mut() {
+            DeValue::String(string) => match self.expand(string) {
+                Ok(expanded) => *string = Cow::Owned(expanded),
+                Err(error) => self.errors.push((span, error)),
+            },
+            DeValue::Array(array) => {
+                let items = std::mem::replace(array, DeArray::new());
+                *array = self.array(items);
+            }
+            DeValue::Table(table) => {
+                for (_, value) in table.iter_mut() {
+                    self.value(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn array<'i>(&mut self, items: DeArray<'i>) -> DeArray<'i> {
+        let mut rendered = DeArray::new();
+        for mut item in items {
+            let DeValue::Table(table) = item.get_mut() else {
+                self.value(&mut item);
+                rendered.push(item);
+                continue;
+            };
+            let for_each = table.remove("for_each");
+            let when = table.remove("when");
+
+            let Some(for_each) = for_each else {
+                if self.when(when.as_ref()) {
+                    self.value(&mut item);
+                    rendered.push(item);
+                }
+                continue;
+            };
+            let parsed =
+                directive(&for_each, "for_each").and_then(|expression| self.for_each(expression));
+            let (variable, items) = match parsed {
+                Ok(parsed) => parsed,
+                Err(error) => {
+                    self.errors.push((for_each.span(), error));
+                    continue;
+                }
+            };
+            for value in items {
+                self.bindings.push((variable.clone(), value));
+                if self.when(when.as_ref()) {
+                    let mut copy = item.clone();
+                    self.value(&mut copy);
+                    rendered.push(copy);
+                }
+                self.bindings.pop();
+            }
+        }
+        rendered
+    }
+
+    fn when(&mut self, when: Option<&Spanned<DeValue>>) -> bool {
+        let Some(when) = when else {
+            return true;
+        };
+        let result = directive(when, "when").and_then(|condition| self.condition(condition));
+        match result {
+            Ok(holds) => holds,
+            Err(error) => {
+                self.errors.push((when.span(), error));
+                false
+            }
+        }
+    }
+
+    fn condition(&self, condition: &str) -> Result<bool, String> {
+        for (operator, equal) in [("==", true), ("!=", false)] {
+            if let Some((left, right)) = condition.split_once(operator) {
+                let left = self.operand(left)?;
+                let right = self.operand(right)?;
+                return Ok((left == right) == equal);
+            }
+        }
+
+        let condition = condition.trim();
+        let (path, negated) = match condition.strip_prefix('!') {
+            Some(path) => (path.trim(), true),
+            None => (condition, false),
+        };
+        if !is_path(path) {
+            return Err(format!(
+                "invalid condition {:?}, use NAME, !NAME, NAME == VALUE or NAME != VALUE",
+                condition
+            ));
+        }
+        Ok(self.lookup(path).is_some_and(truthy) != negated)
+    }
+
+    // Quoted strings, numbers and booleans are literals, anything else is looked up.
+    fn operand(&self, operand: &str) -> Result<String, String> {
+        let operand = operand.trim();
+        for quote in ['"', '\''] {
+            if let Some(literal) = operand
+                .strip_prefix(quote)
+                .and_then(|rest| rest.strip_suffix(quote))
+            {
+                return Ok(String::from(literal));
+            }
+        }
+        if operand.parse::<f64>().is_ok() || operand == "true" || operand == "false" {
+            return Ok(String::from(operand));
+        }
+        if !is_path(operand) {
+            return Err(format!("invalid operand {:?}", operand));
+        }
+        let value = self
+            .lookup(operand)
+            .ok_or_else(|| format!("value {} is not set", operand))?;
+        scalar(operand, value)
+    }
+
+    fn for_each(&self, expression: &str) -> Result<(String, Vec<Value>), String> {
+        let invalid = || {
+            format!(
+                "invalid for_each {:?}, use NAME in LIST or NAME in START..END",
+                expression
+            )
+        };
+        let (variable, source) = expression.split_once(" in ").ok_or_else(invalid)?;
+        let variable = variable.trim();
+        if !is_name(variable) {
+            return Err(invalid());
+        }
+        let source = source.trim();
+
+        if let Some((start, end)) = source.split_once("..") {
+            let (end, inclusive) = match end.strip_prefix('=') {
+                Some(end) => (end, true),
+                None => (end, false),
+            };
+            let start = self.bound(start)?;
+            let end = self.bound(end)?;
+            let items = if inclusive {
+                (start..=end).map(Value::Integer).collect()
+            } else {
+                (start..end).map(Value::Integer).collect()
+            };
+            return Ok((String::from(variable), items));
+        }
+
+        if !is_path(source) {
+            return Err(invalid());
+        }
+        match self.lookup(source) {
+            Some(Value::Array(items)) => Ok((String::from(variable), items.clone())),
+            Some(_) => Err(format!("value {} is not a list", source)),
+            None => Err(format!("value {} is not set", source)),
+        }
+    }
+
+    fn bound(&self, bound: &str) -> Result<i64, String> {
+        let bound = bound.trim();
+        if let Ok(number) = bound.parse() {
+            return Ok(number);
+        }
+        match self.lookup(bound) {
+            Some(Value::Integer(number)) => Ok(*number),
+            Some(_) => Err(format!("value {} is not an integer", bound)),
+            None => Err(format!("value {} is not set", bound)),
+        }
+    }
+
+    fn expand(&self, input: &str) -> Result<String, String> {
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input;
+
+        while let Some(open) = rest.find("{{") {
+            output.push_str(&rest[..open]);
+            let after = &rest[open + 2..];
+            let close = after
+                .find("}}")
+                .ok_or_else(|| format!("unterminated {{{{ in {:?}", input))?;
+            let path = after[..close].trim();
+            rest = &after[close + 2..];
+
+            if !is_path(path) {
+                return Err(format!("invalid value reference {:?} in {:?}", path, input));
+            }
+            let value = self
+                .lookup(path)
+                .ok_or_else(|| format!("value {} is not set", path))?;
+            output.push_str(&scalar(path, value)?);
+        }
+        output.push_str(rest);
+
+        Ok(output)
+    }
+
+    fn lookup(&self, path: &str) -> Option<&Value> {
+        let (first, rest) = match path.split_once('.') {
+            Some((first, rest)) => (first, Some(rest)),
+            None => (path, None),
+        };
+        let Some((_, bound)) = self.bindings.iter().rev().find(|(name, _)| name == first) else {
+            return self.values.get(path);
+        };
+
+        let mut value = bound;
+        for segment in rest.into_iter().flat_map(|rest| rest.split('.')) {
+            value = value.as_table()?.get(segment)?;
+        }
+        Some(value)
+    }
+}
+
+fn directive<'a>(value: &'a Spanned<DeValue>, key: &str) -> Result<&'a str, String> {
+    value
+        .get_ref()
+        .as_str()
+        .ok_or_else(|| format!("{} must be a string", key))
+}
+
+fn scalar(path: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(string) => Ok(string.clone()),
+        Value::Array(_) | Value::Table(_) => Err(format!(
+            "value {} is a {}, not a string or number",
+            path,
+            value.type_str()
+        )),
+        value => Ok(value.to_string()),
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::String(string) => !string.is_empty() && string != "false",
+        Value::Integer(number) => *number != 0,
+        Value::Float(number) => *number != 0.0,
+        Value::Boolean(boolean) => *boolean,
+        Value::Datetime(_) => true,
+        Value::Array(items) => !items.is_empty(),
+        Value::Table(table) => !table.is_empty(),
+    }
+}
+
+fn is_path(path: &str) -> bool {
+    path.split('.').all(is_name)
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
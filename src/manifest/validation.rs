@@ -0,0 +1,357 @@
+use std::{collections::HashSet, net::IpAddr};
+
+use crate::{
+    controllers::schedule::Schedule,
+    entities::container::{ContainerSpec, InitContainer},
+};
+
+use super::{FieldPath, Manifest};
+
+// Types and enum values, restart policies included, are already checked while parsing; this
+// covers what docker would otherwise only reject once the container is created.
+pub struct Violation {
+    pub path: FieldPath,
+    pub message: String,
+}
+
+pub fn validate(manifest: &Manifest) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    let containers = FieldPath::default().field("containers");
+    let mut names = HashSet::new();
+    for (index, spec) in manifest.containers.iter().enumerate() {
+        let path = containers.index(index);
+        validate_container(spec, &path, &mut names, &mut violations);
+    }
+
+    let jobs = FieldPath::default().field("jobs");
+    let mut names = HashSet::new();
+    for (index, job) in manifest.jobs.iter().enumerate() {
+        let path = jobs.index(index);
+        validate_container(&job.template, &path, &mut names, &mut violations);
+    }
+
+    let cron_jobs = FieldPath::default().field("cron_jobs");
+    let mut names = HashSet::new();
+    for (index, cron_job) in manifest.cron_jobs.iter().enumerate() {
+        let path = cron_jobs.index(index);
+        validate_container(&cron_job.job.template, &path, &mut names, &mut violations);
+        if let Err(error) = cron_job.schedule.parse::<Schedule>() {
+            violations.push(Violation {
+                path: path.field("schedule"),
+                message: error.to_string(),
+            });
+        }
+    }
+
+    violations
+}
+
+fn validate_container(
+    spec: &ContainerSpec,
+    path: &FieldPath,
+    names: &mut HashSet<String>,
+    violations: &mut Vec<Violation>,
+) {
+    let mut violation = |field: &str, message: String| {
+        violations.push(Violation {
+            path: path.field(field),
+            message,
+        })
+    };
+
+    match check_name(&spec.name) {
+        Err(message) => violation("name", message),
+        Ok(()) if !names.insert(spec.name.clone()) => {
+            violation("name", format!("{:?} is defined more than once", spec.name))
+        }
+        Ok(()) => {}
+    }
+    if let Err(message) = check_image(&spec.image) {
+        violation("image", message);
+    }
+    if !spec.ports.is_empty() {
+        if let Err(message) = check_ports(&spec.ports) {
+            violation("ports", message);
+        }
+    }
+    if !spec.resources.cpus.is_finite() || spec.resources.cpus < 0.0 {
+        violation(
+            "resources",
+            format!("cpus must not be negative, got {}", spec.resources.cpus),
+        );
+    }
+    for (index, mount) in spec.config_maps.iter().enumerate() {
+        if !mount.mount_path.starts_with('/') {
+            violations.push(Violation {
+                path: path.field("config_maps").index(index).field("mount_path"),
+                message: format!("mount path {:?} must be absolute", mount.mount_path),
+            });
+        }
+    }
+    let mut mount_paths = HashSet::new();
+    for (index, claim) in spec.volumes.iter().enumerate() {
+        let path = path.field("volumes").index(index);
+        if let Err(message) = check_name(&claim.name) {
+            violations.push(Violation {
+                path: path.field("name"),
+                message,
+            });
+        }
+        if !claim.mount_path.starts_with('/') {
+            violations.push(Violation {
+                path: path.field("mount_path"),
+                message: format!("mount path {:?} must be absolute", claim.mount_path),
+            });
+        } else if !mount_paths.insert(claim.mount_path.trim_end_matches('/')) {
+            violations.push(Violation {
+                path: path.field("mount_path"),
+                message: format!("{:?} is mounted more than once", claim.mount_path),
+            });
+        }
+    }
+    for (field, terms) in [
+        ("affinity", &spec.affinity),
+        ("anti_affinity", &spec.anti_affinity),
+    ] {
+        for (index, term) in terms.iter().enumerate() {
+            if let Err(error) = term.selector.validate() {
+                violations.push(Violation {
+                    path: path.field(field).index(index),
+                    message: error.to_string(),
+                });
+            }
+        }
+    }
+    if let Some(probe) = &spec.readiness_probe {
+        let path = path.field("readiness_probe");
+        if probe.command.trim().is_empty() {
+            violations.push(Violation {
+                path: path.field("command"),
+                message: String::from("probe command must not be empty"),
+            });
+        }
+        if probe.period_seconds == 0 || probe.timeout_seconds == 0 {
+            violations.push(Violation {
+                path,
+                message: String::from("probe period and timeout must be at least 1 second"),
+            });
+        }
+    }
+    for (field, hook) in [
+        ("post_start", &spec.lifecycle.post_start),
+        ("pre_stop", &spec.lifecycle.pre_stop),
+    ] {
+        if let Some(Err(error)) = hook.as_ref().map(|hook| hook.validate()) {
+            violations.push(Violation {
+                path: path.field("lifecycle").field(field),
+                message: error.to_string(),
+            });
+        }
+    }
+
+    let mut init_names = HashSet::new();
+    for (index, init) in spec.init_containers.iter().enumerate() {
+        let path = path.field("init_containers").index(index);
+        validate_init_container(init, &path, &mut init_names, violations);
+    }
+}
+
+fn validate_init_container(
+    init: &InitContainer,
+    path: &FieldPath,
+    names: &mut HashSet<String>,
+    violations: &mut Vec<Violation>,
+) {
+    match check_name(&init.name) {
+        Err(message) => violations.push(Violation {
+            path: path.field("name"),
+            message,
+        }),
+        Ok(()) if !names.insert(init.name.clone()) => violations.push(Violation {
+            path: path.field("name"),
+            message: format!("{:?} is defined more than once", init.name),
+        }),
+        Ok(()) => {}
+    }
+    if let Err(message) = check_image(&init.image) {
+        violations.push(Violation {
+            path: path.field("image"),
+            message,
+        });
+    }
+}
+
+// Docker's container name rules.
+fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err(String::from("name is required"));
+    }
+
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+        && name.len() > 1;
+    if !valid {
+        return Err(format!(
+            "invalid name {:?}: use at least two letters, digits, '_', '.' or '-', starting with a letter or digit",
+            name
+        ));
+    }
+    Ok(())
+}
+
+// `[registry[:port]/]path[:tag][@algorithm:digest]`, following docker's reference grammar.
+fn check_image(image: &str) -> Result<(), String> {
+    if image.is_empty() {
+        return Err(String::from("image is required"));
+    }
+    let invalid = |reason: &str| Err(format!("invalid image {:?}: {}", image, reason));
+
+    let (name, digest) = match image.split_once('@') {
+        Some((name, digest)) => (name, Some(digest)),
+        None => (image, None),
+    };
+    if let Some(digest) = digest {
+        let valid = digest.split_once(':').is_some_and(|(algorithm, hex)| {
+            !algorithm.is_empty()
+                && algorithm
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+                && hex.len() >= 32
+                && hex.chars().all(|c| c.is_ascii_hexdigit())
+        });
+        if !valid {
+            return invalid("digest must look like sha256:<hex>");
+        }
+    }
+
+    // A colon after the last slash starts the tag; before it, it is a registry port.
+    let last_slash = name.rfind('/').map_or(0, |slash| slash + 1);
+    let (name, tag) = match name[last_slash..].find(':') {
+        Some(colon) => (
+            &name[..last_slash + colon],
+            Some(&name[last_slash + colon + 1..]),
+        ),
+        None => (name, None),
+    };
+    if let Some(tag) = tag {
+        let valid = tag.len() <= 128
+            && tag
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+        if !valid {
+            return invalid("tags use up to 128 letters, digits, '_', '.' or '-'");
+        }
+    }
+
+    let mut components: Vec<&str> = name.split('/').collect();
+    if components.len() > 1 && (components[0].contains(['.', ':']) || components[0] == "localhost")
+    {
+        let registry = components.remove(0);
+        if !is_registry(registry) {
+            return invalid("registry must be a host name with an optional port");
+        }
+    }
+
+    for component in components {
+        if component.chars().any(|c| c.is_ascii_uppercase()) {
+            return invalid("repository names must be lowercase");
+        }
+        if !is_path_component(component) {
+            return invalid(
+                "repository names use lowercase letters and digits separated by '.', '_', '__' or '-'",
+            );
+        }
+    }
+    Ok(())
+}
+
+fn is_registry(registry: &str) -> bool {
+    let (host, port) = match registry.split_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (registry, None),
+    };
+
+    let valid_host = host.split('.').all(|label| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+    valid_host && port.is_none_or(|port| port.parse::<u16>().is_ok())
+}
+
+fn is_path_component(component: &str) -> bool {
+    let alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    if !component.starts_with(alphanumeric) || !component.ends_with(alphanumeric) {
+        return false;
+    }
+
+    component
+        .split(alphanumeric)
+        .filter(|separator| !separator.is_empty())
+        .all(|separator| {
+            matches!(separator, "." | "_" | "__") || separator.chars().all(|c| c == '-')
+        })
+}
+
+// Docker's `-p` syntax: `[ip:][host_port:]container_port[/protocol]`, where ports can be ranges.
+fn check_ports(ports: &str) -> Result<(), String> {
+    let (mapping, protocol) = match ports.split_once('/') {
+        Some((mapping, protocol)) => (mapping, Some(protocol)),
+        None => (ports, None),
+    };
+    if let Some(protocol) = protocol {
+        if !matches!(protocol, "tcp" | "udp" | "sctp") {
+            return Err(format!(
+                "unknown protocol {:?} in {:?}, expected tcp, udp or sctp",
+                protocol, ports
+            ));
+        }
+    }
+
+    let mut parts = mapping.rsplitn(3, ':');
+    let container_port = parts.next().unwrap_or_default();
+    let host_port = parts.next();
+    let ip = parts.next();
+
+    if let Some(ip) = ip {
+        let ip = ip.trim_start_matches('[').trim_end_matches(']');
+        if ip.parse::<IpAddr>().is_err() {
+            return Err(format!("invalid host IP {:?} in {:?}", ip, ports));
+        }
+    }
+    // An empty host port after an IP lets docker pick one.
+    if let Some(host_port) = host_port.filter(|host_port| !host_port.is_empty() || ip.is_none()) {
+        check_port_range(host_port, ports)?;
+    }
+    check_port_range(container_port, ports)
+}
+
+fn check_port_range(range: &str, ports: &str) -> Result<(), String> {
+    let port = |port: &str| match port.parse::<u32>() {
+        Ok(port @ 1..=65535) => Ok(port),
+        Ok(port) => Err(format!(
+            "port {} in {:?} is out of range (1-65535)",
+            port, ports
+        )),
+        Err(_) => Err(format!("invalid port {:?} in {:?}", port, ports)),
+    };
+
+    match range.split_once('-') {
+        Some((start, end)) => {
+            if port(start)? > port(end)? {
+                return Err(format!("port range {:?} in {:?} is reversed", range, ports));
+            }
+            Ok(())
+        }
+        None => port(range).map(|_| ()),
+    }
+}
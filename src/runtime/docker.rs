@@ -0,0 +1,751 @@
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr, TcpListener, UdpSocket},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use tokio::process::Command;
+
+use crate::{
+    config::DockerConfig,
+    entities::{
+        container::{
+            Container, ContainerSpec, ContainerStatus, RestartPolicy, Termination, APP_LABEL,
+            MANAGED_LABEL, SPEC_LABEL,
+        },
+        node::NodeCapacity,
+        ports::{HostPorts, PortConflict},
+        resource_usage::{parse_percent, parse_size, ResourceUsage},
+    },
+};
+
+use super::{error::RuntimeError, ContainerRuntime, ExecOutput, Image, LogLine, RunOptions};
+
+// The ports and env-from-secret labels are only read for containers created before the spec label.
+const INSPECT_FORMAT: &str = "{{.Id}}\t{{.Name}}\t{{.Config.Image}}\t{{.Created}}\t{{index .Config.Labels \"nic8s.ports\"}}\t{{.State.Status}}\t{{index .Config.Labels \"nic8s.app\"}}\t{{.State.StartedAt}}\t{{.RestartCount}}\t{{if .State.Health}}{{.State.Health.Status}}{{end}}\t{{index .Config.Labels \"nic8s.env-from-secret\"}}\t{{index .Config.Labels \"nic8s.spec\"}}\t{{.Image}}\t{{.State.ExitCode}}\t{{.State.OOMKilled}}\t{{.State.FinishedAt}}";
+const IMAGE_FORMAT: &str = "{{.Id}}\t{{json .RepoTags}}\t{{.Size}}\t{{.Created}}";
+const STATS_FORMAT: &str = "{{.ID}}\t{{.CPUPerc}}\t{{.MemUsage}}\t{{.NetIO}}";
+
+// Docker reports the zero time for containers that never started.
+pub fn parse_started_at(started_at: &str) -> Option<String> {
+    if started_at.is_empty() || started_at.starts_with("0001-01-01") {
+        None
+    } else {
+        Some(String::from(started_at))
+    }
+}
+
+// Whether inspect only failed on objects that don't exist, like `Error: No such object: 3f2a`
+// from docker or `Error: no such container 3f2a` from podman.
+fn only_missing(stderr: &[u8]) -> bool {
+    String::from_utf8_lossy(stderr)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .all(|line| line.to_lowercase().contains("no such "))
+}
+
+// A line of `docker inspect --format INSPECT_FORMAT` output, for the container `id`.
+fn parse_inspect(id: &str, line: &str) -> Result<Container, anyhow::Error> {
+    // Only strip the newline; trailing fields are empty for containers without nic8s labels.
+    let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split('\t').collect();
+    if fields.len() != 16 {
+        return Err(anyhow!(
+            "unexpected inspect output for container {}: {}",
+            id,
+            line
+        ));
+    }
+
+    let name = fields[1].trim_start_matches('/').to_string();
+    let app = label_value(fields[6]).unwrap_or_else(|| name.clone());
+
+    let status = ContainerStatus::from(fields[5]);
+    let termination = termination(&status, fields[13], fields[14], fields[15]);
+    let spec = match fields[11] {
+        "" | "<no value>" => ContainerSpec {
+            name: name.clone(),
+            image: String::from(fields[2]),
+            ports: label_value(fields[4]).unwrap_or_default(),
+            app: Some(app.clone()),
+            env_from_secret: label_value(fields[10]),
+            ..ContainerSpec::default()
+        },
+        spec => serde_json::from_str(spec)
+            .map_err(|error| anyhow!("invalid spec label on container {}: {}", id, error))?,
+    };
+
+    Ok(Container {
+        id: String::from(fields[0]),
+        name,
+        app,
+        spec,
+        node: String::new(),
+        created: String::from(fields[3]),
+        started_at: parse_started_at(fields[7]),
+        restart_count: fields[8].parse().unwrap_or(0),
+        last_restart: None,
+        back_off_until: None,
+        last_termination: termination,
+        health: parse_health(fields[9]),
+        image_digest: label_value(fields[12]),
+        status,
+    })
+}
+
+// Docker resets the exit code and OOM flag when a container starts again, so they only describe
+// the last exit while the container is down.
+fn termination(
+    status: &ContainerStatus,
+    exit_code: &str,
+    oom_killed: &str,
+    finished_at: &str,
+) -> Option<Termination> {
+    if !matches!(
+        status,
+        ContainerStatus::Exited | ContainerStatus::Dead | ContainerStatus::Restarting
+    ) {
+        return None;
+    }
+    Some(Termination {
+        exit_code: exit_code.trim().parse().ok()?,
+        oom_killed: oom_killed.trim() == "true",
+        finished_at: parse_started_at(finished_at)?,
+    })
+}
+
+pub fn parse_health(health: &str) -> Option<String> {
+    match health.trim() {
+        "" => None,
+        health => Some(String::from(health)),
+    }
+}
+
+// Missing labels render as `<no value>` in docker's templates.
+fn label_value(value: &str) -> Option<String> {
+    match value {
+        "" | "<no value>" => None,
+        value => Some(String::from(value)),
+    }
+}
+
+// Drives the docker CLI, or podman's, which takes the same commands and formats for everything
+// but `info`.
+pub struct DockerRuntime {
+    program: &'static str,
+    host_bind_check: bool,
+    // Environment every docker command runs with, to pick the engine; None removes the variable.
+    engine: Vec<(&'static str, Option<String>)>,
+    // Binding its ports here says nothing about an engine on another machine.
+    remote: bool,
+}
+
+impl DockerRuntime {
+    pub fn new() -> Self {
+        DockerRuntime {
+            program: "docker",
+            host_bind_check: false,
+            engine: Vec::new(),
+            remote: false,
+        }
+    }
+
+    pub fn with_host_bind_check(mut self, enabled: bool) -> Self {
+        self.host_bind_check = enabled;
+        self
+    }
+
+    pub fn with_engine(mut self, config: &DockerConfig) -> Result<Self, anyhow::Error> {
+        match (&config.host, &config.context) {
+            (Some(_), Some(_)) => {
+                return Err(anyhow!(
+                    "runtime.docker.host and runtime.docker.context can't both be set"
+                ))
+            }
+            (Some(host), None) => {
+                let scheme = host_scheme(self.program, host)?;
+                if config.tls_verify && scheme != "tcp" {
+                    return Err(anyhow!(
+                        "runtime.docker.tls_verify only applies to tcp:// hosts, not {}",
+                        host
+                    ));
+                }
+                // A context from the environment would otherwise be ignored silently.
+                self = self
+                    .with_env("DOCKER_CONTEXT", None)
+                    .with_host("DOCKER_HOST", host)?;
+            }
+            (None, Some(context)) => {
+                // DOCKER_HOST wins over the context, so it has to go.
+                self = self
+                    .with_env("DOCKER_HOST", None)
+                    .with_env("DOCKER_CONTEXT", Some(context.clone()));
+            }
+            (None, None) => {}
+        }
+        if config.tls_verify {
+            self = self.with_env("DOCKER_TLS_VERIFY", Some(String::from("1")));
+        }
+        if let Some(cert_path) = &config.cert_path {
+            self = self.with_env(
+                "DOCKER_CERT_PATH",
+                Some(cert_path.to_string_lossy().to_string()),
+            );
+        }
+        Ok(self)
+    }
+
+    pub(super) fn with_program(mut self, program: &'static str) -> Self {
+        self.program = program;
+        self
+    }
+
+    // Points the CLI at the engine at `host` through the variable `name`.
+    pub(super) fn with_host(
+        mut self,
+        name: &'static str,
+        host: &str,
+    ) -> Result<Self, anyhow::Error> {
+        self.remote = host_scheme(self.program, host)? != "unix";
+        Ok(self.with_env(name, Some(String::from(host))))
+    }
+
+    pub(super) fn with_env(mut self, name: &'static str, value: Option<String>) -> Self {
+        self.engine.push((name, value));
+        self
+    }
+
+    pub(super) fn is_remote(&self) -> bool {
+        self.remote
+    }
+
+    pub async fn capacity(&self) -> Result<NodeCapacity, anyhow::Error> {
+        let out = self
+            .docker(&["info", "--format", "{{.NCPU}}\t{{.MemTotal}}"])
+            .await?;
+        parse_capacity(&out)
+    }
+
+    async fn image_digest(&self, image: &str) -> Result<String, anyhow::Error> {
+        let out = self
+            .docker(&["image", "inspect", "--format", "{{.Id}}", image])
+            .await?;
+        Ok(out.trim().to_string())
+    }
+
+    pub(super) async fn docker(&self, args: &[&str]) -> Result<String, anyhow::Error> {
+        self.docker_with_env(args, &BTreeMap::new()).await
+    }
+
+    async fn docker_with_env(
+        &self,
+        args: &[&str],
+        env: &BTreeMap<String, String>,
+    ) -> Result<String, anyhow::Error> {
+        let out = self
+            .command()
+            .args(args)
+            .envs(env)
+            .output()
+            .await
+            .map_err(|error| RuntimeError::spawn(self.program, args, error))?;
+
+        if !out.status.success() {
+            return Err(RuntimeError::failed(self.program, args, out.status, &out.stderr).into());
+        }
+
+        Ok(String::from_utf8_lossy(&out.stdout).to_string())
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(self.program);
+        for (name, value) in self.engine.iter() {
+            match value {
+                Some(value) => command.env(name, value),
+                None => command.env_remove(name),
+            };
+        }
+        command
+    }
+}
+
+impl Default for DockerRuntime {
+    fn default() -> Self {
+        DockerRuntime::new()
+    }
+}
+
+// The scheme of an engine address like DOCKER_HOST's.
+fn host_scheme<'a>(program: &str, host: &'a str) -> Result<&'a str, anyhow::Error> {
+    match host.split_once("://") {
+        Some((scheme @ ("unix" | "tcp" | "ssh"), _)) => Ok(scheme),
+        _ => Err(anyhow!(
+            "invalid {} host {}: expected a unix://, tcp:// or ssh:// address",
+            program,
+            host
+        )),
+    }
+}
+
+// CPUs and memory in bytes, tab-separated.
+pub(super) fn parse_capacity(out: &str) -> Result<NodeCapacity, anyhow::Error> {
+    let (cpus, memory) = out
+        .trim()
+        .split_once('\t')
+        .ok_or_else(|| anyhow!("unexpected info output: {}", out))?;
+
+    Ok(NodeCapacity {
+        cpus: cpus.parse()?,
+        memory_bytes: memory.parse()?,
+    })
+}
+
+// Binds each port and lets it go right away. Docker's own userland proxy holds the ports of
+// running containers, so those fail here too.
+fn check_host_ports(container: &str, ports: &HostPorts) -> Result<(), PortConflict> {
+    let ip = ports.ip.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    for port in ports.ports.clone() {
+        let bound = match ports.protocol.as_str() {
+            "tcp" => TcpListener::bind((ip, port)).map(drop),
+            "udp" => UdpSocket::bind((ip, port)).map(drop),
+            _ => Ok(()),
+        };
+        if let Err(source) = bound {
+            return Err(PortConflict::Host {
+                container: String::from(container),
+                port,
+                protocol: ports.protocol.clone(),
+                source,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl ContainerRuntime for DockerRuntime {
+    async fn pull_if_missing(&self, image: &str) -> Result<bool, anyhow::Error> {
+        if self.docker(&["image", "inspect", image]).await.is_ok() {
+            return Ok(false);
+        }
+
+        self.docker(&["pull", image]).await?;
+        Ok(true)
+    }
+
+    async fn pull(&self, image: &str) -> Result<String, anyhow::Error> {
+        self.docker(&["pull", image]).await?;
+        self.image_digest(image).await
+    }
+
+    async fn create(
+        &self,
+        spec: &ContainerSpec,
+        options: &RunOptions,
+    ) -> Result<Container, anyhow::Error> {
+        if self.host_bind_check && !self.remote {
+            if let Some(ports) = HostPorts::parse(&spec.ports) {
+                check_host_ports(&spec.name, &ports)?;
+            }
+        }
+
+        let app_label = format!("{}={}", APP_LABEL, spec.app_name());
+        // The whole spec travels with the container so it can be adopted after a restart.
+        let spec_label = format!("{}={}", SPEC_LABEL, serde_json::to_string(spec)?);
+
+        let restart = match spec.restart_policy {
+            RestartPolicy::Always => "unless-stopped",
+            RestartPolicy::OnFailure => "on-failure",
+            RestartPolicy::Never => "no",
+        };
+
+        let mut args = vec![
+            "create",
+            "--name",
+            &spec.name,
+            "--restart",
+            restart,
+            "--label",
+            &app_label,
+            "--label",
+            &spec_label,
+        ];
+
+        let labels: Vec<String> = options
+            .labels
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        for label in labels.iter() {
+            args.extend(["--label", label]);
+        }
+
+        // `-e KEY` makes docker read the value from its own environment, keeping secret values
+        // out of the process arguments.
+        for key in options.env.keys() {
+            args.extend(["-e", key]);
+        }
+
+        let volumes: Vec<String> = options
+            .mounts
+            .iter()
+            .map(|mount| {
+                let mode = if mount.read_only { "ro" } else { "rw" };
+                format!("{}:{}:{}", mount.source, mount.target, mode)
+            })
+            .collect();
+        for volume in volumes.iter() {
+            args.extend(["-v", volume]);
+        }
+
+        if let Some(id) = &options.volumes_from {
+            args.extend(["--volumes-from", id]);
+        }
+
+        if !spec.ports.is_empty() {
+            args.extend(["-p", &spec.ports]);
+        }
+
+        // So a plain `docker stop`, as on shutdown, stops it the same way nic8s does.
+        if let Some(signal) = &spec.stop_signal {
+            args.extend(["--stop-signal", signal]);
+        }
+        let stop_timeout = spec
+            .termination_grace_period_seconds
+            .map(|seconds| seconds.to_string());
+        if let Some(seconds) = &stop_timeout {
+            args.extend(["--stop-timeout", seconds]);
+        }
+
+        let probe_args: Vec<String> = match &spec.readiness_probe {
+            Some(probe) => vec![
+                String::from("--health-cmd"),
+                probe.command.clone(),
+                String::from("--health-start-period"),
+                format!("{}s", probe.initial_delay_seconds),
+                String::from("--health-interval"),
+                format!("{}s", probe.period_seconds),
+                String::from("--health-timeout"),
+                format!("{}s", probe.timeout_seconds),
+                String::from("--health-retries"),
+                probe.failure_threshold.to_string(),
+            ],
+            None => Vec::new(),
+        };
+        args.extend(probe_args.iter().map(String::as_str));
+
+        args.push(&spec.image);
+        args.extend(spec.command.iter().map(String::as_str));
+        let out = self.docker_with_env(&args, &options.env).await?;
+
+        let container_id = out.trim().to_string();
+        println!("Container ID: {}", container_id);
+        let image_digest = self.image_digest(&spec.image).await?;
+
+        Ok(Container {
+            id: container_id,
+            name: spec.name.clone(),
+            app: spec.app_name().to_string(),
+            spec: spec.clone(),
+            node: String::new(),
+            created: chrono::Utc::now().to_rfc3339(),
+            started_at: None,
+            restart_count: 0,
+            last_restart: None,
+            back_off_until: None,
+            last_termination: None,
+            health: None,
+            image_digest: Some(image_digest),
+            status: ContainerStatus::Created,
+        })
+    }
+
+    async fn start(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.docker(&["start", id]).await?;
+        Ok(())
+    }
+
+    async fn list_managed(&self) -> Result<Vec<Container>, anyhow::Error> {
+        let filter = format!("label={}=true", MANAGED_LABEL);
+        let out = self
+            .docker(&["ps", "--all", "--filter", &filter, "--format", "{{.ID}}"])
+            .await?;
+        let ids: Vec<&str> = out
+            .lines()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .collect();
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // One inspect for all of them, which prints a line per container it finds. Those removed
+        // since the ps make it fail after printing the others, and are left out.
+        let mut args = vec!["inspect", "--format", INSPECT_FORMAT];
+        args.extend(ids.iter().copied());
+        let out = self
+            .command()
+            .args(&args)
+            .output()
+            .await
+            .map_err(|error| RuntimeError::spawn(self.program, &args, error))?;
+        if !out.status.success() && !only_missing(&out.stderr) {
+            return Err(RuntimeError::failed(self.program, &args, out.status, &out.stderr).into());
+        }
+        String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| parse_inspect(line.split('\t').next().unwrap_or_default(), line))
+            .collect()
+    }
+
+    async fn inspect(&self, id: &str) -> Result<Container, anyhow::Error> {
+        let out = self
+            .docker(&["inspect", "--format", INSPECT_FORMAT, id])
+            .await?;
+        parse_inspect(id, &out)
+    }
+
+    async fn stop(&self, id: &str, grace_period: Option<Duration>) -> Result<(), anyhow::Error> {
+        match grace_period {
+            Some(grace_period) => {
+                let seconds = grace_period.as_secs().to_string();
+                self.docker(&["stop", "--time", &seconds, id]).await?
+            }
+            None => self.docker(&["stop", id]).await?,
+        };
+        Ok(())
+    }
+
+    async fn kill(&self, id: &str, signal: &str) -> Result<(), anyhow::Error> {
+        self.docker(&["kill", "--signal", signal, id]).await?;
+        Ok(())
+    }
+
+    async fn exec(&self, id: &str, command: &[String]) -> Result<ExecOutput, anyhow::Error> {
+        let mut args = vec!["exec", id];
+        args.extend(command.iter().map(String::as_str));
+        // Killed with the caller's future, so a timed out hook doesn't leave docker behind.
+        let out = self
+            .command()
+            .args(&args)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|error| RuntimeError::spawn(self.program, &args, error))?;
+
+        // 125 is docker itself failing, e.g. because the container isn't running.
+        match out.status.code() {
+            Some(code) if code != 125 => {
+                let mut output = String::from_utf8_lossy(&out.stdout).to_string();
+                output.push_str(&String::from_utf8_lossy(&out.stderr));
+                Ok(ExecOutput {
+                    exit_code: i64::from(code),
+                    output,
+                })
+            }
+            _ => Err(RuntimeError::failed(self.program, &args, out.status, &out.stderr).into()),
+        }
+    }
+
+    async fn restart(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.docker(&["restart", id]).await?;
+        Ok(())
+    }
+
+    async fn wait(&self, id: &str) -> Result<i64, anyhow::Error> {
+        let out = self.docker(&["wait", id]).await?;
+        out.trim()
+            .parse()
+            .map_err(|_| anyhow!("unexpected wait output for container {}: {}", id, out))
+    }
+
+    async fn remove(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.docker(&["rm", "--force", id]).await?;
+        Ok(())
+    }
+
+    async fn stats(&self, ids: &[String]) -> Result<Vec<ResourceUsage>, anyhow::Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut args = vec![
+            "stats",
+            "--no-stream",
+            "--no-trunc",
+            "--format",
+            STATS_FORMAT,
+        ];
+        args.extend(ids.iter().map(String::as_str));
+        let out = self.docker(&args).await?;
+
+        let mut usage = Vec::new();
+        for line in out.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 4 {
+                continue;
+            }
+
+            let (memory, memory_limit) = fields[2].split_once('/').unwrap_or((fields[2], ""));
+            let (net_rx, net_tx) = fields[3].split_once('/').unwrap_or((fields[3], ""));
+
+            usage.push(ResourceUsage {
+                container_id: String::from(fields[0]),
+                cpu_percent: parse_percent(fields[1]),
+                memory_bytes: parse_size(memory),
+                memory_limit_bytes: parse_size(memory_limit),
+                net_rx_bytes: parse_size(net_rx),
+                net_tx_bytes: parse_size(net_tx),
+            });
+        }
+
+        Ok(usage)
+    }
+
+    async fn logs(&self, id: &str, tail: Option<usize>) -> Result<String, anyhow::Error> {
+        let tail = tail.map_or(String::from("all"), |tail| tail.to_string());
+        let args = ["logs", "--tail", &tail, id];
+        let out = self
+            .command()
+            .args(args)
+            .output()
+            .await
+            .map_err(|error| RuntimeError::spawn(self.program, &args, error))?;
+
+        if !out.status.success() {
+            return Err(anyhow!(
+                "failed to get logs for container {}: {}\n{}",
+                id,
+                out.status,
+                String::from_utf8_lossy(&out.stderr)
+            ));
+        }
+
+        let mut logs = String::from_utf8_lossy(&out.stdout).to_string();
+        logs.push_str(&String::from_utf8_lossy(&out.stderr));
+        Ok(logs)
+    }
+
+    async fn logs_since(
+        &self,
+        id: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LogLine>, anyhow::Error> {
+        let since_arg = since.map(|since| since.to_rfc3339_opts(SecondsFormat::Nanos, true));
+        let mut args = vec!["logs", "--timestamps"];
+        if let Some(since) = &since_arg {
+            args.extend(["--since", since]);
+        }
+        args.push(id);
+        let out = self
+            .command()
+            .args(&args)
+            .output()
+            .await
+            .map_err(|error| RuntimeError::spawn(self.program, &args, error))?;
+
+        if !out.status.success() {
+            return Err(RuntimeError::failed(self.program, &args, out.status, &out.stderr).into());
+        }
+
+        // `--since` is inclusive, so the line at `since` itself comes back again.
+        let mut lines: Vec<LogLine> = parse_log_lines(&out.stdout)
+            .chain(parse_log_lines(&out.stderr))
+            .filter(|line| since.is_none_or(|since| line.timestamp > since))
+            .collect();
+        lines.sort_by_key(|line| line.timestamp);
+        Ok(lines)
+    }
+
+    async fn create_volume(&self, name: &str) -> Result<(), anyhow::Error> {
+        let label = format!("{}=true", MANAGED_LABEL);
+        self.docker(&["volume", "create", "--label", &label, name])
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_volume(&self, name: &str, force: bool) -> Result<(), anyhow::Error> {
+        let mut args = vec!["volume", "rm"];
+        if force {
+            args.push("--force");
+        }
+        args.push(name);
+        self.docker(&args).await?;
+        Ok(())
+    }
+
+    async fn list_images(&self) -> Result<Vec<Image>, anyhow::Error> {
+        let out = self
+            .docker(&["image", "ls", "--quiet", "--no-trunc"])
+            .await?;
+        let mut ids: Vec<&str> = out
+            .lines()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut args = vec!["image", "inspect", "--format", IMAGE_FORMAT];
+        args.extend(ids);
+        let out = self.docker(&args).await?;
+        out.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                if fields.len() != 4 {
+                    return Err(anyhow!("unexpected image inspect output: {}", line));
+                }
+                Ok(Image {
+                    id: String::from(fields[0]),
+                    tags: serde_json::from_str::<Option<Vec<String>>>(fields[1])?
+                        .unwrap_or_default(),
+                    size_bytes: fields[2].parse()?,
+                    created: String::from(fields[3]),
+                })
+            })
+            .collect()
+    }
+
+    async fn image_disk_usage(&self) -> Result<u64, anyhow::Error> {
+        let out = self
+            .docker(&["system", "df", "--format", "{{.Type}}\t{{.Size}}"])
+            .await?;
+        out.lines()
+            .find_map(|line| line.strip_prefix("Images\t"))
+            .map(parse_size)
+            .ok_or_else(|| anyhow!("unexpected docker system df output: {}", out))
+    }
+
+    // Removing by tag drops every tag and then the image; by ID docker refuses images with
+    // several tags.
+    async fn remove_image(&self, image: &Image) -> Result<(), anyhow::Error> {
+        let mut args = vec!["image", "rm"];
+        if image.tags.is_empty() {
+            args.push(&image.id);
+        } else {
+            args.extend(image.tags.iter().map(String::as_str));
+        }
+        self.docker(&args).await?;
+        Ok(())
+    }
+}
+
+// Lines as printed by `docker logs --timestamps`: an RFC 3339 timestamp, a space and the text.
+fn parse_log_lines(output: &[u8]) -> impl Iterator<Item = LogLine> + '_ {
+    output.split(|byte| *byte == b'\n').filter_map(|line| {
+        let line = String::from_utf8_lossy(line);
+        let (timestamp, text) = line.split_once(' ').unwrap_or((&line, ""));
+        let timestamp = DateTime::parse_from_rfc3339(timestamp).ok()?;
+        Some(LogLine {
+            timestamp: timestamp.with_timezone(&Utc),
+            text: String::from(text),
+        })
+    })
+}
@@ -0,0 +1,70 @@
+use std::{io, process::ExitStatus};
+
+use thiserror::Error;
+
+// Failures of the docker or podman CLI, kept apart so callers can tell a daemon that is down
+// from a command that was rejected.
+#[derive(Debug, Error)]
+pub enum RuntimeError {
+    #[error("failed to run {program} {command}: {source}")]
+    Spawn {
+        program: &'static str,
+        command: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{program} daemon unavailable during {program} {command}: {stderr}")]
+    DaemonUnavailable {
+        program: &'static str,
+        command: String,
+        stderr: String,
+    },
+    #[error("failed to execute {program} {command}: {status}\n{stderr}")]
+    Command {
+        program: &'static str,
+        command: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+}
+
+impl RuntimeError {
+    pub fn spawn(program: &'static str, args: &[&str], source: io::Error) -> Self {
+        RuntimeError::Spawn {
+            program,
+            command: command(args),
+            source,
+        }
+    }
+
+    pub fn failed(program: &'static str, args: &[&str], status: ExitStatus, stderr: &[u8]) -> Self {
+        let stderr = String::from_utf8_lossy(stderr).trim_end().to_string();
+        if stderr.contains("Cannot connect to the Docker daemon")
+            || stderr.contains("Cannot connect to Podman")
+        {
+            return RuntimeError::DaemonUnavailable {
+                program,
+                command: command(args),
+                stderr,
+            };
+        }
+        RuntimeError::Command {
+            program,
+            command: command(args),
+            status,
+            stderr,
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        match self {
+            RuntimeError::Spawn { source, .. } => source.kind() != io::ErrorKind::NotFound,
+            RuntimeError::DaemonUnavailable { .. } => true,
+            RuntimeError::Command { .. } => false,
+        }
+    }
+}
+
+fn command(args: &[&str]) -> String {
+    String::from(*args.first().unwrap_or(&""))
+}
@@ -0,0 +1,200 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::Semaphore;
+
+use crate::entities::{
+    container::{Container, ContainerSpec},
+    resource_usage::ResourceUsage,
+};
+
+use super::{ContainerRuntime, ExecOutput, Image, LogLine, RunOptions};
+
+type Runtime = Arc<dyn ContainerRuntime + Send + Sync>;
+
+// Caps how many operations run at once, queueing the others in the order they came in.
+pub struct OperationLimit {
+    permits: Semaphore,
+    max: usize,
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+    total: AtomicU64,
+    queued_micros: AtomicU64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LimitSnapshot {
+    pub max: usize,
+    pub in_flight: usize,
+    pub queued: usize,
+    // Operations started so far, and how long they spent queued altogether.
+    pub total: u64,
+    pub queued_time: Duration,
+}
+
+impl OperationLimit {
+    pub fn new(max: usize) -> Self {
+        OperationLimit {
+            permits: Semaphore::new(max.max(1)),
+            max: max.max(1),
+            in_flight: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+            total: AtomicU64::new(0),
+            queued_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub async fn run<T>(&self, operation: impl Future<Output = T>) -> T {
+        let queued_at = Instant::now();
+        let queued = Gauge::up(&self.queued);
+        // The semaphore is never closed.
+        let _permit = self.permits.acquire().await;
+        drop(queued);
+        self.queued_micros
+            .fetch_add(queued_at.elapsed().as_micros() as u64, Ordering::Relaxed);
+        self.total.fetch_add(1, Ordering::Relaxed);
+
+        let _in_flight = Gauge::up(&self.in_flight);
+        operation.await
+    }
+
+    pub fn snapshot(&self) -> LimitSnapshot {
+        LimitSnapshot {
+            max: self.max,
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            queued: self.queued.load(Ordering::Relaxed),
+            total: self.total.load(Ordering::Relaxed),
+            queued_time: Duration::from_micros(self.queued_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+// Counts the holder for as long as it lives, so callers that give up on an operation, by dropping
+// it, don't leave it counted.
+struct Gauge<'a>(&'a AtomicUsize);
+
+impl<'a> Gauge<'a> {
+    fn up(gauge: &'a AtomicUsize) -> Self {
+        gauge.fetch_add(1, Ordering::Relaxed);
+        Gauge(gauge)
+    }
+}
+
+impl Drop for Gauge<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// Runs the wrapped runtime's operations under a limit, so reconciling many containers at once
+// doesn't start as many engine calls. Waiting for a container to exit can take as long as the
+// container runs, and an exec as long as the hook or probe it runs, so neither counts.
+pub struct LimitedRuntime {
+    inner: Runtime,
+    limit: Arc<OperationLimit>,
+}
+
+impl LimitedRuntime {
+    pub fn new(inner: Runtime, limit: Arc<OperationLimit>) -> Self {
+        LimitedRuntime { inner, limit }
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for LimitedRuntime {
+    async fn pull_if_missing(&self, image: &str) -> Result<bool, anyhow::Error> {
+        self.limit.run(self.inner.pull_if_missing(image)).await
+    }
+
+    async fn pull(&self, image: &str) -> Result<String, anyhow::Error> {
+        self.limit.run(self.inner.pull(image)).await
+    }
+
+    async fn create(
+        &self,
+        spec: &ContainerSpec,
+        options: &RunOptions,
+    ) -> Result<Container, anyhow::Error> {
+        self.limit.run(self.inner.create(spec, options)).await
+    }
+
+    async fn start(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.limit.run(self.inner.start(id)).await
+    }
+
+    async fn list_managed(&self) -> Result<Vec<Container>, anyhow::Error> {
+        self.limit.run(self.inner.list_managed()).await
+    }
+
+    async fn inspect(&self, id: &str) -> Result<Container, anyhow::Error> {
+        self.limit.run(self.inner.inspect(id)).await
+    }
+
+    async fn stop(&self, id: &str, grace_period: Option<Duration>) -> Result<(), anyhow::Error> {
+        self.limit.run(self.inner.stop(id, grace_period)).await
+    }
+
+    async fn kill(&self, id: &str, signal: &str) -> Result<(), anyhow::Error> {
+        self.limit.run(self.inner.kill(id, signal)).await
+    }
+
+    async fn exec(&self, id: &str, command: &[String]) -> Result<ExecOutput, anyhow::Error> {
+        self.inner.exec(id, command).await
+    }
+
+    async fn restart(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.limit.run(self.inner.restart(id)).await
+    }
+
+    async fn wait(&self, id: &str) -> Result<i64, anyhow::Error> {
+        self.inner.wait(id).await
+    }
+
+    async fn remove(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.limit.run(self.inner.remove(id)).await
+    }
+
+    async fn stats(&self, ids: &[String]) -> Result<Vec<ResourceUsage>, anyhow::Error> {
+        self.limit.run(self.inner.stats(ids)).await
+    }
+
+    async fn logs(&self, id: &str, tail: Option<usize>) -> Result<String, anyhow::Error> {
+        self.limit.run(self.inner.logs(id, tail)).await
+    }
+
+    async fn logs_since(
+        &self,
+        id: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LogLine>, anyhow::Error> {
+        self.limit.run(self.inner.logs_since(id, since)).await
+    }
+
+    async fn create_volume(&self, name: &str) -> Result<(), anyhow::Error> {
+        self.limit.run(self.inner.create_volume(name)).await
+    }
+
+    async fn remove_volume(&self, name: &str, force: bool) -> Result<(), anyhow::Error> {
+        self.limit.run(self.inner.remove_volume(name, force)).await
+    }
+
+    async fn list_images(&self) -> Result<Vec<Image>, anyhow::Error> {
+        self.limit.run(self.inner.list_images()).await
+    }
+
+    async fn image_disk_usage(&self) -> Result<u64, anyhow::Error> {
+        self.limit.run(self.inner.image_disk_usage()).await
+    }
+
+    async fn remove_image(&self, image: &Image) -> Result<(), anyhow::Error> {
+        self.limit.run(self.inner.remove_image(image)).await
+    }
+}
@@ -0,0 +1,591 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    pin::pin,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::{Mutex, Notify};
+
+use crate::entities::{
+    container::{
+        Container, ContainerSpec, ContainerStatus, RestartPolicy, Termination, KILL_SIGNAL,
+        MANAGED_LABEL,
+    },
+    resource_usage::ResourceUsage,
+};
+
+use super::{retry::Operation, ContainerRuntime, ExecOutput, Image, LogLine, RunOptions};
+
+const DEFAULT_IMAGE_SIZE: u64 = 100 * 1024 * 1024;
+
+/// A container in a [`MockRuntime`], with what docker would report about it.
+#[derive(Clone, Debug)]
+pub struct MockContainer {
+    pub container: Container,
+    pub options: RunOptions,
+    pub exit_code: Option<i64>,
+    pub usage: ResourceUsage,
+    pub logs: Vec<LogLine>,
+    // Keeps running after any signal but SIGKILL.
+    pub ignores_signals: bool,
+    // Commands run with `exec`, in order.
+    pub execs: Vec<Vec<String>>,
+    // What `exec` returns; a command that exits 0 without output by default.
+    pub exec_output: ExecOutput,
+}
+
+#[derive(Default)]
+struct MockState {
+    containers: BTreeMap<String, MockContainer>,
+    // Image references and the IDs they resolve to.
+    images: HashMap<String, String>,
+    // Every image pulled or pushed, by ID, including ones no reference points at anymore.
+    stored_images: BTreeMap<String, Image>,
+    volumes: BTreeSet<String>,
+    // Signals sent with `kill`, by container name, kept after the container is removed.
+    signals: HashMap<String, Vec<String>>,
+    failures: HashMap<Operation, Vec<String>>,
+    next_id: u64,
+    next_image: u64,
+}
+
+impl MockState {
+    fn fail(&mut self, operation: Operation) -> Result<(), anyhow::Error> {
+        match self.failures.get_mut(&operation) {
+            Some(errors) if !errors.is_empty() => Err(anyhow!("{}", errors.remove(0))),
+            _ => Ok(()),
+        }
+    }
+
+    fn find(&mut self, id_or_name: &str) -> Result<&mut MockContainer, anyhow::Error> {
+        self.containers
+            .values_mut()
+            .find(|mock| mock.container.id == id_or_name || mock.container.name == id_or_name)
+            .ok_or_else(|| anyhow!("Error: No such container: {}", id_or_name))
+    }
+
+    fn image_id(&mut self, image: &str) -> String {
+        if let Some(id) = self.images.get(image) {
+            return id.clone();
+        }
+        self.push_image(image)
+    }
+
+    fn push_image(&mut self, image: &str) -> String {
+        self.next_image += 1;
+        let id = format!("sha256:{:064x}", self.next_image);
+        self.images.insert(String::from(image), id.clone());
+        self.stored_images.insert(
+            id.clone(),
+            Image {
+                id: id.clone(),
+                tags: Vec::new(),
+                size_bytes: DEFAULT_IMAGE_SIZE,
+                created: Utc::now().to_rfc3339(),
+            },
+        );
+        id
+    }
+}
+
+/// An in-memory [`ContainerRuntime`] for tests. Containers only change state through the trait
+/// methods and the scripting methods below, such as [`MockRuntime::exit`], and any operation can
+/// be made to fail with [`MockRuntime::fail`].
+#[derive(Default)]
+pub struct MockRuntime {
+    state: Mutex<MockState>,
+    // Woken whenever a container exits, for `wait`.
+    exited: Notify,
+}
+
+impl MockRuntime {
+    pub fn new() -> Self {
+        MockRuntime::default()
+    }
+
+    /// Fails the next call of `operation` with `error`. Errors queued for the same operation
+    /// are returned in order, one per call.
+    pub async fn fail(&self, operation: Operation, error: &str) {
+        self.state
+            .lock()
+            .await
+            .failures
+            .entry(operation)
+            .or_default()
+            .push(String::from(error));
+    }
+
+    /// Points `image` at a new image ID, as a push to its tag would.
+    pub async fn push_image(&self, image: &str) -> String {
+        self.state.lock().await.push_image(image)
+    }
+
+    /// Sets the size reported for the image `image` currently points at, pulling it first if
+    /// needed.
+    pub async fn set_image_size(&self, image: &str, size_bytes: u64) {
+        let mut state = self.state.lock().await;
+        let id = state.image_id(image);
+        if let Some(stored) = state.stored_images.get_mut(&id) {
+            stored.size_bytes = size_bytes;
+        }
+    }
+
+    pub async fn get(&self, id_or_name: &str) -> Option<MockContainer> {
+        self.state.lock().await.find(id_or_name).ok().cloned()
+    }
+
+    /// The signals sent to the container named `name`, in order.
+    pub async fn signals(&self, name: &str) -> Vec<String> {
+        let state = self.state.lock().await;
+        state.signals.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Makes a container ignore every signal but SIGKILL, like a process without handlers for
+    /// them running as PID 1.
+    pub async fn ignore_signals(&self, id_or_name: &str) -> Result<(), anyhow::Error> {
+        self.state.lock().await.find(id_or_name)?.ignores_signals = true;
+        Ok(())
+    }
+
+    /// Sets what commands run inside the container with `exec` exit with and print.
+    pub async fn set_exec_output(
+        &self,
+        id_or_name: &str,
+        exit_code: i64,
+        output: &str,
+    ) -> Result<(), anyhow::Error> {
+        self.state.lock().await.find(id_or_name)?.exec_output = ExecOutput {
+            exit_code,
+            output: String::from(output),
+        };
+        Ok(())
+    }
+
+    pub async fn volumes(&self) -> Vec<String> {
+        self.state.lock().await.volumes.iter().cloned().collect()
+    }
+
+    pub async fn list(&self) -> Vec<MockContainer> {
+        self.state
+            .lock()
+            .await
+            .containers
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Makes a running container exit with `code`. Unlike docker it is never restarted by its
+    /// restart policy; see [`MockRuntime::crash`] for that.
+    pub async fn exit(&self, id_or_name: &str, code: i64) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        terminate(state.find(id_or_name)?, code, false);
+        self.exited.notify_waiters();
+        Ok(())
+    }
+
+    /// Kills a running container for running out of memory, with exit code 137. A container
+    /// whose restart policy restarts it shows as Restarting, like docker does while it waits
+    /// to restart it, until it is started again.
+    pub async fn oom_kill(&self, id_or_name: &str) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        let mock = state.find(id_or_name)?;
+        terminate(mock, 137, true);
+        if mock.container.spec.restart_policy != RestartPolicy::Never {
+            mock.container.set_status(ContainerStatus::Restarting);
+        }
+        self.exited.notify_waiters();
+        Ok(())
+    }
+
+    /// Makes a running container exit with `code` and, when its restart policy asks for it,
+    /// starts it again right away, as docker does between two status checks.
+    pub async fn crash(&self, id_or_name: &str, code: i64) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        let mock = state.find(id_or_name)?;
+        let restart = match mock.container.spec.restart_policy {
+            RestartPolicy::Always => true,
+            RestartPolicy::OnFailure => code != 0,
+            RestartPolicy::Never => false,
+        };
+        if restart {
+            mock.container.restart_count += 1;
+            run(&mut mock.container);
+        } else {
+            terminate(mock, code, false);
+            self.exited.notify_waiters();
+        }
+        Ok(())
+    }
+
+    pub async fn set_status(
+        &self,
+        id_or_name: &str,
+        status: ContainerStatus,
+    ) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        let mock = state.find(id_or_name)?;
+        if matches!(status, ContainerStatus::Exited | ContainerStatus::Dead) {
+            mock.exit_code.get_or_insert(0);
+            self.exited.notify_waiters();
+        }
+        mock.container.set_status(status);
+        Ok(())
+    }
+
+    /// Sets the health check status docker reports, e.g. "starting", "healthy" or "unhealthy".
+    pub async fn set_health(
+        &self,
+        id_or_name: &str,
+        health: Option<&str>,
+    ) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.find(id_or_name)?.container.health = health.map(String::from);
+        Ok(())
+    }
+
+    pub async fn set_usage(
+        &self,
+        id_or_name: &str,
+        usage: ResourceUsage,
+    ) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        let mock = state.find(id_or_name)?;
+        mock.usage = ResourceUsage {
+            container_id: mock.container.id.clone(),
+            ..usage
+        };
+        Ok(())
+    }
+
+    pub async fn write_logs(&self, id_or_name: &str, logs: &str) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        let timestamp = Utc::now();
+        state
+            .find(id_or_name)?
+            .logs
+            .extend(logs.lines().map(|text| LogLine {
+                timestamp,
+                text: String::from(text),
+            }));
+        Ok(())
+    }
+}
+
+// Like docker, the last exit is only reported while the container is down.
+fn run(container: &mut Container) {
+    container.set_status(ContainerStatus::Running);
+    container.started_at = Some(Utc::now().to_rfc3339());
+    container.last_termination = None;
+}
+
+fn terminate(mock: &mut MockContainer, code: i64, oom_killed: bool) {
+    mock.container.set_status(ContainerStatus::Exited);
+    mock.container.last_termination = Some(Termination {
+        exit_code: code,
+        oom_killed,
+        finished_at: Utc::now().to_rfc3339(),
+    });
+    mock.exit_code = Some(code);
+}
+
+#[async_trait]
+impl ContainerRuntime for MockRuntime {
+    async fn pull_if_missing(&self, image: &str) -> Result<bool, anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Pull)?;
+        let missing = !state.images.contains_key(image);
+        state.image_id(image);
+        Ok(missing)
+    }
+
+    async fn pull(&self, image: &str) -> Result<String, anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Pull)?;
+        Ok(state.image_id(image))
+    }
+
+    async fn create(
+        &self,
+        spec: &ContainerSpec,
+        options: &RunOptions,
+    ) -> Result<Container, anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Create)?;
+        if state.find(&spec.name).is_ok() {
+            return Err(anyhow!(
+                "Error response from daemon: Conflict. The container name \"/{}\" is already in use",
+                spec.name
+            ));
+        }
+
+        state.next_id += 1;
+        let id = format!("{:064x}", state.next_id);
+        let container = Container {
+            id: id.clone(),
+            name: spec.name.clone(),
+            app: spec.app_name().to_string(),
+            spec: spec.clone(),
+            node: String::new(),
+            created: Utc::now().to_rfc3339(),
+            started_at: None,
+            restart_count: 0,
+            last_restart: None,
+            back_off_until: None,
+            last_termination: None,
+            health: None,
+            image_digest: Some(state.image_id(&spec.image)),
+            status: ContainerStatus::Created,
+        };
+        state.containers.insert(
+            id.clone(),
+            MockContainer {
+                container: container.clone(),
+                options: options.clone(),
+                exit_code: None,
+                usage: ResourceUsage {
+                    container_id: id,
+                    ..ResourceUsage::default()
+                },
+                logs: Vec::new(),
+                ignores_signals: false,
+                execs: Vec::new(),
+                exec_output: ExecOutput::default(),
+            },
+        );
+        Ok(container)
+    }
+
+    async fn start(&self, id: &str) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Start)?;
+        let mock = state.find(id)?;
+        if mock.container.get_status() != ContainerStatus::Running {
+            mock.exit_code = None;
+            run(&mut mock.container);
+        }
+        Ok(())
+    }
+
+    async fn list_managed(&self) -> Result<Vec<Container>, anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::List)?;
+        Ok(state
+            .containers
+            .values()
+            .filter(|mock| {
+                mock.options.labels.get(MANAGED_LABEL).map(String::as_str) == Some("true")
+            })
+            .map(|mock| mock.container.clone())
+            .collect())
+    }
+
+    async fn inspect(&self, id: &str) -> Result<Container, anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Inspect)?;
+        Ok(state.find(id)?.container.clone())
+    }
+
+    async fn stop(&self, id: &str, _grace_period: Option<Duration>) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Stop)?;
+        let mock = state.find(id)?;
+        if mock.container.get_status() == ContainerStatus::Running {
+            terminate(mock, 0, false);
+            self.exited.notify_waiters();
+        }
+        Ok(())
+    }
+
+    async fn kill(&self, id: &str, signal: &str) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Stop)?;
+        let mock = state.find(id)?;
+        if mock.container.get_status() != ContainerStatus::Running {
+            return Err(anyhow!(
+                "Error response from daemon: container {} is not running",
+                id
+            ));
+        }
+        let name = mock.container.name.clone();
+        if signal == KILL_SIGNAL {
+            terminate(mock, 137, false);
+            self.exited.notify_waiters();
+        } else if !mock.ignores_signals {
+            terminate(mock, 0, false);
+            self.exited.notify_waiters();
+        }
+        state
+            .signals
+            .entry(name)
+            .or_default()
+            .push(String::from(signal));
+        Ok(())
+    }
+
+    async fn exec(&self, id: &str, command: &[String]) -> Result<ExecOutput, anyhow::Error> {
+        let mut state = self.state.lock().await;
+        let mock = state.find(id)?;
+        if mock.container.get_status() != ContainerStatus::Running {
+            return Err(anyhow!(
+                "Error response from daemon: container {} is not running",
+                id
+            ));
+        }
+        mock.execs.push(command.to_vec());
+        Ok(mock.exec_output.clone())
+    }
+
+    async fn restart(&self, id: &str) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Restart)?;
+        let mock = state.find(id)?;
+        mock.exit_code = None;
+        mock.container.restart_count += 1;
+        run(&mut mock.container);
+        Ok(())
+    }
+
+    async fn wait(&self, id: &str) -> Result<i64, anyhow::Error> {
+        loop {
+            // Registered before the check so an exit in between isn't missed.
+            let mut exited = pin!(self.exited.notified());
+            exited.as_mut().enable();
+            {
+                let mut state = self.state.lock().await;
+                state.fail(Operation::Wait)?;
+                if let Some(code) = state.find(id)?.exit_code {
+                    return Ok(code);
+                }
+            }
+            exited.await;
+        }
+    }
+
+    async fn remove(&self, id: &str) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Remove)?;
+        let id = state.find(id)?.container.id.clone();
+        state.containers.remove(&id);
+        // Wakes `wait` callers so they see the container is gone.
+        self.exited.notify_waiters();
+        Ok(())
+    }
+
+    async fn stats(&self, ids: &[String]) -> Result<Vec<ResourceUsage>, anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Stats)?;
+        Ok(ids
+            .iter()
+            .filter_map(|id| state.containers.get(id))
+            .filter(|mock| mock.container.get_status() == ContainerStatus::Running)
+            .map(|mock| mock.usage.clone())
+            .collect())
+    }
+
+    async fn logs(&self, id: &str, tail: Option<usize>) -> Result<String, anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Logs)?;
+        let logs = &state.find(id)?.logs;
+        let start = tail.map_or(0, |tail| logs.len().saturating_sub(tail));
+        Ok(logs[start..]
+            .iter()
+            .map(|line| format!("{}\n", line.text))
+            .collect())
+    }
+
+    async fn logs_since(
+        &self,
+        id: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LogLine>, anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Logs)?;
+        Ok(state
+            .find(id)?
+            .logs
+            .iter()
+            .filter(|line| since.is_none_or(|since| line.timestamp > since))
+            .cloned()
+            .collect())
+    }
+
+    async fn create_volume(&self, name: &str) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Volume)?;
+        state.volumes.insert(String::from(name));
+        Ok(())
+    }
+
+    // Like docker, refuses to remove a volume any container mounts, even with `force`.
+    async fn remove_volume(&self, name: &str, force: bool) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Volume)?;
+        if let Some(mock) = state
+            .containers
+            .values()
+            .find(|mock| mock.options.mounts.iter().any(|mount| mount.source == name))
+        {
+            return Err(anyhow!(
+                "Error response from daemon: remove {}: volume is in use - [{}]",
+                name,
+                mock.container.id
+            ));
+        }
+        if !state.volumes.remove(name) && !force {
+            return Err(anyhow!("Error: No such volume: {}", name));
+        }
+        Ok(())
+    }
+
+    async fn list_images(&self) -> Result<Vec<Image>, anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Image)?;
+        let mut images: Vec<Image> = state.stored_images.values().cloned().collect();
+        for image in images.iter_mut() {
+            image.tags = state
+                .images
+                .iter()
+                .filter(|(_, id)| **id == image.id)
+                .map(|(tag, _)| tag.clone())
+                .collect();
+            image.tags.sort();
+        }
+        Ok(images)
+    }
+
+    async fn image_disk_usage(&self) -> Result<u64, anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Image)?;
+        Ok(state
+            .stored_images
+            .values()
+            .map(|image| image.size_bytes)
+            .sum())
+    }
+
+    // Like docker, refuses to remove an image any container was created from.
+    async fn remove_image(&self, image: &Image) -> Result<(), anyhow::Error> {
+        let mut state = self.state.lock().await;
+        state.fail(Operation::Image)?;
+        if let Some(mock) = state
+            .containers
+            .values()
+            .find(|mock| mock.container.image_digest.as_deref() == Some(image.id.as_str()))
+        {
+            return Err(anyhow!(
+                "Error response from daemon: conflict: unable to delete {} - image is being used by container {}",
+                image.id,
+                mock.container.id
+            ));
+        }
+        if state.stored_images.remove(&image.id).is_none() {
+            return Err(anyhow!("Error: No such image: {}", image.id));
+        }
+        state.images.retain(|_, id| *id != image.id);
+        Ok(())
+    }
+}
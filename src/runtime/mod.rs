@@ -0,0 +1,148 @@
+pub mod docker;
+pub mod error;
+pub mod limit;
+pub mod mock;
+pub mod nodes;
+pub mod podman;
+pub mod remote;
+pub mod retry;
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, RuntimeBackend},
+    entities::{
+        container::{Container, ContainerSpec},
+        node::NodeCapacity,
+        resource_usage::ResourceUsage,
+    },
+};
+
+use self::{docker::DockerRuntime, podman::PodmanRuntime};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mount {
+    pub source: String,
+    pub target: String,
+    pub read_only: bool,
+    // The files under `source` when the control plane materialized them (config maps), so an
+    // agent on another host can write its own copy.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<String, String>,
+}
+
+// Values resolved by the control plane for a single run, kept out of the spec so they are never
+// persisted alongside it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RunOptions {
+    pub env: BTreeMap<String, String>,
+    pub mounts: Vec<Mount>,
+    pub labels: BTreeMap<String, String>,
+    // Shares the volumes of another container, as init containers do with theirs.
+    #[serde(default)]
+    pub volumes_from: Option<String>,
+}
+
+// A line of container output, stamped with when the runtime received it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LogLine {
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+}
+
+// An image stored on a node, with every tag that points at it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Image {
+    pub id: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub size_bytes: u64,
+    pub created: String,
+}
+
+// What a command run inside a container exited with and printed, stdout and stderr together.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecOutput {
+    pub exit_code: i64,
+    pub output: String,
+}
+
+#[async_trait]
+pub trait ContainerRuntime {
+    async fn pull_if_missing(&self, image: &str) -> Result<bool, anyhow::Error>;
+    // Pulls the image even when present and returns the ID its reference now resolves to.
+    async fn pull(&self, image: &str) -> Result<String, anyhow::Error>;
+    // Creates the container without starting it.
+    async fn create(
+        &self,
+        spec: &ContainerSpec,
+        options: &RunOptions,
+    ) -> Result<Container, anyhow::Error>;
+    async fn start(&self, id: &str) -> Result<(), anyhow::Error>;
+    async fn run(
+        &self,
+        spec: &ContainerSpec,
+        options: &RunOptions,
+    ) -> Result<Container, anyhow::Error> {
+        let container = self.create(spec, options).await?;
+        self.start(&container.id).await?;
+        Ok(container)
+    }
+    async fn list_managed(&self) -> Result<Vec<Container>, anyhow::Error>;
+    async fn inspect(&self, id: &str) -> Result<Container, anyhow::Error>;
+    async fn stop(&self, id: &str, grace_period: Option<Duration>) -> Result<(), anyhow::Error>;
+    // Sends `signal`, such as SIGTERM, without waiting for the container to exit.
+    async fn kill(&self, id: &str, signal: &str) -> Result<(), anyhow::Error>;
+    // Runs `command` inside the running container. A command that exits non-zero is not an
+    // error; failing to run it at all is.
+    async fn exec(&self, id: &str, command: &[String]) -> Result<ExecOutput, anyhow::Error>;
+    async fn restart(&self, id: &str) -> Result<(), anyhow::Error>;
+    // Blocks until the container exits and returns its exit code.
+    async fn wait(&self, id: &str) -> Result<i64, anyhow::Error>;
+    async fn remove(&self, id: &str) -> Result<(), anyhow::Error>;
+    async fn stats(&self, ids: &[String]) -> Result<Vec<ResourceUsage>, anyhow::Error>;
+    async fn logs(&self, id: &str, tail: Option<usize>) -> Result<String, anyhow::Error>;
+    // Output received after `since`, oldest first, with stdout and stderr interleaved.
+    async fn logs_since(
+        &self,
+        id: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LogLine>, anyhow::Error>;
+    // Creating a volume that already exists succeeds, as with docker.
+    async fn create_volume(&self, name: &str) -> Result<(), anyhow::Error>;
+    async fn remove_volume(&self, name: &str, force: bool) -> Result<(), anyhow::Error>;
+    async fn list_images(&self) -> Result<Vec<Image>, anyhow::Error>;
+    // Disk space taken by images, with layers they share counted once.
+    async fn image_disk_usage(&self) -> Result<u64, anyhow::Error>;
+    // Removes the image and all its tags; fails while a container uses it.
+    async fn remove_image(&self, image: &Image) -> Result<(), anyhow::Error>;
+}
+
+// The engine picked by `[runtime]` for the containers of this machine's node, with the capacity it
+// reports.
+pub async fn local(
+    config: &Config,
+) -> Result<(Arc<dyn ContainerRuntime + Send + Sync>, NodeCapacity), anyhow::Error> {
+    let host_bind_check = config.ports.host_bind_check;
+    match config.runtime.backend {
+        RuntimeBackend::Docker => {
+            let docker = DockerRuntime::new()
+                .with_host_bind_check(host_bind_check)
+                .with_engine(&config.runtime.docker)?;
+            let capacity = docker.capacity().await?;
+            Ok((Arc::new(docker), capacity))
+        }
+        RuntimeBackend::Podman => {
+            let podman = PodmanRuntime::new(&config.runtime.podman, host_bind_check).await?;
+            let capacity = podman.capacity().await?;
+            if podman.is_rootless() {
+                println!("Running containers with rootless podman");
+            }
+            Ok((Arc::new(podman), capacity))
+        }
+    }
+}
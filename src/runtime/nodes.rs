@@ -0,0 +1,437 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
+
+use crate::{
+    entities::{
+        container::{Container, ContainerSpec, ResourceRequests},
+        labels::Labels,
+        node::{Node, NodeCapacity, NodeStatus},
+        resource_usage::ResourceUsage,
+    },
+    scheduler::{Candidate, Scheduler},
+    store::state::StateStore,
+};
+
+use super::{remote::RemoteRuntime, ContainerRuntime, ExecOutput, Image, LogLine, RunOptions};
+
+pub const LOCAL_NODE: &str = "local";
+const KIND: &str = "nodes";
+
+type Runtime = Arc<dyn ContainerRuntime + Send + Sync>;
+
+// Where a container was placed, with what the scheduler needs to know about it.
+#[derive(Clone)]
+struct Assignment {
+    node: String,
+    resources: ResourceRequests,
+    labels: Labels,
+}
+
+struct NodeEntry {
+    node: Node,
+    runtime: Runtime,
+}
+
+// Routes runtime calls to the node that runs each container. Registered agents are reached
+// through a RemoteRuntime; in single-host mode the only node is the local docker runtime.
+pub struct NodeRuntime {
+    state: Arc<StateStore>,
+    nodes: RwLock<BTreeMap<String, NodeEntry>>,
+    // Which node each container runs on and what it requested there.
+    owners: RwLock<HashMap<String, Assignment>>,
+    // Per node, what its containers used at the last stats collection.
+    usage: RwLock<HashMap<String, ResourceRequests>>,
+    scheduler: Scheduler,
+}
+
+impl NodeRuntime {
+    pub fn new(state: Arc<StateStore>, scheduler: Scheduler) -> Self {
+        NodeRuntime {
+            state,
+            nodes: RwLock::new(BTreeMap::new()),
+            owners: RwLock::new(HashMap::new()),
+            usage: RwLock::new(HashMap::new()),
+            scheduler,
+        }
+    }
+
+    pub async fn add_local(&self, runtime: Runtime, capacity: NodeCapacity, labels: Labels) {
+        self.nodes.write().await.insert(
+            String::from(LOCAL_NODE),
+            NodeEntry {
+                node: Node::local(capacity, labels),
+                runtime,
+            },
+        );
+    }
+
+    // Reconnects to the agents registered before the last restart.
+    pub async fn load(&self) -> Result<(), anyhow::Error> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut nodes = self.nodes.write().await;
+
+        for mut node in self.state.list::<Node>(KIND).await? {
+            let Some(address) = node.address.clone() else {
+                continue;
+            };
+            // Agents get a full heartbeat window after a restart before they are marked NotReady.
+            node.status = NodeStatus::Unknown;
+            node.last_seen = now.clone();
+            let runtime = Arc::new(RemoteRuntime::new(&address, node.token.clone()));
+            nodes.insert(node.name.clone(), NodeEntry { node, runtime });
+        }
+        Ok(())
+    }
+
+    // Agents register on start and then keep calling this as their heartbeat.
+    pub async fn register(&self, mut node: Node) -> Result<Node, anyhow::Error> {
+        if node.name == LOCAL_NODE {
+            return Err(anyhow!("node name {} is reserved", LOCAL_NODE));
+        }
+        let address = node
+            .address
+            .clone()
+            .ok_or_else(|| anyhow!("node {} has no address", node.name))?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut nodes = self.nodes.write().await;
+        let existing = nodes.get(&node.name).map(|entry| &entry.node);
+
+        let changed = existing.is_none_or(|existing| {
+            existing.address != node.address
+                || existing.capacity != node.capacity
+                || existing.labels != node.labels
+                || existing.token != node.token
+        });
+        node.registered = existing.map_or(now.clone(), |existing| existing.registered.clone());
+        node.status = existing.map_or(NodeStatus::Unknown, |existing| existing.status);
+        node.last_seen = now;
+
+        // Heartbeats only touch memory; the node is persisted when it first registers or changes.
+        if changed {
+            println!("Registered node {} at {}", node.name, address);
+            self.state.put(KIND, &node.name, &node).await?;
+        }
+
+        let runtime = match nodes.get(&node.name) {
+            Some(entry) if !changed => entry.runtime.clone(),
+            _ => Arc::new(RemoteRuntime::new(&address, node.token.clone())),
+        };
+        nodes.insert(
+            node.name.clone(),
+            NodeEntry {
+                node: node.clone(),
+                runtime,
+            },
+        );
+        Ok(node)
+    }
+
+    pub async fn deregister(&self, name: &str) -> Result<bool, anyhow::Error> {
+        let removed = self.nodes.write().await.remove(name).is_some();
+        self.owners
+            .write()
+            .await
+            .retain(|_, assignment| assignment.node != name);
+        Ok(self.state.delete(KIND, name).await? || removed)
+    }
+
+    pub async fn list(&self) -> Vec<Node> {
+        self.nodes
+            .read()
+            .await
+            .values()
+            .map(|entry| Node {
+                token: None,
+                ..entry.node.clone()
+            })
+            .collect()
+    }
+
+    // Returns the previous status.
+    pub async fn set_status(&self, name: &str, status: NodeStatus) -> Option<NodeStatus> {
+        let mut nodes = self.nodes.write().await;
+        let entry = nodes.get_mut(name)?;
+        Some(std::mem::replace(&mut entry.node.status, status))
+    }
+
+    pub async fn node(&self, name: &str) -> Result<Runtime, anyhow::Error> {
+        self.nodes
+            .read()
+            .await
+            .get(name)
+            .map(|entry| entry.runtime.clone())
+            .ok_or_else(|| anyhow!("node {} not found", name))
+    }
+
+    // Honors `spec.node`, which bypasses the resource checks, and otherwise leaves the choice
+    // among the ready nodes to the scheduler.
+    pub async fn schedule(&self, spec: &ContainerSpec) -> Result<String, anyhow::Error> {
+        let nodes = self.nodes.read().await;
+
+        if let Some(name) = &spec.node {
+            let entry = nodes
+                .get(name)
+                .ok_or_else(|| anyhow!("node {} not found", name))?;
+            if !entry.node.is_schedulable() {
+                return Err(anyhow!("node {} is not ready", name));
+            }
+            return Ok(name.clone());
+        }
+
+        let owners = self.owners.read().await;
+        let usage = self.usage.read().await;
+        let candidates: Vec<Candidate> = nodes
+            .values()
+            .filter(|entry| entry.node.is_schedulable())
+            .map(|entry| {
+                let name = &entry.node.name;
+                let mut allocated = ResourceRequests::default();
+                let mut container_labels = Vec::new();
+                for assignment in owners
+                    .values()
+                    .filter(|assignment| &assignment.node == name)
+                {
+                    allocated.cpus += assignment.resources.cpus;
+                    allocated.memory_bytes += assignment.resources.memory_bytes;
+                    container_labels.push(assignment.labels.clone());
+                }
+
+                Candidate {
+                    name: name.clone(),
+                    labels: entry.node.labels.clone(),
+                    capacity: entry.node.capacity.clone(),
+                    allocated,
+                    used: usage.get(name).cloned().unwrap_or_default(),
+                    containers: container_labels.len(),
+                    container_labels,
+                }
+            })
+            .collect();
+
+        self.scheduler.select(spec, &candidates)
+    }
+
+    pub async fn assign(&self, container: &Container) {
+        self.owners.write().await.insert(
+            container.id.clone(),
+            Assignment {
+                node: container.node.clone(),
+                resources: container.spec.resources.clone(),
+                labels: container.spec.all_labels(),
+            },
+        );
+    }
+
+    fn runtimes(nodes: &BTreeMap<String, NodeEntry>) -> Vec<(String, Runtime)> {
+        nodes
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.runtime.clone()))
+            .collect()
+    }
+
+    async fn owner(&self, id: &str) -> Result<(String, Runtime), anyhow::Error> {
+        let owner = self.owners.read().await.get(id).cloned();
+        if let Some(Assignment { node, .. }) = owner {
+            return Ok((node.clone(), self.node(&node).await?));
+        }
+
+        // Containers that were never listed, like job containers after a restart, are found by
+        // asking every node.
+        let nodes = Self::runtimes(&*self.nodes.read().await);
+        for (node, runtime) in nodes {
+            if let Ok(mut container) = runtime.inspect(id).await {
+                container.node = node.clone();
+                self.assign(&container).await;
+                return Ok((node, runtime));
+            }
+        }
+
+        Err(anyhow!("container {} is not running on any node", id))
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for NodeRuntime {
+    // Pulls on every schedulable node, since the image is needed wherever the container lands.
+    async fn pull_if_missing(&self, image: &str) -> Result<bool, anyhow::Error> {
+        let nodes: Vec<Runtime> = self
+            .nodes
+            .read()
+            .await
+            .values()
+            .filter(|entry| entry.node.is_schedulable())
+            .map(|entry| entry.runtime.clone())
+            .collect();
+
+        let mut pulled = false;
+        for runtime in nodes {
+            pulled |= runtime.pull_if_missing(image).await?;
+        }
+        Ok(pulled)
+    }
+
+    // Like `pull_if_missing`, on every schedulable node. Returns the ID the last node resolved.
+    async fn pull(&self, image: &str) -> Result<String, anyhow::Error> {
+        let nodes: Vec<Runtime> = self
+            .nodes
+            .read()
+            .await
+            .values()
+            .filter(|entry| entry.node.is_schedulable())
+            .map(|entry| entry.runtime.clone())
+            .collect();
+
+        let mut digest = None;
+        for runtime in nodes {
+            digest = Some(runtime.pull(image).await?);
+        }
+        digest.ok_or_else(|| anyhow!("no node is ready to pull image {}", image))
+    }
+
+    async fn create(
+        &self,
+        spec: &ContainerSpec,
+        options: &RunOptions,
+    ) -> Result<Container, anyhow::Error> {
+        let node = self.schedule(spec).await?;
+        let mut container = self.node(&node).await?.create(spec, options).await?;
+
+        container.node = node;
+        self.assign(&container).await;
+        Ok(container)
+    }
+
+    async fn start(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.owner(id).await?.1.start(id).await
+    }
+
+    async fn list_managed(&self) -> Result<Vec<Container>, anyhow::Error> {
+        let nodes = Self::runtimes(&*self.nodes.read().await);
+
+        let mut containers = Vec::new();
+        for (node, runtime) in nodes {
+            match runtime.list_managed().await {
+                Ok(listed) => {
+                    for mut container in listed {
+                        container.node = node.clone();
+                        self.assign(&container).await;
+                        containers.push(container);
+                    }
+                }
+                Err(error) => println!("Failed to list containers on node {}: {}", node, error),
+            }
+        }
+
+        Ok(containers)
+    }
+
+    async fn inspect(&self, id: &str) -> Result<Container, anyhow::Error> {
+        let (node, runtime) = self.owner(id).await?;
+        let mut container = runtime.inspect(id).await?;
+        container.node = node;
+        Ok(container)
+    }
+
+    async fn stop(&self, id: &str, grace_period: Option<Duration>) -> Result<(), anyhow::Error> {
+        self.owner(id).await?.1.stop(id, grace_period).await
+    }
+
+    async fn kill(&self, id: &str, signal: &str) -> Result<(), anyhow::Error> {
+        self.owner(id).await?.1.kill(id, signal).await
+    }
+
+    async fn exec(&self, id: &str, command: &[String]) -> Result<ExecOutput, anyhow::Error> {
+        self.owner(id).await?.1.exec(id, command).await
+    }
+
+    async fn restart(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.owner(id).await?.1.restart(id).await
+    }
+
+    async fn wait(&self, id: &str) -> Result<i64, anyhow::Error> {
+        self.owner(id).await?.1.wait(id).await
+    }
+
+    async fn remove(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.owner(id).await?.1.remove(id).await?;
+        self.owners.write().await.remove(id);
+        Ok(())
+    }
+
+    async fn stats(&self, ids: &[String]) -> Result<Vec<ResourceUsage>, anyhow::Error> {
+        let mut by_node: BTreeMap<String, (Runtime, Vec<String>)> = BTreeMap::new();
+        for id in ids {
+            let Ok((node, runtime)) = self.owner(id).await else {
+                continue;
+            };
+            by_node
+                .entry(node)
+                .or_insert_with(|| (runtime, Vec::new()))
+                .1
+                .push(id.clone());
+        }
+
+        let mut usage = Vec::new();
+        let mut node_usage = HashMap::new();
+        for (node, (runtime, ids)) in by_node {
+            match runtime.stats(&ids).await {
+                Ok(stats) => {
+                    let mut used = ResourceRequests::default();
+                    for container in stats.iter() {
+                        used.cpus += container.cpu_percent / 100.0;
+                        used.memory_bytes += container.memory_bytes;
+                    }
+                    node_usage.insert(node, used);
+                    usage.extend(stats);
+                }
+                Err(error) => println!("Failed to collect stats on node {}: {}", node, error),
+            }
+        }
+
+        *self.usage.write().await = node_usage;
+        Ok(usage)
+    }
+
+    async fn logs(&self, id: &str, tail: Option<usize>) -> Result<String, anyhow::Error> {
+        self.owner(id).await?.1.logs(id, tail).await
+    }
+
+    async fn logs_since(
+        &self,
+        id: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LogLine>, anyhow::Error> {
+        self.owner(id).await?.1.logs_since(id, since).await
+    }
+
+    // Volumes belong to a node rather than a container, so callers pick the node's runtime.
+    async fn create_volume(&self, name: &str) -> Result<(), anyhow::Error> {
+        Err(anyhow!("volume {} must be created on a node", name))
+    }
+
+    async fn remove_volume(&self, name: &str, _force: bool) -> Result<(), anyhow::Error> {
+        Err(anyhow!("volume {} must be removed from its node", name))
+    }
+
+    // Like volumes, images are managed per node.
+    async fn list_images(&self) -> Result<Vec<Image>, anyhow::Error> {
+        Err(anyhow!("images must be listed on a node"))
+    }
+
+    async fn image_disk_usage(&self) -> Result<u64, anyhow::Error> {
+        Err(anyhow!("image disk usage must be checked on a node"))
+    }
+
+    async fn remove_image(&self, image: &Image) -> Result<(), anyhow::Error> {
+        Err(anyhow!("image {} must be removed from its node", image.id))
+    }
+}
@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    config::PodmanConfig,
+    entities::{
+        container::{Container, ContainerSpec},
+        node::NodeCapacity,
+        ports::{HostPorts, PortConflict},
+        resource_usage::ResourceUsage,
+    },
+};
+
+use super::{
+    docker::{parse_capacity, DockerRuntime},
+    ContainerRuntime, ExecOutput, Image, LogLine, RunOptions,
+};
+
+// Where Linux keeps the lowest port unprivileged processes can bind.
+const UNPRIVILEGED_PORT_START: &str = "/proc/sys/net/ipv4/ip_unprivileged_port_start";
+const DEFAULT_UNPRIVILEGED_PORT_START: u16 = 1024;
+
+// Runs containers through the podman CLI, which takes docker's commands and formats for everything
+// but `info`. Rootless podman can't bind host ports below the unprivileged port start, and would
+// only fail once the container starts, so such containers are refused when they're created.
+pub struct PodmanRuntime {
+    cli: DockerRuntime,
+    rootless: bool,
+    // The lowest host port containers can publish; 0 when podman runs as root.
+    lowest_port: u16,
+}
+
+impl PodmanRuntime {
+    pub async fn new(config: &PodmanConfig, host_bind_check: bool) -> Result<Self, anyhow::Error> {
+        let mut cli = DockerRuntime::new()
+            .with_program("podman")
+            .with_host_bind_check(host_bind_check);
+        match (&config.host, &config.connection) {
+            (Some(_), Some(_)) => {
+                return Err(anyhow::anyhow!(
+                    "runtime.podman.host and runtime.podman.connection can't both be set"
+                ))
+            }
+            (Some(host), None) => {
+                cli = cli
+                    .with_env("CONTAINER_CONNECTION", None)
+                    .with_host("CONTAINER_HOST", host)?;
+            }
+            (None, Some(connection)) => {
+                cli = cli
+                    .with_env("CONTAINER_HOST", None)
+                    .with_env("CONTAINER_CONNECTION", Some(connection.clone()));
+            }
+            (None, None) => {}
+        }
+
+        let rootless = cli
+            .docker(&["info", "--format", "{{.Host.Security.Rootless}}"])
+            .await?
+            .trim()
+            == "true";
+        let lowest_port = match rootless {
+            false => 0,
+            // Only this machine's setting is known; a remote engine gets the kernel default.
+            true if cli.is_remote() => DEFAULT_UNPRIVILEGED_PORT_START,
+            true => tokio::fs::read_to_string(UNPRIVILEGED_PORT_START)
+                .await
+                .ok()
+                .and_then(|start| start.trim().parse().ok())
+                .unwrap_or(DEFAULT_UNPRIVILEGED_PORT_START),
+        };
+        Ok(PodmanRuntime {
+            cli,
+            rootless,
+            lowest_port,
+        })
+    }
+
+    pub fn is_rootless(&self) -> bool {
+        self.rootless
+    }
+
+    pub async fn capacity(&self) -> Result<NodeCapacity, anyhow::Error> {
+        let out = self
+            .cli
+            .docker(&["info", "--format", "{{.Host.CPUs}}\t{{.Host.MemTotal}}"])
+            .await?;
+        parse_capacity(&out)
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for PodmanRuntime {
+    async fn pull_if_missing(&self, image: &str) -> Result<bool, anyhow::Error> {
+        self.cli.pull_if_missing(image).await
+    }
+
+    async fn pull(&self, image: &str) -> Result<String, anyhow::Error> {
+        self.cli.pull(image).await
+    }
+
+    async fn create(
+        &self,
+        spec: &ContainerSpec,
+        options: &RunOptions,
+    ) -> Result<Container, anyhow::Error> {
+        if let Some(ports) = HostPorts::parse(&spec.ports) {
+            if *ports.ports.start() < self.lowest_port {
+                return Err(PortConflict::Privileged {
+                    container: spec.name.clone(),
+                    ports,
+                    lowest: self.lowest_port,
+                }
+                .into());
+            }
+        }
+        self.cli.create(spec, options).await
+    }
+
+    async fn start(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.cli.start(id).await
+    }
+
+    async fn list_managed(&self) -> Result<Vec<Container>, anyhow::Error> {
+        self.cli.list_managed().await
+    }
+
+    async fn inspect(&self, id: &str) -> Result<Container, anyhow::Error> {
+        self.cli.inspect(id).await
+    }
+
+    async fn stop(&self, id: &str, grace_period: Option<Duration>) -> Result<(), anyhow::Error> {
+        self.cli.stop(id, grace_period).await
+    }
+
+    async fn kill(&self, id: &str, signal: &str) -> Result<(), anyhow::Error> {
+        self.cli.kill(id, signal).await
+    }
+
+    async fn exec(&self, id: &str, command: &[String]) -> Result<ExecOutput, anyhow::Error> {
+        self.cli.exec(id, command).await
+    }
+
+    async fn restart(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.cli.restart(id).await
+    }
+
+    async fn wait(&self, id: &str) -> Result<i64, anyhow::Error> {
+        self.cli.wait(id).await
+    }
+
+    async fn remove(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.cli.remove(id).await
+    }
+
+    async fn stats(&self, ids: &[String]) -> Result<Vec<ResourceUsage>, anyhow::Error> {
+        self.cli.stats(ids).await
+    }
+
+    async fn logs(&self, id: &str, tail: Option<usize>) -> Result<String, anyhow::Error> {
+        self.cli.logs(id, tail).await
+    }
+
+    async fn logs_since(
+        &self,
+        id: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LogLine>, anyhow::Error> {
+        self.cli.logs_since(id, since).await
+    }
+
+    async fn create_volume(&self, name: &str) -> Result<(), anyhow::Error> {
+        self.cli.create_volume(name).await
+    }
+
+    async fn remove_volume(&self, name: &str, force: bool) -> Result<(), anyhow::Error> {
+        self.cli.remove_volume(name, force).await
+    }
+
+    async fn list_images(&self) -> Result<Vec<Image>, anyhow::Error> {
+        self.cli.list_images().await
+    }
+
+    async fn image_disk_usage(&self) -> Result<u64, anyhow::Error> {
+        self.cli.image_disk_usage().await
+    }
+
+    async fn remove_image(&self, image: &Image) -> Result<(), anyhow::Error> {
+        self.cli.remove_image(image).await
+    }
+}
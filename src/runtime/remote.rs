@@ -0,0 +1,260 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::entities::{
+    container::{Container, ContainerSpec},
+    resource_usage::ResourceUsage,
+};
+
+use super::{ContainerRuntime, ExecOutput, Image, LogLine, RunOptions};
+
+// Keeps calls to an agent that went away from hanging; `wait` and `logs` can legitimately take
+// long once connected, so there is no overall request timeout.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize)]
+pub struct PullRequest {
+    pub image: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateRequest {
+    pub spec: ContainerSpec,
+    pub options: RunOptions,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StopRequest {
+    pub grace_period_seconds: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct KillRequest {
+    pub signal: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExecRequest {
+    pub command: Vec<String>,
+}
+
+// Runs containers on a node agent through its runtime API, authenticating with the token the
+// agent registered with.
+pub struct RemoteRuntime {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl RemoteRuntime {
+    pub fn new(address: &str, token: Option<String>) -> Self {
+        let base_url = if address.starts_with("http://") || address.starts_with("https://") {
+            address.trim_end_matches('/').to_string()
+        } else {
+            format!("http://{}", address)
+        };
+
+        let mut headers = HeaderMap::new();
+        if let Some(mut authorization) =
+            token.and_then(|token| HeaderValue::from_str(&format!("Bearer {}", token)).ok())
+        {
+            authorization.set_sensitive(true);
+            headers.insert(AUTHORIZATION, authorization);
+        }
+        RemoteRuntime {
+            base_url,
+            http: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .default_headers(headers)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, anyhow::Error> {
+        let response = request
+            .send()
+            .await
+            .map_err(|error| anyhow!("agent {} unreachable: {}", self.base_url, error))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "agent {}: {}: {}",
+                self.base_url,
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        Ok(response)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, anyhow::Error> {
+        let request = self.http.get(format!("{}{}", self.base_url, path));
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, anyhow::Error> {
+        let request = self
+            .http
+            .post(format!("{}{}", self.base_url, path))
+            .json(body);
+        Ok(self.send(request).await?.json().await?)
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for RemoteRuntime {
+    async fn pull_if_missing(&self, image: &str) -> Result<bool, anyhow::Error> {
+        self.post(
+            "/runtime/pull",
+            &PullRequest {
+                image: String::from(image),
+            },
+        )
+        .await
+    }
+
+    async fn pull(&self, image: &str) -> Result<String, anyhow::Error> {
+        self.post(
+            "/runtime/images/pull",
+            &PullRequest {
+                image: String::from(image),
+            },
+        )
+        .await
+    }
+
+    async fn create(
+        &self,
+        spec: &ContainerSpec,
+        options: &RunOptions,
+    ) -> Result<Container, anyhow::Error> {
+        self.post(
+            "/runtime/containers",
+            &CreateRequest {
+                spec: spec.clone(),
+                options: options.clone(),
+            },
+        )
+        .await
+    }
+
+    async fn start(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.post(&format!("/runtime/containers/{}/start", id), &())
+            .await
+    }
+
+    async fn list_managed(&self) -> Result<Vec<Container>, anyhow::Error> {
+        self.get("/runtime/containers").await
+    }
+
+    async fn inspect(&self, id: &str) -> Result<Container, anyhow::Error> {
+        self.get(&format!("/runtime/containers/{}", id)).await
+    }
+
+    async fn stop(&self, id: &str, grace_period: Option<Duration>) -> Result<(), anyhow::Error> {
+        let body = StopRequest {
+            grace_period_seconds: grace_period.map(|grace_period| grace_period.as_secs()),
+        };
+        self.post(&format!("/runtime/containers/{}/stop", id), &body)
+            .await
+    }
+
+    async fn kill(&self, id: &str, signal: &str) -> Result<(), anyhow::Error> {
+        let body = KillRequest {
+            signal: String::from(signal),
+        };
+        self.post(&format!("/runtime/containers/{}/kill", id), &body)
+            .await
+    }
+
+    async fn exec(&self, id: &str, command: &[String]) -> Result<ExecOutput, anyhow::Error> {
+        let body = ExecRequest {
+            command: command.to_vec(),
+        };
+        self.post(&format!("/runtime/containers/{}/exec", id), &body)
+            .await
+    }
+
+    async fn restart(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.post(&format!("/runtime/containers/{}/restart", id), &())
+            .await
+    }
+
+    async fn wait(&self, id: &str) -> Result<i64, anyhow::Error> {
+        self.post(&format!("/runtime/containers/{}/wait", id), &())
+            .await
+    }
+
+    async fn remove(&self, id: &str) -> Result<(), anyhow::Error> {
+        let request = self
+            .http
+            .delete(format!("{}/runtime/containers/{}", self.base_url, id));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    async fn stats(&self, ids: &[String]) -> Result<Vec<ResourceUsage>, anyhow::Error> {
+        self.post("/runtime/stats", &ids).await
+    }
+
+    async fn logs(&self, id: &str, tail: Option<usize>) -> Result<String, anyhow::Error> {
+        let mut url = format!("{}/runtime/containers/{}/logs", self.base_url, id);
+        if let Some(tail) = tail {
+            url.push_str(&format!("?tail={}", tail));
+        }
+        Ok(self.send(self.http.get(url)).await?.text().await?)
+    }
+
+    async fn logs_since(
+        &self,
+        id: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LogLine>, anyhow::Error> {
+        let mut path = format!("/runtime/containers/{}/log-lines", id);
+        if let Some(since) = since {
+            path.push_str(&format!(
+                "?since={}",
+                since.to_rfc3339_opts(SecondsFormat::Nanos, true)
+            ));
+        }
+        self.get(&path).await
+    }
+
+    async fn create_volume(&self, name: &str) -> Result<(), anyhow::Error> {
+        self.post(&format!("/runtime/volumes/{}", name), &()).await
+    }
+
+    async fn remove_volume(&self, name: &str, force: bool) -> Result<(), anyhow::Error> {
+        let request = self.http.delete(format!(
+            "{}/runtime/volumes/{}?force={}",
+            self.base_url, name, force
+        ));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    async fn list_images(&self) -> Result<Vec<Image>, anyhow::Error> {
+        self.get("/runtime/images").await
+    }
+
+    async fn image_disk_usage(&self) -> Result<u64, anyhow::Error> {
+        self.get("/runtime/images/usage").await
+    }
+
+    async fn remove_image(&self, image: &Image) -> Result<(), anyhow::Error> {
+        self.post("/runtime/images/remove", image).await
+    }
+}
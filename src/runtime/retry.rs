@@ -0,0 +1,344 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    io,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    config::{RetryConfig, RetryOverride},
+    entities::{
+        container::{Container, ContainerSpec},
+        resource_usage::ResourceUsage,
+    },
+};
+
+use super::{error::RuntimeError, ContainerRuntime, ExecOutput, Image, LogLine, RunOptions};
+
+type Runtime = Arc<dyn ContainerRuntime + Send + Sync>;
+
+// Errors that usually go away on their own: the daemon restarting, a dropped socket, a registry
+// rate limit. Anything else (missing containers, name conflicts, bad specs) fails right away.
+const TRANSIENT_ERRORS: &[&str] = &[
+    "cannot connect to the docker daemon",
+    "is the docker daemon running",
+    "connection refused",
+    "connection reset",
+    "broken pipe",
+    "i/o timeout",
+    "tls handshake timeout",
+    "context deadline exceeded",
+    "unexpected eof",
+    "service unavailable",
+    "toomanyrequests",
+    "unreachable",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Pull,
+    Create,
+    Start,
+    List,
+    Inspect,
+    Stop,
+    Restart,
+    Wait,
+    Remove,
+    Stats,
+    Logs,
+    Volume,
+    Image,
+}
+
+impl Operation {
+    const ALL: [Operation; 13] = [
+        Operation::Pull,
+        Operation::Create,
+        Operation::Start,
+        Operation::List,
+        Operation::Inspect,
+        Operation::Stop,
+        Operation::Restart,
+        Operation::Wait,
+        Operation::Remove,
+        Operation::Stats,
+        Operation::Logs,
+        Operation::Volume,
+        Operation::Image,
+    ];
+
+    // As written in the `[retry.operations]` config table.
+    fn name(&self) -> &'static str {
+        match self {
+            Operation::Pull => "pull",
+            Operation::Create => "create",
+            Operation::Start => "start",
+            Operation::List => "list",
+            Operation::Inspect => "inspect",
+            Operation::Stop => "stop",
+            Operation::Restart => "restart",
+            Operation::Wait => "wait",
+            Operation::Remove => "remove",
+            Operation::Stats => "stats",
+            Operation::Logs => "logs",
+            Operation::Volume => "volume",
+            Operation::Image => "image",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    // Including the first try, so 1 disables retries.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    pub jitter: bool,
+}
+
+impl RetryPolicy {
+    fn new(config: &RetryConfig, overrides: Option<&RetryOverride>) -> Self {
+        let overrides = overrides.cloned().unwrap_or_default();
+        RetryPolicy {
+            max_attempts: overrides.max_attempts.unwrap_or(config.max_attempts).max(1),
+            initial_backoff: Duration::from_millis(
+                overrides
+                    .initial_backoff_ms
+                    .unwrap_or(config.initial_backoff_ms),
+            ),
+            max_backoff: Duration::from_millis(
+                overrides.max_backoff_ms.unwrap_or(config.max_backoff_ms),
+            ),
+            multiplier: overrides.multiplier.unwrap_or(config.multiplier).max(1.0),
+            jitter: overrides.jitter.unwrap_or(config.jitter),
+        }
+    }
+
+    // The wait after the given failed attempt (1-based). With jitter it is picked at random from
+    // the upper half, so callers failing together don't all retry at the same moment.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32) as i32;
+        let backoff = self
+            .initial_backoff
+            .mul_f64(self.multiplier.powi(exponent))
+            .min(self.max_backoff);
+        if !self.jitter {
+            return backoff;
+        }
+
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        backoff.mul_f64(0.5 + random / 2.0)
+    }
+}
+
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    for cause in error.chain() {
+        if let Some(error) = cause.downcast_ref::<RuntimeError>() {
+            if error.is_transient() {
+                return true;
+            }
+        }
+        if let Some(error) = cause.downcast_ref::<io::Error>() {
+            if matches!(
+                error.kind(),
+                io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::UnexpectedEof
+            ) {
+                return true;
+            }
+        }
+        if let Some(error) = cause.downcast_ref::<reqwest::Error>() {
+            if error.is_connect() || error.is_timeout() {
+                return true;
+            }
+        }
+    }
+
+    let message = format!("{:#}", error).to_lowercase();
+    TRANSIENT_ERRORS
+        .iter()
+        .any(|transient| message.contains(transient))
+}
+
+// Retries transient failures of the wrapped runtime, with a policy per operation.
+pub struct RetryingRuntime {
+    inner: Runtime,
+    policies: HashMap<Operation, RetryPolicy>,
+}
+
+impl RetryingRuntime {
+    pub fn new(inner: Runtime, config: &RetryConfig) -> Result<Self, anyhow::Error> {
+        if let Some(unknown) = config
+            .operations
+            .keys()
+            .find(|name| !Operation::ALL.iter().any(|op| op.name() == name.as_str()))
+        {
+            let names: Vec<&str> = Operation::ALL.iter().map(|op| op.name()).collect();
+            return Err(anyhow!(
+                "unknown retry operation {}, expected one of: {}",
+                unknown,
+                names.join(", ")
+            ));
+        }
+
+        let policies = Operation::ALL
+            .iter()
+            .map(|op| {
+                (
+                    *op,
+                    RetryPolicy::new(config, config.operations.get(op.name())),
+                )
+            })
+            .collect();
+        Ok(RetryingRuntime { inner, policies })
+    }
+
+    async fn retry<T, F, Fut>(&self, op: Operation, call: F) -> Result<T, anyhow::Error>
+    where
+        F: Fn() -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T, anyhow::Error>> + Send,
+        T: Send,
+    {
+        let policy = &self.policies[&op];
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Ok(value) => return Ok(value),
+                Err(error) if attempt < policy.max_attempts && is_transient(&error) => {
+                    let backoff = policy.backoff(attempt);
+                    println!(
+                        "Runtime {} failed (attempt {}/{}), retrying in {}ms: {}",
+                        op.name(),
+                        attempt,
+                        policy.max_attempts,
+                        backoff.as_millis(),
+                        error
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for RetryingRuntime {
+    async fn pull_if_missing(&self, image: &str) -> Result<bool, anyhow::Error> {
+        self.retry(Operation::Pull, || self.inner.pull_if_missing(image))
+            .await
+    }
+
+    async fn pull(&self, image: &str) -> Result<String, anyhow::Error> {
+        self.retry(Operation::Pull, || self.inner.pull(image)).await
+    }
+
+    async fn create(
+        &self,
+        spec: &ContainerSpec,
+        options: &RunOptions,
+    ) -> Result<Container, anyhow::Error> {
+        self.retry(Operation::Create, || self.inner.create(spec, options))
+            .await
+    }
+
+    async fn start(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.retry(Operation::Start, || self.inner.start(id)).await
+    }
+
+    async fn list_managed(&self) -> Result<Vec<Container>, anyhow::Error> {
+        self.retry(Operation::List, || self.inner.list_managed())
+            .await
+    }
+
+    async fn inspect(&self, id: &str) -> Result<Container, anyhow::Error> {
+        self.retry(Operation::Inspect, || self.inner.inspect(id))
+            .await
+    }
+
+    async fn stop(&self, id: &str, grace_period: Option<Duration>) -> Result<(), anyhow::Error> {
+        self.retry(Operation::Stop, || self.inner.stop(id, grace_period))
+            .await
+    }
+
+    async fn kill(&self, id: &str, signal: &str) -> Result<(), anyhow::Error> {
+        self.retry(Operation::Stop, || self.inner.kill(id, signal))
+            .await
+    }
+
+    // Not retried: running the command twice may not be safe.
+    async fn exec(&self, id: &str, command: &[String]) -> Result<ExecOutput, anyhow::Error> {
+        self.inner.exec(id, command).await
+    }
+
+    async fn restart(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.retry(Operation::Restart, || self.inner.restart(id))
+            .await
+    }
+
+    async fn wait(&self, id: &str) -> Result<i64, anyhow::Error> {
+        self.retry(Operation::Wait, || self.inner.wait(id)).await
+    }
+
+    async fn remove(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.retry(Operation::Remove, || self.inner.remove(id))
+            .await
+    }
+
+    async fn stats(&self, ids: &[String]) -> Result<Vec<ResourceUsage>, anyhow::Error> {
+        self.retry(Operation::Stats, || self.inner.stats(ids)).await
+    }
+
+    async fn logs(&self, id: &str, tail: Option<usize>) -> Result<String, anyhow::Error> {
+        self.retry(Operation::Logs, || self.inner.logs(id, tail))
+            .await
+    }
+
+    async fn logs_since(
+        &self,
+        id: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<LogLine>, anyhow::Error> {
+        self.retry(Operation::Logs, || self.inner.logs_since(id, since))
+            .await
+    }
+
+    async fn create_volume(&self, name: &str) -> Result<(), anyhow::Error> {
+        self.retry(Operation::Volume, || self.inner.create_volume(name))
+            .await
+    }
+
+    async fn remove_volume(&self, name: &str, force: bool) -> Result<(), anyhow::Error> {
+        self.retry(Operation::Volume, || self.inner.remove_volume(name, force))
+            .await
+    }
+
+    async fn list_images(&self) -> Result<Vec<Image>, anyhow::Error> {
+        self.retry(Operation::Image, || self.inner.list_images())
+            .await
+    }
+
+    async fn image_disk_usage(&self) -> Result<u64, anyhow::Error> {
+        self.retry(Operation::Image, || self.inner.image_disk_usage())
+            .await
+    }
+
+    async fn remove_image(&self, image: &Image) -> Result<(), anyhow::Error> {
+        self.retry(Operation::Image, || self.inner.remove_image(image))
+            .await
+    }
+}
@@ -0,0 +1,230 @@
+pub mod scoring;
+
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+
+use crate::entities::{
+    container::{AffinityTerm, ContainerSpec, ResourceRequests},
+    labels::Labels,
+    node::NodeCapacity,
+    resource_usage::format_size,
+};
+
+use self::scoring::ScoringStrategy;
+
+// A ready node as the scheduler sees it when placing one container.
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub name: String,
+    pub labels: Labels,
+    pub capacity: NodeCapacity,
+    // The sum of the requests of the containers already on the node.
+    pub allocated: ResourceRequests,
+    // What those containers actually use, from the last stats collection.
+    pub used: ResourceRequests,
+    pub containers: usize,
+    // The labels of those containers, for affinity rules.
+    pub container_labels: Vec<Labels>,
+}
+
+impl Candidate {
+    // A container may use more than it requested, so whichever is higher counts as load.
+    fn load(&self) -> ResourceRequests {
+        ResourceRequests {
+            cpus: self.allocated.cpus.max(self.used.cpus),
+            memory_bytes: self.allocated.memory_bytes.max(self.used.memory_bytes),
+        }
+    }
+
+    // Nodes that did not report a capacity are treated as unlimited.
+    pub fn fits(&self, request: &ResourceRequests) -> bool {
+        let load = self.load();
+        let cpus = self.capacity.cpus <= 0.0 || load.cpus + request.cpus <= self.capacity.cpus;
+        let memory = self.capacity.memory_bytes == 0
+            || load.memory_bytes + request.memory_bytes <= self.capacity.memory_bytes;
+        cpus && memory
+    }
+
+    // The node's average CPU and memory utilization once the container is placed, from 0 to 1.
+    pub fn utilization(&self, request: &ResourceRequests) -> f64 {
+        let load = self.load();
+        let mut ratios = Vec::new();
+        if self.capacity.cpus > 0.0 {
+            ratios.push((load.cpus + request.cpus) / self.capacity.cpus);
+        }
+        if self.capacity.memory_bytes > 0 {
+            ratios.push(
+                (load.memory_bytes + request.memory_bytes) as f64
+                    / self.capacity.memory_bytes as f64,
+            );
+        }
+
+        if ratios.is_empty() {
+            return 0.0;
+        }
+        (ratios.iter().sum::<f64>() / ratios.len() as f64).min(1.0)
+    }
+
+    // Which domain of the topology the node is in; None when it lacks the topology label.
+    fn domain(&self, topology_key: Option<&str>) -> Option<&str> {
+        match topology_key {
+            Some(key) => self.labels.get(key).map(String::as_str),
+            None => Some(&self.name),
+        }
+    }
+
+    fn runs(&self, term: &AffinityTerm) -> bool {
+        self.container_labels
+            .iter()
+            .any(|labels| term.selector.matches(labels))
+    }
+}
+
+// Why a node was passed over, in the order the checks run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Unfit {
+    NodeSelector,
+    Affinity,
+    AntiAffinity,
+    Resources,
+}
+
+impl Unfit {
+    fn describe(&self, nodes: usize) -> String {
+        let reason = match self {
+            Unfit::NodeSelector => "don't match its node selector",
+            Unfit::Affinity => "don't run the containers it must run next to",
+            Unfit::AntiAffinity => "run containers it must not run next to",
+            Unfit::Resources => "lack the free resources",
+        };
+        format!("{} node(s) {}", nodes, reason)
+    }
+}
+
+// Whether `term` holds for the container on `candidate`. A container that matches its own
+// affinity rule can start where none of the matching containers run yet, so the first
+// replica of a co-located app can be placed.
+fn affinity_holds(
+    term: &AffinityTerm,
+    labels: &Labels,
+    candidate: &Candidate,
+    candidates: &[Candidate],
+) -> bool {
+    if !candidates.iter().any(|other| other.runs(term)) {
+        return term.selector.matches(labels);
+    }
+    shares_domain(term, candidate, candidates)
+}
+
+// Whether a container matching `term` runs in the same domain as `candidate`.
+fn shares_domain(term: &AffinityTerm, candidate: &Candidate, candidates: &[Candidate]) -> bool {
+    let key = term.topology_key.as_deref();
+    let Some(domain) = candidate.domain(key) else {
+        return false;
+    };
+    candidates
+        .iter()
+        .filter(|other| other.domain(key) == Some(domain))
+        .any(|other| other.runs(term))
+}
+
+pub struct Scheduler {
+    strategy: Box<dyn ScoringStrategy + Send + Sync>,
+}
+
+impl Scheduler {
+    pub fn new(strategy: Box<dyn ScoringStrategy + Send + Sync>) -> Self {
+        Scheduler { strategy }
+    }
+
+    pub fn strategy(&self) -> &str {
+        self.strategy.name()
+    }
+
+    // Only the container's own affinity rules are checked, not those of the containers already
+    // placed, so anti-affinity has to be set on both sides to keep two apps apart.
+    fn check(
+        spec: &ContainerSpec,
+        labels: &Labels,
+        candidate: &Candidate,
+        candidates: &[Candidate],
+    ) -> Result<(), Unfit> {
+        if !spec
+            .node_selector
+            .iter()
+            .all(|(key, value)| candidate.labels.get(key) == Some(value))
+        {
+            return Err(Unfit::NodeSelector);
+        }
+        if !spec
+            .affinity
+            .iter()
+            .all(|term| affinity_holds(term, labels, candidate, candidates))
+        {
+            return Err(Unfit::Affinity);
+        }
+        if spec
+            .anti_affinity
+            .iter()
+            .any(|term| shares_domain(term, candidate, candidates))
+        {
+            return Err(Unfit::AntiAffinity);
+        }
+        if !candidate.fits(&spec.resources) {
+            return Err(Unfit::Resources);
+        }
+        Ok(())
+    }
+
+    // Picks the best scoring node the container may run on. Ties go to the node with fewer
+    // containers, then to the first by name.
+    pub fn select(
+        &self,
+        spec: &ContainerSpec,
+        candidates: &[Candidate],
+    ) -> Result<String, anyhow::Error> {
+        let name = &spec.name;
+        let request = &spec.resources;
+        if candidates.is_empty() {
+            return Err(anyhow!("no ready nodes available to run {}", name));
+        }
+
+        let labels = spec.all_labels();
+        let mut unfit = BTreeMap::new();
+        candidates
+            .iter()
+            .filter(
+                |candidate| match Scheduler::check(spec, &labels, candidate, candidates) {
+                    Ok(()) => true,
+                    Err(reason) => {
+                        *unfit.entry(reason).or_insert(0) += 1;
+                        false
+                    }
+                },
+            )
+            .map(|candidate| (self.strategy.score(candidate, request), candidate))
+            .max_by(|(a_score, a), (b_score, b)| {
+                a_score
+                    .total_cmp(b_score)
+                    .then(b.containers.cmp(&a.containers))
+                    .then(b.name.cmp(&a.name))
+            })
+            .map(|(_, candidate)| candidate.name.clone())
+            .ok_or_else(|| {
+                if unfit.keys().all(|reason| *reason == Unfit::Resources) {
+                    return anyhow!(
+                        "no node has enough free resources to run {} (requests {} CPUs, {} memory)",
+                        name,
+                        request.cpus,
+                        format_size(request.memory_bytes)
+                    );
+                }
+                let reasons: Vec<String> = unfit
+                    .iter()
+                    .map(|(reason, nodes)| reason.describe(*nodes))
+                    .collect();
+                anyhow!("no node can run {}: {}", name, reasons.join(", "))
+            })
+    }
+}
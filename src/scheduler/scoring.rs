@@ -0,0 +1,48 @@
+use anyhow::anyhow;
+
+use crate::entities::container::ResourceRequests;
+
+use super::Candidate;
+
+// Ranks the nodes a container fits on; the highest score wins.
+pub trait ScoringStrategy {
+    fn name(&self) -> &'static str;
+    fn score(&self, candidate: &Candidate, request: &ResourceRequests) -> f64;
+}
+
+// Prefers the least loaded node, so containers spread across the cluster.
+pub struct Spread;
+
+impl ScoringStrategy for Spread {
+    fn name(&self) -> &'static str {
+        "spread"
+    }
+
+    fn score(&self, candidate: &Candidate, request: &ResourceRequests) -> f64 {
+        1.0 - candidate.utilization(request)
+    }
+}
+
+// Prefers the most loaded node that still fits, keeping other nodes free for large containers.
+pub struct BinPacking;
+
+impl ScoringStrategy for BinPacking {
+    fn name(&self) -> &'static str {
+        "bin-packing"
+    }
+
+    fn score(&self, candidate: &Candidate, request: &ResourceRequests) -> f64 {
+        candidate.utilization(request)
+    }
+}
+
+pub fn strategy(name: &str) -> Result<Box<dyn ScoringStrategy + Send + Sync>, anyhow::Error> {
+    match name.to_ascii_lowercase().as_str() {
+        "spread" => Ok(Box::new(Spread)),
+        "bin-packing" | "binpacking" => Ok(Box::new(BinPacking)),
+        _ => Err(anyhow!(
+            "unknown scheduling strategy {:?}: expected spread or bin-packing",
+            name
+        )),
+    }
+}
@@ -0,0 +1,435 @@
+use std::{future::Future, pin::pin, sync::Arc, time::Duration};
+
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    admission::{quota::ResourceQuotas, AdmissionChain},
+    api::{self, ApiState},
+    auth::Authenticator,
+    cluster::Cluster,
+    config::Config,
+    controllers::{
+        autoscaler::AutoscalerController, cron_job::CronJobController, drift::DriftDetector,
+        garbage_collector::GarbageCollector, image_gc::ImageGarbageCollector,
+        image_updater::ImageUpdater, job::JobController, leader_election::LeaderElection,
+        rollout::RolloutController,
+    },
+    entities::{
+        container::{Container, ContainerSpec},
+        node::NodeCapacity,
+    },
+    events::{event::EventReason, notifier::Notifier, recorder::EventRecorder},
+    ingress::IngressController,
+    runtime::{
+        self,
+        limit::{LimitedRuntime, OperationLimit},
+        nodes::NodeRuntime,
+        retry::RetryingRuntime,
+        ContainerRuntime,
+    },
+    scheduler::{scoring, Scheduler},
+    store::{
+        audit::AuditLog, backup, config_maps::ConfigMapStore, logs::LogStore, secrets::SecretStore,
+        state::StateStore, tokens::TokenStore, volumes::VolumeStore,
+    },
+    watchers::{
+        container_status::ContainerStatusWatcher, log_collector::LogCollector,
+        node_status::NodeStatusWatcher, registry::WatcherRegistry,
+        resource_usage::ResourceUsageWatcher,
+    },
+};
+
+/// A control plane: the REST and gRPC APIs together with the controllers and watchers that keep
+/// containers in their desired state.
+pub struct Nic8s {
+    config: Config,
+    api_addr: String,
+    grpc_addr: String,
+    runtime: Option<Arc<dyn ContainerRuntime + Send + Sync>>,
+    capacity: NodeCapacity,
+    local_node: bool,
+    default_container: bool,
+}
+
+/// Configures a [`Nic8s`]; see [`Nic8s::builder`].
+pub struct Nic8sBuilder {
+    config: Config,
+    api_addr: String,
+    grpc_addr: String,
+    runtime: Option<Arc<dyn ContainerRuntime + Send + Sync>>,
+    capacity: NodeCapacity,
+    local_node: bool,
+    default_container: bool,
+}
+
+impl Nic8sBuilder {
+    /// Daemon settings, as read from `nic8s.toml` by [`Config::load`]. Defaults to
+    /// [`Config::default`].
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Where the REST API listens, [`api::DEFAULT_ADDR`] by default.
+    pub fn api_addr(mut self, addr: impl Into<String>) -> Self {
+        self.api_addr = addr.into();
+        self
+    }
+
+    /// Where the gRPC API listens, [`api::grpc::DEFAULT_ADDR`] by default.
+    pub fn grpc_addr(mut self, addr: impl Into<String>) -> Self {
+        self.grpc_addr = addr.into();
+        self
+    }
+
+    /// Runs the local node's containers on this runtime instead of the host's docker.
+    pub fn runtime(mut self, runtime: Arc<dyn ContainerRuntime + Send + Sync>) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// What the scheduler may place on the runtime given to [`Nic8sBuilder::runtime`]. Without
+    /// it the node is treated as unlimited; docker reports its own capacity.
+    pub fn capacity(mut self, capacity: NodeCapacity) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Whether the control plane runs containers itself, as the "local" node, on top of the ones
+    /// on registered agents. On by default.
+    pub fn local_node(mut self, local_node: bool) -> Self {
+        self.local_node = local_node;
+        self
+    }
+
+    /// Whether a first start creates an `nginx` container on the local node. On by default.
+    pub fn default_container(mut self, default_container: bool) -> Self {
+        self.default_container = default_container;
+        self
+    }
+
+    pub fn build(self) -> Nic8s {
+        Nic8s {
+            config: self.config,
+            api_addr: self.api_addr,
+            grpc_addr: self.grpc_addr,
+            runtime: self.runtime,
+            capacity: self.capacity,
+            local_node: self.local_node,
+            default_container: self.default_container,
+        }
+    }
+}
+
+impl Nic8s {
+    pub fn builder() -> Nic8sBuilder {
+        Nic8sBuilder {
+            config: Config::default(),
+            api_addr: String::from(api::DEFAULT_ADDR),
+            grpc_addr: String::from(api::grpc::DEFAULT_ADDR),
+            runtime: None,
+            capacity: NodeCapacity::default(),
+            local_node: true,
+            default_container: true,
+        }
+    }
+
+    /// Runs until `stop` completes, e.g. [`crate::shutdown_signal`], or an API server fails.
+    pub async fn run(self, stop: impl Future<Output = ()>) -> Result<(), anyhow::Error> {
+        let config = self.config;
+        let mut stop = pin!(stop);
+        let state_store = Arc::new(StateStore::open(&config.data_dir).await?);
+        let leader = if config.leader_election.enabled {
+            Some(LeaderElection::new(
+                state_store.clone(),
+                &config.leader_election,
+            )?)
+        } else {
+            None
+        };
+        // Standby servers don't start anything until they hold the lease.
+        if let Some(leader) = &leader {
+            tokio::select! {
+                _ = leader.acquire() => {}
+                _ = &mut stop => {
+                    println!("Shutting down");
+                    return Ok(());
+                }
+            }
+        }
+        let scheduler = Scheduler::new(scoring::strategy(&config.scheduler.strategy)?);
+        println!("Scheduling with the {} strategy", scheduler.strategy());
+        let nodes = Arc::new(NodeRuntime::new(state_store.clone(), scheduler));
+        // Agents limit the operations on their own engine.
+        let operation_limit = Arc::new(OperationLimit::new(
+            config.runtime.max_concurrent_operations,
+        ));
+        match (self.runtime, self.local_node) {
+            (Some(runtime), _) => {
+                let runtime = LimitedRuntime::new(runtime, operation_limit.clone());
+                nodes
+                    .add_local(
+                        Arc::new(runtime),
+                        self.capacity,
+                        config.nodes.labels.clone(),
+                    )
+                    .await
+            }
+            (None, true) => {
+                let (engine, capacity) = runtime::local(&config).await?;
+                let runtime = LimitedRuntime::new(engine, operation_limit.clone());
+                nodes
+                    .add_local(Arc::new(runtime), capacity, config.nodes.labels.clone())
+                    .await;
+            }
+            (None, false) => {}
+        }
+        nodes.load().await?;
+        let runtime: Arc<dyn ContainerRuntime + Send + Sync> =
+            Arc::new(RetryingRuntime::new(nodes.clone(), &config.retry)?);
+        let secrets =
+            Arc::new(SecretStore::open(state_store.clone(), &config.master_key_file()).await?);
+        let config_maps =
+            Arc::new(ConfigMapStore::open(state_store.clone(), &config.config_maps_dir()).await?);
+        let events = Arc::new(EventRecorder::new());
+        let status_watcher = Arc::new(
+            ContainerStatusWatcher::new(runtime.clone(), events.clone())
+                .with_crash_loop(&config.crash_loop),
+        );
+        let admission = AdmissionChain::new(&config.admission.plugins)?;
+        let auth = Arc::new(Authenticator::new(
+            &config.auth,
+            TokenStore::new(state_store.clone()),
+        )?);
+        if auth.enabled() {
+            auth.bootstrap(&config.admin_token_file()).await?;
+            println!("API authentication enabled");
+        }
+        if !admission.names().is_empty() {
+            println!("Admission plugins: {}", admission.names().join(", "));
+        }
+        let quotas = Arc::new(ResourceQuotas::new(
+            state_store.clone(),
+            status_watcher.clone(),
+        ));
+        let admission = Arc::new(admission.with_plugin(quotas.clone()));
+        let resource_usage_watcher = Arc::new(ResourceUsageWatcher::new(
+            runtime.clone(),
+            status_watcher.clone(),
+        ));
+        let logs = if config.logs.persist {
+            let store = LogStore::open(&config.logs_dir(), &config.logs).await?;
+            Some(Arc::new(LogCollector::new(
+                runtime.clone(),
+                status_watcher.clone(),
+                store,
+                &config.logs,
+            )))
+        } else {
+            None
+        };
+        let volumes = Arc::new(VolumeStore::new(
+            state_store.clone(),
+            nodes.clone(),
+            status_watcher.clone(),
+        ));
+        let cluster = Cluster {
+            runtime,
+            nodes,
+            status_watcher,
+            events,
+            state: state_store,
+            secrets,
+            config_maps,
+            volumes,
+            admission,
+            logs: logs.clone(),
+        };
+        let node_status_watcher = Arc::new(NodeStatusWatcher::new(
+            cluster.clone(),
+            Duration::from_secs(config.nodes.heartbeat_timeout_seconds),
+        ));
+
+        let mut adopted = Container::adopt_all(&cluster).await?;
+        if backup::take_pending_restore(&cluster.state).await? {
+            let recreated = Container::recreate_missing(&cluster, &adopted).await?;
+            adopted.extend(recreated);
+        }
+        if self.local_node
+            && self.default_container
+            && !adopted.iter().any(|container| container.name == "nginx")
+        {
+            let spec = ContainerSpec {
+                name: String::from("nginx"),
+                image: String::from("nginx"),
+                ports: String::from("80"),
+                ..ContainerSpec::default()
+            };
+            // The default container is only a demo, so an admission plugin rejecting it is fine.
+            if let Err(error) = Container::new(&spec, &cluster).await {
+                println!("Failed to create the default nginx container: {}", error);
+            }
+        }
+
+        let shutdown = CancellationToken::new();
+        let jobs = Arc::new(JobController::new(cluster.clone(), shutdown.clone()));
+        jobs.resume().await?;
+        let cron_jobs = Arc::new(CronJobController::new(
+            cluster.clone(),
+            jobs.clone(),
+            shutdown.clone(),
+        ));
+        cron_jobs.resume().await?;
+
+        let watchers = Arc::new(WatcherRegistry::new(shutdown.clone()));
+        watchers.register(cluster.status_watcher.clone()).await?;
+        watchers.register(resource_usage_watcher.clone()).await?;
+        watchers.register(node_status_watcher).await?;
+        watchers
+            .register(Arc::new(GarbageCollector::new(cluster.clone(), &config.gc)))
+            .await?;
+        if config.image_gc.enabled {
+            watchers
+                .register(Arc::new(ImageGarbageCollector::new(
+                    cluster.clone(),
+                    &config.image_gc,
+                )?))
+                .await?;
+        }
+        watchers
+            .register(Arc::new(DriftDetector::new(cluster.clone(), &config.drift)))
+            .await?;
+        watchers
+            .register(Arc::new(ImageUpdater::new(
+                cluster.clone(),
+                &config.image_updates,
+            )))
+            .await?;
+        let autoscalers = Arc::new(AutoscalerController::new(
+            cluster.clone(),
+            resource_usage_watcher.clone(),
+            &config.autoscaler,
+        ));
+        watchers.register(autoscalers.clone()).await?;
+        if !config.notifications.webhooks.is_empty() {
+            watchers
+                .register(Arc::new(Notifier::new(
+                    cluster.clone(),
+                    config.notifications.webhooks.clone(),
+                )?))
+                .await?;
+        }
+        if let Some(logs) = logs {
+            watchers.register(logs).await?;
+        }
+        let ingresses = Arc::new(IngressController::new(cluster.clone(), &config.ingress));
+        if config.ingress.enabled {
+            watchers.register(ingresses.clone()).await?;
+        }
+        let rollouts = Arc::new(
+            RolloutController::new(cluster.clone(), &config.rollouts)?
+                .with_ingress(ingresses.clone()),
+        );
+        watchers.register(rollouts.clone()).await?;
+        watchers.start_all().await;
+
+        let api_state = ApiState {
+            cluster: cluster.clone(),
+            jobs,
+            cron_jobs,
+            autoscalers,
+            rollouts,
+            quotas,
+            operation_limit,
+            ingresses,
+            resource_usage_watcher,
+            watchers: watchers.clone(),
+            audit: Arc::new(AuditLog::open(&config.audit_log_file()).await?),
+            auth,
+            shutdown: shutdown.clone(),
+        };
+
+        let mut tasks = JoinSet::new();
+
+        let api_addr = self.api_addr;
+        let rest_state = api_state.clone();
+        tasks.spawn(async move { api::serve(&api_addr, rest_state).await });
+
+        let grpc_addr = self.grpc_addr;
+        tasks.spawn(async move { api::grpc::serve(&grpc_addr, api_state).await });
+
+        let mut result = Ok(());
+        let mut lost_lease = false;
+        tokio::select! {
+            _ = &mut stop => println!("Shutting down"),
+            Some(joined) = tasks.join_next() => {
+                result = joined?;
+                println!("A daemon task exited, shutting down");
+            }
+            error = hold_lease(leader.as_ref()) => {
+                println!("{}, shutting down", error);
+                lost_lease = true;
+                result = Err(error);
+            }
+        }
+
+        shutdown.cancel();
+        watchers.stop_all().await;
+        while let Some(joined) = tasks.join_next().await {
+            if let Err(error) = joined? {
+                println!("Error while shutting down: {}", error);
+            }
+        }
+
+        // The new leader adopts the containers, so they keep running.
+        if config.shutdown.stop_containers && !lost_lease {
+            stop_containers(
+                &cluster,
+                Duration::from_secs(config.shutdown.grace_period_seconds),
+            )
+            .await;
+        }
+        if let Some(leader) = &leader {
+            leader.release().await;
+        }
+
+        result
+    }
+}
+
+async fn hold_lease(leader: Option<&LeaderElection>) -> anyhow::Error {
+    match leader {
+        Some(leader) => leader.hold().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn stop_containers(cluster: &Cluster, grace_period: Duration) {
+    let mut stops = JoinSet::new();
+
+    for container in cluster.status_watcher.list().await {
+        let runtime = cluster.runtime.clone();
+        let events = cluster.events.clone();
+
+        stops.spawn(async move {
+            events
+                .record_container(
+                    &container.name,
+                    EventReason::Killed,
+                    format!(
+                        "Stopping container {} with a {}s grace period",
+                        container.name,
+                        grace_period.as_secs()
+                    ),
+                )
+                .await;
+
+            if let Err(error) = runtime.stop(&container.id, Some(grace_period)).await {
+                println!("Failed to stop container {}: {}", container.name, error);
+            }
+        });
+    }
+
+    while stops.join_next().await.is_some() {}
+}
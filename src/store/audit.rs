@@ -0,0 +1,95 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use tokio::{
+    fs,
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    sync::Mutex,
+};
+
+use crate::entities::audit::{AuditEntry, AuditQuery};
+
+// An append-only file of audit entries, one JSON object per line. Entries are never rewritten;
+// trimming the file is left to the operator's log tooling.
+pub struct AuditLog {
+    path: PathBuf,
+    // Keeps concurrent appends from interleaving.
+    writing: Mutex<()>,
+}
+
+impl AuditLog {
+    pub async fn open(path: &Path) -> Result<Self, anyhow::Error> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        // A crash mid-append leaves a partial last line; start the next entry on a line of its own.
+        match fs::read(path).await {
+            Ok(contents) if !contents.is_empty() && !contents.ends_with(b"\n") => {
+                let mut file = fs::OpenOptions::new().append(true).open(path).await?;
+                file.write_all(b"\n").await?;
+            }
+            Ok(_) => {}
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+
+        Ok(AuditLog {
+            path: path.to_path_buf(),
+            writing: Mutex::new(()),
+        })
+    }
+
+    pub async fn append(&self, entry: &AuditEntry) -> Result<(), anyhow::Error> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let _writing = self.writing.lock().await;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    // An audit trail that can't be written must not go unnoticed, but it shouldn't fail the
+    // call it records either.
+    pub async fn record(&self, entry: AuditEntry) {
+        if let Err(error) = self.append(&entry).await {
+            println!(
+                "Failed to write audit entry for {} {}: {}",
+                entry.operation, entry.resource, error
+            );
+        }
+    }
+
+    // Oldest first. Lines that don't parse, like one cut short by a crash, are skipped.
+    pub async fn list(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, anyhow::Error> {
+        let file = match fs::File::open(&self.path).await {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut entries = Vec::new();
+        let mut lines = BufReader::new(file).lines();
+        while let Some(line) = lines.next_line().await? {
+            let Ok(entry) = serde_json::from_str::<AuditEntry>(&line) else {
+                continue;
+            };
+            if query.matches(&entry) {
+                entries.push(entry);
+            }
+        }
+
+        if let Some(limit) = query.limit {
+            let skip = entries.len().saturating_sub(limit);
+            entries.drain(..skip);
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    admission::quota,
+    controllers::{autoscaler, cron_job, job, rollout},
+    entities::container,
+    ingress,
+};
+
+use super::{
+    config_maps,
+    secrets::{self, SecretStore},
+    state::{validate_name, StateStore},
+    tokens, volumes,
+};
+
+// Bumped whenever the archive or a stored object changes in a way serde defaults can't absorb,
+// together with a migration below.
+pub const FORMAT_VERSION: u32 = 1;
+
+// The kinds that make up desired state. Nodes register again and leases expire, so neither is
+// worth carrying to another host.
+pub const KINDS: [&str; 11] = [
+    container::KIND,
+    job::KIND,
+    cron_job::KIND,
+    autoscaler::KIND,
+    rollout::KIND,
+    quota::KIND,
+    ingress::KIND,
+    config_maps::KIND,
+    secrets::KIND,
+    tokens::KIND,
+    volumes::KIND,
+];
+
+// Marks a restore whose containers the daemon still has to create, which it does on its next
+// start, once it knows what the nodes run.
+const PENDING_KIND: &str = "restores";
+const PENDING: &str = "pending";
+
+// `MIGRATIONS[n]` upgrades an archive written with format version `n + 1` to `n + 2`, so old
+// archives can be restored by newer releases.
+type Migration = fn(&mut Archive) -> Result<(), anyhow::Error>;
+const MIGRATIONS: &[Migration] = &[];
+
+// The desired-state store as a single document. Objects are kept as they are stored, so secrets
+// stay encrypted with the master key of the host the archive was taken on.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Archive {
+    pub version: u32,
+    pub created: String,
+    // The release that wrote the archive, for troubleshooting.
+    pub nic8s_version: String,
+    // Objects by kind and name.
+    pub objects: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+impl Archive {
+    pub async fn create(store: &StateStore) -> Result<Archive, anyhow::Error> {
+        let mut objects = BTreeMap::new();
+        for kind in KINDS {
+            let entries: Vec<(String, Value)> = store.entries(kind).await?;
+            if !entries.is_empty() {
+                objects.insert(String::from(kind), entries.into_iter().collect());
+            }
+        }
+
+        Ok(Archive {
+            version: FORMAT_VERSION,
+            created: chrono::Utc::now().to_rfc3339(),
+            nic8s_version: String::from(env!("CARGO_PKG_VERSION")),
+            objects,
+        })
+    }
+
+    // Reads an archive written by this or an earlier release, migrating it to the current format.
+    pub fn parse(contents: &[u8]) -> Result<Archive, anyhow::Error> {
+        let mut archive: Archive = serde_json::from_slice(contents)
+            .map_err(|error| anyhow!("not a nic8s backup: {}", error))?;
+        if archive.version == 0 || archive.version > FORMAT_VERSION {
+            return Err(anyhow!(
+                "backup format version {} is not supported by this release (latest {}); it was \
+                 written by nic8s {}",
+                archive.version,
+                FORMAT_VERSION,
+                archive.nic8s_version
+            ));
+        }
+
+        while archive.version < FORMAT_VERSION {
+            let migrate = MIGRATIONS[archive.version as usize - 1];
+            migrate(&mut archive).map_err(|error| {
+                anyhow!(
+                    "failed to migrate backup from format version {}: {}",
+                    archive.version,
+                    error
+                )
+            })?;
+            archive.version += 1;
+        }
+
+        for (kind, objects) in archive.objects.iter() {
+            if !KINDS.contains(&kind.as_str()) {
+                return Err(anyhow!("backup holds unknown kind {}", kind));
+            }
+            for name in objects.keys() {
+                validate_name(name).map_err(|error| anyhow!("{} in {}", error, kind))?;
+            }
+        }
+        Ok(archive)
+    }
+
+    pub fn counts(&self) -> Vec<(&str, usize)> {
+        self.objects
+            .iter()
+            .map(|(kind, objects)| (kind.as_str(), objects.len()))
+            .collect()
+    }
+
+    // Writes the archive's objects into the store. A store that already holds desired state is
+    // only overwritten with `replace`, which removes everything not in the archive first.
+    pub async fn restore(&self, store: &StateStore, replace: bool) -> Result<(), anyhow::Error> {
+        let mut existing = Vec::new();
+        for kind in KINDS {
+            let names: Vec<String> = store
+                .entries::<Value>(kind)
+                .await?
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+            if !names.is_empty() {
+                existing.push((kind, names));
+            }
+        }
+        if !existing.is_empty() && !replace {
+            let counts: Vec<String> = existing
+                .iter()
+                .map(|(kind, names)| format!("{} {}", names.len(), kind))
+                .collect();
+            return Err(anyhow!(
+                "the store already holds {}; pass --replace to overwrite it",
+                counts.join(", ")
+            ));
+        }
+
+        for (kind, names) in existing {
+            for name in names {
+                store.delete(kind, &name).await?;
+            }
+        }
+        for (kind, objects) in self.objects.iter() {
+            for (name, value) in objects.iter() {
+                store.put(kind, name, value).await?;
+            }
+        }
+        store.put(PENDING_KIND, PENDING, &self.created).await?;
+        Ok(())
+    }
+
+    // Secrets the given store's master key can't decrypt, which happens when the archive comes
+    // from a host with another key.
+    pub async fn undecryptable_secrets(&self, secrets: &SecretStore) -> Vec<String> {
+        let mut names = Vec::new();
+        for name in self
+            .objects
+            .get(secrets::KIND)
+            .into_iter()
+            .flat_map(|objects| objects.keys())
+        {
+            if secrets.get(name).await.is_err() {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+}
+
+// Whether a restore happened since the daemon last started, clearing the mark.
+pub async fn take_pending_restore(store: &StateStore) -> Result<bool, anyhow::Error> {
+    store.delete(PENDING_KIND, PENDING).await
+}
@@ -0,0 +1,134 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use tokio::fs;
+
+use crate::{
+    entities::{config_map::ConfigMap, container::ContainerSpec},
+    runtime::Mount,
+};
+
+use super::state::{validate_name, StateStore};
+
+pub(crate) const KIND: &str = "configmaps";
+
+// Config maps are stored like any other object and also materialized as plain files under
+// `<mounts_dir>/<name>/<key>`, which is the directory bind-mounted into containers.
+pub struct ConfigMapStore {
+    store: Arc<StateStore>,
+    mounts_dir: PathBuf,
+}
+
+impl ConfigMapStore {
+    pub async fn open(store: Arc<StateStore>, mounts_dir: &Path) -> Result<Self, anyhow::Error> {
+        fs::create_dir_all(mounts_dir).await?;
+
+        // Docker resolves bind mount sources on the host, so they have to be absolute.
+        Ok(ConfigMapStore {
+            store,
+            mounts_dir: fs::canonicalize(mounts_dir).await?,
+        })
+    }
+
+    fn host_path(&self, name: &str) -> Result<PathBuf, anyhow::Error> {
+        validate_name(name)?;
+        Ok(self.mounts_dir.join(name))
+    }
+
+    // Returns the previous version, if any, so callers can tell whether the content changed.
+    pub async fn put(&self, config_map: &ConfigMap) -> Result<Option<ConfigMap>, anyhow::Error> {
+        for key in config_map.data.keys() {
+            validate_name(key).map_err(|error| {
+                anyhow!("invalid key in config map {}: {}", config_map.name, error)
+            })?;
+        }
+
+        let previous = self.get(&config_map.name).await?;
+        self.store.put(KIND, &config_map.name, config_map).await?;
+        self.materialize(config_map).await?;
+
+        Ok(previous)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Option<ConfigMap>, anyhow::Error> {
+        self.store.get(KIND, name).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<ConfigMap>, anyhow::Error> {
+        let mut config_maps: Vec<ConfigMap> = self.store.list(KIND).await?;
+        config_maps.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(config_maps)
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<bool, anyhow::Error> {
+        let deleted = self.store.delete(KIND, name).await?;
+
+        match fs::remove_dir_all(self.host_path(name)?).await {
+            Ok(()) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+
+        Ok(deleted)
+    }
+
+    pub async fn mounts_for(&self, spec: &ContainerSpec) -> Result<Vec<Mount>, anyhow::Error> {
+        let mut mounts = Vec::new();
+
+        for mount in spec.config_maps.iter() {
+            let config_map = self.get(&mount.name).await?.ok_or_else(|| {
+                anyhow!(
+                    "config map {} referenced by container {} not found",
+                    mount.name,
+                    spec.name
+                )
+            })?;
+
+            mounts.push(Mount {
+                source: self.materialize(&config_map).await?.display().to_string(),
+                target: mount.mount_path.clone(),
+                read_only: true,
+                files: config_map.data,
+            });
+        }
+
+        Ok(mounts)
+    }
+
+    async fn materialize(&self, config_map: &ConfigMap) -> Result<PathBuf, anyhow::Error> {
+        let dir = self.host_path(&config_map.name)?;
+        write_files(&dir, &config_map.data).await?;
+        Ok(dir)
+    }
+}
+
+// Files are replaced with a rename rather than recreating the directory, so containers that
+// already have it bind-mounted see the new content without a remount.
+pub async fn write_files(
+    dir: &Path,
+    files: &BTreeMap<String, String>,
+) -> Result<(), anyhow::Error> {
+    fs::create_dir_all(dir).await?;
+
+    for (key, value) in files.iter() {
+        validate_name(key)?;
+        let tmp = dir.join(format!(".{}.tmp", key));
+        fs::write(&tmp, value).await?;
+        fs::rename(&tmp, dir.join(key)).await?;
+    }
+
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !files.contains_key(&name) {
+            fs::remove_file(entry.path()).await?;
+        }
+    }
+
+    Ok(())
+}
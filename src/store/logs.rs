@@ -0,0 +1,187 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::{config::LogsConfig, runtime::LogLine};
+
+use super::state::validate_name;
+
+const LOG_FILE: &str = "output.log";
+
+// Container output under `<dir>/<name>/output.log`, one timestamped line per line of output.
+// Files are kept per container name, so the output of every container that went by that name
+// ends up in the same place. Full files are rotated to `output.log.1`, `output.log.2`, ...
+pub struct LogStore {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    max_files: usize,
+    retention: Duration,
+}
+
+impl LogStore {
+    pub async fn open(dir: &Path, config: &LogsConfig) -> Result<Self, anyhow::Error> {
+        fs::create_dir_all(dir).await?;
+
+        Ok(LogStore {
+            dir: dir.to_path_buf(),
+            max_file_bytes: config.max_file_bytes,
+            max_files: config.max_files.max(1),
+            retention: Duration::from_secs(config.retention_hours * 3600),
+        })
+    }
+
+    fn container_dir(&self, name: &str) -> Result<PathBuf, anyhow::Error> {
+        validate_name(name)?;
+        Ok(self.dir.join(name))
+    }
+
+    // 0 is the file being written, higher numbers are older.
+    fn file(dir: &Path, index: usize) -> PathBuf {
+        match index {
+            0 => dir.join(LOG_FILE),
+            index => dir.join(format!("{}.{}", LOG_FILE, index)),
+        }
+    }
+
+    pub async fn append(&self, name: &str, lines: &[LogLine]) -> Result<(), anyhow::Error> {
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let dir = self.container_dir(name)?;
+        fs::create_dir_all(&dir).await?;
+
+        let mut contents = String::new();
+        for line in lines {
+            contents.push_str(&line.timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true));
+            contents.push(' ');
+            contents.push_str(&line.text);
+            contents.push('\n');
+        }
+
+        let path = LogStore::file(&dir, 0);
+        let size = match fs::metadata(&path).await {
+            Ok(metadata) => metadata.len(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => 0,
+            Err(error) => return Err(error.into()),
+        };
+        if size > 0 && size + contents.len() as u64 > self.max_file_bytes {
+            self.rotate(&dir).await?;
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        file.write_all(contents.as_bytes()).await?;
+        Ok(())
+    }
+
+    async fn rotate(&self, dir: &Path) -> Result<(), anyhow::Error> {
+        if self.max_files == 1 {
+            fs::remove_file(LogStore::file(dir, 0)).await?;
+            return Ok(());
+        }
+        // The oldest file is replaced by the one before it.
+        for index in (1..self.max_files).rev() {
+            let from = LogStore::file(dir, index - 1);
+            if fs::try_exists(&from).await? {
+                fs::rename(&from, LogStore::file(dir, index)).await?;
+            }
+        }
+        Ok(())
+    }
+
+    // When the last stored line was received, so collection can pick up from there.
+    pub async fn last_timestamp(&self, name: &str) -> Result<Option<DateTime<Utc>>, anyhow::Error> {
+        let dir = self.container_dir(name)?;
+        for index in 0..self.max_files {
+            let contents = match fs::read_to_string(LogStore::file(&dir, index)).await {
+                Ok(contents) => contents,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error.into()),
+            };
+            if let Some(timestamp) = contents.lines().rev().find_map(parse_timestamp) {
+                return Ok(Some(timestamp));
+            }
+        }
+        Ok(None)
+    }
+
+    // The last `tail` lines, or all of them, without their timestamps. None when nothing was ever
+    // stored for the container.
+    pub async fn read(
+        &self,
+        name: &str,
+        tail: Option<usize>,
+    ) -> Result<Option<String>, anyhow::Error> {
+        let dir = self.container_dir(name)?;
+        if !fs::try_exists(&dir).await? {
+            return Ok(None);
+        }
+
+        let mut lines = VecDeque::new();
+        for index in 0..self.max_files {
+            if tail.is_some_and(|tail| lines.len() >= tail) {
+                break;
+            }
+            let contents = match fs::read_to_string(LogStore::file(&dir, index)).await {
+                Ok(contents) => contents,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error.into()),
+            };
+            for line in contents.lines().rev() {
+                if tail.is_some_and(|tail| lines.len() >= tail) {
+                    break;
+                }
+                let text = line.split_once(' ').map_or("", |(_, text)| text);
+                lines.push_front(String::from(text));
+            }
+        }
+
+        Ok(Some(
+            lines
+                .into_iter()
+                .map(|line| format!("{}\n", line))
+                .collect(),
+        ))
+    }
+
+    // Deletes the logs of containers not in `keep` that haven't been written to within the
+    // retention period.
+    pub async fn prune(&self, keep: &HashSet<String>) -> Result<(), anyhow::Error> {
+        let mut entries = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if keep.contains(&name) || !entry.file_type().await?.is_dir() {
+                continue;
+            }
+
+            let modified = match fs::metadata(LogStore::file(&entry.path(), 0)).await {
+                Ok(metadata) => metadata.modified()?,
+                Err(_) => entry.metadata().await?.modified()?,
+            };
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+            if age > self.retention {
+                println!("Deleting the logs of container {}", name);
+                fs::remove_dir_all(entry.path()).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_timestamp(line: &str) -> Option<DateTime<Utc>> {
+    let (timestamp, _) = line.split_once(' ')?;
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
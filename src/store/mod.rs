@@ -0,0 +1,8 @@
+pub mod audit;
+pub mod backup;
+pub mod config_maps;
+pub mod logs;
+pub mod secrets;
+pub mod state;
+pub mod tokens;
+pub mod volumes;
@@ -0,0 +1,179 @@
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+use aes_gcm::{
+    aead::{Aead, OsRng, Payload},
+    AeadCore, Aes256Gcm, Key, KeyInit, Nonce,
+};
+use anyhow::anyhow;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::entities::{
+    container::ContainerSpec,
+    secret::{Secret, SecretMetadata},
+};
+
+use super::state::StateStore;
+
+pub(crate) const KIND: &str = "secrets";
+const KEY_SIZE: usize = 32;
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedSecret {
+    #[serde(flatten)]
+    metadata: SecretMetadata,
+    nonce: String,
+    ciphertext: String,
+}
+
+pub struct SecretStore {
+    store: Arc<StateStore>,
+    cipher: Aes256Gcm,
+}
+
+impl SecretStore {
+    pub async fn open(store: Arc<StateStore>, key_file: &Path) -> Result<Self, anyhow::Error> {
+        let key = load_or_create_key(key_file).await?;
+
+        Ok(SecretStore {
+            store,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        })
+    }
+
+    pub async fn put(&self, secret: &Secret) -> Result<SecretMetadata, anyhow::Error> {
+        let metadata = SecretMetadata {
+            name: secret.name.clone(),
+            keys: secret.data.keys().cloned().collect(),
+            created: chrono::Utc::now().to_rfc3339(),
+        };
+
+        // The secret name is bound as associated data so ciphertexts can't be swapped between files.
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let plaintext = serde_json::to_vec(&secret.data)?;
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: secret.name.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("failed to encrypt secret {}", secret.name))?;
+
+        let encrypted = EncryptedSecret {
+            metadata: metadata.clone(),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+        self.store.put(KIND, &secret.name, &encrypted).await?;
+
+        Ok(metadata)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Option<Secret>, anyhow::Error> {
+        let Some(encrypted) = self.store.get::<EncryptedSecret>(KIND, name).await? else {
+            return Ok(None);
+        };
+
+        let nonce = STANDARD.decode(&encrypted.nonce)?;
+        if nonce.len() != 12 {
+            return Err(anyhow!("secret {} has an invalid nonce", name));
+        }
+        let ciphertext = STANDARD.decode(&encrypted.ciphertext)?;
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("failed to decrypt secret {}: wrong master key?", name))?;
+
+        Ok(Some(Secret {
+            name: String::from(name),
+            data: serde_json::from_slice(&plaintext)?,
+        }))
+    }
+
+    pub async fn metadata(&self, name: &str) -> Result<Option<SecretMetadata>, anyhow::Error> {
+        Ok(self
+            .store
+            .get::<EncryptedSecret>(KIND, name)
+            .await?
+            .map(|encrypted| encrypted.metadata))
+    }
+
+    pub async fn list(&self) -> Result<Vec<SecretMetadata>, anyhow::Error> {
+        let mut secrets: Vec<SecretMetadata> = self
+            .store
+            .list::<EncryptedSecret>(KIND)
+            .await?
+            .into_iter()
+            .map(|encrypted| encrypted.metadata)
+            .collect();
+        secrets.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(secrets)
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<bool, anyhow::Error> {
+        self.store.delete(KIND, name).await
+    }
+
+    pub async fn env_for(
+        &self,
+        spec: &ContainerSpec,
+    ) -> Result<BTreeMap<String, String>, anyhow::Error> {
+        let Some(name) = &spec.env_from_secret else {
+            return Ok(BTreeMap::new());
+        };
+
+        let secret = self.get(name).await?.ok_or_else(|| {
+            anyhow!(
+                "secret {} referenced by container {} not found",
+                name,
+                spec.name
+            )
+        })?;
+        Ok(secret.data)
+    }
+}
+
+async fn load_or_create_key(key_file: &Path) -> Result<Vec<u8>, anyhow::Error> {
+    match fs::read(key_file).await {
+        Ok(key) if key.len() == KEY_SIZE => return Ok(key),
+        Ok(_) => {
+            return Err(anyhow!(
+                "master key {} must be exactly {} bytes",
+                key_file.display(),
+                KEY_SIZE
+            ))
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+        Err(error) => return Err(error.into()),
+    }
+
+    if let Some(parent) = key_file.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let key = Aes256Gcm::generate_key(OsRng);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(key_file).await?;
+    // The key has to be on disk before anything is encrypted with it.
+    file.write_all(&key).await?;
+    file.flush().await?;
+    file.sync_all().await?;
+    println!("Generated new master key at {}", key_file.display());
+
+    Ok(key.to_vec())
+}
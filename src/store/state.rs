@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::fs;
+
+// Persists resources as one JSON document per object under `<data_dir>/<kind>/<name>.json`.
+pub struct StateStore {
+    dir: PathBuf,
+}
+
+pub fn validate_name(name: &str) -> Result<(), anyhow::Error> {
+    let valid = !name.is_empty()
+        && name.len() <= 253
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && !name.starts_with('.');
+
+    if !valid {
+        return Err(anyhow!(
+            "invalid name {:?}: use letters, digits, '-', '_' and '.'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+impl StateStore {
+    pub async fn open(dir: &Path) -> Result<StateStore, anyhow::Error> {
+        fs::create_dir_all(dir).await?;
+
+        Ok(StateStore {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    fn path(&self, kind: &str, name: &str) -> Result<PathBuf, anyhow::Error> {
+        validate_name(name)?;
+        Ok(self.dir.join(kind).join(format!("{}.json", name)))
+    }
+
+    pub async fn put<T: Serialize>(
+        &self,
+        kind: &str,
+        name: &str,
+        value: &T,
+    ) -> Result<(), anyhow::Error> {
+        let path = self.path(kind, name)?;
+        fs::create_dir_all(self.dir.join(kind)).await?;
+
+        // Write to a temporary file first so a crash never leaves a half-written object.
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(value)?).await?;
+        fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        kind: &str,
+        name: &str,
+    ) -> Result<Option<T>, anyhow::Error> {
+        let path = self.path(kind, name)?;
+
+        match fs::read(&path).await {
+            Ok(contents) => Ok(Some(serde_json::from_slice(&contents)?)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub async fn list<T: DeserializeOwned>(&self, kind: &str) -> Result<Vec<T>, anyhow::Error> {
+        Ok(self
+            .entries(kind)
+            .await?
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect())
+    }
+
+    // Like `list`, with the name each object is stored under.
+    pub async fn entries<T: DeserializeOwned>(
+        &self,
+        kind: &str,
+    ) -> Result<Vec<(String, T)>, anyhow::Error> {
+        let mut entries = match fs::read_dir(self.dir.join(kind)).await {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut values = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+                let value = serde_json::from_slice(&fs::read(&path).await?)?;
+                values.push((String::from(name), value));
+            }
+        }
+
+        Ok(values)
+    }
+
+    // Whether anything of this kind was ever stored; deleting the objects keeps the kind.
+    pub async fn has_kind(&self, kind: &str) -> Result<bool, anyhow::Error> {
+        Ok(fs::try_exists(self.dir.join(kind)).await?)
+    }
+
+    // Serializes read-modify-write cycles on one object across processes sharing the data dir.
+    // The lock is released when the returned file is dropped.
+    pub async fn lock(&self, kind: &str, name: &str) -> Result<std::fs::File, anyhow::Error> {
+        let path = self.path(kind, name)?.with_extension("lock");
+        fs::create_dir_all(self.dir.join(kind)).await?;
+
+        let file = tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)?;
+            file.lock()?;
+            Ok::<_, std::io::Error>(file)
+        })
+        .await??;
+        Ok(file)
+    }
+
+    pub async fn delete(&self, kind: &str, name: &str) -> Result<bool, anyhow::Error> {
+        let path = self.path(kind, name)?;
+
+        match fs::remove_file(&path).await {
+            Ok(()) => Ok(true),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
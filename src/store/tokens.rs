@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::entities::token::{IssuedToken, Token, TokenError, TokenSpec};
+
+use super::state::{validate_name, StateStore};
+
+pub(crate) const KIND: &str = "tokens";
+
+const SECRET_SIZE: usize = 32;
+
+#[derive(Serialize, Deserialize)]
+struct StoredToken {
+    #[serde(flatten)]
+    token: Token,
+    // SHA-256 of the secret, base64 encoded. A leaked data dir doesn't leak usable tokens.
+    hash: String,
+}
+
+fn hash(secret: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+// API tokens. A token's secret is `<name>.<random>`, so verifying one is a single lookup.
+pub struct TokenStore {
+    store: Arc<StateStore>,
+}
+
+impl TokenStore {
+    pub fn new(store: Arc<StateStore>) -> Self {
+        TokenStore { store }
+    }
+
+    // Roles are checked by the caller, which knows which exist.
+    pub async fn create(&self, spec: TokenSpec) -> Result<IssuedToken, anyhow::Error> {
+        validate_name(&spec.name)?;
+        if self
+            .store
+            .get::<StoredToken>(KIND, &spec.name)
+            .await?
+            .is_some()
+        {
+            return Err(TokenError::Exists(spec.name).into());
+        }
+
+        let mut random = [0u8; SECRET_SIZE];
+        OsRng.fill_bytes(&mut random);
+        let secret = format!("{}.{}", spec.name, URL_SAFE_NO_PAD.encode(random));
+        let token = Token {
+            name: spec.name,
+            roles: spec.roles,
+            created: chrono::Utc::now().to_rfc3339(),
+        };
+        let stored = StoredToken {
+            token: token.clone(),
+            hash: hash(&secret),
+        };
+        self.store.put(KIND, &token.name, &stored).await?;
+        Ok(IssuedToken { token, secret })
+    }
+
+    pub async fn list(&self) -> Result<Vec<Token>, anyhow::Error> {
+        let mut tokens: Vec<Token> = self
+            .store
+            .list::<StoredToken>(KIND)
+            .await?
+            .into_iter()
+            .map(|stored| stored.token)
+            .collect();
+        tokens.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tokens)
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<bool, anyhow::Error> {
+        self.store.delete(KIND, name).await
+    }
+
+    // The token a secret belongs to, if it is valid.
+    pub async fn verify(&self, secret: &str) -> Result<Option<Token>, anyhow::Error> {
+        let Some((name, _)) = secret.rsplit_once('.') else {
+            return Ok(None);
+        };
+        if validate_name(name).is_err() {
+            return Ok(None);
+        }
+        let Some(stored) = self.store.get::<StoredToken>(KIND, name).await? else {
+            return Ok(None);
+        };
+        Ok((stored.hash == hash(secret)).then_some(stored.token))
+    }
+}
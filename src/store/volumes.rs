@@ -0,0 +1,217 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use anyhow::anyhow;
+use tokio::sync::Mutex;
+
+use crate::{
+    entities::{
+        container::ContainerSpec,
+        volume::{Volume, VolumeError, VolumeSpec},
+    },
+    runtime::{nodes::NodeRuntime, Mount},
+    watchers::container_status::ContainerStatusWatcher,
+};
+
+use super::state::{validate_name, StateStore};
+
+pub(crate) const KIND: &str = "volumes";
+
+// Named volumes and the nodes they live on. Which containers use a volume is read off the claims
+// in the specs of the containers the status watcher tracks.
+pub struct VolumeStore {
+    store: Arc<StateStore>,
+    nodes: Arc<NodeRuntime>,
+    status_watcher: Arc<ContainerStatusWatcher>,
+    // Held while binding volumes to nodes, so two containers can't bind one to different nodes.
+    binding: Mutex<()>,
+}
+
+impl VolumeStore {
+    pub fn new(
+        store: Arc<StateStore>,
+        nodes: Arc<NodeRuntime>,
+        status_watcher: Arc<ContainerStatusWatcher>,
+    ) -> Self {
+        VolumeStore {
+            store,
+            nodes,
+            status_watcher,
+            binding: Mutex::new(()),
+        }
+    }
+
+    // Without a node the volume is created once a container claiming it is scheduled.
+    pub async fn create(&self, spec: VolumeSpec) -> Result<Volume, anyhow::Error> {
+        validate_name(&spec.name)?;
+        let _binding = self.binding.lock().await;
+        if self.store.get::<Volume>(KIND, &spec.name).await?.is_some() {
+            return Err(VolumeError::Exists(spec.name).into());
+        }
+        if let Some(node) = &spec.node {
+            self.nodes
+                .node(node)
+                .await?
+                .create_volume(&spec.name)
+                .await?;
+        }
+
+        let volume = Volume::new(spec);
+        self.store.put(KIND, &volume.name, &volume).await?;
+        Ok(volume)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Option<Volume>, anyhow::Error> {
+        let Some(mut volume) = self.store.get::<Volume>(KIND, name).await? else {
+            return Ok(None);
+        };
+        volume.used_by = self.users().await.remove(&volume.name).unwrap_or_default();
+        Ok(Some(volume))
+    }
+
+    pub async fn list(&self) -> Result<Vec<Volume>, anyhow::Error> {
+        let mut volumes: Vec<Volume> = self.store.list(KIND).await?;
+        let mut users = self.users().await;
+        for volume in volumes.iter_mut() {
+            volume.used_by = users.remove(&volume.name).unwrap_or_default();
+        }
+        volumes.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(volumes)
+    }
+
+    // Refuses while containers claim the volume, unless `force`d. A forced delete also forgets
+    // the volume when its node can't remove it, e.g. because the node is gone.
+    pub async fn delete(&self, name: &str, force: bool) -> Result<bool, anyhow::Error> {
+        let _binding = self.binding.lock().await;
+        let Some(volume) = self.get(name).await? else {
+            return Ok(false);
+        };
+        if !volume.used_by.is_empty() && !force {
+            return Err(VolumeError::InUse {
+                name: volume.name,
+                containers: volume.used_by,
+            }
+            .into());
+        }
+
+        if let Some(node) = &volume.node {
+            let removed = match self.nodes.node(node).await {
+                Ok(runtime) => runtime.remove_volume(name, force).await,
+                Err(error) => Err(error),
+            };
+            match removed {
+                Ok(()) => {}
+                Err(error) if force => println!(
+                    "Failed to remove volume {} from node {}, forgetting it anyway: {}",
+                    name, node, error
+                ),
+                Err(error) => return Err(error),
+            }
+        }
+        self.store.delete(KIND, name).await
+    }
+
+    // The node a container has to run on for the volumes it claims, if any of them is bound yet.
+    pub async fn node_for(&self, spec: &ContainerSpec) -> Result<Option<String>, anyhow::Error> {
+        let mut bound: Option<(String, String)> = None;
+        for claim in spec.volumes.iter() {
+            let volume = self.claimed(spec, &claim.name).await?;
+            let Some(node) = volume.node else {
+                continue;
+            };
+            match &bound {
+                Some((other, other_node)) if other_node != &node => {
+                    return Err(anyhow!(
+                        "container {} claims volumes on different nodes: {} on {} and {} on {}",
+                        spec.name,
+                        other,
+                        other_node,
+                        volume.name,
+                        node
+                    ));
+                }
+                Some(_) => {}
+                None => bound = Some((volume.name, node)),
+            }
+        }
+
+        match (bound, &spec.node) {
+            (Some((volume, node)), Some(pinned)) if &node != pinned => Err(anyhow!(
+                "container {} is pinned to node {} but volume {} is on node {}",
+                spec.name,
+                pinned,
+                volume,
+                node
+            )),
+            (bound, _) => Ok(bound.map(|(_, node)| node)),
+        }
+    }
+
+    // Mounts for the volumes a container scheduled to `node` claims, creating the ones not bound
+    // to a node yet there.
+    pub async fn mounts_for(
+        &self,
+        spec: &ContainerSpec,
+        node: &str,
+    ) -> Result<Vec<Mount>, anyhow::Error> {
+        let _binding = self.binding.lock().await;
+        let mut mounts = Vec::new();
+        for claim in spec.volumes.iter() {
+            let mut volume = self.claimed(spec, &claim.name).await?;
+            match &volume.node {
+                Some(bound) if bound != node => {
+                    return Err(anyhow!(
+                        "volume {} is on node {}, not {}",
+                        volume.name,
+                        bound,
+                        node
+                    ));
+                }
+                Some(_) => {}
+                None => {
+                    self.nodes
+                        .node(node)
+                        .await?
+                        .create_volume(&volume.name)
+                        .await?;
+                    volume.node = Some(String::from(node));
+                    self.store.put(KIND, &volume.name, &volume).await?;
+                }
+            }
+
+            mounts.push(Mount {
+                source: volume.name,
+                target: claim.mount_path.clone(),
+                read_only: claim.read_only,
+                files: BTreeMap::new(),
+            });
+        }
+        Ok(mounts)
+    }
+
+    async fn claimed(&self, spec: &ContainerSpec, name: &str) -> Result<Volume, anyhow::Error> {
+        self.store.get(KIND, name).await?.ok_or_else(|| {
+            VolumeError::NotFound {
+                volume: String::from(name),
+                container: spec.name.clone(),
+            }
+            .into()
+        })
+    }
+
+    async fn users(&self) -> BTreeMap<String, Vec<String>> {
+        let mut users: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for container in self.status_watcher.list().await {
+            for claim in container.spec.volumes.iter() {
+                users
+                    .entry(claim.name.clone())
+                    .or_default()
+                    .push(container.name.clone());
+            }
+        }
+        for containers in users.values_mut() {
+            containers.sort();
+            containers.dedup();
+        }
+        users
+    }
+}
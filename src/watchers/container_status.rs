@@ -1,77 +1,569 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
 
 use async_trait::async_trait;
-use tokio::{process::Command, sync::Mutex};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Mutex};
 
-use crate::entities::container::{Container, ContainerStatus};
+use crate::{
+    config::CrashLoopConfig,
+    controllers::work_queue::WorkQueue,
+    entities::container::{Container, ContainerStatus},
+    events::{event::EventReason, recorder::EventRecorder},
+    runtime::ContainerRuntime,
+};
 
-pub struct ContainerStatusWatcher {
-    pub containers: Arc<Mutex<HashMap<String, ContainerStatus>>>,
+use super::{
+    error::WatcherError,
+    watcher::{Watcher, WatcherContext},
+};
+
+const WATCH_EVENTS_CAPACITY: usize = 256;
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// Status transitions kept per container, oldest dropped first.
+const STATUS_HISTORY_LIMIT: usize = 20;
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum WatchEventType {
+    Added,
+    Modified,
+    Deleted,
 }
 
-#[async_trait]
-pub trait ContainerStatusWatcherTrait {
-    async fn check_status(&self);
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WatchEvent {
+    #[serde(rename = "type")]
+    pub event_type: WatchEventType,
+    pub object: Container,
 }
 
-#[async_trait]
-impl ContainerStatusWatcherTrait for ContainerStatusWatcher {
-    async fn check_status(&self) {
-        println!("Checking status");
-        let containers = self.containers.lock();
-
-        for (id, status) in containers.await.iter_mut() {
-            println!(
-                "Checking status for container: {}\nCurrent status is: {:?}\n------------------",
-                id,
-                status.clone()
-            );
-            let mut command = Command::new("docker");
-
-            command
-                .arg("inspect")
-                .arg("--format")
-                .arg("{{.State.Status}}")
-                .arg(id);
-
-            let out = command.output().await.unwrap();
-
-            if out.status.success() {
-                let new_container_status = self.container_status_mapper(
-                    String::from_utf8_lossy(&out.stdout).trim().to_string(),
-                );
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusTransition {
+    pub status: ContainerStatus,
+    pub timestamp: DateTime<Utc>,
+}
 
-                if new_container_status != status.clone() {
-                    *status = new_container_status
-                }
-            }
-        }
-    }
+// Recent restarts of one container, for telling a crash loop from the odd restart.
+#[derive(Default)]
+struct CrashLoop {
+    // Within the window, oldest first.
+    restarts: VecDeque<DateTime<Utc>>,
+    // Back-offs since the container last stayed up for a whole window.
+    back_offs: u32,
+    // Set once the watcher started the container after its back-off, until it is seen running.
+    starting: bool,
+}
+
+// What to do to a container once the containers lock is released.
+enum CrashLoopAction {
+    Stop,
+    Start,
+}
+
+pub struct ContainerStatusWatcher {
+    pub containers: Arc<Mutex<HashMap<String, Container>>>,
+    watch_events: broadcast::Sender<WatchEvent>,
+    runtime: Arc<dyn ContainerRuntime + Send + Sync>,
+    recorder: Arc<EventRecorder>,
+    // Container ids to inspect, backing off on the ones whose inspect keeps failing.
+    queue: WorkQueue<String>,
+    // What the last resync listed, by id, taken by the first sync of each container after it.
+    listed: Mutex<HashMap<String, Container>>,
+    history: Mutex<HashMap<String, VecDeque<StatusTransition>>>,
+    crash_loop: CrashLoopConfig,
+    crash_loops: Mutex<HashMap<String, CrashLoop>>,
+    // Containers nic8s stopped on purpose, by id, which stay stopped whatever their restart
+    // policy until nic8s starts them again.
+    held_stopped: Mutex<HashSet<String>>,
 }
 
 impl ContainerStatusWatcher {
-    fn new() -> Self {
+    pub fn new(
+        runtime: Arc<dyn ContainerRuntime + Send + Sync>,
+        recorder: Arc<EventRecorder>,
+    ) -> Self {
+        let (watch_events, _) = broadcast::channel(WATCH_EVENTS_CAPACITY);
+
         ContainerStatusWatcher {
             containers: Arc::new(Mutex::new(HashMap::new())),
+            watch_events,
+            runtime,
+            recorder,
+            queue: WorkQueue::new(RETRY_BASE_DELAY, RETRY_MAX_DELAY),
+            listed: Mutex::new(HashMap::new()),
+            history: Mutex::new(HashMap::new()),
+            crash_loop: CrashLoopConfig::default(),
+            crash_loops: Mutex::new(HashMap::new()),
+            held_stopped: Mutex::new(HashSet::new()),
         }
     }
 
+    pub fn with_crash_loop(mut self, config: &CrashLoopConfig) -> Self {
+        self.crash_loop = config.clone();
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchEvent> {
+        self.watch_events.subscribe()
+    }
+
+    fn publish(&self, event_type: WatchEventType, container: Container) {
+        // Sending only fails when nobody is subscribed, which is fine.
+        let _ = self.watch_events.send(WatchEvent {
+            event_type,
+            object: container,
+        });
+    }
+
     pub async fn add_container(&self, container: Container) {
+        self.record_transition(&container.id, container.get_status())
+            .await;
         self.containers
             .lock()
             .await
-            .insert(container.clone().id, container.get_status());
+            .insert(container.id.clone(), container.clone());
+        self.publish(WatchEventType::Added, container);
     }
 
-    fn container_status_mapper(&self, status: String) -> ContainerStatus {
-        match status.as_str() {
-            "created" => ContainerStatus::Created,
-            "running" => ContainerStatus::Running,
-            "restarting" => ContainerStatus::Restarting,
-            "exited" => ContainerStatus::Exited,
-            "paused" => ContainerStatus::Paused,
-            "dead" => ContainerStatus::Dead,
-            _ => ContainerStatus::Unknown,
+    pub async fn remove_container(&self, id: &str) -> Option<Container> {
+        let removed = self.containers.lock().await.remove(id);
+        self.history.lock().await.remove(id);
+        self.crash_loops.lock().await.remove(id);
+        self.held_stopped.lock().await.remove(id);
+
+        if let Some(container) = removed.clone() {
+            self.publish(WatchEventType::Deleted, container);
         }
+        removed
+    }
+
+    pub async fn list(&self) -> Vec<Container> {
+        self.containers.lock().await.values().cloned().collect()
+    }
+
+    // Oldest first.
+    pub async fn history(&self, id: &str) -> Vec<StatusTransition> {
+        self.history
+            .lock()
+            .await
+            .get(id)
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    async fn record_transition(&self, id: &str, status: ContainerStatus) {
+        let mut history = self.history.lock().await;
+        let transitions = history.entry(String::from(id)).or_default();
+        if transitions.len() == STATUS_HISTORY_LIMIT {
+            transitions.pop_front();
+        }
+        transitions.push_back(StatusTransition {
+            status,
+            timestamp: Utc::now(),
+        });
+    }
+
+    pub async fn hold_stopped(&self, id: &str) {
+        self.held_stopped.lock().await.insert(String::from(id));
+    }
+
+    pub async fn release(&self, id: &str) {
+        self.held_stopped.lock().await.remove(id);
+    }
+
+    pub async fn is_held_stopped(&self, id: &str) -> bool {
+        self.held_stopped.lock().await.contains(id)
+    }
+
+    pub async fn find(&self, id_or_name: &str) -> Option<Container> {
+        self.containers
+            .lock()
+            .await
+            .values()
+            .find(|container| container.id == id_or_name || container.name == id_or_name)
+            .cloned()
+    }
+
+    // Counts a restart, returning how long to hold the container stopped if it is crash looping.
+    // Once a container was backed off, every restart within the window backs it off again, for
+    // twice as long.
+    async fn restarted(&self, id: &str, now: DateTime<Utc>) -> Option<Duration> {
+        let mut crash_loops = self.crash_loops.lock().await;
+        let crash_loop = crash_loops.entry(String::from(id)).or_default();
+        if std::mem::take(&mut crash_loop.starting) {
+            // The start after a back-off isn't a crash.
+            return None;
+        }
+        crash_loop.restarts.push_back(now);
+        self.forget_old_restarts(crash_loop, now);
+        if crash_loop.back_offs == 0 && crash_loop.restarts.len() <= self.crash_loop.max_restarts {
+            return None;
+        }
+        Some(self.next_back_off(crash_loop))
+    }
+
+    fn next_back_off(&self, crash_loop: &mut CrashLoop) -> Duration {
+        let backoff = self
+            .crash_loop
+            .initial_backoff_seconds
+            .saturating_mul(2u64.saturating_pow(crash_loop.back_offs))
+            .min(self.crash_loop.max_backoff_seconds);
+        crash_loop.back_offs += 1;
+        Duration::from_secs(backoff)
+    }
+
+    async fn back_off(&self, container: &mut Container, backoff: Duration, now: DateTime<Utc>) {
+        self.recorder
+            .record_container(
+                &container.name,
+                EventReason::BackOff,
+                match &container.last_termination {
+                    Some(termination) => format!(
+                        "Back-off {}s restarting crash looping container {} (last exit: {})",
+                        backoff.as_secs(),
+                        container.name,
+                        termination
+                    ),
+                    None => format!(
+                        "Back-off {}s restarting crash looping container {}",
+                        backoff.as_secs(),
+                        container.name
+                    ),
+                },
+            )
+            .await;
+        if container.get_status() != ContainerStatus::CrashLoopBackOff {
+            self.record_transition(&container.id, ContainerStatus::CrashLoopBackOff)
+                .await;
+            container.set_status(ContainerStatus::CrashLoopBackOff);
+        }
+        let until = now + chrono::Duration::seconds(backoff.as_secs() as i64);
+        container.back_off_until = Some(until.to_rfc3339());
+    }
+
+    // A container that failed to start after its back-off waits for a longer one.
+    async fn start_failed(&self, id: &str) {
+        let backoff = {
+            let mut crash_loops = self.crash_loops.lock().await;
+            let crash_loop = crash_loops.entry(String::from(id)).or_default();
+            crash_loop.starting = false;
+            self.next_back_off(crash_loop)
+        };
+        let mut containers = self.containers.lock().await;
+        if let Some(container) = containers.get_mut(id) {
+            self.back_off(container, backoff, Utc::now()).await;
+            self.publish(WatchEventType::Modified, container.clone());
+        }
+    }
+
+    fn forget_old_restarts(&self, crash_loop: &mut CrashLoop, now: DateTime<Utc>) {
+        let window = chrono::Duration::seconds(self.crash_loop.window_seconds as i64);
+        while crash_loop
+            .restarts
+            .front()
+            .is_some_and(|restart| now - *restart > window)
+        {
+            crash_loop.restarts.pop_front();
+        }
+    }
+
+    // A container that stayed up for a whole window since its last restart starts over with the
+    // shortest back-off.
+    async fn forgive(&self, id: &str, now: DateTime<Utc>) {
+        let mut crash_loops = self.crash_loops.lock().await;
+        if let Some(crash_loop) = crash_loops.get_mut(id) {
+            self.forget_old_restarts(crash_loop, now);
+            if crash_loop.restarts.is_empty() && !crash_loop.starting {
+                crash_loops.remove(id);
+            }
+        }
+    }
+
+    // Lists the managed containers in one call and queues every tracked container, which then
+    // syncs from the listing. Ones the listing misses, such as those on a node that didn't
+    // answer, are inspected one by one instead, and ones backing off after a failed inspect keep
+    // waiting.
+    pub async fn resync(&self) {
+        println!("Checking status");
+        let listed = match self.runtime.list_managed().await {
+            Ok(listed) => listed
+                .into_iter()
+                .map(|container| (container.id.clone(), container))
+                .collect(),
+            Err(error) => {
+                println!(
+                    "Failed to list containers, inspecting them instead: {}",
+                    error
+                );
+                HashMap::new()
+            }
+        };
+        *self.listed.lock().await = listed;
+
+        let ids: Vec<String> = self.containers.lock().await.keys().cloned().collect();
+        for id in ids {
+            self.queue.add(id).await;
+        }
+    }
+
+    async fn process(&self, ctx: &WatcherContext) {
+        loop {
+            let id = tokio::select! {
+                id = self.queue.get() => id,
+                _ = ctx.shutdown.cancelled() => return,
+            };
+            match self.sync_container(&id).await {
+                Ok(()) => self.queue.forget(&id).await,
+                Err(error) => {
+                    let delay = self.queue.add_rate_limited(id.clone()).await;
+                    println!("{}, retrying in {}ms", error, delay.as_millis());
+                }
+            }
+            self.queue.done(&id).await;
+        }
+    }
+
+    async fn sync_container(&self, id: &str) -> Result<(), WatcherError> {
+        let Some(name) = self
+            .containers
+            .lock()
+            .await
+            .get(id)
+            .map(|container| container.name.clone())
+        else {
+            return Ok(());
+        };
+
+        // Containers may run on other nodes, so the state comes from the runtime rather than the
+        // local docker.
+        let listed = self.listed.lock().await.remove(id);
+        let current = match listed {
+            Some(current) => current,
+            None => match self.runtime.inspect(id).await {
+                Ok(current) => current,
+                Err(source) => {
+                    let error = WatcherError::Inspect {
+                        container: name.clone(),
+                        source,
+                    };
+                    // Only the first failure is recorded until the container recovers.
+                    if self.queue.failures(id).await == 0 {
+                        self.recorder
+                            .record_container(&name, EventReason::Failed, error.to_string())
+                            .await;
+                    }
+                    return Err(error);
+                }
+            },
+        };
+
+        let action = self.update_container(id, current).await;
+        match action {
+            Some(CrashLoopAction::Stop) => {
+                if let Err(error) = self.runtime.stop(id, None).await {
+                    println!("Failed to stop crash looping container {}: {}", name, error);
+                }
+            }
+            Some(CrashLoopAction::Start) => {
+                if let Err(error) = self.runtime.start(id).await {
+                    self.recorder
+                        .record_container(
+                            &name,
+                            EventReason::Failed,
+                            format!("Failed to start container after back-off: {}", error),
+                        )
+                        .await;
+                    self.start_failed(id).await;
+                }
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    async fn update_container(&self, id: &str, current: Container) -> Option<CrashLoopAction> {
+        let mut containers = self.containers.lock().await;
+        let container = containers.get_mut(id)?;
+        let now = Utc::now();
+        let mut action = None;
+        println!(
+            "Checking status for container: {}\nCurrent status is: {:?}\n------------------",
+            id,
+            container.get_status()
+        );
+        let was_ready = container.is_ready();
+        let new_container_status = current.get_status();
+        let started_at = current.started_at;
+        let health = current.health;
+        let mut changed = false;
+
+        // Each exit is reported once, with why the container stopped, and kept on the container
+        // after it runs again so a restart loop shows what it is looping on.
+        let mut terminated = false;
+        if let Some(termination) = current.last_termination {
+            let seen = container
+                .last_termination
+                .as_ref()
+                .is_some_and(|last| last.finished_at == termination.finished_at);
+            if !seen {
+                let reason = if termination.oom_killed {
+                    EventReason::OOMKilled
+                } else {
+                    EventReason::Exited
+                };
+                self.recorder
+                    .record_container(
+                        &container.name,
+                        reason,
+                        format!("Container {} exited ({})", container.name, termination),
+                    )
+                    .await;
+                container.last_termination = Some(termination);
+                terminated = true;
+                changed = true;
+            }
+        }
+
+        if started_at.is_some() && started_at != container.started_at {
+            // A new start time for a container we already saw start means it restarted.
+            let restarted = container.started_at.is_some();
+            let message = if restarted {
+                container.restart_count += 1;
+                container.last_restart = Some(now.to_rfc3339());
+                // Restarts between two checks never show up as a status change.
+                if new_container_status == container.get_status() {
+                    self.record_transition(id, new_container_status.clone())
+                        .await;
+                }
+                format!(
+                    "Started container {} (restart {})",
+                    container.name, container.restart_count
+                )
+            } else {
+                format!("Started container {}", container.name)
+            };
+            self.recorder
+                .record_container(&container.name, EventReason::Started, message)
+                .await;
+            container.started_at = started_at;
+            changed = true;
+
+            if restarted {
+                if let Some(backoff) = self.restarted(id, now).await {
+                    self.back_off(container, backoff, now).await;
+                    action = Some(CrashLoopAction::Stop);
+                }
+            }
+        }
+
+        let due = container.back_off_until.as_deref().is_some_and(|until| {
+            DateTime::parse_from_rfc3339(until).map_or(true, |until| until <= now)
+        });
+        if due && action.is_none() {
+            container.back_off_until = None;
+            if let Some(crash_loop) = self.crash_loops.lock().await.get_mut(id) {
+                crash_loop.starting = true;
+            }
+            action = Some(CrashLoopAction::Start);
+            changed = true;
+        }
+        if container.get_status() == ContainerStatus::Running {
+            self.forgive(id, now).await;
+        }
+
+        // A crash looping container stays in CrashLoopBackOff, whatever the runtime says about it
+        // while it is stopped, until it is seen running again.
+        let backing_off = container.get_status() == ContainerStatus::CrashLoopBackOff
+            && (container.back_off_until.is_some()
+                || new_container_status != ContainerStatus::Running);
+        if !backing_off && new_container_status != container.get_status() {
+            let reason = match new_container_status {
+                ContainerStatus::Exited if !terminated => Some(EventReason::Exited),
+                ContainerStatus::Dead => Some(EventReason::Dead),
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                self.recorder
+                    .record_container(
+                        &container.name,
+                        reason,
+                        format!("Container {} is {:?}", container.name, new_container_status),
+                    )
+                    .await;
+            }
+            self.record_transition(id, new_container_status.clone())
+                .await;
+            container.set_status(new_container_status);
+            changed = true;
+        }
+
+        if health != container.health {
+            if health.as_deref() == Some("unhealthy") {
+                self.recorder
+                    .record_container(
+                        &container.name,
+                        EventReason::Unhealthy,
+                        format!("Health check failed for container {}", container.name),
+                    )
+                    .await;
+            }
+            container.health = health;
+            changed = true;
+        }
+
+        if container.is_ready() != was_ready {
+            let (reason, message) = if container.is_ready() {
+                (
+                    EventReason::Ready,
+                    format!(
+                        "Container {} is ready, added to the endpoints of app {}",
+                        container.name, container.app
+                    ),
+                )
+            } else {
+                (
+                    EventReason::NotReady,
+                    format!(
+                        "Container {} is not ready, removed from the endpoints of app {}",
+                        container.name, container.app
+                    ),
+                )
+            };
+            self.recorder
+                .record_container(&container.name, reason, message)
+                .await;
+        }
+
+        if changed {
+            self.publish(WatchEventType::Modified, container.clone());
+        }
+        action
+    }
+}
+
+#[async_trait]
+impl Watcher for ContainerStatusWatcher {
+    fn name(&self) -> &str {
+        "container-status"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        let resync = async {
+            loop {
+                self.resync().await;
+                if !ctx.sleep(CHECK_INTERVAL).await {
+                    return;
+                }
+            }
+        };
+        tokio::join!(resync, self.process(&ctx));
+        Ok(())
     }
 }
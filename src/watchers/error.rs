@@ -0,0 +1,34 @@
+use thiserror::Error;
+
+// A failed check. Watchers log these and carry on with the next check rather than stopping.
+#[derive(Debug, Error)]
+pub enum WatcherError {
+    #[error("failed to inspect container {container}: {source}")]
+    Inspect {
+        container: String,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("failed to collect resource usage: {0}")]
+    Stats(#[source] anyhow::Error),
+    #[error("failed to list containers on node {node}: {source}")]
+    ListNode {
+        node: String,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("failed to reschedule container {container} from node {node}: {source}")]
+    Reschedule {
+        container: String,
+        node: String,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("failed to remove container {container} from node {node}: {source}")]
+    Remove {
+        container: String,
+        node: String,
+        #[source]
+        source: anyhow::Error,
+    },
+}
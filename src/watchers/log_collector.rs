@@ -0,0 +1,152 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::Mutex;
+
+use crate::{
+    config::LogsConfig,
+    entities::container::{Container, ContainerStatus},
+    runtime::ContainerRuntime,
+    store::logs::LogStore,
+};
+
+use super::{
+    container_status::ContainerStatusWatcher,
+    watcher::{Watcher, WatcherContext},
+};
+
+#[derive(Default)]
+struct Cursors {
+    // Per container name, when the last stored line was received.
+    received: HashMap<String, Option<DateTime<Utc>>>,
+    // Stopped containers whose output was collected, with the start it was collected after.
+    stopped: HashMap<String, Option<String>>,
+}
+
+// Copies the output of managed containers to the log store, so it can still be read once the
+// containers are removed or recreated.
+pub struct LogCollector {
+    runtime: Arc<dyn ContainerRuntime + Send + Sync>,
+    status_watcher: Arc<ContainerStatusWatcher>,
+    store: LogStore,
+    interval: Duration,
+    cursors: Mutex<Cursors>,
+}
+
+impl LogCollector {
+    pub fn new(
+        runtime: Arc<dyn ContainerRuntime + Send + Sync>,
+        status_watcher: Arc<ContainerStatusWatcher>,
+        store: LogStore,
+        config: &LogsConfig,
+    ) -> Self {
+        LogCollector {
+            runtime,
+            status_watcher,
+            store,
+            interval: Duration::from_secs(config.interval_seconds),
+            cursors: Mutex::new(Cursors::default()),
+        }
+    }
+
+    pub fn store(&self) -> &LogStore {
+        &self.store
+    }
+
+    pub async fn collect_all(&self) {
+        let mut cursors = self.cursors.lock().await;
+        let containers = self.status_watcher.list().await;
+
+        for container in containers.iter() {
+            let stopped = matches!(
+                container.get_status(),
+                ContainerStatus::Exited | ContainerStatus::Dead | ContainerStatus::CrashLoopBackOff
+            );
+            // Stopped containers have nothing new to say until they start again.
+            if stopped && cursors.stopped.get(&container.id) == Some(&container.started_at) {
+                continue;
+            }
+
+            match self.collect(&mut cursors, container).await {
+                Ok(()) if stopped => {
+                    cursors
+                        .stopped
+                        .insert(container.id.clone(), container.started_at.clone());
+                }
+                Ok(()) => {
+                    cursors.stopped.remove(&container.id);
+                }
+                Err(error) => println!(
+                    "Failed to collect the logs of container {}: {}",
+                    container.name, error
+                ),
+            }
+        }
+
+        let names: HashSet<String> = containers
+            .iter()
+            .map(|container| container.name.clone())
+            .collect();
+        cursors
+            .stopped
+            .retain(|id, _| containers.iter().any(|container| &container.id == id));
+        cursors.received.retain(|name, _| names.contains(name));
+        if let Err(error) = self.store.prune(&names).await {
+            println!("Failed to delete old container logs: {}", error);
+        }
+    }
+
+    // Collects what the container printed since the last pass, before it is removed.
+    pub async fn flush(&self, container: &Container) {
+        let mut cursors = self.cursors.lock().await;
+        if let Err(error) = self.collect(&mut cursors, container).await {
+            println!(
+                "Failed to collect the logs of container {}: {}",
+                container.name, error
+            );
+        }
+    }
+
+    async fn collect(
+        &self,
+        cursors: &mut Cursors,
+        container: &Container,
+    ) -> Result<(), anyhow::Error> {
+        let since = match cursors.received.get(&container.name) {
+            Some(since) => *since,
+            None => self.store.last_timestamp(&container.name).await?,
+        };
+
+        let lines = self.runtime.logs_since(&container.id, since).await?;
+        self.store.append(&container.name, &lines).await?;
+        let received = lines.last().map(|line| line.timestamp).or(since);
+        cursors.received.insert(container.name.clone(), received);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Watcher for LogCollector {
+    fn name(&self) -> &str {
+        "log-collector"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        loop {
+            self.collect_all().await;
+            if !ctx.sleep(self.interval).await {
+                return Ok(());
+            }
+        }
+    }
+
+    // Picks up what was printed since the last pass before the daemon stops.
+    async fn shutdown(&self) {
+        self.collect_all().await;
+    }
+}
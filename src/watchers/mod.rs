@@ -1,2 +1,7 @@
 pub mod container_status;
-pub mod watchers;
+pub mod error;
+pub mod log_collector;
+pub mod node_status;
+pub mod registry;
+pub mod resource_usage;
+pub mod watcher;
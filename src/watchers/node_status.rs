@@ -0,0 +1,239 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::{
+    cluster::Cluster,
+    controllers::work_queue::WorkQueue,
+    entities::{
+        container::Container,
+        node::{Node, NodeStatus},
+    },
+    events::event::{EventReason, ObjectKind},
+};
+
+use super::{
+    error::WatcherError,
+    watcher::{Watcher, WatcherContext},
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const RESCHEDULE_BASE_DELAY: Duration = Duration::from_secs(5);
+const RESCHEDULE_MAX_DELAY: Duration = Duration::from_secs(300);
+
+pub struct NodeStatusWatcher {
+    cluster: Cluster,
+    heartbeat_timeout: Duration,
+    // Ids of containers to move off nodes that are not ready.
+    queue: WorkQueue<String>,
+}
+
+impl NodeStatusWatcher {
+    pub fn new(cluster: Cluster, heartbeat_timeout: Duration) -> Self {
+        NodeStatusWatcher {
+            cluster,
+            heartbeat_timeout,
+            queue: WorkQueue::new(RESCHEDULE_BASE_DELAY, RESCHEDULE_MAX_DELAY),
+        }
+    }
+
+    async fn record(&self, node: &Node, reason: EventReason, message: String) {
+        self.cluster
+            .events
+            .record(ObjectKind::Node, &node.name, reason, message)
+            .await
+    }
+
+    async fn reschedule(&self, node: &Node) {
+        let stranded = self
+            .cluster
+            .status_watcher
+            .list()
+            .await
+            .into_iter()
+            .filter(|container| container.node == node.name && container.spec.node.is_none());
+
+        for container in stranded {
+            self.queue.add(container.id).await;
+        }
+    }
+
+    async fn process(&self, ctx: &WatcherContext) {
+        loop {
+            let id = tokio::select! {
+                id = self.queue.get() => id,
+                _ = ctx.shutdown.cancelled() => return,
+            };
+            match self.reschedule_container(&id).await {
+                Ok(()) => self.queue.forget(&id).await,
+                Err(error) => {
+                    let delay = self.queue.add_rate_limited(id.clone()).await;
+                    println!("{}, retrying in {}ms", error, delay.as_millis());
+                }
+            }
+            self.queue.done(&id).await;
+        }
+    }
+
+    async fn reschedule_container(&self, id: &str) -> Result<(), WatcherError> {
+        let Some(container) = self.cluster.status_watcher.find(id).await else {
+            return Ok(());
+        };
+        // The node may have come back while the container was waiting.
+        let not_ready = self
+            .cluster
+            .nodes
+            .list()
+            .await
+            .iter()
+            .any(|node| node.name == container.node && node.status == NodeStatus::NotReady);
+        if !not_ready {
+            return Ok(());
+        }
+
+        let replacement = Container::new(&container.spec, &self.cluster)
+            .await
+            .map_err(|source| WatcherError::Reschedule {
+                container: container.name.clone(),
+                node: container.node.clone(),
+                source,
+            })?;
+        self.cluster
+            .events
+            .record_container(
+                &container.name,
+                EventReason::Rescheduled,
+                format!(
+                    "Rescheduled container {} from node {} to node {}: node not ready",
+                    container.name, container.node, replacement.node
+                ),
+            )
+            .await;
+        self.cluster
+            .status_watcher
+            .remove_container(&container.id)
+            .await;
+        Ok(())
+    }
+
+    // A node that comes back still runs the containers that were rescheduled away from it.
+    async fn remove_rescheduled(&self, node: &Node) {
+        let runtime = match self.cluster.nodes.node(&node.name).await {
+            Ok(runtime) => runtime,
+            Err(_) => return,
+        };
+        let containers = match runtime.list_managed().await {
+            Ok(containers) => containers,
+            Err(source) => {
+                let error = WatcherError::ListNode {
+                    node: node.name.clone(),
+                    source,
+                };
+                println!("{}", error);
+                return;
+            }
+        };
+
+        let tracked = self.cluster.status_watcher.list().await;
+        for container in containers {
+            let Some(replacement) = tracked
+                .iter()
+                .find(|tracked| tracked.name == container.name && tracked.node != node.name)
+            else {
+                continue;
+            };
+
+            self.cluster
+                .events
+                .record_container(
+                    &container.name,
+                    EventReason::Killed,
+                    format!(
+                        "Removing container {} from node {}: it was rescheduled to node {}",
+                        container.name, node.name, replacement.node
+                    ),
+                )
+                .await;
+            if let Err(source) = runtime.remove(&container.id).await {
+                let error = WatcherError::Remove {
+                    container: container.name.clone(),
+                    node: node.name.clone(),
+                    source,
+                };
+                println!("{}", error);
+            }
+        }
+    }
+
+    pub async fn check_nodes(&self) {
+        for node in self.cluster.nodes.list().await {
+            // The local node is this process, so there is nothing to miss.
+            if node.address.is_none() {
+                continue;
+            }
+
+            let silent = node
+                .since_last_seen()
+                .and_then(|silent| silent.to_std().ok())
+                .unwrap_or_default();
+            let status = if silent > self.heartbeat_timeout {
+                NodeStatus::NotReady
+            } else {
+                NodeStatus::Ready
+            };
+
+            if status != node.status {
+                self.cluster.nodes.set_status(&node.name, status).await;
+                match status {
+                    NodeStatus::NotReady => {
+                        self.record(
+                            &node,
+                            EventReason::NodeNotReady,
+                            format!(
+                                "Node {} missed heartbeats for {}s",
+                                node.name,
+                                silent.as_secs()
+                            ),
+                        )
+                        .await
+                    }
+                    _ => {
+                        self.record(
+                            &node,
+                            EventReason::NodeReady,
+                            format!("Node {} is ready", node.name),
+                        )
+                        .await;
+                        self.remove_rescheduled(&node).await;
+                    }
+                }
+            }
+
+            // Queued on every check; containers that failed to reschedule, typically because no
+            // other node is ready, back off before the next attempt.
+            if status == NodeStatus::NotReady {
+                self.reschedule(&node).await;
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Watcher for NodeStatusWatcher {
+    fn name(&self) -> &str {
+        "node-status"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        let check = async {
+            loop {
+                self.check_nodes().await;
+                if !ctx.sleep(CHECK_INTERVAL).await {
+                    return;
+                }
+            }
+        };
+        tokio::join!(check, self.process(&ctx));
+        Ok(())
+    }
+}
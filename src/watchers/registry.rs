@@ -0,0 +1,305 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::{
+    sync::Mutex,
+    task::{JoinError, JoinHandle},
+};
+use tokio_util::sync::CancellationToken;
+
+use super::watcher::{Watcher, WatcherContext};
+
+// A failed watcher is restarted after a backoff that doubles with each failure in a row.
+const RESTART_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+// A restarted watcher has recovered once it finishes a check, or, for watchers that don't sleep
+// between checks, once it has run this long.
+const RECOVERED_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WatcherStatus {
+    pub name: String,
+    pub running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_tick: Option<DateTime<Utc>>,
+    pub restarts: u32,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+struct Running {
+    ctx: WatcherContext,
+    task: JoinHandle<()>,
+    supervision: Arc<std::sync::Mutex<Supervision>>,
+}
+
+// How a watcher's runs went since it was started.
+struct Supervision {
+    restarts: u32,
+    failures: u32,
+    last_error: Option<String>,
+    started: DateTime<Utc>,
+    failed: Option<DateTime<Utc>>,
+}
+
+impl Supervision {
+    // Failures in a row, which stop counting once a later run recovers.
+    fn consecutive_failures(&self, ctx: &WatcherContext, now: DateTime<Utc>) -> u32 {
+        let Some(failed) = self.failed else {
+            return 0;
+        };
+        let recovered = self.started > failed
+            && (ctx.ticked().is_some_and(|ticked| ticked > failed)
+                || (now - self.started).to_std().unwrap_or_default() >= RECOVERED_AFTER);
+        if recovered {
+            0
+        } else {
+            self.failures
+        }
+    }
+}
+
+struct Entry {
+    watcher: Arc<dyn Watcher>,
+    running: Option<Running>,
+}
+
+// Every watcher runs in its own task, on a child of the daemon's shutdown token so it stops
+// with the daemon but can also be stopped and started on its own.
+pub struct WatcherRegistry {
+    shutdown: CancellationToken,
+    watchers: Mutex<BTreeMap<String, Entry>>,
+}
+
+impl WatcherRegistry {
+    pub fn new(shutdown: CancellationToken) -> Self {
+        WatcherRegistry {
+            shutdown,
+            watchers: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub async fn register(&self, watcher: Arc<dyn Watcher>) -> Result<(), anyhow::Error> {
+        let mut watchers = self.watchers.lock().await;
+        let name = watcher.name().to_string();
+        if watchers.contains_key(&name) {
+            return Err(anyhow!("watcher {} is already registered", name));
+        }
+        watchers.insert(
+            name,
+            Entry {
+                watcher,
+                running: None,
+            },
+        );
+        Ok(())
+    }
+
+    // False when no watcher has that name. Starting a running watcher does nothing.
+    pub async fn start(&self, name: &str) -> bool {
+        let mut watchers = self.watchers.lock().await;
+        let Some(entry) = watchers.get_mut(name) else {
+            return false;
+        };
+        if entry
+            .running
+            .as_ref()
+            .is_some_and(|running| !running.task.is_finished())
+        {
+            return true;
+        }
+
+        let ctx = WatcherContext::new(self.shutdown.child_token());
+        let supervision = Arc::new(std::sync::Mutex::new(Supervision {
+            restarts: 0,
+            failures: 0,
+            last_error: None,
+            started: Utc::now(),
+            failed: None,
+        }));
+        let task = tokio::spawn(supervise(
+            entry.watcher.clone(),
+            ctx.clone(),
+            supervision.clone(),
+        ));
+        entry.running = Some(Running {
+            ctx,
+            task,
+            supervision,
+        });
+        true
+    }
+
+    pub async fn start_all(&self) {
+        let names: Vec<String> = self.watchers.lock().await.keys().cloned().collect();
+        for name in names {
+            self.start(&name).await;
+        }
+    }
+
+    // Waits for the watcher's loop to return before calling its `shutdown`.
+    pub async fn stop(&self, name: &str) -> bool {
+        let (watcher, running) = {
+            let mut watchers = self.watchers.lock().await;
+            let Some(entry) = watchers.get_mut(name) else {
+                return false;
+            };
+            (entry.watcher.clone(), entry.running.take())
+        };
+
+        if let Some(running) = running {
+            running.ctx.shutdown.cancel();
+            if let Err(error) = running.task.await {
+                println!("Watcher {} panicked: {}", name, error);
+            }
+            watcher.shutdown().await;
+        }
+        true
+    }
+
+    pub async fn stop_all(&self) {
+        let names: Vec<String> = self.watchers.lock().await.keys().cloned().collect();
+        for name in names {
+            self.stop(&name).await;
+        }
+    }
+
+    // What is wrong with the watchers that should be running: ones failing until they're
+    // restarted, and ones more than `grace` late back from their sleep, which are likely stuck.
+    // Ones stopped through `stop` don't count.
+    pub async fn problems(&self, grace: Duration) -> Vec<String> {
+        let now = Utc::now();
+        let mut problems = Vec::new();
+        for (name, entry) in self.watchers.lock().await.iter() {
+            let Some(running) = &entry.running else {
+                continue;
+            };
+            if running.task.is_finished() {
+                problems.push(format!("watcher {} stopped unexpectedly", name));
+                continue;
+            }
+            let failing = {
+                let supervision = running.supervision.lock().unwrap();
+                match supervision.consecutive_failures(&running.ctx, now) {
+                    0 => None,
+                    failures => Some((failures, supervision.last_error.clone())),
+                }
+            };
+            if let Some((failures, error)) = failing {
+                let error = error.unwrap_or_default();
+                problems.push(match failures {
+                    1 => format!("watcher {} {}", name, error),
+                    _ => format!("watcher {} {}, {} times in a row", name, error, failures),
+                });
+                continue;
+            }
+            if let Some(due) = running.ctx.due() {
+                let late = (now - due).to_std().unwrap_or_default();
+                if late > grace {
+                    problems.push(format!(
+                        "watcher {} is {}s late for its next check",
+                        name,
+                        late.as_secs()
+                    ));
+                }
+            }
+        }
+        problems
+    }
+
+    pub async fn list(&self) -> Vec<WatcherStatus> {
+        let now = Utc::now();
+        self.watchers
+            .lock()
+            .await
+            .iter()
+            .map(|(name, entry)| {
+                let mut status = WatcherStatus {
+                    name: name.clone(),
+                    running: false,
+                    last_tick: None,
+                    restarts: 0,
+                    consecutive_failures: 0,
+                    last_error: None,
+                };
+                if let Some(running) = &entry.running {
+                    let supervision = running.supervision.lock().unwrap();
+                    status.running = !running.task.is_finished();
+                    status.last_tick = running.ctx.ticked();
+                    status.restarts = supervision.restarts;
+                    status.consecutive_failures =
+                        supervision.consecutive_failures(&running.ctx, now);
+                    status.last_error = supervision.last_error.clone();
+                }
+                status
+            })
+            .collect()
+    }
+}
+
+// Runs the watcher until its context is cancelled, in a task of its own so a panic is caught
+// like an error. Either, or returning early, restarts it after a backoff.
+async fn supervise(
+    watcher: Arc<dyn Watcher>,
+    ctx: WatcherContext,
+    supervision: Arc<std::sync::Mutex<Supervision>>,
+) {
+    loop {
+        let run = tokio::spawn({
+            let watcher = watcher.clone();
+            let ctx = ctx.clone();
+            async move { watcher.run(ctx).await }
+        });
+        let failure = match run.await {
+            Ok(Ok(())) if ctx.shutdown.is_cancelled() => return,
+            Ok(Ok(())) => String::from("stopped unexpectedly"),
+            Ok(Err(error)) => format!("failed: {}", error),
+            Err(error) => format!("panicked: {}", panic_message(error)),
+        };
+        if ctx.shutdown.is_cancelled() {
+            println!("Watcher {} {}", watcher.name(), failure);
+            return;
+        }
+
+        let backoff = {
+            let now = Utc::now();
+            let mut supervision = supervision.lock().unwrap();
+            supervision.failures = supervision.consecutive_failures(&ctx, now) + 1;
+            supervision.failed = Some(now);
+            supervision.last_error = Some(failure.clone());
+            RESTART_BACKOFF
+                .saturating_mul(2u32.saturating_pow(supervision.failures - 1))
+                .min(MAX_RESTART_BACKOFF)
+        };
+        println!(
+            "Watcher {} {}; restarting it in {}s",
+            watcher.name(),
+            failure,
+            backoff.as_secs()
+        );
+        tokio::select! {
+            _ = ctx.shutdown.cancelled() => return,
+            _ = tokio::time::sleep(backoff) => {}
+        }
+
+        let mut supervision = supervision.lock().unwrap();
+        supervision.restarts += 1;
+        supervision.started = Utc::now();
+    }
+}
+
+fn panic_message(error: JoinError) -> String {
+    match error.try_into_panic() {
+        Ok(panic) => match panic.downcast::<String>() {
+            Ok(message) => *message,
+            Err(panic) => panic.downcast_ref::<&str>().map_or_else(
+                || String::from("unknown panic"),
+                |message| message.to_string(),
+            ),
+        },
+        Err(error) => error.to_string(),
+    }
+}
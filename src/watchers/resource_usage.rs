@@ -0,0 +1,77 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use crate::{entities::resource_usage::ResourceUsage, runtime::ContainerRuntime};
+
+use super::{
+    container_status::ContainerStatusWatcher,
+    error::WatcherError,
+    watcher::{Watcher, WatcherContext},
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct ResourceUsageWatcher {
+    pub usage: Arc<Mutex<HashMap<String, ResourceUsage>>>,
+    runtime: Arc<dyn ContainerRuntime + Send + Sync>,
+    status_watcher: Arc<ContainerStatusWatcher>,
+}
+
+impl ResourceUsageWatcher {
+    pub fn new(
+        runtime: Arc<dyn ContainerRuntime + Send + Sync>,
+        status_watcher: Arc<ContainerStatusWatcher>,
+    ) -> Self {
+        ResourceUsageWatcher {
+            usage: Arc::new(Mutex::new(HashMap::new())),
+            runtime,
+            status_watcher,
+        }
+    }
+
+    pub async fn list(&self) -> Vec<ResourceUsage> {
+        self.usage.lock().await.values().cloned().collect()
+    }
+
+    pub async fn check_usage(&self) -> Result<(), WatcherError> {
+        let ids: Vec<String> = self
+            .status_watcher
+            .list()
+            .await
+            .into_iter()
+            .map(|container| container.id)
+            .collect();
+
+        let stats = self
+            .runtime
+            .stats(&ids)
+            .await
+            .map_err(WatcherError::Stats)?;
+        let mut usage = self.usage.lock().await;
+        usage.clear();
+        for container_usage in stats {
+            usage.insert(container_usage.container_id.clone(), container_usage);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Watcher for ResourceUsageWatcher {
+    fn name(&self) -> &str {
+        "resource-usage"
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        loop {
+            if let Err(error) = self.check_usage().await {
+                println!("{}", error);
+            }
+            if !ctx.sleep(CHECK_INTERVAL).await {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Clone)]
+pub struct WatcherContext {
+    // Cancelled when the watcher is stopped or the daemon shuts down.
+    pub shutdown: CancellationToken,
+    // When the watcher is next expected back from `sleep`, and when it last went to sleep after
+    // a check, in milliseconds since the epoch; 0 until it first sleeps.
+    due: Arc<AtomicI64>,
+    ticked: Arc<AtomicI64>,
+}
+
+impl WatcherContext {
+    pub fn new(shutdown: CancellationToken) -> Self {
+        WatcherContext {
+            shutdown,
+            due: Arc::new(AtomicI64::new(0)),
+            ticked: Arc::new(AtomicI64::new(0)),
+        }
+    }
+
+    // Sleeps between checks; false once the watcher should return.
+    pub async fn sleep(&self, duration: Duration) -> bool {
+        let now = Utc::now();
+        self.ticked.store(now.timestamp_millis(), Ordering::Relaxed);
+        self.due
+            .store((now + duration).timestamp_millis(), Ordering::Relaxed);
+        tokio::select! {
+            _ = self.shutdown.cancelled() => false,
+            _ = tokio::time::sleep(duration) => true,
+        }
+    }
+
+    // None for watchers that don't loop on `sleep`, such as those waiting on events instead.
+    // A time long past means the watcher is stuck in a check.
+    pub fn due(&self) -> Option<DateTime<Utc>> {
+        timestamp(&self.due)
+    }
+
+    // When the watcher last finished a check, as far as it sleeps between them.
+    pub fn ticked(&self) -> Option<DateTime<Utc>> {
+        timestamp(&self.ticked)
+    }
+}
+
+fn timestamp(millis: &AtomicI64) -> Option<DateTime<Utc>> {
+    match millis.load(Ordering::Relaxed) {
+        0 => None,
+        millis => DateTime::from_timestamp_millis(millis),
+    }
+}
+
+// A background loop run by the `WatcherRegistry`. `run` keeps going until the context is
+// cancelled; `shutdown` is called after it returns, to release anything the watcher holds.
+#[async_trait]
+pub trait Watcher: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error>;
+
+    async fn shutdown(&self) {}
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware,
+    routing::{get, post},
+    Router,
+};
+use nic8s::{
+    api::audit,
+    entities::audit::{AuditQuery, USER_HEADER},
+    store::audit::AuditLog,
+};
+use tower::ServiceExt;
+
+// Containers can be created and restarted; restarting web-2 fails.
+fn router(log: Arc<AuditLog>) -> Router {
+    Router::new()
+        .route(
+            "/containers",
+            get(|| async { "[]" }).post(|| async { StatusCode::CREATED }),
+        )
+        .route(
+            "/containers/{id}/restart",
+            post(
+                |axum::extract::Path(id): axum::extract::Path<String>| async move {
+                    match id.as_str() {
+                        "web-2" => Err((StatusCode::CONFLICT, "web-2 is stopping")),
+                        _ => Ok(StatusCode::NO_CONTENT),
+                    }
+                },
+            ),
+        )
+        .layer(middleware::from_fn_with_state(log, audit::record))
+}
+
+async fn call(router: &Router, method: &str, path: &str, user: Option<&str>) -> StatusCode {
+    let mut request = Request::builder().method(method).uri(path);
+    if let Some(user) = user {
+        request = request.header(USER_HEADER, user);
+    }
+    let response = router
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    response.status()
+}
+
+#[tokio::test]
+async fn records_mutating_calls_with_their_outcome() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = Arc::new(AuditLog::open(&dir.path().join("audit.log")).await.unwrap());
+    let router = router(log.clone());
+
+    assert_eq!(
+        call(&router, "GET", "/containers", Some("ana")).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        call(&router, "POST", "/containers", Some("ana")).await,
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        call(&router, "POST", "/containers/web-1/restart", None).await,
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        call(&router, "POST", "/containers/web-2/restart", Some("bo")).await,
+        StatusCode::CONFLICT
+    );
+
+    let entries = log.list(&AuditQuery::default()).await.unwrap();
+    let summary: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.user.as_str(),
+                entry.operation.as_str(),
+                entry.resource.as_str(),
+                entry.name.as_deref(),
+                entry.status,
+                entry.error.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("ana", "create", "containers", None, 201, None),
+            ("unknown", "restart", "containers", Some("web-1"), 204, None),
+            (
+                "bo",
+                "restart",
+                "containers",
+                Some("web-2"),
+                409,
+                Some("web-2 is stopping")
+            ),
+        ]
+    );
+
+    let query = AuditQuery {
+        name: Some(String::from("web-2")),
+        ..AuditQuery::default()
+    };
+    assert_eq!(log.list(&query).await.unwrap(), entries[2..]);
+    let query = AuditQuery {
+        resource: Some(String::from("containers")),
+        limit: Some(1),
+        ..AuditQuery::default()
+    };
+    assert_eq!(log.list(&query).await.unwrap(), entries[2..]);
+}
+
+#[tokio::test]
+async fn keeps_entries_across_reopening_and_skips_torn_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.log");
+    let log = Arc::new(AuditLog::open(&path).await.unwrap());
+    call(&router(log), "POST", "/containers", Some("ana")).await;
+
+    // A crash mid-write leaves a partial line behind.
+    let mut contents = std::fs::read_to_string(&path).unwrap();
+    contents.push_str("{\"timestamp\":");
+    std::fs::write(&path, contents).unwrap();
+
+    let log = Arc::new(AuditLog::open(&path).await.unwrap());
+    call(&router(log.clone()), "POST", "/containers", Some("bo")).await;
+    let users: Vec<_> = log
+        .list(&AuditQuery::default())
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.user)
+        .collect();
+    assert_eq!(users, vec!["ana", "bo"]);
+}
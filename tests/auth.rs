@@ -0,0 +1,255 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    middleware,
+    routing::{delete, get},
+    Router,
+};
+use nic8s::{
+    agent::api::{self as agent_api, AgentState},
+    api::{audit, auth},
+    auth::Authenticator,
+    config::AuthConfig,
+    entities::{
+        audit::AuditQuery,
+        token::{PolicyRule, Role, TokenSpec},
+    },
+    runtime::mock::MockRuntime,
+    store::{audit::AuditLog, state::StateStore, tokens::TokenStore},
+};
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+struct Api {
+    router: Router,
+    auth: Arc<Authenticator>,
+    log: Arc<AuditLog>,
+    dir: TempDir,
+}
+
+// Containers can be listed, created and deleted, and rollouts read. Besides the built-in roles
+// there is a deployer role, which may create containers but not delete them, and a web role,
+// which may only read the rollout and containers of the web app.
+async fn api(enabled: bool) -> Api {
+    let dir = tempfile::tempdir().unwrap();
+    let state = Arc::new(StateStore::open(dir.path()).await.unwrap());
+    let config = AuthConfig {
+        enabled,
+        roles: vec![
+            Role {
+                name: String::from("deployer"),
+                rules: vec![PolicyRule {
+                    verbs: vec![String::from("list"), String::from("create")],
+                    resources: vec![String::from("containers")],
+                    apps: vec![String::from("*")],
+                }],
+            },
+            Role {
+                name: String::from("web"),
+                rules: vec![PolicyRule {
+                    verbs: vec![String::from("get"), String::from("list")],
+                    resources: vec![String::from("rollouts"), String::from("containers")],
+                    apps: vec![String::from("web")],
+                }],
+            },
+        ],
+    };
+    let auth = Arc::new(Authenticator::new(&config, TokenStore::new(state)).unwrap());
+    let log = Arc::new(AuditLog::open(&dir.path().join("audit.log")).await.unwrap());
+
+    let router = Router::new()
+        .route(
+            "/containers",
+            get(|| async { "[]" }).post(|| async { StatusCode::CREATED }),
+        )
+        .route(
+            "/containers/{id}",
+            delete(|| async { StatusCode::NO_CONTENT }),
+        )
+        .route("/rollouts/{app}", get(|| async { "{}" }))
+        .layer(middleware::from_fn_with_state(
+            auth.clone(),
+            auth::authorize,
+        ))
+        .layer(middleware::from_fn_with_state(log.clone(), audit::record));
+    Api {
+        router,
+        auth,
+        log,
+        dir,
+    }
+}
+
+async fn call(api: &Api, method: &str, path: &str, token: Option<&str>) -> StatusCode {
+    let mut request = Request::builder().method(method).uri(path);
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let response = api
+        .router
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    response.status()
+}
+
+async fn token(api: &Api, name: &str, role: &str) -> String {
+    api.auth
+        .create_token(TokenSpec {
+            name: String::from(name),
+            roles: vec![String::from(role)],
+        })
+        .await
+        .unwrap()
+        .secret
+}
+
+#[tokio::test]
+async fn allows_calls_by_the_roles_of_the_token() {
+    let api = api(true).await;
+    let viewer = token(&api, "dana", "viewer").await;
+    let deployer = token(&api, "ci", "deployer").await;
+
+    assert_eq!(
+        call(&api, "GET", "/containers", None).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        call(&api, "GET", "/containers", Some("ci.forged")).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        call(&api, "GET", "/containers", Some(&viewer)).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        call(&api, "POST", "/containers", Some(&viewer)).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        call(&api, "POST", "/containers", Some(&deployer)).await,
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        call(&api, "DELETE", "/containers/web-1", Some(&deployer)).await,
+        StatusCode::FORBIDDEN
+    );
+
+    // Deleting a token revokes it.
+    assert!(api.auth.delete_token("ci").await.unwrap());
+    assert_eq!(
+        call(&api, "POST", "/containers", Some(&deployer)).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    let entries = api.log.list(&AuditQuery::default()).await.unwrap();
+    let summary: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry.user.as_str(), entry.operation.as_str(), entry.status))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("unknown", "list", 401),
+            ("unknown", "list", 401),
+            ("dana", "create", 403),
+            ("ci", "create", 201),
+            ("ci", "delete", 403),
+            ("unknown", "create", 401),
+        ]
+    );
+    assert_eq!(
+        entries[2].error.as_deref(),
+        Some("dana is not allowed to create containers")
+    );
+}
+
+#[tokio::test]
+async fn limits_rules_to_their_apps() {
+    let api = api(true).await;
+    let web = token(&api, "web-ci", "web").await;
+
+    assert_eq!(
+        call(&api, "GET", "/rollouts/web", Some(&web)).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        call(&api, "GET", "/rollouts/api", Some(&web)).await,
+        StatusCode::FORBIDDEN
+    );
+    // Listing every container isn't about one app.
+    assert_eq!(
+        call(&api, "GET", "/containers", Some(&web)).await,
+        StatusCode::FORBIDDEN
+    );
+
+    let entries = api.log.list(&AuditQuery::default()).await.unwrap();
+    assert_eq!(
+        entries[0].error.as_deref(),
+        Some("web-ci is not allowed to get rollouts of app api")
+    );
+}
+
+#[tokio::test]
+async fn bootstraps_an_admin_token_only_when_there_is_none() {
+    let api = api(true).await;
+    let path = api.dir.path().join("admin.token");
+    api.auth.bootstrap(&path).await.unwrap();
+    let admin = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        call(&api, "DELETE", "/containers/web-1", Some(&admin)).await,
+        StatusCode::NO_CONTENT
+    );
+
+    std::fs::remove_file(&path).unwrap();
+    api.auth.bootstrap(&path).await.unwrap();
+    assert!(!path.exists());
+
+    assert!(api
+        .auth
+        .create_token(TokenSpec {
+            name: String::from("ops"),
+            roles: vec![String::from("root")],
+        })
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn lets_everything_through_when_disabled() {
+    let api = api(false).await;
+    assert_eq!(
+        call(&api, "DELETE", "/containers/web-1", None).await,
+        StatusCode::NO_CONTENT
+    );
+}
+
+#[tokio::test]
+async fn agents_only_take_calls_with_their_token() {
+    let dir = tempfile::tempdir().unwrap();
+    let router = agent_api::router(AgentState {
+        runtime: Arc::new(MockRuntime::new()),
+        mounts_dir: dir.path().to_path_buf(),
+        token: Arc::from("agent-secret"),
+    });
+    let call = |token: Option<&str>| {
+        let mut request = Request::builder().uri("/runtime/containers");
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        router.clone().oneshot(request.body(Body::empty()).unwrap())
+    };
+
+    assert_eq!(call(None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        call(Some("wrong")).await.unwrap().status(),
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        call(Some("agent-secret")).await.unwrap().status(),
+        StatusCode::OK
+    );
+}
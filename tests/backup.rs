@@ -0,0 +1,67 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use nic8s::{
+    entities::{
+        container::{Container, ContainerSpec},
+        secret::Secret,
+    },
+    store::backup::{self, Archive, FORMAT_VERSION},
+};
+
+#[tokio::test]
+async fn restores_desired_state_on_a_new_host() {
+    let (cluster, _mock, _dir) = common::cluster().await;
+    let spec = ContainerSpec {
+        name: String::from("web"),
+        image: String::from("nginx"),
+        ..ContainerSpec::default()
+    };
+    Container::new(&spec, &cluster).await.unwrap();
+    cluster
+        .secrets
+        .put(&Secret {
+            name: String::from("db"),
+            data: BTreeMap::from([(String::from("password"), String::from("hunter2"))]),
+        })
+        .await
+        .unwrap();
+
+    let archive = Archive::create(&cluster.state).await.unwrap();
+    assert_eq!(archive.version, FORMAT_VERSION);
+    assert_eq!(archive.counts(), vec![("containers", 1), ("secrets", 1)]);
+    let contents = serde_json::to_vec(&archive).unwrap();
+
+    let (restored, mock, _restored_dir) = common::cluster().await;
+    let archive = Archive::parse(&contents).unwrap();
+    archive.restore(&restored.state, false).await.unwrap();
+    // The new host has another master key.
+    assert_eq!(
+        archive.undecryptable_secrets(&restored.secrets).await,
+        vec![String::from("db")]
+    );
+    let error = archive.restore(&restored.state, false).await.unwrap_err();
+    assert!(error.to_string().contains("--replace"), "{}", error);
+    archive.restore(&restored.state, true).await.unwrap();
+
+    assert!(backup::take_pending_restore(&restored.state).await.unwrap());
+    assert!(!backup::take_pending_restore(&restored.state).await.unwrap());
+    let recreated = Container::recreate_missing(&restored, &[]).await.unwrap();
+    assert_eq!(recreated.len(), 1);
+    assert_eq!(recreated[0].spec, spec);
+    assert!(mock.get("web").await.is_some());
+}
+
+#[tokio::test]
+async fn refuses_backups_from_newer_releases() {
+    let contents = format!(
+        r#"{{"version": {}, "created": "2030-01-01T00:00:00Z", "nic8s_version": "9.0.0", "objects": {{}}}}"#,
+        FORMAT_VERSION + 1
+    );
+    let error = Archive::parse(contents.as_bytes()).unwrap_err();
+    assert!(error.to_string().contains("nic8s 9.0.0"), "{}", error);
+
+    let contents = r#"{"version": 1, "created": "", "nic8s_version": "0.1.0", "objects": {"nodes": {"a": {}}}}"#;
+    assert!(Archive::parse(contents.as_bytes()).is_err());
+}
@@ -0,0 +1,84 @@
+// Not every test binary uses every helper.
+#![allow(dead_code)]
+
+use std::{future::Future, sync::Arc, time::Duration};
+
+use nic8s::{
+    admission::AdmissionChain,
+    cluster::Cluster,
+    entities::{labels::Labels, node::NodeCapacity},
+    events::{event::EventReason, recorder::EventRecorder},
+    runtime::{mock::MockRuntime, nodes::NodeRuntime},
+    scheduler::{scoring, Scheduler},
+    store::{
+        config_maps::ConfigMapStore, secrets::SecretStore, state::StateStore, volumes::VolumeStore,
+    },
+    watchers::container_status::ContainerStatusWatcher,
+};
+use tempfile::TempDir;
+
+// A cluster whose only node is a mock runtime. The directory holds its state and must outlive it.
+pub async fn cluster() -> (Cluster, Arc<MockRuntime>, TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let state = Arc::new(StateStore::open(dir.path()).await.unwrap());
+
+    let mock = Arc::new(MockRuntime::new());
+    let scheduler = Scheduler::new(scoring::strategy("spread").unwrap());
+    let nodes = Arc::new(NodeRuntime::new(state.clone(), scheduler));
+    nodes
+        .add_local(mock.clone(), NodeCapacity::default(), Labels::new())
+        .await;
+
+    let events = Arc::new(EventRecorder::new());
+    let status_watcher = Arc::new(ContainerStatusWatcher::new(nodes.clone(), events.clone()));
+    let cluster = Cluster {
+        runtime: nodes.clone(),
+        volumes: Arc::new(VolumeStore::new(
+            state.clone(),
+            nodes.clone(),
+            status_watcher.clone(),
+        )),
+        status_watcher,
+        nodes,
+        events,
+        secrets: Arc::new(
+            SecretStore::open(state.clone(), &dir.path().join("master.key"))
+                .await
+                .unwrap(),
+        ),
+        config_maps: Arc::new(
+            ConfigMapStore::open(state.clone(), &dir.path().join("config-maps"))
+                .await
+                .unwrap(),
+        ),
+        state,
+        admission: Arc::new(AdmissionChain::new(&[]).unwrap()),
+        logs: None,
+    };
+    (cluster, mock, dir)
+}
+
+// Polls until `condition` holds, failing the test after a while.
+pub async fn eventually<F, Fut>(what: &str, mut condition: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    for _ in 0..200 {
+        if condition().await {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("timed out waiting for {}", what);
+}
+
+pub async fn reasons(cluster: &Cluster, name: &str) -> Vec<EventReason> {
+    cluster
+        .events
+        .list(Some(name))
+        .await
+        .into_iter()
+        .map(|event| event.reason)
+        .collect()
+}
@@ -0,0 +1,516 @@
+mod common;
+
+use std::sync::Arc;
+
+use nic8s::{
+    cluster::Cluster,
+    config::CrashLoopConfig,
+    entities::{
+        container::{
+            Container, ContainerSpec, ContainerStatus, Dependency, DependencyCondition, Probe,
+        },
+        lifecycle::{HookFailurePolicy, Lifecycle, LifecycleHook},
+    },
+    events::event::EventReason,
+    runtime::{mock::MockRuntime, retry::Operation},
+    watchers::{
+        container_status::ContainerStatusWatcher,
+        watcher::{Watcher, WatcherContext},
+    },
+};
+use tokio_util::sync::CancellationToken;
+
+use common::{eventually, reasons};
+
+fn spec(name: &str) -> ContainerSpec {
+    ContainerSpec {
+        name: String::from(name),
+        image: String::from("nginx"),
+        ..ContainerSpec::default()
+    }
+}
+
+fn watch(cluster: &Cluster) -> CancellationToken {
+    let shutdown = CancellationToken::new();
+    let watcher = cluster.status_watcher.clone();
+    let ctx = WatcherContext::new(shutdown.clone());
+    tokio::spawn(async move { watcher.run(ctx).await });
+    shutdown
+}
+
+async fn status(cluster: &Cluster, name: &str) -> Option<ContainerStatus> {
+    Some(cluster.status_watcher.find(name).await?.get_status())
+}
+
+#[tokio::test]
+async fn tracks_exit_and_restart() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let shutdown = watch(&cluster);
+
+    let container = Container::new(&spec("web"), &cluster).await.unwrap();
+    eventually("web to run", || async {
+        reasons(&cluster, "web").await.contains(&EventReason::Ready)
+    })
+    .await;
+    assert_eq!(
+        status(&cluster, "web").await,
+        Some(ContainerStatus::Running)
+    );
+
+    mock.exit("web", 1).await.unwrap();
+    eventually("web to exit", || async {
+        status(&cluster, "web").await == Some(ContainerStatus::Exited)
+    })
+    .await;
+    let events = reasons(&cluster, "web").await;
+    assert!(events.contains(&EventReason::Exited));
+    assert_eq!(events.last(), Some(&EventReason::NotReady));
+
+    cluster.runtime.restart(&container.id).await.unwrap();
+    eventually("web to restart", || async {
+        status(&cluster, "web").await == Some(ContainerStatus::Running)
+    })
+    .await;
+    let restarted = cluster.status_watcher.find("web").await.unwrap();
+    assert_eq!(restarted.restart_count, 1);
+
+    let history: Vec<ContainerStatus> = cluster
+        .status_watcher
+        .history(&container.id)
+        .await
+        .into_iter()
+        .map(|transition| transition.status)
+        .collect();
+    assert_eq!(
+        history,
+        [
+            ContainerStatus::Created,
+            ContainerStatus::Running,
+            ContainerStatus::Exited,
+            ContainerStatus::Running
+        ]
+    );
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn reports_oom_kills() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let shutdown = watch(&cluster);
+
+    let container = Container::new(&spec("web"), &cluster).await.unwrap();
+    eventually("web to run", || async {
+        reasons(&cluster, "web").await.contains(&EventReason::Ready)
+    })
+    .await;
+
+    mock.oom_kill("web").await.unwrap();
+    eventually("the OOM kill to be reported", || async {
+        reasons(&cluster, "web")
+            .await
+            .contains(&EventReason::OOMKilled)
+    })
+    .await;
+    let events = cluster.events.list(Some("web")).await;
+    let killed = events
+        .iter()
+        .find(|event| event.reason == EventReason::OOMKilled)
+        .unwrap();
+    assert!(
+        killed.message.contains("Reason: OOMKilled, ExitCode: 137"),
+        "{}",
+        killed.message
+    );
+    assert!(!reasons(&cluster, "web")
+        .await
+        .contains(&EventReason::Exited));
+
+    // The last exit stays visible once the container runs again.
+    cluster.runtime.start(&container.id).await.unwrap();
+    eventually("web to restart", || async {
+        cluster
+            .status_watcher
+            .find("web")
+            .await
+            .is_some_and(|container| container.restart_count == 1)
+    })
+    .await;
+    let restarted = cluster.status_watcher.find("web").await.unwrap();
+    let termination = restarted.last_termination.unwrap();
+    assert!(termination.oom_killed);
+    assert_eq!(termination.exit_code, 137);
+    assert_eq!(termination.reason(), "OOMKilled");
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn counts_restarts_between_checks() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let shutdown = watch(&cluster);
+
+    let container = Container::new(&spec("web"), &cluster).await.unwrap();
+    eventually("web to run", || async {
+        reasons(&cluster, "web").await.contains(&EventReason::Ready)
+    })
+    .await;
+
+    // The default restart policy starts it again before the watcher sees it exit.
+    mock.crash("web", 137).await.unwrap();
+    eventually("the restart to be noticed", || async {
+        cluster
+            .status_watcher
+            .find("web")
+            .await
+            .is_some_and(|container| container.restart_count == 1)
+    })
+    .await;
+
+    assert_eq!(
+        status(&cluster, "web").await,
+        Some(ContainerStatus::Running)
+    );
+    assert!(!reasons(&cluster, "web")
+        .await
+        .contains(&EventReason::Exited));
+    let history = cluster.status_watcher.history(&container.id).await;
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[2].status, ContainerStatus::Running);
+    shutdown.cancel();
+}
+
+// Crashes web, waiting for the watcher to notice it was restarted.
+async fn crash(cluster: &Cluster, mock: &MockRuntime, restarts: u32) {
+    mock.crash("web", 1).await.unwrap();
+    eventually("the restart to be noticed", || async {
+        cluster
+            .status_watcher
+            .find("web")
+            .await
+            .is_some_and(|container| container.restart_count == restarts)
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn backs_off_crash_looping_containers() {
+    let (mut cluster, mock, _dir) = common::cluster().await;
+    cluster.status_watcher = Arc::new(
+        ContainerStatusWatcher::new(cluster.runtime.clone(), cluster.events.clone())
+            .with_crash_loop(&CrashLoopConfig {
+                max_restarts: 1,
+                window_seconds: 60,
+                initial_backoff_seconds: 1,
+                max_backoff_seconds: 1,
+            }),
+    );
+    let shutdown = watch(&cluster);
+
+    Container::new(&spec("web"), &cluster).await.unwrap();
+    eventually("web to run", || async {
+        reasons(&cluster, "web").await.contains(&EventReason::Ready)
+    })
+    .await;
+
+    // One restart within the window is tolerated, the second one is held back.
+    crash(&cluster, &mock, 1).await;
+    assert_eq!(
+        status(&cluster, "web").await,
+        Some(ContainerStatus::Running)
+    );
+    crash(&cluster, &mock, 2).await;
+    let container = cluster.status_watcher.find("web").await.unwrap();
+    assert_eq!(container.get_status(), ContainerStatus::CrashLoopBackOff);
+    assert!(container.back_off_until.is_some());
+    assert!(container.last_restart.is_some());
+    assert!(reasons(&cluster, "web")
+        .await
+        .contains(&EventReason::BackOff));
+    eventually("web to be stopped", || async {
+        mock.get("web").await.unwrap().container.get_status() == ContainerStatus::Exited
+    })
+    .await;
+
+    eventually("web to run again after its back-off", || async {
+        status(&cluster, "web").await == Some(ContainerStatus::Running)
+    })
+    .await;
+    let container = cluster.status_watcher.find("web").await.unwrap();
+    assert_eq!(container.restart_count, 3);
+    assert!(container.back_off_until.is_none());
+
+    // Until it stays up for a whole window, every crash backs it off again.
+    crash(&cluster, &mock, 4).await;
+    assert_eq!(
+        status(&cluster, "web").await,
+        Some(ContainerStatus::CrashLoopBackOff)
+    );
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn records_inspect_failures_once() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    Container::new(&spec("web"), &cluster).await.unwrap();
+
+    // Containers are only inspected one by one when the listing fails.
+    mock.fail(Operation::List, "connection refused").await;
+    mock.fail(Operation::Inspect, "connection refused").await;
+    mock.fail(Operation::Inspect, "connection refused").await;
+    let shutdown = watch(&cluster);
+
+    eventually("web to be inspected", || async {
+        reasons(&cluster, "web").await.contains(&EventReason::Ready)
+    })
+    .await;
+    let failures = reasons(&cluster, "web")
+        .await
+        .into_iter()
+        .filter(|reason| *reason == EventReason::Failed)
+        .count();
+    assert_eq!(failures, 1);
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn syncs_status_from_one_listing() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    Container::new(&spec("web"), &cluster).await.unwrap();
+    Container::new(&spec("api"), &cluster).await.unwrap();
+
+    // Inspecting would fail, so the status can only come from the listing.
+    for _ in 0..4 {
+        mock.fail(Operation::Inspect, "connection refused").await;
+    }
+    let shutdown = watch(&cluster);
+    mock.exit("api", 1).await.unwrap();
+
+    eventually("api to exit", || async {
+        status(&cluster, "api").await == Some(ContainerStatus::Exited)
+    })
+    .await;
+    assert_eq!(
+        status(&cluster, "web").await,
+        Some(ContainerStatus::Running)
+    );
+    assert!(!reasons(&cluster, "web")
+        .await
+        .contains(&EventReason::Failed));
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn starts_dependents_once_dependencies_are_healthy() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let shutdown = watch(&cluster);
+
+    let db = ContainerSpec {
+        readiness_probe: Some(Probe {
+            command: String::from("pg_isready"),
+            initial_delay_seconds: 0,
+            period_seconds: 1,
+            timeout_seconds: 1,
+            failure_threshold: 1,
+        }),
+        ..spec("db")
+    };
+    Container::new(&db, &cluster).await.unwrap();
+    mock.set_health("db", Some("starting")).await.unwrap();
+    let web = ContainerSpec {
+        depends_on: vec![Dependency {
+            name: String::from("db"),
+            condition: DependencyCondition::Healthy,
+        }],
+        ..spec("web")
+    };
+    Container::new(&web, &cluster).await.unwrap();
+
+    eventually("web to wait for db", || async {
+        reasons(&cluster, "web")
+            .await
+            .contains(&EventReason::Waiting)
+    })
+    .await;
+    assert_eq!(
+        status(&cluster, "web").await,
+        Some(ContainerStatus::Created)
+    );
+
+    mock.set_health("db", Some("healthy")).await.unwrap();
+    eventually("web to start", || async {
+        status(&cluster, "web").await == Some(ContainerStatus::Running)
+    })
+    .await;
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn reports_dependencies_that_do_not_exist() {
+    let (cluster, _mock, _dir) = common::cluster().await;
+    let shutdown = watch(&cluster);
+
+    let web = ContainerSpec {
+        depends_on: vec![Dependency {
+            name: String::from("cache"),
+            condition: DependencyCondition::Started,
+        }],
+        ..spec("web")
+    };
+    let error = Container::check_dependencies(&web, &cluster)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "container web depends on cache, which does not exist"
+    );
+
+    // Containers created around the check say what they wait for.
+    Container::new(&web, &cluster).await.unwrap();
+    eventually("web to report cache missing", || async {
+        cluster
+            .events
+            .list(Some("web"))
+            .await
+            .iter()
+            .any(|event| event.message.ends_with("it does not exist"))
+    })
+    .await;
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn follows_readiness_probe() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let shutdown = watch(&cluster);
+
+    let spec = ContainerSpec {
+        readiness_probe: Some(Probe {
+            command: String::from("curl -f localhost"),
+            initial_delay_seconds: 0,
+            period_seconds: 1,
+            timeout_seconds: 1,
+            failure_threshold: 1,
+        }),
+        ..spec("web")
+    };
+    Container::new(&spec, &cluster).await.unwrap();
+    mock.set_health("web", Some("starting")).await.unwrap();
+    eventually("the probe to start", || async {
+        cluster
+            .status_watcher
+            .find("web")
+            .await
+            .is_some_and(|container| container.health.as_deref() == Some("starting"))
+    })
+    .await;
+    assert!(!cluster.status_watcher.find("web").await.unwrap().is_ready());
+
+    mock.set_health("web", Some("healthy")).await.unwrap();
+    eventually("web to be ready", || async {
+        reasons(&cluster, "web").await.contains(&EventReason::Ready)
+    })
+    .await;
+
+    mock.set_health("web", Some("unhealthy")).await.unwrap();
+    eventually("web to be unready", || async {
+        reasons(&cluster, "web")
+            .await
+            .contains(&EventReason::NotReady)
+    })
+    .await;
+    assert!(reasons(&cluster, "web")
+        .await
+        .contains(&EventReason::Unhealthy));
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn ignores_removed_containers() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let shutdown = watch(&cluster);
+
+    let container = Container::new(&spec("web"), &cluster).await.unwrap();
+    container.delete(&cluster).await.unwrap();
+    assert!(mock.get("web").await.is_none());
+    assert!(cluster.status_watcher.find("web").await.is_none());
+    assert!(cluster
+        .status_watcher
+        .history(&container.id)
+        .await
+        .is_empty());
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn stops_containers_gracefully_before_removing_them() {
+    let (cluster, mock, _dir) = common::cluster().await;
+
+    let web = Container::new(&spec("web"), &cluster).await.unwrap();
+    let stubborn = Container::new(
+        &ContainerSpec {
+            termination_grace_period_seconds: Some(1),
+            stop_signal: Some(String::from("SIGQUIT")),
+            ..spec("stubborn")
+        },
+        &cluster,
+    )
+    .await
+    .unwrap();
+    mock.ignore_signals("stubborn").await.unwrap();
+
+    web.delete(&cluster).await.unwrap();
+    assert_eq!(mock.signals("web").await, ["SIGTERM"]);
+    let events = reasons(&cluster, "web").await;
+    assert!(events.ends_with(&[EventReason::Stopping, EventReason::Stopped]));
+
+    stubborn.delete(&cluster).await.unwrap();
+    assert_eq!(mock.signals("stubborn").await, ["SIGQUIT", "SIGKILL"]);
+    let events = reasons(&cluster, "stubborn").await;
+    assert!(events.ends_with(&[EventReason::Stopping, EventReason::ForceKilled]));
+    assert!(mock.get("stubborn").await.is_none());
+}
+
+#[tokio::test]
+async fn runs_lifecycle_hooks() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let hook = |command: &str, on_failure| LifecycleHook {
+        exec: vec![
+            String::from("sh"),
+            String::from("-c"),
+            String::from(command),
+        ],
+        http: None,
+        timeout_seconds: 5,
+        on_failure,
+    };
+    let web = Container::new(
+        &ContainerSpec {
+            lifecycle: Lifecycle {
+                post_start: Some(hook("warm-cache", HookFailurePolicy::Ignore)),
+                pre_stop: Some(hook("deregister", HookFailurePolicy::Fail)),
+            },
+            ..spec("web")
+        },
+        &cluster,
+    )
+    .await
+    .unwrap();
+    let execs = mock.get("web").await.unwrap().execs;
+    assert_eq!(execs, [["sh", "-c", "warm-cache"]]);
+
+    // A pre_stop hook that must succeed keeps the container when it fails.
+    mock.set_exec_output("web", 1, "load balancer unreachable")
+        .await
+        .unwrap();
+    let error = web.delete(&cluster).await.unwrap_err().to_string();
+    assert!(error.contains("pre_stop hook failed"), "{}", error);
+    assert!(error.contains("load balancer unreachable"), "{}", error);
+    assert!(reasons(&cluster, "web")
+        .await
+        .contains(&EventReason::HookFailed));
+    assert!(mock.signals("web").await.is_empty());
+
+    mock.set_exec_output("web", 0, "").await.unwrap();
+    web.delete(&cluster).await.unwrap();
+    assert_eq!(mock.signals("web").await, ["SIGTERM"]);
+    assert!(mock.get("web").await.is_none());
+}
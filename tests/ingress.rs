@@ -0,0 +1,281 @@
+mod common;
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use axum::{extract::Request, Router};
+use nic8s::{
+    config::IngressConfig,
+    entities::{
+        container::{Container, ContainerSpec},
+        ingress::{IngressRule, IngressSpec, IngressTls},
+        secret::Secret,
+    },
+    ingress::IngressController,
+    watchers::watcher::{Watcher, WatcherContext},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tokio_rustls::{
+    rustls::{
+        crypto::ring,
+        pki_types::{pem::PemObject, CertificateDer, ServerName},
+        ClientConfig, RootCertStore,
+    },
+    TlsConnector,
+};
+use tokio_util::sync::CancellationToken;
+
+use common::eventually;
+
+// Answers with the path it was asked for and the host the client asked the proxy for.
+async fn backend() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let router = Router::new().fallback(|request: Request| async move {
+        let host = request
+            .headers()
+            .get("x-forwarded-host")
+            .and_then(|host| host.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        format!("{} {}", request.uri(), host)
+    });
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    port
+}
+
+async fn free_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+fn rule(host: &str, path: &str, app: &str) -> IngressRule {
+    IngressRule {
+        host: Some(String::from(host)),
+        path: String::from(path),
+        app: String::from(app),
+    }
+}
+
+// None until the proxy listens.
+async fn get(port: u16, host: &str, path: &str) -> Option<(u16, String)> {
+    let response = reqwest::Client::new()
+        .get(format!("http://127.0.0.1:{}{}", port, path))
+        .header("host", host)
+        .send()
+        .await
+        .ok()?;
+    let status = response.status().as_u16();
+    Some((status, response.text().await.unwrap()))
+}
+
+// Both fixtures are self-signed for example.com and *.example.org.
+const CERT_A: &str = include_str!("fixtures/tls-a.crt");
+const KEY_A: &str = include_str!("fixtures/tls-a.key");
+const CERT_B: &str = include_str!("fixtures/tls-b.crt");
+const KEY_B: &str = include_str!("fixtures/tls-b.key");
+
+fn tls_secret(name: &str, cert: &str, key: &str) -> Secret {
+    Secret {
+        name: String::from(name),
+        data: BTreeMap::from([
+            (String::from("tls.crt"), String::from(cert)),
+            (String::from("tls.key"), String::from(key)),
+        ]),
+    }
+}
+
+// Requests `path` over TLS, trusting only `cert`. None until the proxy listens or when the
+// handshake fails, e.g. because the proxy served another certificate.
+async fn get_tls(port: u16, host: &str, cert: &str, path: &str) -> Option<String> {
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(CertificateDer::from_pem_slice(cert.as_bytes()).unwrap())
+        .unwrap();
+    let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).await.ok()?;
+    let server_name = ServerName::try_from(host.to_string()).unwrap();
+    let mut stream = TlsConnector::from(Arc::new(config))
+        .connect(server_name, stream)
+        .await
+        .ok()?;
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    stream.write_all(request.as_bytes()).await.ok()?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.ok()?;
+    Some(response)
+}
+
+#[tokio::test]
+async fn routes_by_host_and_path_to_ready_containers() {
+    let (cluster, _mock, _dir) = common::cluster().await;
+    let backend = backend().await;
+    let spec = ContainerSpec {
+        name: String::from("web"),
+        image: String::from("nginx"),
+        ports: format!("{}:80", backend),
+        ..ContainerSpec::default()
+    };
+    let container = Container::new(&spec, &cluster).await.unwrap();
+
+    let port = free_port().await;
+    let ingresses = Arc::new(IngressController::new(
+        cluster.clone(),
+        &IngressConfig {
+            enabled: true,
+            addr: format!("127.0.0.1:{}", port),
+            ..IngressConfig::default()
+        },
+    ));
+    ingresses
+        .create(IngressSpec {
+            name: String::from("web"),
+            rules: vec![rule("example.com", "/api", "web")],
+            tls: Vec::new(),
+        })
+        .await
+        .unwrap();
+    ingresses
+        .create(IngressSpec {
+            name: String::from("other"),
+            rules: vec![rule("other.com", "/", "missing")],
+            tls: Vec::new(),
+        })
+        .await
+        .unwrap();
+
+    let shutdown = CancellationToken::new();
+    let ctx = WatcherContext::new(shutdown.clone());
+    let status_watcher = cluster.status_watcher.clone();
+    let status_ctx = ctx.clone();
+    tokio::spawn(async move { status_watcher.run(status_ctx).await });
+    let watcher = ingresses.clone();
+    let server = tokio::spawn(async move { watcher.run(ctx).await });
+
+    eventually("the web route to be served", || async {
+        get(port, "example.com", "/api")
+            .await
+            .is_some_and(|(status, _)| status == 200)
+    })
+    .await;
+    assert_eq!(
+        get(port, "Example.com:8080", "/api/users?page=2").await,
+        Some((200, String::from("/api/users?page=2 Example.com:8080")))
+    );
+    assert_eq!(get(port, "example.com", "/apis").await.unwrap().0, 404);
+    assert_eq!(get(port, "other.com", "/").await.unwrap().0, 503);
+
+    // Removing the only container takes the route down without touching the ingress.
+    container.delete(&cluster).await.unwrap();
+    eventually("the web route to lose its backend", || async {
+        get(port, "example.com", "/api")
+            .await
+            .is_some_and(|(status, _)| status == 503)
+    })
+    .await;
+
+    shutdown.cancel();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn terminates_tls_with_the_certificate_for_each_host() {
+    let (cluster, _mock, _dir) = common::cluster().await;
+    let backend = backend().await;
+    let spec = ContainerSpec {
+        name: String::from("web"),
+        image: String::from("nginx"),
+        ports: format!("{}:80", backend),
+        ..ContainerSpec::default()
+    };
+    Container::new(&spec, &cluster).await.unwrap();
+    cluster
+        .secrets
+        .put(&tls_secret("site", CERT_A, KEY_A))
+        .await
+        .unwrap();
+    cluster
+        .secrets
+        .put(&tls_secret("wildcard", CERT_B, KEY_B))
+        .await
+        .unwrap();
+
+    let port = free_port().await;
+    let ingresses = Arc::new(IngressController::new(
+        cluster.clone(),
+        &IngressConfig {
+            enabled: true,
+            addr: format!("127.0.0.1:{}", free_port().await),
+            tls_addr: Some(format!("127.0.0.1:{}", port)),
+            ..IngressConfig::default()
+        },
+    ));
+    ingresses
+        .create(IngressSpec {
+            name: String::from("web"),
+            rules: vec![IngressRule {
+                host: None,
+                path: String::from("/"),
+                app: String::from("web"),
+            }],
+            tls: vec![
+                IngressTls {
+                    hosts: vec![String::from("example.com")],
+                    secret: String::from("site"),
+                },
+                IngressTls {
+                    hosts: vec![String::from("*.example.org")],
+                    secret: String::from("wildcard"),
+                },
+            ],
+        })
+        .await
+        .unwrap();
+
+    let shutdown = CancellationToken::new();
+    let ctx = WatcherContext::new(shutdown.clone());
+    let status_watcher = cluster.status_watcher.clone();
+    let status_ctx = ctx.clone();
+    tokio::spawn(async move { status_watcher.run(status_ctx).await });
+    let watcher = ingresses.clone();
+    let server = tokio::spawn(async move { watcher.run(ctx).await });
+
+    eventually("the site to be served over TLS", || async {
+        get_tls(port, "example.com", CERT_A, "/")
+            .await
+            .is_some_and(|response| response.starts_with("HTTP/1.1 200"))
+    })
+    .await;
+    let response = get_tls(port, "www.example.org", CERT_B, "/home")
+        .await
+        .unwrap();
+    assert!(response.ends_with("/home www.example.org"), "{}", response);
+    assert_eq!(get_tls(port, "www.example.org", CERT_A, "/").await, None);
+    assert_eq!(get_tls(port, "other.com", CERT_A, "/").await, None);
+
+    // Rotating the certificate takes effect without restarting the proxy.
+    cluster
+        .secrets
+        .put(&tls_secret("site", CERT_B, KEY_B))
+        .await
+        .unwrap();
+    ingresses.secrets_changed();
+    eventually("the rotated certificate to be served", || async {
+        get_tls(port, "example.com", CERT_B, "/").await.is_some()
+    })
+    .await;
+    assert_eq!(get_tls(port, "example.com", CERT_A, "/").await, None);
+
+    shutdown.cancel();
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use std::sync::Arc;
+
+use nic8s::{
+    config::LogsConfig,
+    entities::container::{Container, ContainerSpec},
+    store::logs::LogStore,
+    watchers::log_collector::LogCollector,
+};
+
+fn spec() -> ContainerSpec {
+    ContainerSpec {
+        name: String::from("web"),
+        image: String::from("nginx"),
+        ..ContainerSpec::default()
+    }
+}
+
+#[tokio::test]
+async fn keeps_output_of_removed_and_recreated_containers() {
+    let (mut cluster, mock, dir) = common::cluster().await;
+    let config = LogsConfig {
+        persist: true,
+        ..LogsConfig::default()
+    };
+    let store = LogStore::open(&dir.path().join("logs"), &config)
+        .await
+        .unwrap();
+    let logs = Arc::new(LogCollector::new(
+        cluster.runtime.clone(),
+        cluster.status_watcher.clone(),
+        store,
+        &config,
+    ));
+    cluster.logs = Some(logs.clone());
+
+    let container = Container::new(&spec(), &cluster).await.unwrap();
+    mock.write_logs("web", "first\nsecond\n").await.unwrap();
+    logs.collect_all().await;
+    mock.write_logs("web", "third\n").await.unwrap();
+    // Collected on removal, without waiting for the next pass.
+    container.delete(&cluster).await.unwrap();
+    assert_eq!(
+        logs.store().read("web", None).await.unwrap().as_deref(),
+        Some("first\nsecond\nthird\n")
+    );
+
+    Container::new(&spec(), &cluster).await.unwrap();
+    mock.write_logs("web", "fourth\n").await.unwrap();
+    logs.collect_all().await;
+    logs.collect_all().await;
+    assert_eq!(
+        logs.store().read("web", Some(2)).await.unwrap().as_deref(),
+        Some("third\nfourth\n")
+    );
+    assert_eq!(logs.store().read("other", None).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn rotates_full_files() {
+    let (cluster, mock, dir) = common::cluster().await;
+    let config = LogsConfig {
+        persist: true,
+        // Room for one line, with its timestamp, per file.
+        max_file_bytes: 40,
+        max_files: 2,
+        ..LogsConfig::default()
+    };
+    let store = LogStore::open(&dir.path().join("logs"), &config)
+        .await
+        .unwrap();
+    let logs = LogCollector::new(
+        cluster.runtime.clone(),
+        cluster.status_watcher.clone(),
+        store,
+        &config,
+    );
+
+    Container::new(&spec(), &cluster).await.unwrap();
+    for line in ["one", "two", "three"] {
+        mock.write_logs("web", line).await.unwrap();
+        logs.collect_all().await;
+    }
+
+    assert_eq!(
+        logs.store().read("web", None).await.unwrap().as_deref(),
+        Some("two\nthree\n")
+    );
+    let web = dir.path().join("logs").join("web");
+    assert!(web.join("output.log.1").exists());
+    assert!(!web.join("output.log.2").exists());
+}
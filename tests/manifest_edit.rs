@@ -0,0 +1,153 @@
+use std::path::PathBuf;
+
+use nic8s::manifest::{FieldPath, ManifestFile};
+use toml::Value;
+
+const MANIFEST: &str = r#"# Production services
+[[containers]]
+name = "web"    # the storefront
+image = 'nginx:1.25'
+ports = "8080:80"
+
+[containers.resources]
+cpus = 0.5
+
+[[containers]]
+name = "api"
+image = "api:1"
+env = { RUST_LOG = "info" }
+"#;
+
+fn manifest() -> ManifestFile {
+    ManifestFile {
+        path: PathBuf::from("nic8s.toml"),
+        contents: String::from(MANIFEST),
+    }
+}
+
+#[test]
+fn replaces_values_in_place() {
+    let mut manifest = manifest();
+    let web = manifest.find("containers", "web").unwrap();
+    assert_eq!(web.to_string(), "containers[0]");
+    manifest
+        .set(
+            &web.field("image"),
+            &Value::String(String::from("nginx:1.27")),
+        )
+        .unwrap();
+    let api = manifest.find("containers", "api").unwrap();
+    manifest
+        .set(&api.field("image"), &Value::String(String::from("api:2")))
+        .unwrap();
+
+    assert_eq!(
+        manifest.contents,
+        MANIFEST
+            .replace("'nginx:1.25'", "'nginx:1.27'")
+            .replace("\"api:1\"", "\"api:2\"")
+    );
+    assert!(manifest
+        .find("containers", "db")
+        .unwrap_err()
+        .to_string()
+        .contains("no containers named db"));
+}
+
+#[test]
+fn adds_missing_values_to_their_table() {
+    let mut manifest = manifest();
+    let web = manifest.find("containers", "web").unwrap();
+    let api = manifest.find("containers", "api").unwrap();
+    manifest
+        .set(&web.field("replicas"), &Value::Integer(3))
+        .unwrap();
+    manifest
+        .set(
+            &web.field("resources").field("memory_bytes"),
+            &Value::Integer(1024),
+        )
+        .unwrap();
+    manifest
+        .set(
+            &api.field("env").field("PORT"),
+            &Value::String(String::from("80")),
+        )
+        .unwrap();
+    manifest
+        .set(&FieldPath::default().field("version"), &Value::Integer(1))
+        .unwrap();
+
+    assert_eq!(
+        manifest.contents,
+        r#"version = 1
+# Production services
+[[containers]]
+name = "web"    # the storefront
+image = 'nginx:1.25'
+ports = "8080:80"
+replicas = 3
+
+[containers.resources]
+cpus = 0.5
+memory_bytes = 1024
+
+[[containers]]
+name = "api"
+image = "api:1"
+env = { RUST_LOG = "info", PORT = "80" }
+"#
+    );
+}
+
+#[test]
+fn finds_the_text_of_an_entry() {
+    let manifest = manifest();
+    let web = manifest.find("containers", "web").unwrap();
+    let api = manifest.find("containers", "api").unwrap();
+
+    let section = manifest.section(&web).unwrap();
+    assert_eq!(
+        &manifest.contents[section],
+        "[[containers]]\nname = \"web\"    # the storefront\nimage = 'nginx:1.25'\nports = \"8080:80\"\n\n[containers.resources]\ncpus = 0.5\n"
+    );
+    let section = manifest.section(&api).unwrap();
+    assert_eq!(
+        &manifest.contents[section],
+        "[[containers]]\nname = \"api\"\nimage = \"api:1\"\nenv = { RUST_LOG = \"info\" }\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn writes_in_place_keeping_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nic8s.toml");
+    std::fs::write(&path, MANIFEST).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+    std::fs::write(dir.path().join("nic8s.tmp"), "unrelated").unwrap();
+
+    let mut manifest = ManifestFile::read(&path).unwrap();
+    let web = manifest.find("containers", "web").unwrap();
+    manifest
+        .set(
+            &web.field("image"),
+            &Value::String(String::from("nginx:1.27")),
+        )
+        .unwrap();
+    manifest.write().unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        MANIFEST.replace("'nginx:1.25'", "'nginx:1.27'")
+    );
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("nic8s.tmp")).unwrap(),
+        "unrelated"
+    );
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+}
@@ -0,0 +1,223 @@
+use std::fs;
+
+use nic8s::manifest::{
+    plan::{self, LiveState},
+    template::Values,
+    LoadOptions, Manifest,
+};
+
+const MANIFEST: &str = r#"
+[[containers]]
+for_each = "i in 1..=replicas"
+name = "web-{{ i }}"
+image = "nginx:{{ image.tag }}"
+ports = "{{ base_port }}{{ i }}:80"
+
+[[containers]]
+when = "env == 'prod'"
+name = "metrics"
+image = "prom/prometheus"
+
+[[containers]]
+when = "!debug"
+name = "quiet"
+image = "busybox"
+"#;
+
+#[test]
+fn renders_manifests_with_values_and_overrides() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("app.toml");
+    fs::write(&manifest, MANIFEST).unwrap();
+    fs::write(
+        dir.path().join("values.toml"),
+        "replicas = 2\nbase_port = 808\nenv = \"dev\"\n\n[image]\ntag = \"1.25\"\n",
+    )
+    .unwrap();
+
+    let options = LoadOptions {
+        values: Values::load(&manifest, &[], &[]).unwrap(),
+        ..LoadOptions::default()
+    };
+    let loaded = Manifest::load(&manifest, &options).unwrap();
+    let names: Vec<&str> = loaded.containers.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["web-1", "web-2", "quiet"]);
+    assert_eq!(loaded.containers[1].image, "nginx:1.25");
+    assert_eq!(loaded.containers[1].ports, "8082:80");
+
+    let sets = [
+        String::from("env=prod"),
+        String::from("replicas=1"),
+        String::from("debug=true"),
+    ];
+    let options = LoadOptions {
+        values: Values::load(&manifest, &[], &sets).unwrap(),
+        ..LoadOptions::default()
+    };
+    let loaded = Manifest::load(&manifest, &options).unwrap();
+    let names: Vec<&str> = loaded.containers.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["web-1", "metrics"]);
+
+    // Tags that look like numbers keep the text they were set to.
+    let sets = [
+        String::from("image.tag=1.10"),
+        String::from("base_port=0808"),
+    ];
+    let options = LoadOptions {
+        values: Values::load(&manifest, &[], &sets).unwrap(),
+        ..LoadOptions::default()
+    };
+    let loaded = Manifest::load(&manifest, &options).unwrap();
+    assert_eq!(loaded.containers[0].image, "nginx:1.10");
+    assert_eq!(loaded.containers[0].ports, "08081:80");
+}
+
+#[test]
+fn reports_unset_values_where_they_are_used() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("app.toml");
+    fs::write(&manifest, MANIFEST).unwrap();
+
+    let error = Manifest::load(&manifest, &LoadOptions::default())
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("app.toml:3:12: value replicas is not set"),
+        "{}",
+        error
+    );
+    assert!(error.contains("value env is not set"), "{}", error);
+}
+
+#[test]
+fn orders_containers_after_their_dependencies_and_rejects_cycles_and_missing_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("app.toml");
+    fs::write(
+        &manifest,
+        r#"
+[[containers]]
+name = "web"
+image = "nginx"
+depends_on = [{ name = "api" }]
+
+[[containers]]
+name = "api"
+image = "api"
+depends_on = [{ name = "db", condition = "healthy" }]
+
+[[containers]]
+name = "db"
+image = "postgres"
+"#,
+    )
+    .unwrap();
+    let loaded = Manifest::load(&manifest, &LoadOptions::default()).unwrap();
+    let live = LiveState {
+        containers: Vec::new(),
+        jobs: Vec::new(),
+        cron_jobs: Vec::new(),
+    };
+    let names: Vec<String> = plan::plan(&loaded, &live, false)
+        .into_iter()
+        .map(|change| change.name)
+        .collect();
+    assert_eq!(names, ["db", "api", "web"]);
+
+    fs::write(
+        &manifest,
+        r#"
+[[containers]]
+name = "web"
+image = "nginx"
+depends_on = [{ name = "api" }]
+
+[[containers]]
+name = "api"
+image = "api"
+depends_on = [{ name = "web", condition = "healthy" }]
+"#,
+    )
+    .unwrap();
+    let error = Manifest::load(&manifest, &LoadOptions::default())
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("dependency cycle: web -> api -> web"),
+        "{}",
+        error
+    );
+
+    fs::write(
+        &manifest,
+        r#"
+[[containers]]
+name = "web"
+image = "nginx"
+depends_on = [{ name = "cache" }]
+"#,
+    )
+    .unwrap();
+    // Dependencies outside the manifest are looked for in the cluster.
+    let loaded = Manifest::load(&manifest, &LoadOptions::default()).unwrap();
+    let error = plan::check_dependencies(&loaded, &live, false)
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        error,
+        "container web depends on cache, which does not exist"
+    );
+    let running = LiveState {
+        containers: vec![serde_json::from_value(serde_json::json!({
+            "id": "c4c8e",
+            "name": "cache",
+            "app": "cache",
+            "spec": { "name": "cache", "image": "redis" },
+            "created": "2026-10-17T00:00:00Z",
+            "status": "Running",
+        }))
+        .unwrap()],
+        jobs: Vec::new(),
+        cron_jobs: Vec::new(),
+    };
+    assert!(plan::check_dependencies(&loaded, &running, false).is_ok());
+    assert!(plan::check_dependencies(&loaded, &running, true).is_err());
+}
+
+#[test]
+fn follows_dependencies_across_included_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("app.toml");
+    fs::write(&manifest, "include = [\"web.toml\", \"db.toml\"]\n").unwrap();
+    fs::write(
+        dir.path().join("web.toml"),
+        "[[containers]]\nname = \"web\"\nimage = \"nginx\"\ndepends_on = [{ name = \"db\" }]\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("db.toml"),
+        "[[containers]]\nname = \"db\"\nimage = \"postgres\"\n",
+    )
+    .unwrap();
+    let loaded = Manifest::load(&manifest, &LoadOptions::default()).unwrap();
+    let live = LiveState {
+        containers: Vec::new(),
+        jobs: Vec::new(),
+        cron_jobs: Vec::new(),
+    };
+    assert!(plan::check_dependencies(&loaded, &live, false).is_ok());
+
+    fs::write(
+        dir.path().join("db.toml"),
+        "[[containers]]\nname = \"db\"\nimage = \"postgres\"\ndepends_on = [{ name = \"web\" }]\n",
+    )
+    .unwrap();
+    let error = Manifest::load(&manifest, &LoadOptions::default())
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("web.toml:4:14: dependency cycle: web -> db -> web"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,567 @@
+mod common;
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use nic8s::{
+    config::{DriftConfig, DriftPolicy, GcConfig, ImageGcConfig, RolloutConfig},
+    controllers::{
+        cron_job::CronJobController,
+        drift::DriftDetector,
+        garbage_collector::GarbageCollector,
+        image_gc::ImageGarbageCollector,
+        job::JobController,
+        rollout::{self, RolloutController},
+    },
+    entities::{
+        container::{Container, ContainerSpec, ContainerStatus, KIND, MANAGED_LABEL},
+        cron_job::{CronJobExists, CronJobSpec},
+        job::{Job, JobPhase, JobSpec},
+        rollout::{
+            BlueGreenStrategy, CanaryStrategy, Rollout, RolloutPhase, RolloutSpec, RolloutStrategy,
+        },
+    },
+    events::event::EventReason,
+    runtime::{retry::Operation, ContainerRuntime, RunOptions},
+    watchers::watcher::{Watcher, WatcherContext},
+};
+use tokio_util::sync::CancellationToken;
+
+use common::reasons;
+
+fn spec(name: &str) -> ContainerSpec {
+    ContainerSpec {
+        name: String::from(name),
+        image: String::from("nginx"),
+        app: Some(String::from("web")),
+        ..ContainerSpec::default()
+    }
+}
+
+fn managed() -> RunOptions {
+    RunOptions {
+        labels: BTreeMap::from([(String::from(MANAGED_LABEL), String::from("true"))]),
+        ..RunOptions::default()
+    }
+}
+
+async fn names(mock: &nic8s::runtime::mock::MockRuntime) -> Vec<String> {
+    let mut names: Vec<String> = mock
+        .list()
+        .await
+        .into_iter()
+        .map(|mock| mock.container.name)
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn scales_apps_up_and_down() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    Container::new(&spec("web"), &cluster).await.unwrap();
+
+    let containers = Container::scale("web", 3, &cluster).await.unwrap();
+    assert_eq!(containers.len(), 3);
+    assert_eq!(names(&mock).await, ["web", "web-1", "web-2"]);
+    for container in mock.list().await {
+        assert_eq!(container.container.get_status(), ContainerStatus::Running);
+    }
+
+    Container::scale("web", 1, &cluster).await.unwrap();
+    assert_eq!(names(&mock).await, ["web"]);
+    assert_eq!(cluster.status_watcher.list().await.len(), 1);
+    assert!(reasons(&cluster, "web-2")
+        .await
+        .contains(&EventReason::Killed));
+}
+
+#[tokio::test]
+async fn removes_orphaned_containers() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    Container::new(&spec("web"), &cluster).await.unwrap();
+    // Left behind by a delete whose removal failed.
+    let orphan = mock.create(&spec("stale"), &managed()).await.unwrap();
+    mock.start(&orphan.id).await.unwrap();
+    // Not managed, so never touched.
+    mock.create(&spec("other"), &RunOptions::default())
+        .await
+        .unwrap();
+
+    let config = GcConfig {
+        grace_period_seconds: 0,
+        ..GcConfig::default()
+    };
+    GarbageCollector::new(cluster.clone(), &config)
+        .collect()
+        .await
+        .unwrap();
+
+    assert_eq!(names(&mock).await, ["other", "web"]);
+    assert!(reasons(&cluster, "stale")
+        .await
+        .contains(&EventReason::Killed));
+}
+
+#[tokio::test]
+async fn keeps_orphans_on_dry_run_and_within_grace_period() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    mock.create(&spec("stale"), &managed()).await.unwrap();
+
+    for config in [
+        GcConfig::default(),
+        GcConfig {
+            grace_period_seconds: 0,
+            dry_run: true,
+            ..GcConfig::default()
+        },
+    ] {
+        GarbageCollector::new(cluster.clone(), &config)
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(names(&mock).await, ["stale"]);
+    }
+}
+
+#[tokio::test]
+async fn adopts_and_starts_existing_containers() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let running = mock.create(&spec("web"), &managed()).await.unwrap();
+    mock.start(&running.id).await.unwrap();
+    // Created but never started, as when the daemon stopped during init.
+    mock.create(&spec("web-1"), &managed()).await.unwrap();
+
+    let adopted = Container::adopt_all(&cluster).await.unwrap();
+    assert_eq!(adopted.len(), 2);
+    common::eventually("web-1 to start", || async {
+        mock.get("web-1")
+            .await
+            .is_some_and(|mock| mock.container.get_status() == ContainerStatus::Running)
+    })
+    .await;
+
+    // Recorded as desired state, so the garbage collector leaves them alone.
+    let desired: Vec<ContainerSpec> = cluster.state.list(KIND).await.unwrap();
+    assert_eq!(desired.len(), 2);
+    assert_eq!(cluster.status_watcher.list().await.len(), 2);
+}
+
+#[tokio::test]
+async fn reports_failed_pulls() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    mock.fail(Operation::Pull, "manifest unknown").await;
+
+    let error = Container::new(&spec("web"), &cluster).await.unwrap_err();
+    assert!(error.to_string().contains("manifest unknown"));
+    assert!(mock.list().await.is_empty());
+    assert!(cluster.status_watcher.list().await.is_empty());
+    assert_eq!(
+        reasons(&cluster, "web").await,
+        [EventReason::Scheduled, EventReason::Failed]
+    );
+
+    Container::new(&spec("web"), &cluster).await.unwrap();
+    assert_eq!(
+        reasons(&cluster, "web").await[2..],
+        [
+            EventReason::Scheduled,
+            EventReason::Pulled,
+            EventReason::Created
+        ]
+    );
+}
+
+async fn finish_job(backoff_limit: u32, exit_code: i64) -> Job {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let jobs = Arc::new(JobController::new(cluster, CancellationToken::new()));
+    let spec = JobSpec {
+        template: spec("migrate"),
+        backoff_limit,
+    };
+    jobs.create(spec).await.unwrap();
+
+    common::eventually("the attempt to start", || async {
+        mock.get("migrate-1")
+            .await
+            .is_some_and(|mock| mock.container.get_status() == ContainerStatus::Running)
+    })
+    .await;
+    mock.exit("migrate-1", exit_code).await.unwrap();
+
+    common::eventually("the job to finish", || async {
+        jobs.get("migrate")
+            .await
+            .unwrap()
+            .is_some_and(|job| job.is_finished())
+    })
+    .await;
+    jobs.get("migrate").await.unwrap().unwrap()
+}
+
+#[tokio::test]
+async fn completes_jobs_whose_container_succeeds() {
+    let job = finish_job(0, 0).await;
+    assert_eq!(job.status.phase, JobPhase::Complete);
+    assert_eq!(job.status.attempts.len(), 1);
+    assert_eq!(job.status.attempts[0].exit_code, Some(0));
+}
+
+#[tokio::test]
+async fn fails_jobs_past_their_backoff_limit() {
+    let job = finish_job(0, 2).await;
+    assert_eq!(job.status.phase, JobPhase::Failed);
+    assert_eq!(job.status.attempts[0].exit_code, Some(2));
+}
+
+#[tokio::test]
+async fn creates_a_cron_job_only_once() {
+    let (cluster, _mock, _dir) = common::cluster().await;
+    let shutdown = CancellationToken::new();
+    let jobs = Arc::new(JobController::new(cluster.clone(), shutdown.clone()));
+    let cron_jobs = Arc::new(CronJobController::new(cluster, jobs, shutdown.clone()));
+    let spec: CronJobSpec = serde_json::from_value(serde_json::json!({
+        "name": "backup",
+        "image": "alpine:3",
+        "schedule": "0 3 * * *",
+    }))
+    .unwrap();
+
+    let (first, second) = tokio::join!(
+        cron_jobs.create(spec.clone()),
+        cron_jobs.create(spec.clone())
+    );
+    let error = first.and(second).unwrap_err();
+    assert!(error.is::<CronJobExists>());
+    assert!(cron_jobs
+        .create(spec)
+        .await
+        .unwrap_err()
+        .is::<CronJobExists>());
+    assert_eq!(cron_jobs.list().await.unwrap().len(), 1);
+
+    assert!(cron_jobs.delete("backup").await.unwrap());
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn reports_drift_seen_by_two_checks() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let web = Container::new(&spec("web"), &cluster).await.unwrap();
+    mock.stop(&web.id, None).await.unwrap();
+
+    let drift = DriftDetector::new(cluster.clone(), &DriftConfig::default());
+    drift.check().await.unwrap();
+    assert!(!reasons(&cluster, "web")
+        .await
+        .contains(&EventReason::DriftDetected));
+    drift.check().await.unwrap();
+    drift.check().await.unwrap();
+
+    let detected = reasons(&cluster, "web")
+        .await
+        .into_iter()
+        .filter(|reason| *reason == EventReason::DriftDetected)
+        .count();
+    assert_eq!(detected, 1);
+    // Reported only, so the container stays stopped.
+    assert_eq!(
+        mock.get("web").await.unwrap().container.get_status(),
+        ContainerStatus::Exited
+    );
+}
+
+#[tokio::test]
+async fn repairs_drift() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let stopped = Container::new(&spec("web"), &cluster).await.unwrap();
+    let removed = Container::new(&spec("api"), &cluster).await.unwrap();
+    mock.stop(&stopped.id, None).await.unwrap();
+    mock.remove(&removed.id).await.unwrap();
+
+    let config = DriftConfig {
+        policy: DriftPolicy::Repair,
+        ..DriftConfig::default()
+    };
+    let drift = DriftDetector::new(cluster.clone(), &config);
+    drift.check().await.unwrap();
+    drift.check().await.unwrap();
+
+    assert_eq!(names(&mock).await, ["api", "web"]);
+    for container in mock.list().await {
+        assert_eq!(container.container.get_status(), ContainerStatus::Running);
+    }
+    let api = cluster.status_watcher.find("api").await.unwrap();
+    assert_ne!(api.id, removed.id);
+    assert!(reasons(&cluster, "api")
+        .await
+        .contains(&EventReason::DriftDetected));
+}
+
+#[tokio::test]
+async fn leaves_containers_stopped_through_nic8s_alone() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let web = Container::new(&spec("web"), &cluster).await.unwrap();
+    // As the stop endpoint does.
+    cluster.status_watcher.hold_stopped(&web.id).await;
+    mock.stop(&web.id, None).await.unwrap();
+
+    let config = DriftConfig {
+        policy: DriftPolicy::Repair,
+        ..DriftConfig::default()
+    };
+    let drift = DriftDetector::new(cluster.clone(), &config);
+    drift.check().await.unwrap();
+    drift.check().await.unwrap();
+
+    assert_eq!(
+        mock.get("web").await.unwrap().container.get_status(),
+        ContainerStatus::Exited
+    );
+    assert!(!reasons(&cluster, "web")
+        .await
+        .contains(&EventReason::DriftDetected));
+}
+
+#[tokio::test]
+async fn removes_unused_images_over_the_threshold() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    Container::new(&spec("web"), &cluster).await.unwrap();
+    // Pushing a new nginx leaves the running container on the old, now untagged, image.
+    mock.push_image("nginx").await;
+    for image in ["redis", "postgres:16", "memcached"] {
+        mock.pull(image).await.unwrap();
+    }
+
+    let tags = || async {
+        let mut tags: Vec<String> = mock
+            .list_images()
+            .await
+            .unwrap()
+            .into_iter()
+            .flat_map(|image| image.tags)
+            .collect();
+        tags.sort();
+        tags
+    };
+    let mib = 1024 * 1024;
+    let config = ImageGcConfig {
+        enabled: true,
+        high_threshold_bytes: 450 * mib,
+        low_threshold_bytes: 400 * mib,
+        keep: vec![String::from("postgres:*")],
+        dry_run: true,
+        ..ImageGcConfig::default()
+    };
+    ImageGarbageCollector::new(cluster.clone(), &config)
+        .unwrap()
+        .collect(false)
+        .await
+        .unwrap();
+    assert_eq!(tags().await.len(), 4);
+
+    let config = ImageGcConfig {
+        dry_run: false,
+        ..config
+    };
+    let collector = ImageGarbageCollector::new(cluster.clone(), &config).unwrap();
+    collector.collect(false).await.unwrap();
+    assert_eq!(tags().await, ["memcached", "nginx", "postgres:16"]);
+    assert_eq!(mock.image_disk_usage().await.unwrap(), 400 * mib);
+
+    collector.collect(true).await.unwrap();
+    assert_eq!(tags().await, ["nginx", "postgres:16"]);
+    assert!(cluster
+        .events
+        .list(Some("local"))
+        .await
+        .iter()
+        .any(|event| event.reason == EventReason::ImagesRemoved));
+}
+
+fn watch(cluster: &nic8s::cluster::Cluster) -> CancellationToken {
+    let shutdown = CancellationToken::new();
+    let watcher = cluster.status_watcher.clone();
+    let ctx = WatcherContext::new(shutdown.clone());
+    tokio::spawn(async move { watcher.run(ctx).await });
+    shutdown
+}
+
+// Rolls web, running `replicas` containers of nginx, out to nginx:2.
+async fn roll_out(
+    cluster: &nic8s::cluster::Cluster,
+    replicas: usize,
+    strategy: RolloutStrategy,
+) -> RolloutController {
+    Container::new(&spec("web"), cluster).await.unwrap();
+    Container::scale("web", replicas, cluster).await.unwrap();
+
+    let rollouts = RolloutController::new(cluster.clone(), &RolloutConfig::default()).unwrap();
+    let spec = RolloutSpec {
+        app: String::from("web"),
+        template: ContainerSpec {
+            image: String::from("nginx:2"),
+            ..spec("web")
+        },
+        strategy,
+        progress_deadline_seconds: 60,
+    };
+    rollouts.create(spec).await.unwrap();
+    rollouts
+}
+
+// Takes rollout steps, with the status watcher catching up in between, until `phase`.
+fn canary() -> RolloutStrategy {
+    RolloutStrategy::Canary(CanaryStrategy {
+        percent: 50,
+        analysis_seconds: 0,
+        min_success_rate: Some(1.0),
+        metric: None,
+    })
+}
+
+async fn step_until(
+    cluster: &nic8s::cluster::Cluster,
+    rollouts: &RolloutController,
+    phase: RolloutPhase,
+) -> Rollout {
+    common::eventually(&format!("the rollout to reach {:?}", phase), || async {
+        cluster.status_watcher.resync().await;
+        rollouts.check_rollouts().await.unwrap();
+        rollouts.get("web").await.unwrap().unwrap().status.phase == phase
+    })
+    .await;
+    rollouts.get("web").await.unwrap().unwrap()
+}
+
+#[tokio::test]
+async fn promotes_canaries_that_pass_analysis() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let _shutdown = watch(&cluster);
+    let rollouts = roll_out(&cluster, 3, canary()).await;
+
+    let rollout = step_until(&cluster, &rollouts, RolloutPhase::Analyzing).await;
+    // Half of three replicas, rounded up.
+    assert_eq!(rollout.status.canaries, ["web-canary-1", "web-canary-2"]);
+
+    let rollout = step_until(&cluster, &rollouts, RolloutPhase::Succeeded).await;
+    assert_eq!(rollout.status.promoted, ["web", "web-1", "web-2"]);
+    assert_eq!(names(&mock).await, ["web", "web-1", "web-2"]);
+    for container in mock.list().await {
+        assert_eq!(container.container.spec.image, "nginx:2");
+    }
+    assert!(reasons(&cluster, "web")
+        .await
+        .contains(&EventReason::RolledOut));
+}
+
+#[tokio::test]
+async fn rolls_back_canaries_that_fail_analysis() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let _shutdown = watch(&cluster);
+    let rollouts = roll_out(&cluster, 3, canary()).await;
+
+    step_until(&cluster, &rollouts, RolloutPhase::Analyzing).await;
+    mock.exit("web-canary-1", 1).await.unwrap();
+    common::eventually("web-canary-1 to exit", || async {
+        cluster.status_watcher.resync().await;
+        let canary = cluster.status_watcher.find("web-canary-1").await.unwrap();
+        canary.get_status() == ContainerStatus::Exited
+    })
+    .await;
+
+    let rollout = step_until(&cluster, &rollouts, RolloutPhase::RolledBack).await;
+    assert!(rollout.status.promoted.is_empty());
+    assert_eq!(names(&mock).await, ["web", "web-1", "web-2"]);
+    for container in mock.list().await {
+        assert_eq!(container.container.spec.image, "nginx");
+    }
+    assert!(reasons(&cluster, "web")
+        .await
+        .contains(&EventReason::RolledBack));
+}
+
+#[tokio::test]
+async fn scales_apps_without_touching_their_canaries() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let _shutdown = watch(&cluster);
+    let rollouts = roll_out(&cluster, 3, canary()).await;
+    step_until(&cluster, &rollouts, RolloutPhase::Analyzing).await;
+
+    Container::scale("web", 1, &cluster).await.unwrap();
+    assert_eq!(names(&mock).await, ["web", "web-canary-1", "web-canary-2"]);
+}
+
+#[tokio::test]
+async fn rolls_back_blue_green_rollouts_with_no_replicas_to_replace() {
+    let (cluster, _mock, _dir) = common::cluster().await;
+    let rollouts = RolloutController::new(cluster.clone(), &RolloutConfig::default()).unwrap();
+    rollouts
+        .create(RolloutSpec {
+            app: String::from("web"),
+            template: spec("web"),
+            strategy: RolloutStrategy::BlueGreen(BlueGreenStrategy {
+                scale_down_delay_seconds: 0,
+            }),
+            progress_deadline_seconds: 60,
+        })
+        .await
+        .unwrap();
+
+    let rollout = step_until(&cluster, &rollouts, RolloutPhase::RolledBack).await;
+    assert_eq!(
+        rollout.status.message.as_deref(),
+        Some("app web has no replicas to replace")
+    );
+}
+
+async fn serving(cluster: &nic8s::cluster::Cluster) -> Vec<String> {
+    let mut names: Vec<String> = rollout::serving(cluster)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|container| container.name)
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn switches_traffic_to_a_new_set_and_back() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let _shutdown = watch(&cluster);
+    let strategy = RolloutStrategy::BlueGreen(BlueGreenStrategy {
+        scale_down_delay_seconds: 300,
+    });
+    let rollouts = roll_out(&cluster, 2, strategy).await;
+
+    let rollout = step_until(&cluster, &rollouts, RolloutPhase::Switched).await;
+    assert_eq!(rollout.status.replicas, ["web-blue-1", "web-blue-2"]);
+    assert_eq!(
+        names(&mock).await,
+        ["web", "web-1", "web-blue-1", "web-blue-2"]
+    );
+    assert_eq!(serving(&cluster).await, ["web-blue-1", "web-blue-2"]);
+
+    rollouts.undo("web").await.unwrap();
+    assert_eq!(serving(&cluster).await, ["web", "web-1"]);
+    step_until(&cluster, &rollouts, RolloutPhase::RolledBack).await;
+    assert_eq!(names(&mock).await, ["web", "web-1"]);
+    assert!(reasons(&cluster, "web")
+        .await
+        .contains(&EventReason::Switched));
+}
+
+#[tokio::test]
+async fn removes_the_old_set_after_the_scale_down_delay() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let _shutdown = watch(&cluster);
+    let strategy = RolloutStrategy::BlueGreen(BlueGreenStrategy {
+        scale_down_delay_seconds: 0,
+    });
+    let rollouts = roll_out(&cluster, 2, strategy).await;
+
+    step_until(&cluster, &rollouts, RolloutPhase::Succeeded).await;
+    assert_eq!(names(&mock).await, ["web-blue-1", "web-blue-2"]);
+    assert_eq!(serving(&cluster).await, ["web-blue-1", "web-blue-2"]);
+    assert!(rollouts.undo("web").await.is_err());
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use std::sync::Arc;
+
+use nic8s::{
+    admission::{quota::ResourceQuotas, AdmissionChain, AdmissionError},
+    cluster::Cluster,
+    entities::{
+        container::{Container, ContainerSpec, ResourceRequests},
+        resource_quota::{QuotaUsage, ResourceQuotaSpec},
+    },
+};
+use tempfile::TempDir;
+
+async fn cluster() -> (Cluster, Arc<ResourceQuotas>, TempDir) {
+    let (cluster, _mock, dir) = common::cluster().await;
+    let quotas = Arc::new(ResourceQuotas::new(
+        cluster.state.clone(),
+        cluster.status_watcher.clone(),
+    ));
+    let cluster = Cluster {
+        admission: Arc::new(AdmissionChain::default().with_plugin(quotas.clone())),
+        ..cluster
+    };
+    (cluster, quotas, dir)
+}
+
+fn spec(name: &str, cpus: f64) -> ContainerSpec {
+    ContainerSpec {
+        name: String::from(name),
+        image: String::from("nginx"),
+        app: Some(String::from("web")),
+        resources: ResourceRequests {
+            cpus,
+            memory_bytes: 64 * 1024 * 1024,
+        },
+        ..ContainerSpec::default()
+    }
+}
+
+fn is_denied(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref(), Some(AdmissionError::Denied { .. }))
+}
+
+#[tokio::test]
+async fn rejects_creates_over_the_quota() {
+    let (cluster, quotas, _dir) = cluster().await;
+    quotas
+        .create(ResourceQuotaSpec {
+            app: String::from("web"),
+            cpus: Some(1.0),
+            memory_bytes: None,
+            containers: Some(3),
+        })
+        .await
+        .unwrap();
+
+    Container::new(&spec("web", 0.5), &cluster).await.unwrap();
+    Container::new(&spec("web-1", 0.5), &cluster).await.unwrap();
+    let error = Container::new(&spec("web-2", 0.5), &cluster)
+        .await
+        .unwrap_err();
+    assert!(is_denied(&error), "{}", error);
+
+    // Scaling goes through admission too, and other apps aren't limited.
+    let error = Container::scale("web", 3, &cluster).await.unwrap_err();
+    assert!(is_denied(&error), "{}", error);
+    Container::new(
+        &ContainerSpec {
+            app: Some(String::from("api")),
+            ..spec("api", 4.0)
+        },
+        &cluster,
+    )
+    .await
+    .unwrap();
+
+    let quota = quotas.get("web").await.unwrap().unwrap();
+    assert_eq!(
+        quota.used,
+        QuotaUsage {
+            cpus: 1.0,
+            memory_bytes: 128 * 1024 * 1024,
+            containers: 2,
+        }
+    );
+}
+
+#[tokio::test]
+async fn requires_requests_for_limited_resources() {
+    let (cluster, quotas, _dir) = cluster().await;
+    quotas
+        .create(ResourceQuotaSpec {
+            app: String::from("web"),
+            cpus: Some(2.0),
+            memory_bytes: None,
+            containers: None,
+        })
+        .await
+        .unwrap();
+
+    let error = Container::new(&spec("web", 0.0), &cluster)
+        .await
+        .unwrap_err();
+    assert!(is_denied(&error), "{}", error);
+    assert!(cluster.status_watcher.list().await.is_empty());
+
+    // Deleting the quota lifts the limits.
+    assert!(quotas.delete("web").await.unwrap());
+    Container::new(&spec("web", 0.0), &cluster).await.unwrap();
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use nic8s::{
+    config::{Config, RuntimeBackend},
+    entities::container::ContainerStatus,
+    runtime::{
+        docker::DockerRuntime,
+        limit::{LimitedRuntime, OperationLimit},
+        mock::MockRuntime,
+        ContainerRuntime,
+    },
+};
+
+#[tokio::test]
+async fn queues_operations_over_the_limit() {
+    let limit = Arc::new(OperationLimit::new(2));
+    let operations: Vec<_> = (0..5)
+        .map(|_| {
+            let limit = limit.clone();
+            tokio::spawn(async move {
+                // A sleep's deadline is set when it's created, so it's created once running.
+                let operation = async { tokio::time::sleep(Duration::from_millis(200)).await };
+                limit.run(operation).await
+            })
+        })
+        .collect();
+
+    common::eventually("the operations to start", || async {
+        let snapshot = limit.snapshot();
+        snapshot.in_flight == 2 && snapshot.queued == 3
+    })
+    .await;
+
+    for operation in operations {
+        operation.await.unwrap();
+    }
+    let snapshot = limit.snapshot();
+    assert_eq!((snapshot.in_flight, snapshot.queued), (0, 0));
+    assert_eq!(snapshot.total, 5);
+    // Two waited for the first round of operations and the last one for the second round too.
+    assert!(snapshot.queued_time >= Duration::from_millis(2 * 200 + 400));
+}
+
+#[tokio::test]
+async fn runs_execs_outside_the_limit() {
+    let limit = Arc::new(OperationLimit::new(1));
+    let runtime = LimitedRuntime::new(Arc::new(MockRuntime::new()), limit.clone());
+    let (release, held) = tokio::sync::oneshot::channel::<()>();
+    let holder = tokio::spawn({
+        let limit = limit.clone();
+        async move { limit.run(held).await }
+    });
+    common::eventually("the permit to be taken", || async {
+        limit.snapshot().in_flight == 1
+    })
+    .await;
+
+    // A slow hook holding a permit would hold up every other operation on the node.
+    let command = [String::from("true")];
+    let exec = tokio::time::timeout(Duration::from_secs(1), runtime.exec("web", &command));
+    assert!(exec.await.is_ok());
+    assert_eq!(limit.snapshot().queued, 0);
+
+    release.send(()).unwrap();
+    holder.await.unwrap().unwrap();
+}
+
+#[test]
+fn checks_the_docker_engine_settings() {
+    let engine = |toml: &str| {
+        let config: Config = toml::from_str(toml).unwrap();
+        DockerRuntime::new().with_engine(&config.runtime.docker)
+    };
+
+    assert!(engine("").is_ok());
+    assert!(engine("[runtime.docker]\nhost = \"ssh://deploy@build-1\"").is_ok());
+    assert!(engine(
+        "[runtime.docker]\nhost = \"tcp://10.0.0.5:2376\"\ntls_verify = true\ncert_path = \"/etc/nic8s/docker\""
+    )
+    .is_ok());
+    assert!(engine("[runtime.docker]\ncontext = \"staging\"").is_ok());
+
+    let error = |toml: &str| engine(toml).err().unwrap().to_string();
+    assert!(
+        error("[runtime.docker]\nhost = \"unix:///run/docker.sock\"\ncontext = \"staging\"")
+            .contains("can't both be set")
+    );
+    assert!(error("[runtime.docker]\nhost = \"10.0.0.5:2376\"").contains("invalid docker host"));
+    assert!(
+        error("[runtime.docker]\nhost = \"ssh://deploy@build-1\"\ntls_verify = true")
+            .contains("only applies to tcp://")
+    );
+}
+
+#[test]
+fn selects_podman_in_the_config() {
+    let backend = |toml: &str| toml::from_str::<Config>(toml).unwrap().runtime.backend;
+    assert_eq!(backend(""), RuntimeBackend::Docker);
+    assert_eq!(backend("runtime = \"podman\""), RuntimeBackend::Podman);
+
+    let config: Config = toml::from_str(
+        "[runtime]\nbackend = \"podman\"\nmax_concurrent_operations = 4\n[runtime.podman]\nconnection = \"build-1\"",
+    )
+    .unwrap();
+    assert_eq!(config.runtime.backend, RuntimeBackend::Podman);
+    assert_eq!(config.runtime.max_concurrent_operations, 4);
+    assert_eq!(config.runtime.podman.connection.as_deref(), Some("build-1"));
+    assert!(toml::from_str::<Config>("runtime = \"containerd\"").is_err());
+
+    // Podman reports states docker has no names for.
+    assert_eq!(
+        ContainerStatus::from("configured"),
+        ContainerStatus::Created
+    );
+    assert_eq!(ContainerStatus::from("stopped"), ContainerStatus::Exited);
+}
@@ -0,0 +1,139 @@
+use nic8s::{
+    entities::{
+        container::{AffinityTerm, ContainerSpec},
+        labels::{LabelExpression, LabelSelector, Labels, Operator},
+    },
+    scheduler::{scoring, Candidate, Scheduler},
+};
+
+fn labels(pairs: &[(&str, &str)]) -> Labels {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn node(name: &str, node_labels: &[(&str, &str)], apps: &[&str]) -> Candidate {
+    Candidate {
+        name: String::from(name),
+        labels: labels(node_labels),
+        capacity: Default::default(),
+        allocated: Default::default(),
+        used: Default::default(),
+        containers: apps.len(),
+        container_labels: apps.iter().map(|app| labels(&[("app", app)])).collect(),
+    }
+}
+
+fn app(app: &str) -> AffinityTerm {
+    AffinityTerm {
+        selector: LabelSelector {
+            match_labels: labels(&[("app", app)]),
+            ..LabelSelector::default()
+        },
+        topology_key: None,
+    }
+}
+
+fn spec(name: &str, app: &str) -> ContainerSpec {
+    ContainerSpec {
+        name: String::from(name),
+        image: String::from("nginx"),
+        app: Some(String::from(app)),
+        ..ContainerSpec::default()
+    }
+}
+
+fn scheduler() -> Scheduler {
+    Scheduler::new(scoring::strategy("spread").unwrap())
+}
+
+#[test]
+fn node_selector_limits_the_candidates() {
+    let nodes = [
+        node("a", &[("disk", "hdd")], &[]),
+        node("b", &[("disk", "ssd")], &["other", "other"]),
+    ];
+    let spec = ContainerSpec {
+        node_selector: labels(&[("disk", "ssd")]),
+        ..spec("db", "db")
+    };
+    assert_eq!(scheduler().select(&spec, &nodes).unwrap(), "b");
+
+    let spec = ContainerSpec {
+        node_selector: labels(&[("disk", "nvme")]),
+        ..spec
+    };
+    let error = scheduler().select(&spec, &nodes).unwrap_err().to_string();
+    assert!(
+        error.contains("2 node(s) don't match its node selector"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn anti_affinity_spreads_replicas() {
+    let spec = ContainerSpec {
+        anti_affinity: vec![app("web")],
+        ..spec("web-1", "web")
+    };
+
+    let nodes = [
+        node("a", &[], &["web"]),
+        node("b", &[], &["other", "other"]),
+    ];
+    assert_eq!(scheduler().select(&spec, &nodes).unwrap(), "b");
+
+    let nodes = [node("a", &[], &["web"]), node("b", &[], &["web"])];
+    let error = scheduler().select(&spec, &nodes).unwrap_err().to_string();
+    assert!(error.contains("must not run next to"), "{}", error);
+
+    // With a topology key, nodes in the same zone count as one.
+    let spec = ContainerSpec {
+        anti_affinity: vec![AffinityTerm {
+            topology_key: Some(String::from("zone")),
+            ..app("web")
+        }],
+        ..spec
+    };
+    let nodes = [
+        node("a", &[("zone", "1")], &["web"]),
+        node("b", &[("zone", "1")], &[]),
+        node("c", &[("zone", "2")], &["other", "other"]),
+    ];
+    assert_eq!(scheduler().select(&spec, &nodes).unwrap(), "c");
+}
+
+#[test]
+fn affinity_co_locates_containers() {
+    let cache = ContainerSpec {
+        affinity: vec![AffinityTerm {
+            selector: LabelSelector {
+                match_expressions: vec![LabelExpression {
+                    key: String::from("app"),
+                    operator: Operator::In,
+                    values: vec![String::from("web")],
+                }],
+                ..LabelSelector::default()
+            },
+            topology_key: None,
+        }],
+        ..spec("cache", "cache")
+    };
+    let nodes = [node("a", &[], &[]), node("b", &[], &["web", "web"])];
+    assert_eq!(scheduler().select(&cache, &nodes).unwrap(), "b");
+
+    // Nothing to co-locate with yet.
+    let nodes = [node("a", &[], &[]), node("b", &[], &[])];
+    assert!(scheduler().select(&cache, &nodes).is_err());
+
+    // Unless the container matches its own rule, as replicas kept together do.
+    let web = ContainerSpec {
+        affinity: vec![app("web")],
+        ..spec("web", "web")
+    };
+    assert!(scheduler().select(&web, &nodes).is_ok());
+    let nodes = [node("a", &[], &[]), node("b", &[], &["web", "web"])];
+    assert_eq!(scheduler().select(&web, &nodes).unwrap(), "b");
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use nic8s::{
+    entities::{
+        container::{Container, ContainerSpec},
+        volume::{VolumeClaim, VolumeError, VolumeSpec},
+    },
+    runtime::nodes::LOCAL_NODE,
+};
+
+fn spec(name: &str, volume: &str) -> ContainerSpec {
+    ContainerSpec {
+        name: String::from(name),
+        image: String::from("postgres"),
+        volumes: vec![VolumeClaim {
+            name: String::from(volume),
+            mount_path: String::from("/var/lib/postgresql/data"),
+            read_only: false,
+        }],
+        ..ContainerSpec::default()
+    }
+}
+
+#[tokio::test]
+async fn claims_bind_volumes_and_block_their_deletion() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    let volume = cluster
+        .volumes
+        .create(VolumeSpec {
+            name: String::from("data"),
+            node: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(volume.node, None);
+    assert!(mock.volumes().await.is_empty());
+
+    let container = Container::new(&spec("db", "data"), &cluster).await.unwrap();
+    assert_eq!(mock.volumes().await, vec![String::from("data")]);
+    let mounts = mock.get("db").await.unwrap().options.mounts;
+    assert_eq!(mounts.len(), 1);
+    assert_eq!(mounts[0].source, "data");
+    assert_eq!(mounts[0].target, "/var/lib/postgresql/data");
+
+    let volume = cluster.volumes.get("data").await.unwrap().unwrap();
+    assert_eq!(volume.node.as_deref(), Some(LOCAL_NODE));
+    assert_eq!(volume.used_by, vec![String::from("db")]);
+
+    let error = cluster.volumes.delete("data", false).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref(),
+        Some(VolumeError::InUse { .. })
+    ));
+    assert_eq!(mock.volumes().await, vec![String::from("data")]);
+
+    container.delete(&cluster).await.unwrap();
+    assert!(cluster.volumes.delete("data", false).await.unwrap());
+    assert!(mock.volumes().await.is_empty());
+    assert!(cluster.volumes.list().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn forced_deletes_forget_volumes_the_runtime_keeps() {
+    let (cluster, mock, _dir) = common::cluster().await;
+    cluster
+        .volumes
+        .create(VolumeSpec {
+            name: String::from("data"),
+            node: Some(String::from(LOCAL_NODE)),
+        })
+        .await
+        .unwrap();
+    assert_eq!(mock.volumes().await, vec![String::from("data")]);
+
+    let error = Container::new(&spec("db", "missing"), &cluster)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref(),
+        Some(VolumeError::NotFound { .. })
+    ));
+
+    Container::new(&spec("db", "data"), &cluster).await.unwrap();
+    // The runtime still refuses to remove a mounted volume, but nic8s lets go of it.
+    assert!(cluster.volumes.delete("data", true).await.unwrap());
+    assert!(cluster.volumes.get("data").await.unwrap().is_none());
+    assert_eq!(mock.volumes().await, vec![String::from("data")]);
+}
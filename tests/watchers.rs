@@ -0,0 +1,129 @@
+mod common;
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use nic8s::watchers::{
+    registry::WatcherRegistry,
+    watcher::{Watcher, WatcherContext},
+};
+use tokio_util::sync::CancellationToken;
+
+enum Behavior {
+    // Checks every 10ms.
+    Ticks,
+    // Gets stuck in its second check.
+    Hangs,
+    // Waits on events instead of sleeping.
+    Waits,
+    Returns,
+    // Panics in its first run and ticks in the ones after.
+    PanicsOnce,
+}
+
+struct TestWatcher {
+    name: &'static str,
+    behavior: Behavior,
+    runs: AtomicUsize,
+}
+
+#[async_trait]
+impl Watcher for TestWatcher {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    async fn run(&self, ctx: WatcherContext) -> Result<(), anyhow::Error> {
+        match self.behavior {
+            Behavior::Ticks => while ctx.sleep(Duration::from_millis(10)).await {},
+            Behavior::Hangs => {
+                ctx.sleep(Duration::from_millis(10)).await;
+                ctx.shutdown.cancelled().await;
+            }
+            Behavior::Waits => ctx.shutdown.cancelled().await,
+            Behavior::Returns => {}
+            Behavior::PanicsOnce => {
+                if self.runs.fetch_add(1, Ordering::SeqCst) == 0 {
+                    panic!("boom");
+                }
+                while ctx.sleep(Duration::from_millis(10)).await {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn reports_watchers_that_hang_or_return() {
+    let registry = WatcherRegistry::new(CancellationToken::new());
+    for (name, behavior) in [
+        ("ticks", Behavior::Ticks),
+        ("hangs", Behavior::Hangs),
+        ("waits", Behavior::Waits),
+        ("returns", Behavior::Returns),
+        ("stopped", Behavior::Ticks),
+    ] {
+        let watcher = Arc::new(TestWatcher {
+            name,
+            behavior,
+            runs: AtomicUsize::new(0),
+        });
+        registry.register(watcher).await.unwrap();
+    }
+    registry.start_all().await;
+    registry.stop("stopped").await;
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let mut problems = registry.problems(Duration::from_millis(200)).await;
+    problems.sort();
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+    assert!(
+        problems[0].starts_with("watcher hangs is "),
+        "{:?}",
+        problems
+    );
+    assert_eq!(problems[1], "watcher returns stopped unexpectedly");
+
+    assert!(registry.problems(Duration::from_secs(60)).await.len() == 1);
+    registry.stop_all().await;
+}
+
+#[tokio::test]
+async fn restarts_watchers_that_panic() {
+    let registry = WatcherRegistry::new(CancellationToken::new());
+    let watcher = Arc::new(TestWatcher {
+        name: "panics",
+        behavior: Behavior::PanicsOnce,
+        runs: AtomicUsize::new(0),
+    });
+    registry.register(watcher.clone()).await.unwrap();
+    registry.start_all().await;
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        registry.problems(Duration::from_secs(60)).await,
+        vec![String::from("watcher panics panicked: boom")]
+    );
+    let status = &registry.list().await[0];
+    assert!(status.running);
+    assert_eq!(status.consecutive_failures, 1);
+    assert_eq!(status.restarts, 0);
+
+    common::eventually("the watcher to be restarted and tick", || async {
+        let status = &registry.list().await[0];
+        status.restarts == 1 && status.last_tick.is_some()
+    })
+    .await;
+    let status = &registry.list().await[0];
+    assert_eq!(status.consecutive_failures, 0);
+    assert_eq!(status.last_error.as_deref(), Some("panicked: boom"));
+    assert!(registry.problems(Duration::from_secs(60)).await.is_empty());
+    assert_eq!(watcher.runs.load(Ordering::SeqCst), 2);
+    registry.stop_all().await;
+}